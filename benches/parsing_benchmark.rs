@@ -2,8 +2,8 @@ use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use asyncfsm::TextFSM;
 
 fn benchmark_parsing(c: &mut Criterion) {
-    let template_path = "tests/data/cli/cisco_version_template";
-    let data_path = "tests/data/cli/cisco_version_example";
+    let template_path = "tests/basic_template/cli/cisco_version_template";
+    let data_path = "tests/basic_template/cli/cisco_version_example";
     let data = std::fs::read_to_string(data_path).unwrap();
 
     // Bench 1: Compilation
@@ -16,7 +16,7 @@ fn benchmark_parsing(c: &mut Criterion) {
     c.bench_function("parse cisco version data", |b| {
         b.iter(|| {
             fsm.reset();
-            fsm.parse_string(black_box(&data), None).unwrap()
+            fsm.parse_records(black_box(&data), None).unwrap()
         })
     });
 
@@ -24,7 +24,7 @@ fn benchmark_parsing(c: &mut Criterion) {
     c.bench_function("parse cisco version (full)", |b| {
         b.iter(|| {
             let mut fsm = TextFSM::from_file(black_box(template_path)).unwrap();
-            fsm.parse_string(black_box(&data), None).unwrap();
+            fsm.parse_records(black_box(&data), None).unwrap();
         })
     });
 }
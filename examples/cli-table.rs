@@ -27,11 +27,11 @@ fn verify(
             let template_name = format!("{}/{}", template_dir, short_template_name);
             let mut textfsm = TextFSM::from_file(&template_name)?;
             let new_result =
-                textfsm.parse_file(data_name, Some(DataRecordConversion::LowercaseKeys))?;
+                textfsm.parse_file_records(data_name, Some(DataRecordConversion::LowercaseKeys))?;
             println!("NEW RESULT from {}: {:?}", short_template_name, &new_result);
             // merge with the result
             if result.is_empty() {
-                result = new_result;
+                result = new_result.into_vec();
             } else {
                 for nrow in new_result {
                     for res in result.iter_mut() {
@@ -8,6 +8,6 @@ fn main() {
         .nth(2)
         .expect("Missing TextFSM data file name");
     let mut textfsm = TextFSM::from_file(&template_name).unwrap();
-    let result = textfsm.parse_file(&data_name, None);
+    let result = textfsm.parse_file_records(&data_name, None);
     println!("Records: {:?}", &result);
 }
@@ -16,7 +16,7 @@ fn verify(template_name: &str, data_name: &str, yaml_verify_name: &str) -> async
     let mut textfsm = TextFSM::from_file(template_name)?;
     let yaml = std::fs::read_to_string(yaml_verify_name).expect("YAML File read failed");
 
-    let result = textfsm.parse_file(data_name, Some(DataRecordConversion::LowercaseKeys))?;
+    let result = textfsm.parse_file_records(data_name, Some(DataRecordConversion::LowercaseKeys))?;
     println!("RESULT: {:?}\n", &result);
     if let Ok(yaml_map) = serde_yaml::from_str::<ParsedSample>(&yaml) {
         if result == yaml_map.parsed_sample {
@@ -21,7 +21,7 @@ fn main() {
     let mut textfsm = TextFSM::from_file(&template_name).unwrap();
     let yaml = std::fs::read_to_string(&yaml_verify_name).expect("YAML File read failed");
     let result = textfsm
-        .parse_file(&data_name, Some(DataRecordConversion::LowercaseKeys))
+        .parse_file_records(&data_name, Some(DataRecordConversion::LowercaseKeys))
         .unwrap();
     println!("RESULT: {:?}\n", &result);
 
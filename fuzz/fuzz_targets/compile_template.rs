@@ -0,0 +1,17 @@
+//! Compiles arbitrary bytes as a template and, when that succeeds, runs it over a
+//! fixed input. Neither step may panic, whatever the template says.
+#![no_main]
+
+use asyncfsm::{TextFSM, TextFSMParser, TextFsmOptions};
+use libfuzzer_sys::fuzz_target;
+
+const INPUT: &str = "Interface Gi0/1 is up\n  mtu 1500\n!\nError here\n\tlast line\n";
+
+fuzz_target!(|data: &[u8]| {
+    let template = String::from_utf8_lossy(data);
+    if let Ok(parser) = TextFSMParser::from_string(&template) {
+        let options = TextFsmOptions::default().max_rule_evaluations(1_000);
+        let mut fsm = TextFSM::from_parser(parser).with_options(options);
+        let _ = fsm.parse_records(INPUT, None);
+    }
+});
@@ -0,0 +1,79 @@
+//! Feeds arbitrary input to a set of compiled templates covering the value options
+//! and actions. The first byte picks the template; the rest is the device output.
+#![no_main]
+
+use asyncfsm::{DataRecordConversion, TextFSM, TextFSMParser};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+const TEMPLATES: &[&str] = &[
+    // Filldown, Required and List values over several records.
+    r#"Value Filldown Hostname (\S+)
+Value Required Interface (\S+)
+Value List Vlans (\d+)
+Value Status (up|down|administratively down)
+
+Start
+  ^hostname ${Hostname}
+  ^interface ${Interface} is ${Status} -> Continue
+  ^interface -> Interfaces
+
+Interfaces
+  ^\s+vlan ${Vlans}
+  ^! -> Record Start
+  ^interface ${Interface} is ${Status} -> Record
+"#,
+    // Key and Fillup values, Clear and Clearall.
+    r#"Value Key Name (\w+)
+Value Fillup Total (\d+)
+Value Count (\d+)
+
+Start
+  ^${Name}\s+${Count} -> Record
+  ^total ${Total} -> Clear
+  ^reset -> Clearall
+"#,
+    // Continue chains between states, Error and an explicit EOF state.
+    r#"Value Word (\S+)
+
+Start
+  ^${Word} -> Continue.Record Second
+  ^fail -> Error "unexpected"
+
+Second
+  ^\S+ -> Continue Start
+  ^.* -> Start
+
+EOF
+  ^.* -> Record
+"#,
+    // A look-ahead that needs the fancy regex engine.
+    r#"Value Addr ((?=\d)\S+)
+Value Mask (\S+)
+
+Start
+  ^\s*${Addr}/${Mask}(?!\S) -> Record
+"#,
+];
+
+fn parsers() -> &'static Vec<TextFSMParser> {
+    static PARSERS: OnceLock<Vec<TextFSMParser>> = OnceLock::new();
+    PARSERS.get_or_init(|| {
+        TEMPLATES
+            .iter()
+            .map(|t| TextFSMParser::from_string(t).expect("fuzz templates compile"))
+            .collect()
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, input)) = data.split_first() else {
+        return;
+    };
+    let parsers = parsers();
+    let parser = &parsers[selector as usize % parsers.len()];
+    let input = String::from_utf8_lossy(input);
+    let conversion = (selector & 0x80 != 0).then_some(DataRecordConversion::LowercaseKeys);
+    let mut fsm = TextFSM::from_parser(parser.clone());
+    let _ = fsm.parse_records(&input, conversion);
+});
@@ -0,0 +1,60 @@
+//! Named actions that template rules can call, for computed fields and side effects.
+//!
+//! A rule lists calls before its usual transition:
+//!
+//! ```text
+//! ^${INTERFACE} is ${STATUS} -> Call(CountDown) Record
+//! ```
+//!
+//! The actions themselves are closures registered with
+//! [`TextFsmOptions::action`](crate::TextFsmOptions::action). When the rule matches,
+//! each call runs in order after the captures were added to the record and before
+//! the record action, so fields it sets end up in the record `Record` emits. An
+//! action returning an error, or a call nobody registered, aborts the parse with
+//! [`TextFsmError::ActionError`](crate::TextFsmError::ActionError).
+
+use crate::DataRecord;
+use std::fmt;
+use std::sync::Arc;
+
+/// What an action sees of the rule that called it.
+#[derive(Debug)]
+pub struct ActionContext<'a> {
+    /// The state the rule belongs to.
+    pub state: &'a str,
+    /// The 1-based number of the input line that matched.
+    pub line_number: usize,
+    /// The input line that matched.
+    pub line: &'a str,
+    /// The values the rule captured from this line.
+    pub captures: &'a DataRecord,
+    /// The record being built; changes are kept.
+    pub record: &'a mut DataRecord,
+}
+
+type ActionFn = dyn Fn(&mut ActionContext<'_>) -> Result<(), String> + Send + Sync;
+
+/// A closure callable from templates as `Call(Name)`.
+#[derive(Clone)]
+pub struct RuleAction(Arc<ActionFn>);
+
+impl RuleAction {
+    /// Wraps a closure; an `Err` explains why the parse has to stop.
+    pub fn new<F>(action: F) -> Self
+    where
+        F: Fn(&mut ActionContext<'_>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        RuleAction(Arc::new(action))
+    }
+
+    /// Runs the action.
+    pub fn call(&self, context: &mut ActionContext<'_>) -> Result<(), String> {
+        (self.0)(context)
+    }
+}
+
+impl fmt::Debug for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RuleAction(..)")
+    }
+}
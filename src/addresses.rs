@@ -0,0 +1,160 @@
+//! One spelling for MAC and IPv6 addresses, so records from different vendors compare
+//! equal.
+//!
+//! Cisco prints `aabb.ccdd.eeff` where most others print `aa:bb:cc:dd:ee:ff`, and an
+//! IPv6 address can be written compressed (`2001:db8::1`) or in full. [`AddressFormats`]
+//! names the fields holding each kind of address and the form to rewrite them to.
+//! Values that do not parse as the expected address are left alone.
+//!
+//! ```
+//! use asyncfsm::{AddressFormats, DataRecordConversion, MacFormat, TextFSM};
+//!
+//! let template = "Value MAC (\\S+)\n\nStart\n  ^${MAC} -> Record\n";
+//! let formats = AddressFormats::new().mac("MAC", MacFormat::Colon);
+//! let records = TextFSM::from_string(template)?
+//!     .parse_records("AABB.CCDD.EEFF\n", Some(DataRecordConversion::NormalizeAddresses(formats)))?;
+//! assert_eq!(records[0].fields["MAC"].to_string(), "aa:bb:cc:dd:ee:ff");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Value};
+use std::net::Ipv6Addr;
+
+/// How [`AddressFormats`] writes MAC addresses. Hex digits are always lowercase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MacFormat {
+    /// `aa:bb:cc:dd:ee:ff`
+    #[default]
+    Colon,
+    /// `aa-bb-cc-dd-ee-ff`
+    Hyphen,
+    /// `aabb.ccdd.eeff`, as Cisco writes them
+    Dotted,
+    /// `aabbccddeeff`
+    Bare,
+}
+
+impl MacFormat {
+    /// `mac` in this format, or `None` if it is not 12 hex digits with the usual
+    /// separators.
+    pub fn format(self, mac: &str) -> Option<String> {
+        let digits: String = mac
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ':' | '-' | '.'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let groups = |size: usize, sep: &str| {
+            digits
+                .as_bytes()
+                .chunks(size)
+                .map(|chunk| std::str::from_utf8(chunk).unwrap())
+                .collect::<Vec<_>>()
+                .join(sep)
+        };
+        Some(match self {
+            MacFormat::Colon => groups(2, ":"),
+            MacFormat::Hyphen => groups(2, "-"),
+            MacFormat::Dotted => groups(4, "."),
+            MacFormat::Bare => digits,
+        })
+    }
+}
+
+/// How [`AddressFormats`] writes IPv6 addresses. A `/LEN` suffix is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ipv6Format {
+    /// The RFC 5952 form: `2001:db8::1`
+    #[default]
+    Compressed,
+    /// All eight groups of four digits: `2001:0db8:0000:0000:0000:0000:0000:0001`
+    Expanded,
+}
+
+impl Ipv6Format {
+    /// `address` in this format, or `None` if it is not an IPv6 address.
+    pub fn format(self, address: &str) -> Option<String> {
+        let (address, prefix) = match address.trim().split_once('/') {
+            Some((address, len)) if len.parse::<u8>().is_ok_and(|len| len <= 128) => {
+                (address, Some(len))
+            }
+            Some(_) => return None,
+            None => (address.trim(), None),
+        };
+        let ip: Ipv6Addr = address.parse().ok()?;
+        let mut out = match self {
+            Ipv6Format::Compressed => ip.to_string(),
+            Ipv6Format::Expanded => ip
+                .segments()
+                .iter()
+                .map(|s| format!("{:04x}", s))
+                .collect::<Vec<_>>()
+                .join(":"),
+        };
+        if let Some(len) = prefix {
+            out.push('/');
+            out.push_str(len);
+        }
+        Some(out)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AddressKind {
+    Mac(MacFormat),
+    Ipv6(Ipv6Format),
+}
+
+/// The fields holding addresses and the form to write each in; see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressFormats {
+    fields: Vec<(String, AddressKind)>,
+}
+
+impl AddressFormats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the MAC addresses in `field` (in any case) as `format`.
+    pub fn mac(mut self, field: impl Into<String>, format: MacFormat) -> Self {
+        self.fields.push((field.into(), AddressKind::Mac(format)));
+        self
+    }
+
+    /// Writes the IPv6 addresses in `field` (in any case) as `format`.
+    pub fn ipv6(mut self, field: impl Into<String>, format: Ipv6Format) -> Self {
+        self.fields.push((field.into(), AddressKind::Ipv6(format)));
+        self
+    }
+
+    /// Rewrites the addresses in `record`'s configured fields, including list items.
+    pub fn apply(&self, record: &mut DataRecord) {
+        for (field, value) in record.fields.iter_mut() {
+            let Some((_, kind)) = self
+                .fields
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            else {
+                continue;
+            };
+            let rewrite = |text: &mut String| {
+                let formatted = match kind {
+                    AddressKind::Mac(format) => format.format(text),
+                    AddressKind::Ipv6(format) => format.format(text),
+                };
+                if let Some(formatted) = formatted {
+                    *text = formatted;
+                }
+            };
+            match value {
+                Value::Single(text) => rewrite(text),
+                Value::List(items) => items.iter_mut().for_each(rewrite),
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Rewriting sensitive values in device output, for sample data that can be shared.
+//!
+//! The template finds the values: an [`Anonymizer`] learns the values captured into the
+//! chosen fields, gives each distinct one a pseudonym and replaces every occurrence in
+//! the raw text. The same value always gets the same pseudonym, so output that mentions
+//! a host or address in several places stays consistent.
+//!
+//! Pseudonyms keep the shape of what they replace, so the template still parses the
+//! rewritten text: IPv4 and IPv6 addresses become documentation addresses
+//! (`192.0.2.0/24`, `2001:db8::/32`, keeping any prefix length), hex strings such as MAC
+//! addresses keep their separators and other text keeps its letter, digit and
+//! punctuation pattern. Pseudonyms depend only on the order values are first seen, not
+//! on the values themselves.
+//!
+//! ```
+//! use asyncfsm::{Anonymizer, TextFSM};
+//!
+//! let template = "Value HOST (\\S+)\nValue IP (\\S+)\n\nStart\n  ^${HOST} has ${IP} -> Record\n";
+//! let input = "core1 has 10.1.1.1\nping 10.1.1.1 from core1\n";
+//! let mut anonymizer = Anonymizer::new(["HOST", "IP"]);
+//! anonymizer.learn(&TextFSM::from_string(template)?.parse_records(input, None)?);
+//! let host = anonymizer.pseudonym("core1").unwrap();
+//! assert_eq!(
+//!     anonymizer.rewrite(input),
+//!     format!("{host} has 192.0.2.1\nping 192.0.2.1 from {host}\n")
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{Records, Value};
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Learns pseudonyms for the values of some fields and rewrites text with them.
+#[derive(Debug, Clone, Default)]
+pub struct Anonymizer {
+    fields: Vec<String>,
+    pseudonyms: HashMap<String, String>,
+    /// Pseudonyms handed out, so no two values share one.
+    used: HashSet<String>,
+    ipv4: u32,
+    ipv6: u32,
+    other: u64,
+}
+
+impl Anonymizer {
+    /// An anonymizer for the named values; names match case-insensitively.
+    pub fn new<S: Into<String>>(fields: impl IntoIterator<Item = S>) -> Self {
+        Anonymizer {
+            fields: fields.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Assigns pseudonyms to the chosen fields' values in `records`, in record order.
+    pub fn learn(&mut self, records: &Records) {
+        let mut values = vec![];
+        for record in records.iter() {
+            for field in &self.fields {
+                match record
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(field))
+                {
+                    Some((_, Value::Single(s))) => values.push(s),
+                    Some((_, Value::List(items))) => values.extend(items),
+                    None => {}
+                }
+            }
+        }
+        for value in values {
+            self.add(value);
+        }
+    }
+
+    /// The pseudonym learned for `value`.
+    pub fn pseudonym(&self, value: &str) -> Option<&str> {
+        self.pseudonyms.get(value).map(String::as_str)
+    }
+
+    /// The number of distinct values learned.
+    pub fn len(&self) -> usize {
+        self.pseudonyms.len()
+    }
+
+    /// True if no value has been learned.
+    pub fn is_empty(&self) -> bool {
+        self.pseudonyms.is_empty()
+    }
+
+    fn add(&mut self, value: &str) {
+        if value.trim().is_empty() || self.pseudonyms.contains_key(value) {
+            return;
+        }
+        // `10.1.1.1/24` also hides `10.1.1.1` when it appears on its own.
+        if let Some((addr, len)) = value.split_once('/') {
+            if len.parse::<u8>().is_ok() && is_address(addr) {
+                self.add(addr);
+                let pseudonym = format!("{}/{}", self.pseudonyms[addr], len);
+                self.used.insert(pseudonym.clone());
+                self.pseudonyms.insert(value.to_string(), pseudonym);
+                return;
+            }
+        }
+        let pseudonym = loop {
+            let candidate = self.next_pseudonym(value);
+            if candidate != value && !self.used.contains(&candidate) {
+                break candidate;
+            }
+        };
+        self.used.insert(pseudonym.clone());
+        self.pseudonyms.insert(value.to_string(), pseudonym);
+    }
+
+    fn next_pseudonym(&mut self, value: &str) -> String {
+        if value.parse::<Ipv4Addr>().is_ok() {
+            self.ipv4 += 1;
+            return documentation_ipv4(self.ipv4 - 1).to_string();
+        }
+        if value.parse::<Ipv6Addr>().is_ok() {
+            self.ipv6 += 1;
+            return format!("2001:db8::{:x}", self.ipv6);
+        }
+        self.other += 1;
+        let mut rng = SplitMix(self.other);
+        let hex = value.chars().any(|c| c.is_ascii_digit())
+            && value
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() || matches!(c, ':' | '.' | '-'));
+        value
+            .chars()
+            .map(|c| match c {
+                _ if hex && c.is_ascii_hexdigit() => {
+                    let digit = char::from_digit(rng.below(16), 16).unwrap();
+                    if c.is_ascii_uppercase() {
+                        digit.to_ascii_uppercase()
+                    } else {
+                        digit
+                    }
+                }
+                '0'..='9' => char::from(b'0' + rng.below(10) as u8),
+                'a'..='z' => char::from(b'a' + rng.below(26) as u8),
+                'A'..='Z' => char::from(b'A' + rng.below(26) as u8),
+                other => other,
+            })
+            .collect()
+    }
+
+    /// Replaces every learned value in `text` with its pseudonym. Values only match
+    /// whole: `10.1.1.1` is not replaced inside `10.1.1.10`.
+    pub fn rewrite(&self, text: &str) -> String {
+        if self.pseudonyms.is_empty() {
+            return text.to_string();
+        }
+        let mut values: Vec<&String> = self.pseudonyms.keys().collect();
+        values.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let pattern = values
+            .iter()
+            .map(|v| regex::escape(v))
+            .collect::<Vec<_>>()
+            .join("|");
+        let re = regex::Regex::new(&pattern).expect("escaped literals form a valid regex");
+        let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut at = 0;
+        while let Some(m) = re.find_at(text, at) {
+            let before = text[..m.start()].chars().next_back();
+            let after = text[m.end()..].chars().next();
+            let first = m.as_str().chars().next();
+            let end = m.as_str().chars().next_back();
+            let joined = word(first) && word(before) || word(end) && word(after);
+            // Digits followed by `.N` are part of a longer address or number.
+            let longer = end.is_some_and(|c| c.is_ascii_digit())
+                && after == Some('.')
+                && text[m.end() + 1..].starts_with(|c: char| c.is_ascii_digit());
+            if !joined && !longer {
+                out.push_str(&text[last..m.start()]);
+                out.push_str(&self.pseudonyms[m.as_str()]);
+                last = m.end();
+                at = m.end();
+            } else {
+                at = m.start() + first.map_or(1, char::len_utf8);
+            }
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+}
+
+fn is_address(value: &str) -> bool {
+    value.parse::<Ipv4Addr>().is_ok() || value.parse::<Ipv6Addr>().is_ok()
+}
+
+/// The `n`th address of the three IPv4 documentation networks, then of `10.0.0.0/8`.
+fn documentation_ipv4(n: u32) -> Ipv4Addr {
+    const NETWORKS: [[u8; 3]; 3] = [[192, 0, 2], [198, 51, 100], [203, 0, 113]];
+    match NETWORKS.get((n / 254) as usize) {
+        Some(&[a, b, c]) => Ipv4Addr::new(a, b, c, (n % 254 + 1) as u8),
+        None => Ipv4Addr::from(0x0a00_0000 + (n - 762) + 1),
+    }
+}
+
+/// A small deterministic generator, so pseudonyms are the same on every run.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn below(&mut self, n: u32) -> u32 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) % u64::from(n)) as u32
+    }
+}
@@ -0,0 +1,464 @@
+//! Parsing many captures at once: the hosts of an [`Inventory`], or every file under a
+//! directory with [`parse_dir`].
+
+#[cfg(feature = "clitable")]
+use crate::CliTable;
+use crate::{Records, Result, Template};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A list of hosts and the captured command outputs to parse for each.
+///
+/// ```yaml
+/// hosts:
+///   r1:
+///     platform: cisco_ios
+///     captures:
+///       show version: r1/show_version.txt
+///       show ip interface brief: r1/show_ip_int_brief.txt
+/// ```
+#[cfg(feature = "clitable")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Inventory {
+    /// Hosts keyed by name.
+    pub hosts: BTreeMap<String, InventoryHost>,
+}
+
+/// A single host entry of an `Inventory`.
+#[cfg(feature = "clitable")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct InventoryHost {
+    /// The platform name, as used in the index.
+    pub platform: String,
+    /// Capture files keyed by the command that produced them.
+    #[serde(default)]
+    pub captures: BTreeMap<String, PathBuf>,
+}
+
+/// Parsed results of one host, keyed by command.
+#[cfg(feature = "clitable")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct HostResult {
+    /// The platform the captures were parsed for.
+    pub platform: String,
+    /// Records produced by each command.
+    pub commands: BTreeMap<String, Records>,
+    /// Commands whose capture could not be parsed, with the reason; only filled by
+    /// `Inventory::run_keep_going`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+/// The outcome of parsing one command's output for one host, shaped like the task
+/// results of Python automation frameworks such as Nornir and Netmiko.
+///
+/// `failed` is set, and `error` holds the reason, when the output could not be read or
+/// parsed; `records` is then empty.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ResultEnvelope {
+    pub host: String,
+    pub command: String,
+    pub failed: bool,
+    pub records: Records,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ResultEnvelope {
+    /// Wraps the result of parsing `command`'s output on `host`.
+    pub fn new(
+        host: impl Into<String>,
+        command: impl Into<String>,
+        result: Result<Records>,
+    ) -> Self {
+        let (records, error) = match result {
+            Ok(records) => (records, None),
+            Err(e) => (Records::default(), Some(e.to_string())),
+        };
+        ResultEnvelope {
+            host: host.into(),
+            command: command.into(),
+            failed: error.is_some(),
+            records,
+            error,
+        }
+    }
+}
+
+#[cfg(feature = "clitable")]
+impl Inventory {
+    /// Parses an inventory from YAML.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(content: &str) -> Result<Self> {
+        serde_yaml::from_str(content).map_err(|e| crate::TextFsmError::ParseError(e.to_string()))
+    }
+
+    /// Loads an inventory from a YAML file.
+    #[cfg(feature = "yaml")]
+    pub fn from_file<P: AsRef<Path>>(fname: P) -> Result<Self> {
+        let content = std::fs::read_to_string(fname)?;
+        Self::from_yaml_str(&content)
+    }
+
+    /// Parses every capture of every host with the templates selected by `table`.
+    ///
+    /// Relative capture paths are resolved against `base_dir`.
+    pub fn run(&self, table: &CliTable, base_dir: &Path) -> Result<BTreeMap<String, HostResult>> {
+        self.run_with(table, base_dir, false)
+    }
+
+    /// Like `run`, but a capture that fails to read or parse is recorded in its host's
+    /// `errors` and the remaining captures are still parsed.
+    pub fn run_keep_going(
+        &self,
+        table: &CliTable,
+        base_dir: &Path,
+    ) -> BTreeMap<String, HostResult> {
+        self.run_with(table, base_dir, true)
+            .expect("errors are collected when keep_going is set")
+    }
+
+    /// Parses every capture like `run_keep_going`, giving one envelope per host and
+    /// command, ordered by host and then by command.
+    pub fn run_envelopes(&self, table: &CliTable, base_dir: &Path) -> Vec<ResultEnvelope> {
+        self.hosts
+            .iter()
+            .flat_map(|(name, host)| {
+                host.captures.iter().map(move |(command, capture)| {
+                    let parsed = Self::parse_capture(table, base_dir, host, command, capture);
+                    ResultEnvelope::new(name, command, parsed)
+                })
+            })
+            .collect()
+    }
+
+    fn parse_capture(
+        table: &CliTable,
+        base_dir: &Path,
+        host: &InventoryHost,
+        command: &str,
+        capture: &Path,
+    ) -> Result<Records> {
+        crate::encoding::read_file(base_dir.join(capture), table.options())
+            .and_then(|content| table.parse(&host.platform, command, &content))
+    }
+
+    fn run_with(
+        &self,
+        table: &CliTable,
+        base_dir: &Path,
+        keep_going: bool,
+    ) -> Result<BTreeMap<String, HostResult>> {
+        let mut out = BTreeMap::new();
+        for (name, host) in &self.hosts {
+            let mut result = HostResult {
+                platform: host.platform.clone(),
+                ..Default::default()
+            };
+            for (command, capture) in &host.captures {
+                match Self::parse_capture(table, base_dir, host, command, capture) {
+                    Ok(records) => {
+                        result.commands.insert(command.clone(), records);
+                    }
+                    Err(e) if keep_going => {
+                        result.errors.insert(command.clone(), e.to_string());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            out.insert(name.clone(), result);
+        }
+        Ok(out)
+    }
+}
+
+/// What [`parse_dir`] parses each file with.
+#[derive(Debug, Clone, Copy)]
+pub enum DirParser<'a> {
+    /// The same template for every file.
+    Template(&'a Template),
+    /// The template an index selects for the platform and command detected in each file,
+    /// as `CliTable::detect` finds them.
+    #[cfg(feature = "clitable")]
+    Table(&'a CliTable),
+}
+
+impl<'a> From<&'a Template> for DirParser<'a> {
+    fn from(template: &'a Template) -> Self {
+        DirParser::Template(template)
+    }
+}
+
+#[cfg(feature = "clitable")]
+impl<'a> From<&'a CliTable> for DirParser<'a> {
+    fn from(table: &'a CliTable) -> Self {
+        DirParser::Table(table)
+    }
+}
+
+/// How far a [`parse_dir`] run has got, as passed to its progress callback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirProgress {
+    /// Files parsed or failed so far.
+    pub files_done: usize,
+    /// Files found under the directory.
+    pub files_total: usize,
+    /// Records emitted by the files parsed so far.
+    pub records: usize,
+    /// Files that failed so far.
+    pub errors: usize,
+}
+
+type ProgressCallback = Arc<dyn Fn(&DirProgress) + Send + Sync>;
+
+/// Settings for [`parse_dir`].
+#[derive(Clone, Default)]
+pub struct DirOptions {
+    /// Number of files parsed at a time; 0 uses the available parallelism.
+    pub jobs: usize,
+    /// File extensions to parse, without the dot; empty parses every file.
+    pub extensions: Vec<String>,
+    /// Where results parsed with a template are stored and looked up.
+    #[cfg(feature = "cache")]
+    pub cache: Option<crate::ResultCache>,
+    progress: Option<ProgressCallback>,
+}
+
+impl DirOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses up to `jobs` files at a time.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Only parses files with this extension; may be given several times.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extensions.push(extension.into());
+        self
+    }
+
+    /// Reuses the results in `cache` for files parsed with a template before, and stores
+    /// the others there. Files parsed through an index are not cached.
+    #[cfg(feature = "cache")]
+    pub fn cache(mut self, cache: crate::ResultCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Calls `callback` once before the first file and after every file. It runs on the
+    /// worker threads, one call at a time, with counts that only grow.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&DirProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl fmt::Debug for DirOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("DirOptions");
+        s.field("jobs", &self.jobs)
+            .field("extensions", &self.extensions);
+        #[cfg(feature = "cache")]
+        s.field("cache", &self.cache);
+        s.field("progress", &self.progress.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// The records parsed from one file by [`parse_dir`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DirFile {
+    /// The file's path relative to the directory.
+    pub path: PathBuf,
+    /// The detected platform and command, when parsed through an index.
+    pub platform: Option<String>,
+    pub command: Option<String>,
+    pub records: Records,
+    /// Number of input lines read.
+    pub lines: usize,
+    /// Number of input lines that matched no rule.
+    pub unmatched_lines: usize,
+    /// Number of warnings and validation issues raised while parsing.
+    pub warnings: usize,
+}
+
+/// The outcome of [`parse_dir`]: the files that parsed, in path order, and why the
+/// others did not.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirResult {
+    pub files: Vec<DirFile>,
+    /// Files that could not be read or parsed, keyed by relative path.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<PathBuf, String>,
+}
+
+impl DirResult {
+    /// Total number of records across all files.
+    pub fn records(&self) -> usize {
+        self.files.iter().map(|f| f.records.len()).sum()
+    }
+}
+
+/// Parses every file under `dir`, descending into subdirectories, on several threads.
+///
+/// Hidden files and directories are skipped. A file that fails to read or parse is
+/// recorded in [`DirResult::errors`] and the others are still parsed; only failing to
+/// list the directory tree itself is an error.
+///
+/// ```no_run
+/// use asyncfsm::batch::{parse_dir, DirOptions};
+/// use asyncfsm::Template;
+///
+/// let template = Template::from_file("show_version.textfsm")?;
+/// let options = DirOptions::new().extension("txt").on_progress(|p| {
+///     eprintln!("{}/{} files, {} records", p.files_done, p.files_total, p.records)
+/// });
+/// let result = parse_dir("captures", &template, &options)?;
+/// println!("{} records, {} failures", result.records(), result.errors.len());
+/// # Ok::<(), asyncfsm::TextFsmError>(())
+/// ```
+pub fn parse_dir<'a>(
+    dir: impl AsRef<Path>,
+    parser: impl Into<DirParser<'a>>,
+    options: &DirOptions,
+) -> Result<DirResult> {
+    let dir = dir.as_ref();
+    let parser = parser.into();
+    let mut paths = vec![];
+    collect_files(dir, &options.extensions, &mut paths)?;
+    paths.sort();
+
+    let jobs = match options.jobs {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(paths.len())
+    .max(1);
+    let next = AtomicUsize::new(0);
+    let state = Mutex::new((
+        DirResult::default(),
+        DirProgress {
+            files_total: paths.len(),
+            ..Default::default()
+        },
+    ));
+    if let Some(callback) = &options.progress {
+        callback(&state.lock().unwrap().1);
+    }
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let relative = path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+                    let parsed = parse_one(parser, options, path, relative.clone());
+                    let mut state = state.lock().unwrap();
+                    let (result, progress) = &mut *state;
+                    progress.files_done += 1;
+                    match parsed {
+                        Ok(file) => {
+                            progress.records += file.records.len();
+                            result.files.push(file);
+                        }
+                        Err(e) => {
+                            progress.errors += 1;
+                            result.errors.insert(relative, e.to_string());
+                        }
+                    }
+                    if let Some(callback) = &options.progress {
+                        callback(progress);
+                    }
+                }
+            });
+        }
+    });
+    let (mut result, _) = state.into_inner().unwrap();
+    result.files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(result)
+}
+
+fn parse_one(
+    parser: DirParser<'_>,
+    #[cfg_attr(not(feature = "cache"), allow(unused_variables))] options: &DirOptions,
+    path: &Path,
+    relative: PathBuf,
+) -> Result<DirFile> {
+    match parser {
+        #[cfg(feature = "cache")]
+        DirParser::Template(template) if options.cache.is_some() => {
+            let cache = options.cache.as_ref().expect("checked by the guard");
+            let parsed = cache.parse_file(&mut template.session(), path)?;
+            Ok(DirFile {
+                path: relative,
+                platform: None,
+                command: None,
+                records: parsed.records,
+                lines: parsed.lines,
+                unmatched_lines: parsed.unmatched_lines,
+                warnings: parsed.warnings,
+            })
+        }
+        DirParser::Template(template) => {
+            let mut session = template.session();
+            let records = session.parse_file_records(path, None)?;
+            Ok(DirFile {
+                path: relative,
+                platform: None,
+                command: None,
+                records,
+                lines: session.line_number,
+                unmatched_lines: session.unmatched_lines,
+                warnings: session.warnings.len() + session.validation_issues.len(),
+            })
+        }
+        #[cfg(feature = "clitable")]
+        DirParser::Table(table) => {
+            let content = crate::encoding::read_file(path, table.options())?;
+            let detected = table.detect(&content).ok_or_else(|| {
+                crate::TextFsmError::ParseError("could not detect platform and command".to_string())
+            })?;
+            let found = table.parse_match(&detected.platform, &detected.command, &content)?;
+            Ok(DirFile {
+                path: relative,
+                platform: Some(detected.platform),
+                command: Some(detected.command),
+                records: found.records,
+                lines: found.lines,
+                unmatched_lines: found.unmatched_lines,
+                warnings: found.warnings.len(),
+            })
+        }
+    }
+}
+
+fn collect_files(dir: &Path, extensions: &[String], files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, extensions, files)?;
+        } else if extensions.is_empty()
+            || path
+                .extension()
+                .is_some_and(|ext| extensions.iter().any(|e| ext == e.as_str()))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
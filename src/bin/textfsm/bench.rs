@@ -0,0 +1,216 @@
+//! `bench-compare`: times this crate and Python `textfsm` on the same template and input.
+
+use asyncfsm::{TextFSM, TextFSMParser, TextFsmOptions};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Compiles the template once, then parses the input `iterations` times. Prints the
+/// record count, the compile time and each parse time in seconds.
+const PYTHON_BENCH: &str = r#"
+import sys, time
+import textfsm
+template, data, iterations = sys.argv[1], open(sys.argv[2]).read(), int(sys.argv[3])
+start = time.perf_counter()
+with open(template) as f:
+    fsm = textfsm.TextFSM(f)
+compile_time = time.perf_counter() - start
+times = []
+for _ in range(iterations):
+    fsm.Reset()
+    start = time.perf_counter()
+    rows = fsm.ParseText(data)
+    times.append(time.perf_counter() - start)
+print(len(rows), compile_time, *times)
+"#;
+
+/// The measurements for one implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub implementation: String,
+    pub records: usize,
+    pub compile: Duration,
+    pub parse_mean: Duration,
+    pub parse_min: Duration,
+}
+
+impl Timing {
+    fn new(implementation: &str, records: usize, compile: Duration, parses: &[Duration]) -> Self {
+        Timing {
+            implementation: implementation.to_string(),
+            records,
+            compile,
+            parse_mean: parses.iter().sum::<Duration>() / parses.len().max(1) as u32,
+            parse_min: parses.iter().min().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Times this crate: one compile of `template`, then `iterations` parses after a warm-up.
+pub fn time_rust(
+    template: &str,
+    input: &str,
+    options: &TextFsmOptions,
+    iterations: usize,
+) -> anyhow::Result<Timing> {
+    let start = Instant::now();
+    let parser = TextFSMParser::from_string(template)?;
+    let compile = start.elapsed();
+    let mut fsm = TextFSM::from_parser(parser).with_options(options.clone());
+    let records = fsm.parse_records(input, None)?.len();
+    let mut parses = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        fsm.reset();
+        let start = Instant::now();
+        fsm.parse_records(input, None)?;
+        parses.push(start.elapsed());
+    }
+    Ok(Timing::new("asyncfsm", records, compile, &parses))
+}
+
+/// Times Python `textfsm` with `python`, or returns `None` when it cannot import it.
+pub fn time_python(
+    python: &str,
+    template: &Path,
+    input: &Path,
+    iterations: usize,
+) -> anyhow::Result<Option<Timing>> {
+    let available = Command::new(python)
+        .args(["-c", "import textfsm"])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !available {
+        return Ok(None);
+    }
+    let output = Command::new(python)
+        .args(["-c", PYTHON_BENCH])
+        .arg(template)
+        .arg(input)
+        .arg(iterations.to_string())
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Python textfsm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split_whitespace();
+    let records = fields.next().unwrap_or_default().parse()?;
+    let seconds = fields
+        .map(|f| f.parse::<f64>().map(Duration::from_secs_f64))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some((compile, parses)) = seconds.split_first() else {
+        anyhow::bail!("Python textfsm printed no timings");
+    };
+    Ok(Some(Timing::new(
+        "python textfsm",
+        records,
+        *compile,
+        parses,
+    )))
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3} ms", d.as_secs_f64() * 1000.0)
+}
+
+/// How `ours` compares to `other`, e.g. `3.2x faster`.
+fn relative(ours: Duration, other: Duration) -> String {
+    let ratio = other.as_secs_f64() / ours.as_secs_f64().max(f64::EPSILON);
+    if ratio >= 1.0 {
+        format!("{:.1}x faster", ratio)
+    } else {
+        format!("{:.1}x slower", 1.0 / ratio.max(f64::EPSILON))
+    }
+}
+
+/// Renders the timings as a table, followed by how the first one compares to the second.
+pub fn table(timings: &[Timing]) -> String {
+    let mut rows = vec![[
+        "implementation".to_string(),
+        "records".to_string(),
+        "compile".to_string(),
+        "parse (mean)".to_string(),
+        "parse (min)".to_string(),
+    ]];
+    for t in timings {
+        rows.push([
+            t.implementation.clone(),
+            t.records.to_string(),
+            millis(t.compile),
+            millis(t.parse_mean),
+            millis(t.parse_min),
+        ]);
+    }
+    let widths: Vec<usize> = (0..5)
+        .map(|i| rows.iter().map(|r| r[i].len()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for row in &rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &w))| {
+                if i == 0 {
+                    format!("{:<w$}", cell)
+                } else {
+                    format!("{:>w$}", cell)
+                }
+            })
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    if let [ours, other, ..] = timings {
+        out.push_str(&format!(
+            "\n{} is {} to compile and {} to parse (mean) than {}\n",
+            ours.implementation,
+            relative(ours.compile, other.compile),
+            relative(ours.parse_mean, other.parse_mean),
+            other.implementation
+        ));
+        if ours.records != other.records {
+            out.push_str(&format!(
+                "warning: record counts differ ({} vs {})\n",
+                ours.records, other.records
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_aligns_columns_and_reports_speedup() {
+        let ms = Duration::from_millis;
+        let timings = [
+            Timing::new("asyncfsm", 2, ms(1), &[ms(2), ms(4)]),
+            Timing::new("python textfsm", 2, ms(5), &[ms(30)]),
+        ];
+        let out = table(&timings);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "implementation  records   compile  parse (mean)  parse (min)"
+        );
+        assert_eq!(
+            lines[1],
+            "asyncfsm              2  1.000 ms      3.000 ms     2.000 ms"
+        );
+        assert!(out.contains("5.0x faster to compile and 10.0x faster to parse"));
+        assert!(!out.contains("warning"));
+    }
+
+    #[test]
+    fn relative_speed_reads_both_ways() {
+        let ms = Duration::from_millis;
+        assert_eq!(relative(ms(2), ms(5)), "2.5x faster");
+        assert_eq!(relative(ms(4), ms(1)), "4.0x slower");
+    }
+}
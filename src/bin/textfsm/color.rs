@@ -0,0 +1,213 @@
+//! ANSI syntax coloring for the JSON, YAML, diffs and traces the CLI prints.
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+
+const KEY: &str = "\x1b[34m";
+const STRING: &str = "\x1b[32m";
+const LITERAL: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+const ADDED: &str = "\x1b[32m";
+const REMOVED: &str = "\x1b[31m";
+const CHANGED: &str = "\x1b[33m";
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+fn paint(out: &mut String, color: &str, text: &str) {
+    out.push_str(color);
+    out.push_str(text);
+    out.push_str(RESET);
+}
+
+/// Colors pretty-printed JSON: keys, strings and literals (numbers, booleans, null).
+pub fn json(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut end = text.len();
+                let mut escaped = false;
+                for (i, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => {
+                            end = i + 1;
+                            break;
+                        }
+                        _ => escaped = false,
+                    }
+                }
+                let is_key = text[end..].trim_start().starts_with(':');
+                paint(
+                    &mut out,
+                    if is_key { KEY } else { STRING },
+                    &text[start..end],
+                );
+            }
+            c if c == '-' || c.is_ascii_alphanumeric() => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c == '-' || c == '+' || c == '.' || c.is_ascii_alphanumeric() {
+                        chars.next();
+                        end = i + c.len_utf8();
+                    } else {
+                        break;
+                    }
+                }
+                paint(&mut out, LITERAL, &text[start..end]);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Colors block-style YAML as produced by `serde_yaml`: mapping keys and scalar values.
+pub fn yaml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for line in text.lines() {
+        let mut body = line.trim_start();
+        while let Some(rest) = body.strip_prefix("- ") {
+            body = rest.trim_start();
+        }
+        out.push_str(&line[..line.len() - body.len()]);
+        match split_key(body) {
+            Some((key, value)) => {
+                paint(&mut out, KEY, key);
+                out.push(':');
+                if !value.is_empty() {
+                    out.push(' ');
+                    paint(&mut out, STRING, value);
+                }
+            }
+            None if !body.is_empty() => paint(&mut out, STRING, body),
+            None => {}
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Colors the text of a `tdiff` or `schema-diff`: added lines green, removed lines red
+/// and changed lines yellow.
+pub fn diff(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for line in text.lines() {
+        let body = line.trim_start();
+        let color = match body.split_once(' ') {
+            Some(("+", _)) => Some(ADDED),
+            Some(("-", _)) => Some(REMOVED),
+            Some(("~", _)) => Some(CHANGED),
+            _ => None,
+        };
+        match color {
+            Some(color) => {
+                out.push_str(&line[..line.len() - body.len()]);
+                paint(&mut out, color, body);
+            }
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Marks the byte ranges `spans` of `line`, in order and not overlapping, as captured:
+/// green, or in brackets when not coloring.
+pub fn captures(line: &str, spans: &[(usize, usize)], color: bool) -> String {
+    let mut out = String::with_capacity(line.len() * 2);
+    let mut at = 0;
+    for &(start, end) in spans {
+        out.push_str(&line[at..start]);
+        if color {
+            paint(&mut out, ADDED, &line[start..end]);
+        } else {
+            out.push('[');
+            out.push_str(&line[start..end]);
+            out.push(']');
+        }
+        at = end;
+    }
+    out.push_str(&line[at..]);
+    out
+}
+
+/// Colors a line no rule matched red.
+pub fn unmatched(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 10);
+    paint(&mut out, REMOVED, line);
+    out
+}
+
+/// Splits `key: value` (or a bare `key:`), leaving quoted scalars alone.
+fn split_key(body: &str) -> Option<(&str, &str)> {
+    if body.starts_with(['\'', '"']) {
+        return None;
+    }
+    if let Some(key) = body.strip_suffix(':') {
+        return Some((key, ""));
+    }
+    body.split_once(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_keys_and_values() {
+        let colored = json("{\n  \"A\": \"x\\\"y\",\n  \"B\": 12\n}");
+        assert_eq!(
+            colored,
+            "{\n  \x1b[34m\"A\"\x1b[0m: \x1b[32m\"x\\\"y\"\x1b[0m,\n  \x1b[34m\"B\"\x1b[0m: \x1b[33m12\x1b[0m\n}"
+        );
+    }
+
+    #[test]
+    fn yaml_keys_and_values() {
+        let colored = yaml("- Name: r1\n  List:\n  - a\n");
+        assert_eq!(
+            colored,
+            "- \x1b[34mName\x1b[0m: \x1b[32mr1\x1b[0m\n  \x1b[34mList\x1b[0m:\n  - \x1b[32ma\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn capture_spans() {
+        assert_eq!(
+            captures("Gi1 is up", &[(0, 3), (7, 9)], true),
+            "\x1b[32mGi1\x1b[0m is \x1b[32mup\x1b[0m"
+        );
+        assert_eq!(captures("Gi1 is up", &[(0, 3)], false), "[Gi1] is up");
+    }
+
+    #[test]
+    fn diff_marks() {
+        let colored = diff("values:\n  + A\n  - B\n  ~ C: regex x -> y\n");
+        assert_eq!(
+            colored,
+            "values:\n  \x1b[32m+ A\x1b[0m\n  \x1b[31m- B\x1b[0m\n  \x1b[33m~ C: regex x -> y\x1b[0m\n"
+        );
+    }
+}
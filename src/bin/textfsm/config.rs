@@ -0,0 +1,102 @@
+//! User configuration for the `textfsm` CLI.
+//!
+//! Defaults are read from `$XDG_CONFIG_HOME/asyncfsm/config.toml` (falling back
+//! to `~/.config/asyncfsm/config.toml`), or from the file named by
+//! `ASYNCFSM_CONFIG`. Command-line flags and `ASYNCFSM_*` environment variables
+//! take precedence over anything set here.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Default index file for `auto`, `model` and `batch`
+    pub index: Option<PathBuf>,
+
+    /// Directories searched for templates given by name, after any `--template-dir`
+    pub template_dirs: Vec<PathBuf>,
+
+    /// Default output format, e.g. "json"
+    pub format: Option<String>,
+
+    /// Convert keys to lowercase unless overridden
+    pub lowercase: Option<bool>,
+}
+
+impl Config {
+    /// Location of the config file when none is given explicitly.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("asyncfsm").join("config.toml"))
+    }
+
+    pub fn from_toml_str(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Loads `path`, or the default location when `path` is `None`.
+    ///
+    /// A missing file at the default location yields an empty config; an
+    /// explicitly requested file must exist.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::from_toml_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e)),
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::anyhow!(
+                "Could not read config file {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_keys() {
+        let config = Config::from_toml_str(
+            r#"
+index = "/opt/ntc-templates/templates/index"
+template_dirs = ["/opt/ntc-templates/templates"]
+format = "json"
+lowercase = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.index,
+            Some(PathBuf::from("/opt/ntc-templates/templates/index"))
+        );
+        assert_eq!(
+            config.template_dirs,
+            vec![PathBuf::from("/opt/ntc-templates/templates")]
+        );
+        assert_eq!(config.format.as_deref(), Some("json"));
+        assert_eq!(config.lowercase, Some(true));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::from_toml_str("indx = \"index\"").is_err());
+    }
+
+    #[test]
+    fn explicit_missing_file_is_an_error() {
+        assert!(Config::load(Some(Path::new("/nonexistent/config.toml"))).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Reporting CLI failures, as text or as JSON for automation wrappers.
+
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A plain message, as printed by default
+    #[default]
+    Human,
+    /// One JSON object with a stable `kind` and the error's details
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Describes an error as a JSON object.
+///
+/// `kind` is the [`asyncfsm::TextFsmError::kind`] of the underlying library error,
+/// `io` for other I/O errors and `error` for anything else; `causes` lists the
+/// context the CLI added on the way out.
+#[cfg(feature = "json")]
+pub fn to_json(err: &anyhow::Error) -> serde_json::Value {
+    use asyncfsm::TextFsmError;
+    use serde_json::json;
+
+    let mut report = json!({ "kind": "error", "message": err.to_string() });
+    let causes: Vec<String> = err.chain().skip(1).map(|e| e.to_string()).collect();
+    if !causes.is_empty() {
+        report["causes"] = json!(causes);
+    }
+    let io_kind = |e: &std::io::Error| format!("{:?}", e.kind());
+    if let Some(e) = err.chain().find_map(|e| e.downcast_ref::<TextFsmError>()) {
+        report["kind"] = json!(e.kind());
+        match e {
+            TextFsmError::IoError(io) => report["io_kind"] = json!(io_kind(io)),
+            TextFsmError::TemplateSyntaxError {
+                file,
+                line,
+                column,
+                message,
+            } => {
+                report["file"] = json!(file);
+                report["line"] = json!(line);
+                report["column"] = json!(column);
+                report["expected"] = json!(message);
+            }
+            TextFsmError::TemplateErrors { file, diagnostics } => {
+                report["file"] = json!(file);
+                report["diagnostics"] = json!(diagnostics);
+            }
+            TextFsmError::TemplateNotFound(name) => report["template"] = json!(name),
+            TextFsmError::TemplateErrorAction {
+                state,
+                line_number,
+                line,
+                span,
+                ..
+            } => {
+                report["state"] = json!(state);
+                report["line"] = json!(line_number);
+                report["input"] = json!(line);
+                report["template_line"] = json!(span.line);
+            }
+            TextFsmError::ActionError {
+                action,
+                line_number,
+                span,
+                ..
+            } => {
+                report["action"] = json!(action);
+                report["line"] = json!(line_number);
+                report["template_line"] = json!(span.line);
+            }
+            TextFsmError::ValidationError(issue) => report["issue"] = json!(issue),
+            _ => {}
+        }
+    } else if let Some(io) = err.chain().find_map(|e| e.downcast_ref::<std::io::Error>()) {
+        report["kind"] = json!("io");
+        report["io_kind"] = json!(io_kind(io));
+    }
+    report
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_errors_carry_their_position() {
+        let err =
+            asyncfsm::TextFSMParser::from_string("Value X (\\S+)\nValue Y\n\nStart\n  ^${X}\n")
+                .err()
+                .unwrap();
+        let report = to_json(&anyhow::Error::from(err).context("compiling t.textfsm"));
+        assert_eq!(report["kind"], "template_syntax");
+        assert_eq!(report["message"], "compiling t.textfsm");
+        assert_eq!(report["line"], 2);
+        assert!(report["column"].as_u64().is_some());
+        assert_eq!(report["causes"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn other_errors() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        let report = to_json(&anyhow::Error::from(io));
+        assert_eq!(report["kind"], "io");
+        assert_eq!(report["io_kind"], "NotFound");
+        assert_eq!(to_json(&anyhow::anyhow!("nope"))["kind"], "error");
+    }
+}
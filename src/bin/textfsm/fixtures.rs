@@ -0,0 +1,255 @@
+//! Fixtures: captured command output next to the records a template should produce.
+//!
+//! A fixture is a `NAME.raw` file with `NAME.yml`, `NAME.yaml` or `NAME.json` beside it,
+//! laid out as in ntc-templates: either a list of records or a mapping whose
+//! `parsed_sample` key holds that list. Field names compare case-insensitively, scalars
+//! compare as text, and an empty string or list counts the same as a missing field.
+
+use asyncfsm::{Records, TextFSM, TextFSMParser, TextFsmOptions};
+use serde::Serialize;
+use serde_yaml::Value as Yaml;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One record, normalized for comparison.
+pub type Row = BTreeMap<String, Yaml>;
+
+/// The bookkeeping fields the engine adds to serialized records.
+const ENGINE_FIELDS: &[&str] = &["record_key", "_raw", "_index", "_id"];
+
+/// A loaded fixture.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// The input file's path relative to the fixture directory, without `.raw`.
+    pub name: String,
+    pub input: String,
+    pub expected: Vec<Row>,
+}
+
+impl Fixture {
+    /// Parses the input with `parser`; an error during parsing counts as a mismatch.
+    pub fn passes(&self, parser: &Arc<TextFSMParser>, options: &TextFsmOptions) -> bool {
+        let mut fsm = TextFSM::from_parser(Arc::clone(parser)).with_options(options.clone());
+        fsm.parse_records(&self.input, None)
+            .is_ok_and(|records| rows(&records) == self.expected)
+    }
+}
+
+/// Finds and loads the fixtures under `dir`, sorted by name.
+pub fn discover(dir: &Path, options: &TextFsmOptions) -> anyhow::Result<Vec<Fixture>> {
+    let mut inputs = vec![];
+    collect_inputs(dir, &mut inputs)?;
+    inputs.sort();
+    let mut fixtures = vec![];
+    for input in inputs {
+        let Some(expected) = ["yml", "yaml", "json"]
+            .iter()
+            .map(|ext| input.with_extension(ext))
+            .find(|p| p.exists())
+        else {
+            continue;
+        };
+        let name = input.strip_prefix(dir).unwrap_or(&input).with_extension("");
+        fixtures.push(Fixture {
+            name: name.to_string_lossy().into_owned(),
+            input: asyncfsm::encoding::read_file(&input, options)?,
+            expected: load_expected(&expected)?,
+        });
+    }
+    Ok(fixtures)
+}
+
+fn collect_inputs(dir: &Path, inputs: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_inputs(&path, inputs)?;
+        } else if path.extension().is_some_and(|ext| ext == "raw") {
+            inputs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// One way the parsed records differ from the expected ones. Records are compared by
+/// position; `record` is 0-based.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Difference {
+    /// An expected record past the end of the parsed ones.
+    Missing { record: usize, expected: Row },
+    /// A parsed record past the end of the expected ones.
+    Unexpected { record: usize, actual: Row },
+    /// A field whose value differs; `None` means the field is absent or empty.
+    Field {
+        record: usize,
+        field: String,
+        expected: Option<Yaml>,
+        actual: Option<Yaml>,
+    },
+}
+
+/// The result of checking parsed records against an expected file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    pub passed: bool,
+    pub expected_records: usize,
+    pub actual_records: usize,
+    pub ignored_fields: Vec<String>,
+    pub differences: Vec<Difference>,
+}
+
+impl Verification {
+    pub fn new(expected: &[Row], records: &Records, ignore: Vec<String>) -> Self {
+        let actual = rows(records);
+        let differences = compare(expected, &actual, &ignore);
+        Verification {
+            passed: differences.is_empty(),
+            expected_records: expected.len(),
+            actual_records: actual.len(),
+            ignored_fields: ignore,
+            differences,
+        }
+    }
+}
+
+/// Compares `actual` with `expected`, skipping the fields named in `ignore` (in any case).
+pub fn compare(expected: &[Row], actual: &[Row], ignore: &[String]) -> Vec<Difference> {
+    let ignored = |field: &String| ignore.iter().any(|i| i.eq_ignore_ascii_case(field));
+    let strip = |row: &Row| -> Row {
+        row.iter()
+            .filter(|(field, _)| !ignored(field))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    };
+    let mut differences = vec![];
+    for (record, (want, got)) in expected.iter().zip(actual).enumerate() {
+        let fields: BTreeSet<&String> = want.keys().chain(got.keys()).collect();
+        for field in fields.into_iter().filter(|f| !ignored(f)) {
+            let (expected, actual) = (want.get(field), got.get(field));
+            if expected != actual {
+                differences.push(Difference::Field {
+                    record,
+                    field: field.clone(),
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+        }
+    }
+    for (record, row) in expected.iter().enumerate().skip(actual.len()) {
+        differences.push(Difference::Missing {
+            record,
+            expected: strip(row),
+        });
+    }
+    for (record, row) in actual.iter().enumerate().skip(expected.len()) {
+        differences.push(Difference::Unexpected {
+            record,
+            actual: strip(row),
+        });
+    }
+    differences
+}
+
+/// Reads the expected records from a YAML or JSON file.
+pub fn load_expected(path: &Path) -> anyhow::Result<Vec<Row>> {
+    let value: Yaml = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+    expected_rows(value).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// The expected records in an already loaded document, laid out as in an expected file.
+pub fn expected_rows(value: Yaml) -> anyhow::Result<Vec<Row>> {
+    let records = match value {
+        Yaml::Mapping(mut map) => map
+            .remove("parsed_sample")
+            .ok_or_else(|| anyhow::anyhow!("no `parsed_sample` list"))?,
+        other => other,
+    };
+    match records {
+        Yaml::Sequence(items) => Ok(items.into_iter().map(normalize).collect()),
+        _ => anyhow::bail!("expected a list of records"),
+    }
+}
+
+/// The parsed records, normalized like [`load_expected`]'s.
+pub fn rows(records: &Records) -> Vec<Row> {
+    match serde_yaml::to_value(records) {
+        Ok(Yaml::Sequence(items)) => items.into_iter().map(normalize).collect(),
+        _ => vec![],
+    }
+}
+
+fn normalize(record: Yaml) -> Row {
+    let Yaml::Mapping(map) = record else {
+        return Row::new();
+    };
+    map.into_iter()
+        .filter_map(|(name, value)| {
+            let name = scalar(name)?.to_lowercase();
+            if ENGINE_FIELDS.contains(&name.as_str()) {
+                return None;
+            }
+            let value = match value {
+                Yaml::Sequence(items) if items.is_empty() => return None,
+                Yaml::Sequence(items) => Yaml::Sequence(
+                    items
+                        .into_iter()
+                        .filter_map(scalar)
+                        .map(Yaml::String)
+                        .collect(),
+                ),
+                other => Yaml::String(scalar(other).filter(|s| !s.is_empty())?),
+            };
+            Some((name, value))
+        })
+        .collect()
+}
+
+fn scalar(value: Yaml) -> Option<String> {
+    match value {
+        Yaml::String(s) => Some(s),
+        Yaml::Number(n) => Some(n.to_string()),
+        Yaml::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_reports_fields_and_extra_records() {
+        let row = |text: &str| normalize(serde_yaml::from_str(text).unwrap());
+        let expected = [row("{a: 1, uptime: 5d}"), row("{a: 2}")];
+        let actual = [row("{a: 1, uptime: 6d}"), row("{a: 3}"), row("{a: 4}")];
+        let differences = compare(&expected, &actual, &["UPTIME".to_string()]);
+        assert_eq!(
+            differences,
+            vec![
+                Difference::Field {
+                    record: 1,
+                    field: "a".into(),
+                    expected: Some(Yaml::String("2".into())),
+                    actual: Some(Yaml::String("3".into())),
+                },
+                Difference::Unexpected {
+                    record: 2,
+                    actual: row("{a: 4}"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn expected_records_normalize_like_parsed_ones() {
+        let rows: Vec<Row> = ["{VLAN: 10, Name: ''}", "{vlan: '10', record_key: null}"]
+            .into_iter()
+            .map(|text| normalize(serde_yaml::from_str(text).unwrap()))
+            .collect();
+        assert_eq!(rows[0], rows[1]);
+        assert_eq!(rows[0]["vlan"], Yaml::String("10".into()));
+    }
+}
@@ -0,0 +1,1875 @@
+#[cfg(all(feature = "clitable", feature = "yaml"))]
+use asyncfsm::Inventory;
+use asyncfsm::{
+    Anonymizer, DataRecordConversion, Nesting, Records, SchemaDiff, Template, TemplateAst,
+    TemplateDiff, TemplateResolver, TemplateSummary, TextFSM, TextFsmOptions,
+    WhitespaceNormalization,
+};
+#[cfg(feature = "clitable")]
+use asyncfsm::{CliTable, DeviceFacts, DeviceModel};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+mod bench;
+mod color;
+mod config;
+mod errors;
+#[cfg(feature = "yaml")]
+mod fixtures;
+#[cfg(feature = "yaml")]
+mod mutate;
+mod progress;
+#[cfg(all(feature = "bundle", feature = "yaml"))]
+mod replay;
+mod sources;
+
+use color::ColorChoice;
+use config::Config;
+use errors::ErrorFormat;
+use sources::SourceResult;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Output format [default: yaml]
+    #[arg(short, long, value_enum, global = true, env = "ASYNCFSM_FORMAT")]
+    format: Option<OutputFormat>,
+
+    /// Convert keys to lowercase (parse and auto)
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "ASYNCFSM_LOWERCASE",
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
+    lowercase: Option<bool>,
+
+    /// Color the output
+    #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Render the records through a Handlebars template instead of printing them (parse and auto)
+    #[cfg(feature = "report")]
+    #[arg(long, value_name = "TEMPLATE", global = true)]
+    render: Option<PathBuf>,
+
+    /// Run each record through a Rhai script that can edit, add or drop fields (parse and auto)
+    #[cfg(feature = "script")]
+    #[arg(long, value_name = "SCRIPT", global = true)]
+    script: Option<PathBuf>,
+
+    /// Exit with an error when no records were parsed (parse and auto)
+    #[arg(long, global = true)]
+    fail_on_empty: bool,
+
+    /// Include the input lines each record was parsed from, as `_raw` (parse and auto)
+    #[arg(long, global = true)]
+    with_raw: bool,
+
+    /// Number each record as `_index` and add a content hash as `_id`, for upserts
+    /// downstream (parse and auto)
+    #[arg(long, global = true)]
+    with_ids: bool,
+
+    /// Write byte-identical output for identical input: records in the order of the lines
+    /// they were parsed from, fields in template order and no times (parse and auto)
+    #[arg(long, global = true)]
+    deterministic: bool,
+
+    /// Stop parsing once this many records are kept, to preview a large input (parse
+    /// and auto)
+    #[arg(long, value_name = "N", global = true)]
+    limit: Option<usize>,
+
+    /// Drop the first N records parsed from each input (parse and auto)
+    #[arg(long, value_name = "N", global = true)]
+    skip: Option<usize>,
+
+    /// Keep about this share of the records, from 0 to 1, the same ones on every run
+    /// (parse and auto)
+    #[arg(long, value_name = "RATE", global = true, value_parser = parse_rate)]
+    sample: Option<f64>,
+
+    /// Write the records with a named writer instead of `--format`: json, yaml, csv,
+    /// ndjson, sqlite (with the `sqlite` feature), text, html, xml, jsonl or one added by
+    /// a plugin; `NAME:PATH` writes to a file instead of stdout (parse and auto)
+    #[arg(long, value_name = "NAME", global = true)]
+    writer: Option<String>,
+
+    /// Write the records to one file per value of this field, or of the `source`,
+    /// `platform`, `command` or `template` they were parsed from, in --out-dir (parse and
+    /// auto); as --format json or yaml, or with --writer
+    #[arg(long, value_name = "FIELD", global = true, requires = "out_dir")]
+    partition_by: Option<String>,
+
+    /// Directory for the files of --partition-by, created if needed
+    #[arg(long, value_name = "DIR", global = true, requires = "partition_by")]
+    out_dir: Option<PathBuf>,
+
+    /// Compress the files of `--writer NAME:PATH` and --partition-by: gzip or zstd,
+    /// adding `.gz` or `.zst` to their names (parse and auto)
+    #[cfg(feature = "compress")]
+    #[arg(long, value_name = "CODEC", global = true, value_parser = parse_compression)]
+    compress: Option<asyncfsm::compress::Compression>,
+
+    /// Shared library adding output writers (repeatable); it must be built with the same
+    /// compiler and asyncfsm version as this binary
+    #[cfg(feature = "plugins")]
+    #[arg(long, value_name = "LIBRARY", global = true)]
+    plugin: Vec<PathBuf>,
+
+    /// Print only the values a path such as `records[*].INTERFACE` selects, one per
+    /// line when they are plain strings (parse and auto)
+    #[arg(long, value_name = "PATH", global = true)]
+    get: Option<String>,
+
+    /// Strip carriage returns and trailing whitespace and expand tabs before matching,
+    /// for captures taken on Windows or with tab-aligned columns (parse and auto)
+    #[arg(long, global = true)]
+    normalize_whitespace: bool,
+
+    /// Translate the records with a mapping file, or a built-in mapping such as
+    /// `openconfig-interfaces`, and print the resulting document (parse and auto);
+    /// with `--format netbox`, the mapping to the payloads
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    #[arg(long, global = true, value_name = "FILE|NAME")]
+    mapping: Option<String>,
+
+    /// Device the NetBox payloads belong to (with `--format netbox`)
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    #[arg(long, global = true, value_name = "NAME")]
+    netbox_device: Option<String>,
+
+    /// Encoding of the input files, e.g. `latin1` or `utf-16le`; without it UTF-8 is
+    /// assumed and other input decoded as windows-1252 (parse and auto)
+    #[cfg(feature = "encoding_rs")]
+    #[arg(long, global = true)]
+    encoding: Option<String>,
+
+    /// Report a failing input or capture in the output and carry on with the rest
+    /// (parse, auto and batch); the exit code still reflects the failures
+    #[arg(long, global = true)]
+    keep_going: bool,
+
+    /// How to report a failure on stderr; `json` prints one object with a stable `kind`
+    #[arg(long, value_enum, global = true, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    /// Directory to search for templates given by name (repeatable)
+    #[arg(long = "template-dir", global = true, env = "ASYNCFSM_TEMPLATE_DIR")]
+    template_dirs: Vec<PathBuf>,
+
+    /// Directory to keep parse results in, reused while the template and input are
+    /// unchanged (parse)
+    #[cfg(feature = "cache")]
+    #[arg(long, global = true, env = "ASYNCFSM_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Config file to read defaults from [default: ~/.config/asyncfsm/config.toml]
+    #[arg(long, global = true, env = "ASYNCFSM_CONFIG")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum OutputFormat {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// JSON as Ansible's `cli_parse` expects it: `{"parsed": [...]}` with lowercase keys
+    #[cfg(feature = "json")]
+    Ansible,
+    /// NetBox API payloads for the interfaces, IP addresses or inventory items parsed
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    Netbox,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Parse a file using a specific TextFSM template
+    Parse {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Input data file (repeatable); reads standard input when omitted
+        ///
+        /// With several inputs the output lists each one with its records, stats and errors.
+        /// On a terminal, the output is pasted at a prompt and ended with a `.` line.
+        #[arg(short, long)]
+        input: Vec<PathBuf>,
+
+        /// Read standard input to its end even when it is a terminal, without the prompt
+        #[arg(long, conflicts_with_all = ["input", "input_dir"])]
+        stdin: bool,
+
+        /// End standard input at the first line matching this regex, e.g. the device
+        /// prompt `^\S+[#>]`, so pasted output needs no end marker; a match on the first
+        /// line, the prompt with the command, is skipped
+        #[arg(long, value_name = "REGEX", value_parser = parse_regex, conflicts_with_all = ["input", "input_dir"])]
+        until: Option<regex::Regex>,
+
+        /// Parse every file under this directory, several at a time, instead of --input
+        #[arg(long, conflicts_with_all = ["input", "carry_over"])]
+        input_dir: Option<PathBuf>,
+
+        /// Number of files parsed at a time with --input-dir [default: one per CPU]
+        #[arg(
+            long,
+            default_value_t = 0,
+            hide_default_value = true,
+            requires = "input_dir"
+        )]
+        jobs: usize,
+
+        /// Show a progress bar on stderr while --input-dir is parsed
+        #[arg(long, requires = "input_dir")]
+        progress: bool,
+
+        /// State to begin parsing in (defaults to Start)
+        #[arg(long)]
+        start_state: Option<String>,
+
+        /// Exit with an error when more than this share of input lines matched no rule, e.g. `20%`
+        #[arg(long, value_name = "PERCENT", value_parser = parse_percent)]
+        fail_on_unmatched_threshold: Option<f64>,
+
+        /// Parse the inputs as one continuous input, e.g. a log rotated into several files,
+        /// so records and Filldown values carry over from one file to the next
+        #[arg(long)]
+        carry_over: bool,
+
+        /// Emit a record for every matching line, for single-line logs such as syslog;
+        /// the template must have only a Start state
+        #[arg(long)]
+        line_mode: bool,
+
+        /// Read this log file as it grows, like `tail -f`, and write each record as soon
+        /// as it is parsed, with --writer (jsonl by default)
+        #[cfg(feature = "follow")]
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["input", "input_dir", "carry_over"])]
+        follow: Option<PathBuf>,
+
+        /// Read the new journal entries of a systemd unit and write each record as soon
+        /// as it is parsed, like --follow
+        #[cfg(feature = "follow")]
+        #[arg(
+            long,
+            value_name = "UNIT",
+            conflicts_with_all = ["input", "input_dir", "carry_over", "follow"]
+        )]
+        journal: Option<String>,
+
+        /// Nest records under their parents, e.g. `VRF -> NEIGHBORS`; without a value,
+        /// uses the levels in the template's `# Nest:` header
+        #[arg(long, value_name = "LEVELS", num_args = 0..=1, default_missing_value = "")]
+        nest: Option<String>,
+    },
+    /// Use CLI Table (ntc-templates index) to parse data
+    #[cfg(feature = "clitable")]
+    Auto {
+        /// Path to the index file (e.g. ntc_templates/templates/index)
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+
+        /// Platform name (e.g. cisco_ios)
+        #[arg(short, long, required_unless_present = "guess")]
+        platform: Option<String>,
+
+        /// Command executed (e.g. "show version"); when omitted, every template of the
+        /// platform is tried and the best fit is used
+        #[arg(short, long)]
+        command: Option<String>,
+
+        /// Guess platform and command from the input itself (required without --platform)
+        #[arg(long)]
+        guess: bool,
+
+        /// Input data file (repeatable)
+        ///
+        /// With several inputs the output lists each one with its records, stats and errors.
+        #[arg(short, long, required = true)]
+        input: Vec<PathBuf>,
+    },
+    /// Parse a directory of command outputs for one device into a single document
+    ///
+    /// Each file is named after the command it holds, e.g. `show_version.txt`.
+    #[cfg(feature = "clitable")]
+    Model {
+        /// Path to the index file (e.g. ntc_templates/templates/index)
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+
+        /// Platform name; detected from each file when omitted
+        #[arg(short, long)]
+        platform: Option<String>,
+
+        /// Directory holding the device's command outputs
+        #[arg(long)]
+        input_dir: PathBuf,
+    },
+    /// Summarize a directory of command outputs for one device as normalized facts:
+    /// hostname, model, serial, version and interfaces
+    ///
+    /// Each file is named after the command it holds, e.g. `show_version.txt`.
+    #[cfg(feature = "clitable")]
+    Facts {
+        /// Path to the index file (e.g. ntc_templates/templates/index)
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+
+        /// Platform name; detected from each file when omitted
+        #[arg(short, long)]
+        platform: Option<String>,
+
+        /// Directory holding the device's command outputs
+        #[arg(long)]
+        input_dir: PathBuf,
+    },
+    /// Parse the capture files listed in an inventory, producing results per host
+    #[cfg(all(feature = "clitable", feature = "yaml"))]
+    Batch {
+        /// Path to the YAML inventory listing hosts and their capture files
+        #[arg(long)]
+        inventory: PathBuf,
+
+        /// Path to the index file (e.g. ntc_templates/templates/index)
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+    },
+    /// Generate a CLI table index from the templates in a directory
+    ///
+    /// Platforms and commands come from `# Platform:` / `# Command:` headers, or from
+    /// file names such as `cisco_ios_show_version.textfsm`.
+    #[cfg(feature = "clitable")]
+    IndexGen {
+        /// Directory holding the templates
+        #[arg(long)]
+        templates_dir: PathBuf,
+
+        /// File to write the index to [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List and inspect the templates available from indexes and Git repositories
+    #[cfg(feature = "registry")]
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+    /// Check the templates of an index against this engine
+    #[cfg(feature = "clitable")]
+    Templates {
+        #[command(subcommand)]
+        action: TemplatesAction,
+    },
+    /// Time this crate and Python textfsm (when installed) on the same template and input
+    BenchCompare {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Input data file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Number of timed parses per implementation
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+
+        /// Python interpreter with textfsm installed
+        #[arg(long, env = "ASYNCFSM_PYTHON", default_value = "python3")]
+        python: String,
+    },
+    /// Print a template's values and states, and the states each state's rules move to
+    ///
+    /// With `--format` the whole parsed template is emitted as data instead.
+    Inspect {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+    },
+    /// Rewrite templates in a canonical layout: sorted and aligned value options, rules
+    /// indented by two spaces and single blank lines between states
+    ///
+    /// Prints the formatted template unless `--write` or `--check` is given.
+    Fmt {
+        /// Template files to format
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Rewrite the files in place
+        #[arg(short, long, conflicts_with = "check")]
+        write: bool,
+
+        /// List the files that are not formatted, and fail if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Remove the rules of a template that can never run and merge duplicated ones
+    ///
+    /// Prints the optimized template, and what was changed or looks suspicious on
+    /// stderr. With `--format` both are emitted as data.
+    Optimize {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// File to write the optimized template to [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Report rules anchored loosely enough to match lines they were not written for
+    ///
+    /// Fails when there are any. With `--fix` the ones with a safe fix are rewritten in
+    /// the file, and only the others are reported.
+    Lint {
+        /// TextFSM template file
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Apply the safe fixes to the file
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Serve the Language Server Protocol on stdin and stdout, for editors
+    ///
+    /// Publishes diagnostics and lint issues, and answers hover, go-to-definition and
+    /// document symbol requests for the templates the editor opens.
+    #[cfg(feature = "lsp")]
+    Lsp,
+    /// Compare two templates by values, states and rules rather than by lines
+    ///
+    /// Prints a report by default; with `--format` the differences are emitted as data.
+    Tdiff {
+        /// The template before the change, as a file or a template name
+        old: PathBuf,
+
+        /// The template after the change, as a file or a template name
+        new: PathBuf,
+    },
+    /// Compare the fields of the records two templates produce
+    ///
+    /// Reports fields added, removed, renamed (a removed and an added field with the same
+    /// regex) or changed between single values and lists, which break code reading the
+    /// records. With `--format` the differences are emitted as data.
+    SchemaDiff {
+        /// The template before the change, as a file or a template name
+        old: PathBuf,
+
+        /// The template after the change, as a file or a template name
+        new: PathBuf,
+
+        /// Exit with an error if a field was removed, renamed or changed type
+        #[arg(long)]
+        fail_on_breaking: bool,
+    },
+    /// Show which rule matched each input line and where its values were captured
+    ///
+    /// Prints every input line after the rules that matched it, as `STATE#RULE`, with
+    /// the captured text in green (in brackets without color). Lines no rule matched are
+    /// marked `-` and printed in red. With `--format` the matches are emitted as data.
+    Trace {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Device output to trace; standard input if not given
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+    /// Replace the values of some fields in device output with stable pseudonyms
+    ///
+    /// The template finds the values; every occurrence in the text is then rewritten, so
+    /// the result can be shared as sample data or a fixture.
+    Anonymize {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Input data file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Values whose captures are rewritten, such as `IP,HOSTNAME,SERIAL`
+        #[arg(long, required = true, value_delimiter = ',')]
+        fields: Vec<String>,
+
+        /// File to write the rewritten text to [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check parse output against a file of expected records
+    ///
+    /// Emits the differences as data and fails when there are any. The expected file is
+    /// YAML or JSON, a list of records or one under `parsed_sample` as in ntc-templates.
+    #[cfg(feature = "yaml")]
+    Verify {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Input data file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// File with the expected records
+        #[arg(short, long)]
+        expected: PathBuf,
+
+        /// Fields left out of the comparison, such as `UPTIME,LAST_INPUT`
+        #[arg(long, value_delimiter = ',')]
+        ignore_fields: Vec<String>,
+    },
+    /// Remove each rule in turn and report the rules no fixture depends on
+    ///
+    /// Fixtures are `NAME.raw` inputs with the expected records in `NAME.yml`, `NAME.yaml`
+    /// or `NAME.json` beside them. Fails when a rule survives, i.e. every fixture still
+    /// passes without it.
+    #[cfg(feature = "yaml")]
+    Mutate {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Directory searched recursively for fixtures
+        #[arg(long)]
+        fixtures: PathBuf,
+    },
+    /// Package a template, an input and the options into one file for a bug report
+    ///
+    /// The bundle also holds the output of this version and, with --expected, the
+    /// records that should have been produced. `replay` runs it again.
+    #[cfg(all(feature = "bundle", feature = "yaml"))]
+    Bundle {
+        /// TextFSM template file, or a template name looked up in the template directories
+        #[arg(short, long)]
+        template: PathBuf,
+
+        /// Input data file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// File with the expected records, YAML or JSON
+        #[arg(short, long)]
+        expected: Option<PathBuf>,
+
+        /// File to write the bundle to, e.g. `case.tar.zst`
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Run a bundle made by `bundle` and compare the output with the one it recorded
+    ///
+    /// Fails when the output differs from the expected records or, without them, from
+    /// the recorded output.
+    #[cfg(all(feature = "bundle", feature = "yaml"))]
+    Replay {
+        /// The bundle file
+        bundle: PathBuf,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+
+        /// Index file whose platforms and commands are offered as completions for `auto`
+        #[cfg(feature = "clitable")]
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+    },
+}
+
+#[cfg(feature = "registry")]
+#[derive(Subcommand)]
+enum RegistryAction {
+    /// List every template, highest precedence first
+    List {
+        #[command(flatten)]
+        sources: RegistrySources,
+    },
+    /// Show the template used for a platform and command
+    Info {
+        #[command(flatten)]
+        sources: RegistrySources,
+
+        /// Platform name (e.g. cisco_ios)
+        #[arg(short, long)]
+        platform: String,
+
+        /// Command executed (e.g. "show version")
+        #[arg(short, long)]
+        command: String,
+
+        /// Only consider templates of this version
+        #[arg(long)]
+        template_version: Option<String>,
+    },
+}
+
+#[cfg(feature = "clitable")]
+#[derive(Subcommand)]
+enum TemplatesAction {
+    /// Compile every template of an index and report the platforms covered and the
+    /// templates that fail
+    ///
+    /// Prints a summary by default; with `--format` the report is emitted as data, to be
+    /// kept and compared across ntc-templates releases.
+    Audit {
+        /// Path to the index file (e.g. ntc_templates/templates/index)
+        #[arg(long, env = "ASYNCFSM_INDEX")]
+        index: Option<PathBuf>,
+    },
+}
+
+/// Template sources, in precedence order: indexes first, then Git repositories.
+#[cfg(feature = "registry")]
+#[derive(clap::Args)]
+struct RegistrySources {
+    /// Index file to include (repeatable) [default: the configured index]
+    #[arg(long)]
+    index: Vec<PathBuf>,
+
+    /// Git repository to include, as URL#REF (repeatable)
+    #[arg(long, value_name = "URL#REF")]
+    git: Vec<String>,
+}
+
+#[cfg(feature = "registry")]
+impl RegistrySources {
+    fn build(self, config: &Config) -> anyhow::Result<asyncfsm::TemplateRegistry> {
+        let mut registry = asyncfsm::TemplateRegistry::new();
+        let mut indexes = self.index;
+        if indexes.is_empty() && self.git.is_empty() {
+            indexes.extend(config.index.clone());
+        }
+        for index in indexes {
+            registry.add_index(index)?;
+        }
+        for spec in &self.git {
+            let Some((url, reference)) = spec.rsplit_once('#') else {
+                anyhow::bail!("--git expects URL#REF, got `{}`", spec);
+            };
+            registry.add_git(&asyncfsm::GitSource::new(url, reference))?;
+        }
+        Ok(registry)
+    }
+}
+
+/// What `registry info` prints about a template.
+#[cfg(feature = "registry")]
+#[derive(serde::Serialize)]
+struct TemplateInfo<'a> {
+    #[serde(flatten)]
+    entry: &'a asyncfsm::RegistryEntry,
+    path: Option<&'a std::path::Path>,
+    values: Vec<String>,
+    metadata: std::collections::BTreeMap<String, String>,
+}
+
+/// Parses a share from 0 to 1, such as `0.1`.
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let value: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", s))?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("`{}` is not between 0 and 1", s))
+    }
+}
+
+/// Parses a percentage such as `20%` or `12.5` into a number between 0 and 100.
+fn parse_percent(s: &str) -> Result<f64, String> {
+    let value: f64 = s
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("`{}` is not a percentage", s))?;
+    if (0.0..=100.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("`{}` is not between 0% and 100%", s))
+    }
+}
+
+/// Offers the platforms and commands of `table` as possible values of `auto`.
+#[cfg(feature = "clitable")]
+fn complete_from_index(cmd: clap::Command, table: &CliTable) -> clap::Command {
+    use clap::builder::PossibleValuesParser;
+
+    let platforms: Vec<String> = table.platforms().iter().map(|p| p.to_string()).collect();
+    let mut commands: Vec<String> = platforms
+        .iter()
+        .flat_map(|p| table.commands_for_platform(p))
+        .collect();
+    commands.sort();
+    commands.dedup();
+    cmd.mut_subcommand("auto", |auto| {
+        auto.mut_arg("platform", |a| {
+            a.value_parser(PossibleValuesParser::new(platforms))
+        })
+        .mut_arg("command", |a| {
+            a.value_parser(PossibleValuesParser::new(commands))
+        })
+    })
+}
+
+fn emit<T: serde::Serialize>(format: OutputFormat, color: bool, value: &T) -> anyhow::Result<()> {
+    match format {
+        #[cfg(feature = "json")]
+        OutputFormat::Json => {
+            let text = serde_json::to_string_pretty(value)?;
+            println!("{}", if color { color::json(&text) } else { text });
+        }
+        #[cfg(feature = "yaml")]
+        OutputFormat::Yaml => {
+            let text = serde_yaml::to_string(value)?;
+            println!("{}", if color { color::yaml(&text) } else { text });
+        }
+        #[cfg(all(feature = "json", feature = "yaml"))]
+        OutputFormat::Netbox => {
+            let text = serde_json::to_string_pretty(value)?;
+            println!("{}", if color { color::json(&text) } else { text });
+        }
+        #[cfg(feature = "json")]
+        OutputFormat::Ansible => {
+            let parsed = serde_json::json!({ "parsed": serde_json::to_value(value)? });
+            let text = serde_json::to_string_pretty(&parsed)?;
+            println!("{}", if color { color::json(&text) } else { text });
+        }
+    }
+    Ok(())
+}
+
+/// Prints `records` like `emit`, writing JSON and YAML through the library's sinks so
+/// the CLI prints records the way `record_sink` writes them.
+fn emit_records(format: OutputFormat, color: bool, records: &Records) -> anyhow::Result<()> {
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    use asyncfsm::RecordSink;
+
+    let mut out = vec![];
+    let paint: fn(&str) -> String = match format {
+        #[cfg(feature = "json")]
+        OutputFormat::Json => {
+            asyncfsm::sink::JsonSink::new(&mut out).write_all(records)?;
+            color::json
+        }
+        #[cfg(feature = "yaml")]
+        OutputFormat::Yaml => {
+            asyncfsm::sink::YamlSink::new(&mut out).write_all(records)?;
+            color::yaml
+        }
+        #[cfg(all(feature = "json", feature = "yaml"))]
+        OutputFormat::Netbox => return emit(format, color, records),
+        #[cfg(feature = "json")]
+        OutputFormat::Ansible => return emit(format, color, records),
+    };
+    let text = String::from_utf8(out)?;
+    print!("{}", if color { paint(&text) } else { text });
+    Ok(())
+}
+
+fn parse_regex(s: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(s).map_err(|e| e.to_string())
+}
+
+/// Reads command output from standard input up to the end of input or a line matching
+/// `until`. At a terminal, a line holding only `.` ends it too, and the user is told how
+/// to finish first.
+fn read_pasted(interactive: bool, until: Option<&regex::Regex>) -> anyhow::Result<String> {
+    if interactive {
+        match until {
+            Some(until) => eprintln!(
+                "Paste the command output; it ends at a line matching `{}`, a line holding \
+                 only `.` or Ctrl-D.",
+                until
+            ),
+            None => eprintln!(
+                "Paste the command output, then end it with a line holding only `.` (or Ctrl-D)."
+            ),
+        }
+        eprintln!("To parse a file instead, pass --input FILE; --stdin reads without this prompt.");
+    }
+    let mut pasted = String::new();
+    for (i, line) in std::io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        if interactive && line.trim_end() == "." {
+            break;
+        }
+        if until.is_some_and(|until| until.is_match(&line)) {
+            // The first line is usually the prompt with the command that was run.
+            if i == 0 {
+                continue;
+            }
+            break;
+        }
+        pasted.push_str(&line);
+        pasted.push('\n');
+    }
+    Ok(pasted)
+}
+
+/// Writes each record of an endless input as soon as it is parsed.
+#[cfg(feature = "follow")]
+fn stream_records(
+    fsm: TextFSM,
+    reader: Box<dyn std::io::Read>,
+    conv: Option<DataRecordConversion>,
+    mut sink: Box<dyn asyncfsm::RecordSink>,
+) -> anyhow::Result<()> {
+    let schema = fsm.parser.value_names.clone();
+    let fields = Records::new(vec![], schema.clone()).convert(conv.clone());
+    sink.open(fields.schema())?;
+    for record in fsm.parse_reader(std::io::BufReader::new(reader)) {
+        let records = Records::new(vec![record?], schema.clone()).convert(conv.clone());
+        sink.write(&records)?;
+        sink.flush()?;
+    }
+    sink.close()?;
+    Ok(())
+}
+
+#[cfg(feature = "compress")]
+fn parse_compression(s: &str) -> Result<asyncfsm::compress::Compression, String> {
+    s.parse().map_err(|e: asyncfsm::TextFsmError| e.to_string())
+}
+
+/// The extension --compress adds to output files, if given.
+fn compressed_extension(cli: &Cli) -> Option<&'static str> {
+    #[cfg(feature = "compress")]
+    if let Some(compression) = cli.compress {
+        return Some(compression.extension());
+    }
+    let _ = cli;
+    None
+}
+
+/// The sink spec `NAME:PATH`, with `.EXTENSION` added to `PATH` unless it ends in it.
+fn output_spec(name: &str, path: &str, extension: Option<&str>) -> String {
+    match extension {
+        Some(extension) if !path.ends_with(&format!(".{}", extension)) => {
+            format!("{}:{}.{}", name, path, extension)
+        }
+        _ => format!("{}:{}", name, path),
+    }
+}
+
+/// Prints each line of `input` after the rules that matched it, with their captures
+/// marked.
+fn print_trace(input: &str, matches: &[asyncfsm::RuleMatch], color: bool) {
+    let mut matches = matches.iter().peekable();
+    for (i, line) in input.lines().enumerate() {
+        let mut rules = vec![];
+        let mut text = line;
+        let mut spans = vec![];
+        while let Some(found) = matches.next_if(|m| m.line_number <= i + 1) {
+            if found.line_number == i + 1 {
+                rules.push(format!("{}#{}", found.state, found.rule));
+                // Spans refer to the line as the rules saw it.
+                text = &found.line;
+                spans.extend(found.captures.iter().map(|c| (c.start, c.end)));
+            }
+        }
+        // Rules seeing the line after a `Continue` may capture the same text again.
+        spans.sort_unstable();
+        spans.dedup_by(|next, kept| {
+            let overlaps = next.0 < kept.1;
+            if overlaps {
+                kept.1 = kept.1.max(next.1);
+            }
+            overlaps
+        });
+        if rules.is_empty() {
+            let line = if color {
+                color::unmatched(line)
+            } else {
+                line.to_string()
+            };
+            println!("{:>5} {:<16} {}", i + 1, "-", line);
+        } else {
+            let line = color::captures(text, &spans, color);
+            println!("{:>5} {:<16} {}", i + 1, rules.join(","), line);
+        }
+    }
+}
+
+/// The fields of all `sources`: their schemas in order, then any other fields sorted.
+fn merged_fields(sources: &[SourceResult]) -> Vec<String> {
+    let mut fields: Vec<String> = vec![];
+    let mut extra = std::collections::BTreeSet::new();
+    for source in sources {
+        for name in source.records.schema() {
+            if !fields.contains(name) {
+                fields.push(name.clone());
+            }
+        }
+        extra.extend(source.records.iter().flat_map(|r| r.fields.keys().cloned()));
+    }
+    extra.retain(|name| !fields.contains(name));
+    fields.extend(extra);
+    fields
+}
+
+/// Writes the records of `sources` with the sink `writer` names, to a file in `dir` for
+/// each value of `key`.
+fn write_partitions(
+    sources: &[SourceResult],
+    key: &str,
+    dir: &std::path::Path,
+    writer: &str,
+    extension: Option<&str>,
+) -> anyhow::Result<()> {
+    use asyncfsm::RecordSink;
+
+    std::fs::create_dir_all(dir)?;
+    let fields = merged_fields(sources);
+    let mut names = asyncfsm::sink::PartitionFileNames::new();
+    let mut sink = asyncfsm::sink::PartitionedSink::new(key, |value| {
+        let path = dir.join(format!("{}.{}", names.name(value), writer));
+        asyncfsm::record_sink(&output_spec(writer, &path.display().to_string(), extension))
+    });
+    sink.open(&fields)?;
+    for source in sources {
+        let value = match key {
+            "source" => Some(source.source.as_str()),
+            "platform" => Some(source.platform.as_deref().unwrap_or_default()),
+            "command" => Some(source.command.as_deref().unwrap_or_default()),
+            "template" => Some(source.template.as_deref().unwrap_or_default()),
+            _ => None,
+        };
+        match value {
+            Some(value) => sink.write_partition(value, &source.records)?,
+            None => sink.write(&source.records)?,
+        }
+    }
+    sink.close()?;
+    eprintln!(
+        "Wrote {} partitions to {}",
+        sink.partitions().count(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Parses one capture with the template `table` selects. Without a command the best
+/// fitting template of the platform is used; without either both are detected.
+#[cfg(feature = "clitable")]
+fn parse_indexed(
+    table: &CliTable,
+    path: &std::path::Path,
+    platform: &Option<String>,
+    command: &Option<String>,
+    conv: Option<DataRecordConversion>,
+) -> anyhow::Result<SourceResult> {
+    let content = asyncfsm::encoding::read_file(path, table.options())?;
+    let (platform, command) = match (platform, command) {
+        (Some(platform), Some(command)) => (platform.clone(), command.clone()),
+        (Some(platform), None) => {
+            let Some(best) = table.parse_best(Some(platform), &content) else {
+                anyhow::bail!("No template of {} matches {}", platform, path.display());
+            };
+            report_deprecation(&best.result);
+            let mut source =
+                SourceResult::from_match(&path.display().to_string(), best.result, conv);
+            source.platform = Some(best.platform);
+            source.command = Some(best.command);
+            return Ok(source);
+        }
+        _ => {
+            let Some(detected) = table.detect(&content) else {
+                anyhow::bail!(
+                    "Could not detect platform and command from {}",
+                    path.display()
+                );
+            };
+            (detected.platform, detected.command)
+        }
+    };
+    let found = table.parse_match(&platform, &command, &content)?;
+    report_deprecation(&found);
+    let mut source = SourceResult::from_match(&path.display().to_string(), found, conv);
+    source.platform = Some(platform);
+    source.command = Some(command);
+    Ok(source)
+}
+
+/// Tells the user on stderr that the template behind `found` is deprecated.
+#[cfg(feature = "clitable")]
+fn report_deprecation(found: &asyncfsm::TemplateMatch) {
+    if let Some(deprecation) = &found.deprecation {
+        eprintln!(
+            "{}: template {} is {}",
+            deprecation.severity,
+            found.template.display(),
+            deprecation
+        );
+    }
+}
+
+/// Picks the index from the command line or environment, then the config file.
+#[cfg(feature = "clitable")]
+fn resolve_index(index: Option<PathBuf>, config: &Config) -> anyhow::Result<PathBuf> {
+    match index.or_else(|| config.index.clone()) {
+        Some(index) => Ok(index),
+        None => anyhow::bail!(
+            "No index given; pass --index, set ASYNCFSM_INDEX or add `index` to the config file"
+        ),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let error_format = cli.error_format;
+    match (run(cli), error_format) {
+        #[cfg(feature = "json")]
+        (Err(e), ErrorFormat::Json) => {
+            eprintln!("{}", serde_json::to_string(&errors::to_json(&e))?);
+            std::process::exit(1);
+        }
+        (result, _) => result,
+    }
+}
+
+fn run(cli: Cli) -> anyhow::Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    #[cfg(feature = "plugins")]
+    for plugin in &cli.plugin {
+        // SAFETY: the user asked for this library to be loaded.
+        unsafe { asyncfsm::output::load_plugin(plugin)? };
+    }
+    #[cfg(feature = "compress")]
+    if cli.compress.is_some() {
+        match cli.writer.as_deref() {
+            Some(spec) if spec.split(':').next() == Some("sqlite") => {
+                anyhow::bail!("--compress does not apply to sqlite databases")
+            }
+            Some(spec) if spec.contains(':') => {}
+            _ if cli.partition_by.is_some() => {}
+            _ => anyhow::bail!(
+                "--compress applies to files: give --writer NAME:PATH or --partition-by"
+            ),
+        }
+    }
+    let extension = compressed_extension(&cli);
+    let mut sink = match cli.writer.as_deref() {
+        Some(spec) => Some(asyncfsm::record_sink(&match spec.split_once(':') {
+            Some((name, path)) => output_spec(name, path, extension),
+            None => spec.to_string(),
+        })?),
+        None => None,
+    };
+
+    let format = match (cli.format, &config.format) {
+        (Some(format), _) => format,
+        (None, Some(name)) => OutputFormat::from_str(name, true)
+            .map_err(|e| anyhow::anyhow!("Invalid format in config file: {}", e))?,
+        #[cfg(feature = "yaml")]
+        (None, None) => OutputFormat::Yaml,
+        #[cfg(all(feature = "json", not(feature = "yaml")))]
+        (None, None) => OutputFormat::Json,
+        #[cfg(not(any(feature = "json", feature = "yaml")))]
+        (None, None) => anyhow::bail!("no output format is built in; enable json or yaml"),
+    };
+    let lowercase = cli.lowercase.or(config.lowercase).unwrap_or(false);
+    // ntc-templates callers in Ansible read lowercase keys.
+    #[cfg(feature = "json")]
+    let lowercase = lowercase || format == OutputFormat::Ansible;
+    let conv = if lowercase {
+        Some(DataRecordConversion::LowercaseKeys)
+    } else {
+        None
+    };
+
+    let color = cli.color.enabled();
+    #[cfg(feature = "report")]
+    let report = cli
+        .render
+        .as_deref()
+        .map(asyncfsm::Report::from_file)
+        .transpose()?;
+    #[cfg(feature = "script")]
+    let script = cli
+        .script
+        .as_deref()
+        .map(asyncfsm::RecordScript::from_file)
+        .transpose()?;
+    let mut threshold = None;
+    let mut nesting = None;
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    let mapping = match cli.mapping.as_deref() {
+        Some(file) if std::path::Path::new(file).is_file() => {
+            Some(asyncfsm::mapping::Mapping::from_file(file)?)
+        }
+        Some(name) => Some(asyncfsm::mapping::mapping(name)?),
+        None => None,
+    };
+    let mut base_options = TextFsmOptions::default()
+        .with_raw(cli.with_raw)
+        .with_ids(cli.with_ids);
+    if cli.normalize_whitespace {
+        base_options = base_options.normalize_whitespace(WhitespaceNormalization::all());
+    }
+    if let Some(limit) = cli.limit {
+        base_options = base_options.max_records(limit);
+    }
+    if let Some(skip) = cli.skip {
+        base_options = base_options.skip_records(skip);
+    }
+    if let Some(rate) = cli.sample {
+        base_options = base_options.sample(rate);
+    }
+    #[cfg(feature = "encoding_rs")]
+    if let Some(label) = &cli.encoding {
+        base_options = base_options.encoding(asyncfsm::encoding::for_label(label)?);
+    }
+    let sources: Vec<SourceResult> = match cli.command {
+        Commands::Parse {
+            template,
+            input,
+            stdin: read_stdin,
+            until,
+            input_dir,
+            jobs,
+            progress,
+            start_state,
+            fail_on_unmatched_threshold,
+            carry_over,
+            line_mode,
+            #[cfg(feature = "follow")]
+            follow,
+            #[cfg(feature = "follow")]
+            journal,
+            nest,
+        } => {
+            let mut options = base_options
+                .clone()
+                .line_mode(line_mode)
+                .with_raw(cli.with_raw || cli.deterministic);
+            if let Some(state) = start_state {
+                options = options.start_state(&state);
+            }
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let mut fsm = TextFSM::from_parser(resolver.load(template)?).with_options(options);
+            threshold = fail_on_unmatched_threshold;
+            nesting = match nest.as_deref() {
+                None => None,
+                Some("") => Some(Nesting::from_template(&fsm.parser).ok_or_else(|| {
+                    anyhow::anyhow!("--nest needs levels: the template has no `# Nest:` header")
+                })??),
+                Some(spec) => Some(Nesting::parse(spec)?),
+            };
+            #[cfg(feature = "follow")]
+            {
+                let reader: Option<Box<dyn std::io::Read>> = match (follow, journal) {
+                    (Some(path), _) => Some(Box::new(asyncfsm::follow::FollowFile::open(path)?)),
+                    (None, Some(unit)) => Some(Box::new(asyncfsm::follow::Journal::follow(&unit)?)),
+                    (None, None) => None,
+                };
+                if let Some(reader) = reader {
+                    let sink = match sink {
+                        Some(sink) => sink,
+                        None => asyncfsm::record_sink("jsonl")?,
+                    };
+                    return stream_records(fsm, reader, conv, sink);
+                }
+            }
+
+            if let Some(dir) = input_dir {
+                let template = Template::from_parser(Arc::clone(&fsm.parser))
+                    .with_options(fsm.options.clone());
+                let mut dir_options = asyncfsm::DirOptions::new().jobs(jobs);
+                #[cfg(feature = "cache")]
+                if let Some(dir) = &cli.cache_dir {
+                    dir_options = dir_options.cache(asyncfsm::ResultCache::new(dir));
+                }
+                if progress {
+                    dir_options = dir_options.on_progress(progress::draw);
+                }
+                let result = asyncfsm::batch::parse_dir(&dir, &template, &dir_options)?;
+                let mut sources: Vec<SourceResult> = result
+                    .files
+                    .into_iter()
+                    .map(|file| SourceResult::from_file(&dir, file, conv.clone()))
+                    .collect();
+                if let Some((path, error)) = result.errors.iter().next() {
+                    if !cli.keep_going {
+                        anyhow::bail!("{}: {}", dir.join(path).display(), error);
+                    }
+                }
+                for (path, error) in result.errors {
+                    sources.push(SourceResult::failed(
+                        &dir.join(path).display().to_string(),
+                        error,
+                    ));
+                }
+                sources
+            } else if input.is_empty()
+                && (until.is_some() || !read_stdin && std::io::stdin().is_terminal())
+            {
+                let interactive = !read_stdin && std::io::stdin().is_terminal();
+                let pasted = read_pasted(interactive, until.as_ref())?;
+                let results = fsm.parse_records(&pasted, conv)?;
+                vec![SourceResult::new("-", &fsm, results)]
+            } else if input.is_empty() {
+                let stdin = std::io::stdin();
+                let reader = stdin.lock();
+                let schema = fsm.parser.value_names.clone();
+                let mut iter = fsm.parse_reader(reader);
+                let mut results = Vec::new();
+                for record in iter.by_ref() {
+                    results.push(record?);
+                }
+                let results = Records::new(results, schema).convert(conv);
+                vec![SourceResult::new("-", iter.fsm(), results)]
+            } else if carry_over {
+                let source: Vec<String> = input.iter().map(|p| p.display().to_string()).collect();
+                let results = fsm.parse_files(&input, conv)?;
+                vec![SourceResult::new(&source.join(", "), &fsm, results)]
+            } else {
+                #[cfg(feature = "cache")]
+                let cache = cli.cache_dir.as_ref().map(asyncfsm::ResultCache::new);
+                let mut sources = vec![];
+                for path in input {
+                    let source = path.display().to_string();
+                    #[cfg(feature = "cache")]
+                    if let Some(cache) = &cache {
+                        match cache.parse_file(&mut fsm, &path) {
+                            Ok(parsed) => sources.push(SourceResult::from_cached(
+                                &source,
+                                parsed,
+                                conv.clone(),
+                            )),
+                            Err(e) if cli.keep_going => {
+                                sources.push(SourceResult::failed(&source, e.to_string()))
+                            }
+                            Err(e) => return Err(e.into()),
+                        }
+                        continue;
+                    }
+                    fsm.reset();
+                    match fsm.parse_file_records(&path, conv.clone()) {
+                        Ok(results) => sources.push(SourceResult::new(&source, &fsm, results)),
+                        Err(e) if cli.keep_going => {
+                            sources.push(SourceResult::failed(&source, e.to_string()))
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                sources
+            }
+        }
+        Commands::BenchCompare {
+            template,
+            input,
+            iterations,
+            python,
+        } => {
+            let template = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir))
+                .resolve(template)?;
+            let text = std::fs::read_to_string(&template)?;
+            let data = asyncfsm::encoding::read_file(&input, &base_options)?;
+            let mut timings = vec![bench::time_rust(&text, &data, &base_options, iterations)?];
+            match bench::time_python(&python, &template, &input, iterations)? {
+                Some(timing) => timings.push(timing),
+                None => eprintln!("{} cannot import textfsm; timing asyncfsm only", python),
+            }
+            print!("{}", bench::table(&timings));
+            return Ok(());
+        }
+        Commands::Inspect { template } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let parser = resolver.load(template)?;
+            if cli.format.is_some() {
+                return emit(format, color, &TemplateAst::new(&parser));
+            }
+            print!("{}", TemplateSummary::new(&parser));
+            return Ok(());
+        }
+        Commands::Fmt {
+            files,
+            write,
+            check,
+        } => {
+            let mut unformatted = 0;
+            for path in &files {
+                let source = std::fs::read_to_string(path)?;
+                let formatted = asyncfsm::format::format_template(&source)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+                if check {
+                    if formatted != source {
+                        println!("{}", path.display());
+                        unformatted += 1;
+                    }
+                } else if write {
+                    if formatted != source {
+                        std::fs::write(path, formatted)?;
+                        eprintln!("Formatted {}", path.display());
+                    }
+                } else {
+                    print!("{}", formatted);
+                }
+            }
+            if unformatted > 0 {
+                anyhow::bail!(
+                    "{} of {} templates are not formatted",
+                    unformatted,
+                    files.len()
+                );
+            }
+            return Ok(());
+        }
+        Commands::Optimize { template, output } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let optimized = asyncfsm::optimize::optimize(&resolver.load(template)?)?;
+            if cli.format.is_some() {
+                return emit(format, color, &optimized);
+            }
+            for finding in &optimized.findings {
+                eprintln!("{}", finding);
+            }
+            match output {
+                Some(path) => std::fs::write(path, &optimized.template)?,
+                None => print!("{}", optimized.template),
+            }
+            return Ok(());
+        }
+        Commands::Lint { template, fix } => {
+            let source = std::fs::read_to_string(&template)?;
+            let mut issues = asyncfsm::lint::lint(&source)?;
+            if fix && issues.iter().any(|issue| issue.fix.is_some()) {
+                std::fs::write(&template, asyncfsm::lint::fix(&source, &issues))?;
+                let fixed = issues.iter().filter(|issue| issue.fix.is_some()).count();
+                eprintln!("Fixed {} issue(s) in {}", fixed, template.display());
+                issues.retain(|issue| issue.fix.is_none());
+            }
+            if cli.format.is_some() {
+                emit(format, color, &issues)?;
+            } else {
+                for issue in &issues {
+                    println!("{}", issue);
+                }
+            }
+            if !issues.is_empty() {
+                anyhow::bail!("{} issue(s) in {}", issues.len(), template.display());
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "lsp")]
+        Commands::Lsp => {
+            asyncfsm::lsp::serve(std::io::stdin().lock(), std::io::stdout().lock())?;
+            return Ok(());
+        }
+        Commands::Tdiff { old, new } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let diff = TemplateDiff::between(&resolver.load(old)?, &resolver.load(new)?);
+            if cli.format.is_some() {
+                return emit(format, color, &diff);
+            }
+            let text = diff.to_string();
+            print!("{}", if color { color::diff(&text) } else { text });
+            return Ok(());
+        }
+        Commands::Trace { template, input } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let mut fsm = TextFSM::from_parser(resolver.load(template)?)
+                .with_options(TextFsmOptions::default().track_matches(true));
+            let text = match input {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            // The lines up to an error are still worth seeing.
+            let parsed = fsm.parse_records(&text, None);
+            if cli.format.is_some() {
+                emit(format, color, &fsm.matches)?;
+            } else {
+                print_trace(&text, &fsm.matches, color);
+            }
+            parsed?;
+            return Ok(());
+        }
+        Commands::SchemaDiff {
+            old,
+            new,
+            fail_on_breaking,
+        } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let diff = SchemaDiff::between(&resolver.load(old)?, &resolver.load(&new)?);
+            if cli.format.is_some() {
+                emit(format, color, &diff)?;
+            } else {
+                let text = diff.to_string();
+                print!("{}", if color { color::diff(&text) } else { text });
+            }
+            if fail_on_breaking && diff.is_breaking() {
+                anyhow::bail!("the records of {} are not compatible", new.display());
+            }
+            return Ok(());
+        }
+        Commands::Anonymize {
+            template,
+            input,
+            fields,
+            output,
+        } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let template =
+                Template::from_parser(resolver.load(template)?).with_options(base_options.clone());
+            let text = asyncfsm::encoding::read_file(&input, &base_options)?;
+            let records = template.parse_records(&text, None)?;
+            let mut anonymizer = Anonymizer::new(fields);
+            anonymizer.learn(&records);
+            let rewritten = anonymizer.rewrite(&text);
+            let reparsed = template.parse_records(&rewritten, None)?.len();
+            if reparsed != records.len() {
+                eprintln!(
+                    "warning: the rewritten text parses into {} records instead of {}",
+                    reparsed,
+                    records.len()
+                );
+            }
+            match output {
+                Some(path) => std::fs::write(path, rewritten)?,
+                None => print!("{}", rewritten),
+            }
+            eprintln!("replaced {} distinct values", anonymizer.len());
+            return Ok(());
+        }
+        #[cfg(feature = "yaml")]
+        Commands::Verify {
+            template,
+            input,
+            expected,
+            ignore_fields,
+        } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let mut fsm = TextFSM::from_parser(resolver.load(template)?).with_options(base_options);
+            let records = fsm.parse_file_records(&input, None)?;
+            let verification = fixtures::Verification::new(
+                &fixtures::load_expected(&expected)?,
+                &records,
+                ignore_fields,
+            );
+            emit(format, color, &verification)?;
+            if !verification.passed {
+                anyhow::bail!(
+                    "{} difference(s) from {}",
+                    verification.differences.len(),
+                    expected.display()
+                );
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "yaml")]
+        Commands::Mutate { template, fixtures } => {
+            let resolver = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir));
+            let parser = resolver.load(template)?;
+            let fixtures = fixtures::discover(&fixtures, &base_options)?;
+            if fixtures.is_empty() {
+                anyhow::bail!("No fixtures found");
+            }
+            let report = mutate::run(&parser, &fixtures, &base_options);
+            if cli.format.is_some() {
+                emit(format, color, &report)?;
+            } else {
+                print!("{}", report);
+            }
+            if report.survivors() > 0 {
+                anyhow::bail!(
+                    "{} of {} rules survived mutation",
+                    report.survivors(),
+                    report.rules.len()
+                );
+            }
+            return Ok(());
+        }
+        #[cfg(all(feature = "bundle", feature = "yaml"))]
+        Commands::Bundle {
+            template,
+            input,
+            expected,
+            output,
+        } => {
+            let path = cli
+                .template_dirs
+                .iter()
+                .chain(&config.template_dirs)
+                .fold(TemplateResolver::new(), |r, dir| r.with_dir(dir))
+                .resolve(template)?;
+            let options =
+                asyncfsm::BundleOptions::from(&base_options).with_conversion(conv.as_ref());
+            let mut bundle = asyncfsm::Bundle::new(
+                &path.display().to_string(),
+                &std::fs::read_to_string(&path)?,
+                &input.display().to_string(),
+                std::fs::read(&input)?,
+                options,
+            );
+            if let Some(expected) = expected {
+                let value: serde_yaml::Value =
+                    serde_yaml::from_str(&std::fs::read_to_string(&expected)?)?;
+                bundle = bundle.expected(serde_json::to_value(value)?);
+            }
+            bundle.to_file(&output)?;
+            match &bundle.actual {
+                asyncfsm::BundleOutput::Error(e) => {
+                    eprintln!("wrote {} (the parse fails: {})", output.display(), e)
+                }
+                asyncfsm::BundleOutput::Records(_) => eprintln!("wrote {}", output.display()),
+            }
+            return Ok(());
+        }
+        #[cfg(all(feature = "bundle", feature = "yaml"))]
+        Commands::Replay { bundle } => {
+            let report = replay::ReplayReport::new(&asyncfsm::Bundle::from_file(&bundle)?)?;
+            emit(format, color, &report)?;
+            match report.matches_expected {
+                Some(false) => anyhow::bail!("the output differs from the expected records"),
+                None if !report.matches_recorded => anyhow::bail!(
+                    "the output differs from the one recorded by version {}",
+                    report.recorded_version
+                ),
+                _ => return Ok(()),
+            }
+        }
+        Commands::Completions {
+            shell,
+            #[cfg(feature = "clitable")]
+            index,
+        } => {
+            #[allow(unused_mut)]
+            let mut cmd = Cli::command();
+            #[cfg(feature = "clitable")]
+            if let Some(index) = index.or(config.index) {
+                cmd = complete_from_index(cmd, &CliTable::from_file(index)?);
+            }
+            clap_complete::generate(shell, &mut cmd, "textfsm", &mut std::io::stdout());
+            return Ok(());
+        }
+        #[cfg(feature = "clitable")]
+        Commands::Auto {
+            index,
+            platform,
+            command,
+            guess: _,
+            input,
+        } => {
+            let table = CliTable::from_file(resolve_index(index, &config)?)?.with_options(
+                base_options
+                    .clone()
+                    .with_raw(cli.with_raw || cli.deterministic),
+            );
+            let mut sources = vec![];
+            for path in input {
+                match parse_indexed(&table, &path, &platform, &command, conv.clone()) {
+                    Ok(source) => sources.push(source),
+                    Err(e) if cli.keep_going => {
+                        let mut source =
+                            SourceResult::failed(&path.display().to_string(), format!("{:#}", e));
+                        source.platform = platform.clone();
+                        source.command = command.clone();
+                        sources.push(source);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            sources
+        }
+        #[cfg(feature = "clitable")]
+        Commands::Model {
+            index,
+            platform,
+            input_dir,
+        } => {
+            let table = CliTable::from_file(resolve_index(index, &config)?)?;
+            let model = DeviceModel::from_dir(&table, platform.as_deref(), &input_dir)?;
+            return emit(format, color, &model);
+        }
+        #[cfg(feature = "clitable")]
+        Commands::Facts {
+            index,
+            platform,
+            input_dir,
+        } => {
+            let table = CliTable::from_file(resolve_index(index, &config)?)?;
+            let facts = DeviceFacts::from_dir(&table, platform.as_deref(), &input_dir)?;
+            return emit(format, color, &facts);
+        }
+        #[cfg(feature = "clitable")]
+        Commands::IndexGen {
+            templates_dir,
+            output,
+        } => {
+            let index = asyncfsm::generate_index(&templates_dir)?;
+            for path in &index.skipped {
+                eprintln!(
+                    "Skipping {}: no platform and command in its header or file name",
+                    path.display()
+                );
+            }
+            match output {
+                Some(path) => std::fs::write(path, index.to_string())?,
+                None => print!("{}", index),
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "registry")]
+        Commands::Registry { action } => match action {
+            RegistryAction::List { sources } => {
+                let registry = sources.build(&config)?;
+                return emit(format, color, &registry.entries());
+            }
+            RegistryAction::Info {
+                sources,
+                platform,
+                command,
+                template_version,
+            } => {
+                let registry = sources.build(&config)?;
+                let Some(entry) = registry.lookup(&platform, &command, template_version.as_deref())
+                else {
+                    anyhow::bail!(
+                        "No template for platform {} and command {}",
+                        platform,
+                        command
+                    );
+                };
+                let template = entry.load()?;
+                let info = TemplateInfo {
+                    entry,
+                    path: entry.path(),
+                    values: template.value_names.clone(),
+                    metadata: template.metadata().clone().into_iter().collect(),
+                };
+                return emit(format, color, &info);
+            }
+        },
+        #[cfg(feature = "clitable")]
+        Commands::Templates {
+            action: TemplatesAction::Audit { index },
+        } => {
+            let audit = CliTable::from_file(resolve_index(index, &config)?)?.audit();
+            if cli.format.is_some() {
+                return emit(format, color, &audit);
+            }
+            print!("{}", audit);
+            return Ok(());
+        }
+        #[cfg(all(feature = "clitable", feature = "yaml"))]
+        Commands::Batch { inventory, index } => {
+            let table = CliTable::from_file(resolve_index(index, &config)?)?;
+            let hosts = Inventory::from_file(&inventory)?;
+            let base_dir = inventory.parent().unwrap_or(std::path::Path::new("."));
+            if !cli.keep_going {
+                return emit(format, color, &hosts.run(&table, base_dir)?);
+            }
+            let results = hosts.run_keep_going(&table, base_dir);
+            emit(format, color, &results)?;
+            let captures: usize = hosts.hosts.values().map(|h| h.captures.len()).sum();
+            let failed: usize = results.values().map(|r| r.errors.len()).sum();
+            if failed > 0 {
+                anyhow::bail!("{} of {} captures failed", failed, captures);
+            }
+            return Ok(());
+        }
+    };
+
+    let mut sources = sources;
+    for source in &mut sources {
+        if cli.deterministic {
+            source.records.sort_by_line();
+            if !cli.with_raw {
+                source
+                    .records
+                    .iter_mut()
+                    .for_each(|record| record.raw = None);
+            }
+        }
+        #[cfg(feature = "script")]
+        if let Some(script) = &script {
+            source.records = script.apply(std::mem::take(&mut source.records))?;
+        }
+        if let Some(threshold) = threshold {
+            let unmatched = source.stats.unmatched_percent();
+            if unmatched > threshold {
+                source.errors.push(format!(
+                    "{:.1}% of input lines matched no rule (threshold {}%)",
+                    unmatched, threshold
+                ));
+            }
+        }
+        if cli.fail_on_empty && source.records.is_empty() {
+            source.errors.push("No records parsed".to_string());
+        }
+    }
+
+    if let (Some(key), Some(dir)) = (&cli.partition_by, &cli.out_dir) {
+        let writer = match cli.writer.as_deref() {
+            Some(name) if name.contains(':') => {
+                anyhow::bail!("--partition-by writes to --out-dir; give --writer without a path")
+            }
+            Some(name) => name.to_string(),
+            None => match format {
+                #[cfg(feature = "json")]
+                OutputFormat::Json => "json".to_string(),
+                #[cfg(feature = "yaml")]
+                OutputFormat::Yaml => "yaml".to_string(),
+                #[allow(unreachable_patterns)]
+                _ => {
+                    anyhow::bail!("--partition-by writes records as json or yaml, or with --writer")
+                }
+            },
+        };
+        write_partitions(&sources, key, dir, &writer, extension)?;
+        let failed = sources.iter().filter(|s| !s.errors.is_empty()).count();
+        if let Some(error) = sources.iter().find_map(|s| s.errors.first()) {
+            match sources.len() {
+                1 => anyhow::bail!(error.clone()),
+                n => anyhow::bail!("{} of {} inputs failed", failed, n),
+            }
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "report")]
+    let rendered = match &report {
+        Some(report) => {
+            for source in &sources {
+                print!("{}", report.render(&source.records)?);
+            }
+            true
+        }
+        None => false,
+    };
+    #[cfg(not(feature = "report"))]
+    let rendered = false;
+    let rendered = rendered || cli.get.is_some() || sink.is_some();
+    if let Some(sink) = sink.as_deref_mut() {
+        // One output for all inputs: a JSON array or a CSV header spans them.
+        sink.open(&merged_fields(&sources))?;
+        for source in &sources {
+            sink.write(&source.records)?;
+        }
+        sink.close()?;
+    }
+    if let Some(path) = &cli.get {
+        let mut values = vec![];
+        for source in &sources {
+            values.extend(source.records.pluck(path)?);
+        }
+        match values
+            .iter()
+            .map(|v| v.as_str())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(lines) => lines.iter().for_each(|line| println!("{}", line)),
+            None => emit(format, color, &values)?,
+        }
+    }
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    if !rendered && format == OutputFormat::Netbox {
+        let mut netbox = asyncfsm::netbox::Netbox::new();
+        if let Some(mapping) = mapping {
+            netbox = netbox.mapping(mapping);
+        }
+        if let Some(device) = &cli.netbox_device {
+            netbox = netbox.device(device);
+        }
+        let payloads = sources
+            .iter()
+            .map(|s| netbox.payloads(&s.records))
+            .collect::<asyncfsm::Result<Vec<_>>>()?;
+        match payloads.as_slice() {
+            [single] => emit(format, color, single)?,
+            _ => emit(format, color, &payloads)?,
+        }
+        if let Some(error) = sources.iter().find_map(|s| s.errors.first()) {
+            anyhow::bail!(error.clone());
+        }
+        return Ok(());
+    }
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    if let (false, Some(mapping)) = (rendered, &mapping) {
+        let documents: Vec<_> = sources.iter().map(|s| mapping.apply(&s.records)).collect();
+        match documents.as_slice() {
+            [single] => emit(format, color, single)?,
+            _ => emit(format, color, &documents)?,
+        }
+        if let Some(error) = sources.iter().find_map(|s| s.errors.first()) {
+            anyhow::bail!(error.clone());
+        }
+        return Ok(());
+    }
+    let failed = sources.iter().filter(|s| !s.errors.is_empty()).count();
+    match sources.as_slice() {
+        [single] => {
+            if let (false, Some(nesting)) = (rendered, &nesting) {
+                emit(format, color, &nesting.apply(&single.records))?;
+            } else if !rendered {
+                emit_records(format, color, &single.records)?;
+            }
+            if let Some(error) = single.errors.first() {
+                anyhow::bail!(error.clone());
+            }
+        }
+        _ => {
+            if let (false, Some(nesting)) = (rendered, &nesting) {
+                let nested: Vec<_> = sources.iter().map(|s| s.nested(nesting)).collect();
+                emit(format, color, &nested)?;
+            } else if !rendered {
+                emit(format, color, &sources)?;
+            }
+            if failed > 0 {
+                anyhow::bail!("{} of {} inputs failed", failed, sources.len());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! `mutate`: removes each rule in turn and checks whether any fixture notices.
+//!
+//! A rule whose removal leaves every fixture passing "survives": either the fixtures
+//! never exercise it or it is redundant. Fixtures that fail with the unmodified
+//! template are left out, since they cannot tell mutants apart.
+
+use crate::fixtures::Fixture;
+use asyncfsm::{TextFSMParser, TextFsmOptions};
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// Which fixtures fail once one rule is removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleCoverage {
+    pub state: String,
+    /// 1-based position of the rule in its state.
+    pub rule: usize,
+    pub pattern: String,
+    pub killed_by: Vec<String>,
+}
+
+impl RuleCoverage {
+    pub fn survived(&self) -> bool {
+        self.killed_by.is_empty()
+    }
+}
+
+/// The outcome of a mutation run.
+#[derive(Debug, Clone, Serialize)]
+pub struct MutationReport {
+    /// Fixtures that pass with the unmodified template.
+    pub fixtures: Vec<String>,
+    /// Fixtures that already fail with the unmodified template.
+    pub failing: Vec<String>,
+    pub rules: Vec<RuleCoverage>,
+}
+
+impl MutationReport {
+    pub fn survivors(&self) -> usize {
+        self.rules.iter().filter(|r| r.survived()).count()
+    }
+}
+
+pub fn run(
+    parser: &TextFSMParser,
+    fixtures: &[Fixture],
+    options: &TextFsmOptions,
+) -> MutationReport {
+    let original = Arc::new(parser.clone());
+    let (passing, failing): (Vec<&Fixture>, Vec<&Fixture>) =
+        fixtures.iter().partition(|f| f.passes(&original, options));
+    let mut rules = vec![];
+    for state in &parser.state_names {
+        for (index, rule) in parser.states[state].rules().iter().enumerate() {
+            let Some(mutant) = parser.without_rule(state, index) else {
+                continue;
+            };
+            let mutant = Arc::new(mutant);
+            rules.push(RuleCoverage {
+                state: state.clone(),
+                rule: index + 1,
+                pattern: rule.to_string(),
+                killed_by: passing
+                    .iter()
+                    .filter(|f| !f.passes(&mutant, options))
+                    .map(|f| f.name.clone())
+                    .collect(),
+            });
+        }
+    }
+    MutationReport {
+        fixtures: passing.iter().map(|f| f.name.clone()).collect(),
+        failing: failing.iter().map(|f| f.name.clone()).collect(),
+        rules,
+    }
+}
+
+impl fmt::Display for MutationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.failing {
+            writeln!(f, "warning: fixture {} fails without any mutation", name)?;
+        }
+        let total = self.fixtures.len();
+        let mut state = None;
+        for rule in &self.rules {
+            if state != Some(&rule.state) {
+                writeln!(f, "{}", rule.state)?;
+                state = Some(&rule.state);
+            }
+            let verdict = if rule.survived() {
+                "SURVIVED".to_string()
+            } else {
+                format!("killed {}/{}", rule.killed_by.len(), total)
+            };
+            writeln!(
+                f,
+                "  rule {:<3} {:<13} {}",
+                rule.rule, verdict, rule.pattern
+            )?;
+        }
+        writeln!(
+            f,
+            "\n{} of {} rules survived: no fixture fails without them",
+            self.survivors(),
+            self.rules.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_no_fixture_needs_survive() {
+        let parser = TextFSMParser::from_string(
+            "Value A (\\d+)\n\nStart\n  ^a ${A} -> Record\n  ^b ${A} -> Record\n",
+        )
+        .unwrap();
+        let fixture = Fixture {
+            name: "only_a".into(),
+            input: "a 1\n".into(),
+            expected: vec![[("a".to_string(), "1".into())].into_iter().collect()],
+        };
+        let report = run(&parser, &[fixture], &TextFsmOptions::default());
+        let survived: Vec<bool> = report.rules.iter().map(|r| r.survived()).collect();
+        assert_eq!(survived, vec![false, true]);
+        assert_eq!(report.survivors(), 1);
+    }
+}
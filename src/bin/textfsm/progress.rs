@@ -0,0 +1,65 @@
+//! The `--progress` bar drawn on stderr while a directory is parsed.
+
+use asyncfsm::DirProgress;
+use std::io::Write;
+
+const WIDTH: usize = 30;
+
+/// One line of the bar, e.g. `[=======>      ] 12/40 files, 340 records, 1 failed`.
+pub fn line(progress: &DirProgress) -> String {
+    let filled = (progress.files_done * WIDTH)
+        .checked_div(progress.files_total)
+        .unwrap_or(WIDTH);
+    let bar = match filled {
+        WIDTH => "=".repeat(WIDTH),
+        n => format!("{}>{}", "=".repeat(n), " ".repeat(WIDTH - n - 1)),
+    };
+    let mut line = format!(
+        "[{}] {}/{} files, {} records",
+        bar, progress.files_done, progress.files_total, progress.records
+    );
+    if progress.errors > 0 {
+        line.push_str(&format!(", {} failed", progress.errors));
+    }
+    line
+}
+
+/// Redraws the bar in place, ending the line once every file is done.
+pub fn draw(progress: &DirProgress) {
+    let mut stderr = std::io::stderr().lock();
+    let end = if progress.files_done == progress.files_total {
+        "\n"
+    } else {
+        ""
+    };
+    let _ = write!(stderr, "\r{}{}", line(progress), end);
+    let _ = stderr.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_fills_with_files_done() {
+        let progress = DirProgress {
+            files_done: 1,
+            files_total: 3,
+            records: 7,
+            errors: 1,
+        };
+        assert_eq!(
+            line(&progress),
+            format!(
+                "[{}>{}] 1/3 files, 7 records, 1 failed",
+                "=".repeat(10),
+                " ".repeat(19)
+            )
+        );
+        let done = DirProgress::default();
+        assert_eq!(
+            line(&done),
+            format!("[{}] 0/0 files, 0 records", "=".repeat(30))
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! `replay`: reruns a bug-report bundle and compares the output with the recorded one.
+
+use crate::fixtures::{self, Difference, Row};
+use asyncfsm::{Bundle, BundleOutput};
+use serde::Serialize;
+
+/// The outcome of replaying a bundle.
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    /// The version that made the bundle.
+    pub recorded_version: String,
+    pub version: String,
+    pub template: String,
+    pub input: String,
+    /// The records this version produces, unless the parse fails.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub records: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// True if this version produces what the bundle recorded.
+    pub matches_recorded: bool,
+    /// Whether the output has the expected records, when the bundle has them.
+    pub matches_expected: Option<bool>,
+    /// How the output differs from the expected records, or from the recorded ones
+    /// without expected records.
+    pub differences: Vec<Difference>,
+}
+
+impl ReplayReport {
+    pub fn new(bundle: &Bundle) -> anyhow::Result<Self> {
+        let output = bundle.run();
+        let now = rows(&output)?;
+        let recorded = rows(&bundle.actual)?;
+        let matches_recorded = match (&output, &bundle.actual) {
+            (BundleOutput::Error(a), BundleOutput::Error(b)) => a == b,
+            _ => now == recorded,
+        };
+        let expected = bundle
+            .expected
+            .as_ref()
+            .map(|value| fixtures::expected_rows(serde_yaml::to_value(value)?))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("expected records in the bundle: {}", e))?;
+        let differences = match (&expected, &now, &recorded) {
+            (Some(expected), Some(now), _) => fixtures::compare(expected, now, &[]),
+            (None, Some(now), Some(recorded)) => fixtures::compare(recorded, now, &[]),
+            _ => vec![],
+        };
+        Ok(ReplayReport {
+            recorded_version: bundle.version.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            template: bundle.template_name.clone(),
+            input: bundle.input_name.clone(),
+            matches_expected: expected.map(|expected| now.as_ref() == Some(&expected)),
+            matches_recorded,
+            differences,
+            records: match &output {
+                BundleOutput::Records(records) => Some(records.clone()),
+                BundleOutput::Error(_) => None,
+            },
+            error: match output {
+                BundleOutput::Error(error) => Some(error),
+                BundleOutput::Records(_) => None,
+            },
+        })
+    }
+}
+
+/// The normalized records of an output, or `None` for an error.
+fn rows(output: &BundleOutput) -> anyhow::Result<Option<Vec<Row>>> {
+    match output {
+        BundleOutput::Records(records) => Ok(Some(fixtures::expected_rows(serde_yaml::to_value(
+            records,
+        )?)?)),
+        BundleOutput::Error(_) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asyncfsm::BundleOptions;
+
+    #[test]
+    fn replay_compares_with_expected_records() {
+        let template = "Value A (\\d+)\n\nStart\n  ^${A} -> Record\n";
+        let bundle = Bundle::new(
+            "t",
+            template,
+            "i",
+            b"1\n2\n".to_vec(),
+            BundleOptions::default(),
+        )
+        .expected(serde_json::json!([{"A": "1"}, {"A": "3"}]));
+        let report = ReplayReport::new(&bundle).unwrap();
+        assert!(report.matches_recorded);
+        assert_eq!(report.matches_expected, Some(false));
+        assert_eq!(report.differences.len(), 1);
+    }
+}
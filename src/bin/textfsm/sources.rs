@@ -0,0 +1,177 @@
+//! Per-input results, for runs over several input files.
+
+#[cfg(feature = "clitable")]
+use asyncfsm::TemplateMatch;
+use asyncfsm::{DataRecordConversion, DirFile, NestedRecord, Nesting, Records, TextFSM};
+use serde::Serialize;
+use std::path::Path;
+
+/// Counters describing how one input was parsed.
+#[derive(Debug, Default, Serialize)]
+pub struct ParseStats {
+    pub records: usize,
+    pub lines: usize,
+    pub unmatched_lines: usize,
+    pub warnings: usize,
+}
+
+impl ParseStats {
+    pub fn from_fsm(fsm: &TextFSM, records: &Records) -> Self {
+        ParseStats {
+            records: records.len(),
+            lines: fsm.line_number,
+            unmatched_lines: fsm.unmatched_lines,
+            warnings: fsm.warnings.len() + fsm.validation_issues.len(),
+        }
+    }
+
+    /// Share of input lines that matched no rule, in percent.
+    pub fn unmatched_percent(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.unmatched_lines as f64 * 100.0 / self.lines as f64
+        }
+    }
+}
+
+/// The outcome of parsing one input, attributed to where it came from.
+#[derive(Debug, Serialize)]
+pub struct SourceResult {
+    /// The input file, or `-` for standard input.
+    pub source: String,
+    pub platform: Option<String>,
+    pub command: Option<String>,
+    /// The template that produced the records, when picked from an index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    pub records: Records,
+    pub stats: ParseStats,
+    /// Why this input is considered failed; empty on success.
+    pub errors: Vec<String>,
+}
+
+/// A [`SourceResult`] with its records nested under their parents.
+#[derive(Debug, Serialize)]
+pub struct NestedSource<'a> {
+    pub source: &'a str,
+    pub records: Vec<NestedRecord>,
+    pub stats: &'a ParseStats,
+    pub errors: &'a [String],
+}
+
+impl SourceResult {
+    pub fn nested(&self, nesting: &Nesting) -> NestedSource<'_> {
+        NestedSource {
+            source: &self.source,
+            records: nesting.apply(&self.records),
+            stats: &self.stats,
+            errors: &self.errors,
+        }
+    }
+
+    pub fn new(source: &str, fsm: &TextFSM, records: Records) -> Self {
+        SourceResult {
+            source: source.to_string(),
+            platform: None,
+            command: None,
+            template: None,
+            stats: ParseStats::from_fsm(fsm, &records),
+            records,
+            errors: vec![],
+        }
+    }
+
+    /// Records picked from an index, converted with `conv`.
+    #[cfg(feature = "clitable")]
+    pub fn from_match(
+        source: &str,
+        found: TemplateMatch,
+        conv: Option<DataRecordConversion>,
+    ) -> Self {
+        SourceResult {
+            source: source.to_string(),
+            platform: None,
+            command: None,
+            template: Some(found.template.display().to_string()),
+            stats: ParseStats {
+                records: found.records.len(),
+                lines: found.lines,
+                unmatched_lines: found.unmatched_lines,
+                warnings: found.warnings.len(),
+            },
+            records: found.records.convert(conv),
+            errors: vec![],
+        }
+    }
+
+    /// A file parsed by `parse_dir` under `dir`, converted with `conv`.
+    pub fn from_file(dir: &Path, file: DirFile, conv: Option<DataRecordConversion>) -> Self {
+        SourceResult {
+            source: dir.join(&file.path).display().to_string(),
+            platform: file.platform,
+            command: file.command,
+            template: None,
+            stats: ParseStats {
+                records: file.records.len(),
+                lines: file.lines,
+                unmatched_lines: file.unmatched_lines,
+                warnings: file.warnings,
+            },
+            records: file.records.convert(conv),
+            errors: vec![],
+        }
+    }
+
+    /// A result from the parse cache, converted with `conv`.
+    #[cfg(feature = "cache")]
+    pub fn from_cached(
+        source: &str,
+        parsed: asyncfsm::CachedParse,
+        conv: Option<DataRecordConversion>,
+    ) -> Self {
+        SourceResult {
+            source: source.to_string(),
+            platform: None,
+            command: None,
+            template: None,
+            stats: ParseStats {
+                records: parsed.records.len(),
+                lines: parsed.lines,
+                unmatched_lines: parsed.unmatched_lines,
+                warnings: parsed.warnings,
+            },
+            records: parsed.records.convert(conv),
+            errors: vec![],
+        }
+    }
+
+    /// An input that could not be parsed at all.
+    pub fn failed(source: &str, error: String) -> Self {
+        SourceResult {
+            source: source.to_string(),
+            platform: None,
+            command: None,
+            template: None,
+            records: Records::default(),
+            stats: ParseStats::default(),
+            errors: vec![error],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmatched_percent() {
+        let stats = ParseStats {
+            lines: 8,
+            unmatched_lines: 2,
+            ..Default::default()
+        };
+        assert_eq!(stats.unmatched_percent(), 25.0);
+        assert_eq!(ParseStats::default().unmatched_percent(), 0.0);
+    }
+}
@@ -0,0 +1,321 @@
+//! Reproducible bug reports: a template, an input and the options in one file.
+//!
+//! A [`Bundle`] holds everything needed to rerun a parse exactly: the template text, the
+//! raw input bytes, the options that change the records, the output this version of
+//! the crate produced and, optionally, the output the reporter expected. It is stored
+//! as a zstd-compressed tar archive:
+//!
+//! ```text
+//! manifest.json    format, crate version, file names and options
+//! template/NAME    the template
+//! input/NAME       the input, byte for byte
+//! actual.json      {"records": [...]} or {"error": "..."}
+//! expected.json    the expected records, when given
+//! ```
+//!
+//! Rule actions, extra validators and time limits are code or timing rather than data,
+//! so they are not part of a bundle.
+
+use crate::{DataRecordConversion, TextFsmOptions, WhitespaceNormalization};
+use crate::{Dedupe, FilldownReset, Result, TextFSM, TextFSMParser, TextFsmError};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The archive layout version written to `manifest.json`.
+const FORMAT: u32 = 1;
+
+/// The parse options a [`Bundle`] records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BundleOptions {
+    pub start_state: Option<String>,
+    pub fill_missing_fields: bool,
+    pub max_records: Option<usize>,
+    pub flush_on_eof: Option<bool>,
+    pub max_rule_evaluations: usize,
+    pub strict_validation: bool,
+    pub with_raw: bool,
+    pub normalize_whitespace: WhitespaceNormalization,
+    pub max_list_len: Option<usize>,
+    pub max_field_bytes: Option<usize>,
+    pub dedupe: Dedupe,
+    pub with_ids: bool,
+    pub skip_records: usize,
+    pub sample: Option<f64>,
+    pub filldown_reset: FilldownReset,
+    pub line_mode: bool,
+    /// The WHATWG label of the input encoding.
+    pub encoding: Option<String>,
+    /// Whether record keys were lowercased, as `DataRecordConversion::LowercaseKeys` does.
+    pub lowercase_keys: bool,
+}
+
+impl Default for BundleOptions {
+    fn default() -> Self {
+        BundleOptions::from(&TextFsmOptions::default())
+    }
+}
+
+impl From<&TextFsmOptions> for BundleOptions {
+    fn from(options: &TextFsmOptions) -> Self {
+        BundleOptions {
+            start_state: options.start_state.clone(),
+            fill_missing_fields: options.fill_missing_fields,
+            max_records: options.max_records,
+            flush_on_eof: options.flush_on_eof,
+            max_rule_evaluations: options.max_rule_evaluations,
+            strict_validation: options.strict_validation,
+            with_raw: options.with_raw,
+            normalize_whitespace: options.normalize_whitespace,
+            max_list_len: options.max_list_len,
+            max_field_bytes: options.max_field_bytes,
+            dedupe: options.dedupe,
+            with_ids: options.with_ids,
+            skip_records: options.skip_records,
+            sample: options.sample,
+            filldown_reset: options.filldown_reset,
+            line_mode: options.line_mode,
+            #[cfg(feature = "encoding_rs")]
+            encoding: options.encoding.map(|e| e.name().to_string()),
+            #[cfg(not(feature = "encoding_rs"))]
+            encoding: None,
+            lowercase_keys: false,
+        }
+    }
+}
+
+impl BundleOptions {
+    /// Records that keys are converted with `conversion`.
+    pub fn with_conversion(mut self, conversion: Option<&DataRecordConversion>) -> Self {
+        self.lowercase_keys = matches!(conversion, Some(DataRecordConversion::LowercaseKeys));
+        self
+    }
+
+    /// The engine options to replay with.
+    pub fn to_options(&self) -> Result<TextFsmOptions> {
+        #[cfg_attr(not(feature = "encoding_rs"), allow(unused_mut))]
+        let mut options = TextFsmOptions {
+            start_state: self.start_state.clone(),
+            fill_missing_fields: self.fill_missing_fields,
+            max_records: self.max_records,
+            flush_on_eof: self.flush_on_eof,
+            max_rule_evaluations: self.max_rule_evaluations,
+            strict_validation: self.strict_validation,
+            with_raw: self.with_raw,
+            normalize_whitespace: self.normalize_whitespace,
+            max_list_len: self.max_list_len,
+            max_field_bytes: self.max_field_bytes,
+            dedupe: self.dedupe,
+            with_ids: self.with_ids,
+            skip_records: self.skip_records,
+            sample: self.sample,
+            filldown_reset: self.filldown_reset,
+            line_mode: self.line_mode,
+            ..TextFsmOptions::default()
+        };
+        match &self.encoding {
+            #[cfg(feature = "encoding_rs")]
+            Some(label) => options.encoding = Some(crate::encoding::for_label(label)?),
+            #[cfg(not(feature = "encoding_rs"))]
+            Some(label) => {
+                return Err(TextFsmError::ParseError(format!(
+                    "the bundle's input is {}; decoding it needs the `encoding_rs` feature",
+                    label
+                )))
+            }
+            None => {}
+        }
+        Ok(options)
+    }
+
+    /// The record conversion to replay with.
+    pub fn conversion(&self) -> Option<DataRecordConversion> {
+        self.lowercase_keys
+            .then_some(DataRecordConversion::LowercaseKeys)
+    }
+}
+
+/// What a parse produced: its records, serialized as the CLI prints them, or its error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleOutput {
+    Records(serde_json::Value),
+    Error(String),
+}
+
+/// A template, an input and the options to parse it with, plus the outputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    /// The version of this crate that made the bundle.
+    pub version: String,
+    pub template_name: String,
+    pub template: String,
+    pub input_name: String,
+    pub input: Vec<u8>,
+    pub options: BundleOptions,
+    /// The output when the bundle was made.
+    pub actual: BundleOutput,
+    /// The records the reporter expected, in any layout they chose.
+    pub expected: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    version: String,
+    template: String,
+    input: String,
+    options: BundleOptions,
+}
+
+impl Bundle {
+    /// Parses `input` with `template` and bundles them with the result. A template that
+    /// fails to compile is bundled too, with the error as its output.
+    pub fn new(
+        template_name: &str,
+        template: &str,
+        input_name: &str,
+        input: Vec<u8>,
+        options: BundleOptions,
+    ) -> Self {
+        let mut bundle = Bundle {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            template_name: file_name(template_name),
+            template: template.to_string(),
+            input_name: file_name(input_name),
+            input,
+            options,
+            actual: BundleOutput::Error(String::new()),
+            expected: None,
+        };
+        bundle.actual = bundle.run();
+        bundle
+    }
+
+    /// Attaches the records the reporter expected.
+    pub fn expected(mut self, expected: serde_json::Value) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Parses the input again with this version of the crate.
+    pub fn run(&self) -> BundleOutput {
+        let parse = || -> Result<serde_json::Value> {
+            let options = self.options.to_options()?;
+            let parser = TextFSMParser::from_string(&self.template)?;
+            let input = crate::encoding::decode(&self.input, &options);
+            let records = TextFSM::from_parser(parser)
+                .with_options(options)
+                .parse_records(&input, self.options.conversion())?;
+            serde_json::to_value(&records).map_err(|e| TextFsmError::ParseError(e.to_string()))
+        };
+        match parse() {
+            Ok(records) => BundleOutput::Records(records),
+            Err(e) => BundleOutput::Error(e.to_string()),
+        }
+    }
+
+    /// Writes the bundle as a zstd-compressed tar archive.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let manifest = Manifest {
+            format: FORMAT,
+            version: self.version.clone(),
+            template: self.template_name.clone(),
+            input: self.input_name.clone(),
+            options: self.options.clone(),
+        };
+        let mut files = vec![
+            ("manifest.json".to_string(), to_json(&manifest)?),
+            (
+                format!("template/{}", self.template_name),
+                self.template.clone().into_bytes(),
+            ),
+            (format!("input/{}", self.input_name), self.input.clone()),
+            ("actual.json".to_string(), to_json(&self.actual)?),
+        ];
+        if let Some(expected) = &self.expected {
+            files.push(("expected.json".to_string(), to_json(expected)?));
+        }
+        let mut archive = tar::Builder::new(zstd::Encoder::new(writer, 0)?.auto_finish());
+        for (path, data) in files {
+            let mut header = tar::Header::new_ustar();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            archive.append_data(&mut header, path, data.as_slice())?;
+        }
+        archive.into_inner()?.flush()?;
+        Ok(())
+    }
+
+    /// Reads a bundle written by [`Bundle::write_to`].
+    pub fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let mut archive = tar::Archive::new(zstd::Decoder::new(reader)?);
+        let mut files = std::collections::HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let mut data = vec![];
+            entry.read_to_end(&mut data)?;
+            files.insert(path, data);
+        }
+        let mut take = |path: &str| {
+            files
+                .remove(path)
+                .ok_or_else(|| invalid(&format!("no {}", path)))
+        };
+        let manifest: Manifest = from_json(&take("manifest.json")?)?;
+        if manifest.format > FORMAT {
+            return Err(invalid(&format!(
+                "format {} is newer than this version reads",
+                manifest.format
+            )));
+        }
+        let template = String::from_utf8(take(&format!("template/{}", manifest.template))?)
+            .map_err(|_| invalid("the template is not UTF-8"))?;
+        Ok(Bundle {
+            input: take(&format!("input/{}", manifest.input))?,
+            actual: from_json(&take("actual.json")?)?,
+            expected: take("expected.json")
+                .ok()
+                .map(|d| from_json(&d))
+                .transpose()?,
+            version: manifest.version,
+            template_name: manifest.template,
+            template,
+            input_name: manifest.input,
+            options: manifest.options,
+        })
+    }
+
+    /// Writes the bundle to a file.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_to(std::io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    /// Reads a bundle from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read_from(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(|e| TextFsmError::ParseError(e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T> {
+    serde_json::from_slice(data).map_err(|e| invalid(&e.to_string()))
+}
+
+fn invalid(why: &str) -> TextFsmError {
+    TextFsmError::ParseError(format!("invalid bundle: {}", why))
+}
+
+/// The last component of `path`, so a bundle never names files outside its directories.
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || "unnamed".to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
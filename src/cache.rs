@@ -0,0 +1,259 @@
+//! An on-disk cache of parse results, for re-running over captures that did not change.
+//!
+//! Entries are keyed by two SHA-256 hashes: one of the compiled template together with
+//! the options that change its output and this crate's version, and one of the raw
+//! input bytes. They are stored as JSON under `DIR/TEMPLATE_HASH/INPUT_HASH.json`, so
+//! removing a template's directory drops only its entries. A run with options whose
+//! effect cannot be hashed (rule actions, extra validators, time limits, cancel tokens
+//! and `carry_over`) bypasses the cache.
+//!
+//! ```no_run
+//! use asyncfsm::{ResultCache, TextFSM};
+//!
+//! let cache = ResultCache::new("/var/cache/asyncfsm");
+//! let mut fsm = TextFSM::from_file("show_version.textfsm")?;
+//! // Parses the first time; later runs read the stored records instead.
+//! let parsed = cache.parse_file(&mut fsm, "captures/r1/show_version.txt")?;
+//! println!("{} records (cached: {})", parsed.records.len(), parsed.cached);
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, RawText, Records, Result, TextFSM, TextFSMParser, TextFsmOptions, Value};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Where a parse result is stored in a [`ResultCache`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Hash of the template, the relevant options and the crate version, as hex.
+    pub template: String,
+    /// Hash of the input bytes, as hex.
+    pub input: String,
+}
+
+/// A parse result, as stored in or read from a [`ResultCache`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CachedParse {
+    pub records: Records,
+    /// Number of input lines read.
+    pub lines: usize,
+    /// Number of input lines that matched no rule.
+    pub unmatched_lines: usize,
+    /// Number of warnings and validation issues raised while parsing.
+    pub warnings: usize,
+    /// True if the result was read from the cache rather than parsed.
+    pub cached: bool,
+}
+
+/// A directory of stored parse results.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    /// A cache kept under `dir`, which is created on the first store.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ResultCache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The key for parsing `input` with `parser` and `options`, or `None` if the options
+    /// make the result uncacheable.
+    pub fn key(parser: &TextFSMParser, options: &TextFsmOptions, input: &[u8]) -> Option<CacheKey> {
+        Some(CacheKey {
+            template: hex(Sha256::digest(template_identity(parser, options)?)),
+            input: hex(Sha256::digest(input)),
+        })
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir
+            .join(&key.template)
+            .join(format!("{}.json", key.input))
+    }
+
+    /// The stored result for `key`. A missing or unreadable entry is a miss.
+    pub fn get(&self, key: &CacheKey) -> Option<CachedParse> {
+        let text = std::fs::read_to_string(self.path(key)).ok()?;
+        let entry: Entry = serde_json::from_str(&text).ok()?;
+        Some(entry.into_parse())
+    }
+
+    /// Stores `parse` under `key`. The entry is written to a temporary file first, so a
+    /// concurrent reader never sees half of it.
+    pub fn put(&self, key: &CacheKey, parse: &CachedParse) -> Result<()> {
+        let path = self.path(key);
+        let dir = path.parent().expect("entries live in a template directory");
+        std::fs::create_dir_all(dir)?;
+        let text = serde_json::to_string(&Entry::from_parse(parse))
+            .map_err(|e| crate::TextFsmError::ParseError(e.to_string()))?;
+        let partial = dir.join(format!("{}.{}.tmp", key.input, std::process::id()));
+        std::fs::write(&partial, text)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// Parses the file at `path` with `fsm`, or returns the stored result when the same
+    /// template and options have parsed the same bytes before.
+    ///
+    /// `fsm` is reset first. On a miss its result is stored; failing to store it only
+    /// logs a warning. On a hit `fsm` is left without a parse.
+    pub fn parse_file<P: AsRef<Path>>(&self, fsm: &mut TextFSM, path: P) -> Result<CachedParse> {
+        let bytes = std::fs::read(path)?;
+        fsm.reset();
+        let key = ResultCache::key(&fsm.parser, &fsm.options, &bytes);
+        if let Some(hit) = key.as_ref().and_then(|key| self.get(key)) {
+            return Ok(hit);
+        }
+        let input = crate::encoding::decode(&bytes, &fsm.options);
+        let records = fsm.parse_records(&input, None)?;
+        let parse = CachedParse {
+            records,
+            lines: fsm.line_number,
+            unmatched_lines: fsm.unmatched_lines,
+            warnings: fsm.warnings.len() + fsm.validation_issues.len(),
+            cached: false,
+        };
+        if let Some(key) = key {
+            if let Err(e) = self.put(&key, &parse) {
+                tracing::warn!(
+                    "could not store parse result in {}: {}",
+                    self.dir.display(),
+                    e
+                );
+            }
+        }
+        Ok(parse)
+    }
+}
+
+/// Everything about a template and its options that decides the records a parse
+/// produces, written out in a stable form.
+fn template_identity(parser: &TextFSMParser, options: &TextFsmOptions) -> Option<String> {
+    if !options.actions.is_empty()
+        || !options.validators.is_empty()
+        || options.stop_after.is_some()
+        || options.cancel.is_some()
+        || options.carry_over
+    {
+        return None;
+    }
+    let mut text = format!("asyncfsm {}\n", env!("CARGO_PKG_VERSION"));
+    for name in &parser.value_names {
+        let _ = writeln!(text, "{}", parser.values[name]);
+    }
+    for name in &parser.state_names {
+        let _ = writeln!(text, "\n{}", name);
+        // The expanded pattern too, since a rule's macros can be registered in code.
+        for rule in parser.states[name].rules() {
+            let _ = writeln!(text, "  {}\n    {}", rule, rule._expanded_rule_match);
+        }
+    }
+    let _ = writeln!(
+        text,
+        "\neof={} flags={:?}",
+        parser.explicit_eof, parser.regex_flags
+    );
+    let _ = writeln!(
+        text,
+        "fill={} start={:?} max_records={:?} flush={:?} max_rules={} strict={} raw={} \
+         whitespace={:?} max_list={:?} max_bytes={:?} dedupe={:?} ids={} filldown={:?} \
+         line_mode={} skip={} sample={:?}",
+        options.fill_missing_fields,
+        options.start_state,
+        options.max_records,
+        options.flush_on_eof,
+        options.max_rule_evaluations,
+        options.strict_validation,
+        options.with_raw,
+        options.normalize_whitespace,
+        options.max_list_len,
+        options.max_field_bytes,
+        options.dedupe,
+        options.with_ids,
+        options.filldown_reset,
+        options.line_mode,
+        options.skip_records,
+        options.sample,
+    );
+    #[cfg(feature = "encoding_rs")]
+    let _ = writeln!(text, "encoding={:?}", options.encoding.map(|e| e.name()));
+    Some(text)
+}
+
+fn hex(bytes: impl AsRef<[u8]>) -> String {
+    bytes.as_ref().iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+/// The stored form of a [`CachedParse`]. Unlike a record's usual serialization it keeps
+/// the record key and the schema.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    schema: Vec<String>,
+    records: Vec<EntryRecord>,
+    lines: usize,
+    unmatched_lines: usize,
+    warnings: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryRecord {
+    fields: HashMap<String, Value>,
+    record_key: Option<String>,
+    raw: Option<RawText>,
+    index: Option<usize>,
+    id: Option<String>,
+}
+
+impl Entry {
+    fn from_parse(parse: &CachedParse) -> Self {
+        Entry {
+            schema: parse.records.schema().to_vec(),
+            records: parse
+                .records
+                .iter()
+                .map(|r| EntryRecord {
+                    fields: r.fields.clone(),
+                    record_key: r.record_key.clone(),
+                    raw: r.raw.clone(),
+                    index: r.index,
+                    id: r.id.clone(),
+                })
+                .collect(),
+            lines: parse.lines,
+            unmatched_lines: parse.unmatched_lines,
+            warnings: parse.warnings,
+        }
+    }
+
+    fn into_parse(self) -> CachedParse {
+        let records = self
+            .records
+            .into_iter()
+            .map(|r| DataRecord {
+                fields: r.fields,
+                record_key: r.record_key,
+                raw: r.raw,
+                index: r.index,
+                id: r.id,
+            })
+            .collect();
+        CachedParse {
+            records: Records::new(records, self.schema),
+            lines: self.lines,
+            unmatched_lines: self.unmatched_lines,
+            warnings: self.warnings,
+            cached: true,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Stopping a parse from another thread or task.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag a parse checks between lines; see [`TextFsmOptions::cancel_token`].
+///
+/// Clones share the flag, so one clone goes into the options and another stays with
+/// whoever may cancel: a Ctrl-C handler, a request timeout, a UI button. Once cancelled a
+/// token stays cancelled; use a new one for the next parse.
+///
+/// [`TextFsmOptions::cancel_token`]: crate::TextFsmOptions::cancel_token
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every parse using this token to stop before its next line.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -1,8 +1,16 @@
-use crate::{Result, TextFsmError};
+use crate::{
+    Deprecation, Records, Result, Severity, TextFSM, TextFSMParser, TextFsmError, TextFsmOptions,
+    Value, Warnings,
+};
 use fancy_regex::Regex;
-use log::{debug, trace};
-use std::collections::HashMap;
-use std::path::Path;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, error, info, trace, warn};
+
+/// Compiled templates keyed by path, shared by clones of a `CliTable`.
+pub type TemplateCache = Arc<RwLock<HashMap<PathBuf, TextFSMParser>>>;
 
 /// Represents a CLI table index file parsed into memory.
 #[derive(Debug, Clone)]
@@ -20,6 +28,147 @@ pub struct CliTable {
     pub tables: Vec<ParsedCliTable>,
     /// Map of platform names to their associated regex rules for command matching.
     pub platform_regex_rules: HashMap<String, Vec<CliTableRegexRule>>,
+    /// Templates used by `detect` to recognize the platform of a capture.
+    /// When empty, each platform's `show version` template from the index is used.
+    pub fingerprints: Vec<PlatformFingerprint>,
+    /// Templates compiled so far, so each file is only read and compiled once.
+    pub template_cache: TemplateCache,
+    /// Options every parse through the table runs with.
+    pub options: TextFsmOptions,
+}
+
+/// A template that recognizes output produced by a specific platform.
+#[derive(Debug, Clone)]
+pub struct PlatformFingerprint {
+    /// The platform name, as used in the index.
+    pub platform: String,
+    /// The compiled fingerprint template.
+    pub template: TextFSMParser,
+}
+
+/// The platform and command guessed from a capture by `CliTable::detect`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedCommand {
+    /// The platform recognized by the fingerprint templates.
+    pub platform: String,
+    /// The echoed command line found in the input.
+    pub command: String,
+    /// Directory containing the templates of the matching index.
+    pub directory: String,
+    /// The index row matching the platform and command.
+    pub row: CliTableRow,
+}
+
+/// The records a template produced, and how well it fit the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateMatch {
+    /// The template file that produced the records.
+    pub template: PathBuf,
+    /// The parsed records.
+    pub records: Records,
+    /// Warnings and validation issues raised while parsing.
+    pub warnings: Warnings,
+    /// Number of input lines read.
+    pub lines: usize,
+    /// Number of input lines that matched no rule.
+    pub unmatched_lines: usize,
+    /// The template's `# Deprecated:` header, if it has one.
+    pub deprecation: Option<Deprecation>,
+}
+
+impl TemplateMatch {
+    /// Fraction of input lines that matched no rule, between 0 and 1.
+    pub fn unmatched_ratio(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.unmatched_lines as f64 / self.lines as f64
+        }
+    }
+}
+
+/// The best-fitting template found by `CliTable::parse_best`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestMatch {
+    /// The platform of the winning index row.
+    pub platform: String,
+    /// The full command of the winning index row, e.g. `show version`.
+    pub command: String,
+    /// The share of input lines the template matched, between 0 and 1.
+    pub score: f64,
+    /// The records and the template that produced them.
+    pub result: TemplateMatch,
+}
+
+/// What `CliTable::audit` found about the templates of an index.
+///
+/// Serialized, it is a compatibility report that can be kept per ntc-templates release
+/// and compared as the index or this crate is upgraded.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexAudit {
+    /// The version of this crate the templates were compiled with.
+    pub engine_version: String,
+    /// The index files audited.
+    pub indexes: Vec<String>,
+    /// Number of distinct template files the index lists.
+    pub templates: usize,
+    /// Number of those that compile.
+    pub compiled: usize,
+    /// The platforms of the index, sorted by name.
+    pub platforms: Vec<PlatformCoverage>,
+    /// The templates that are missing or fail to compile, sorted by path.
+    pub failures: Vec<TemplateFailure>,
+}
+
+/// How many commands of one platform an index covers; see [`IndexAudit`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlatformCoverage {
+    pub platform: String,
+    /// Number of distinct commands, without `[[abbrev]]` markers.
+    pub commands: usize,
+}
+
+/// A template `CliTable::audit` could not compile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateFailure {
+    pub template: String,
+    pub error: String,
+}
+
+impl IndexAudit {
+    /// True if every template the index lists compiles.
+    pub fn is_compatible(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for IndexAudit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "asyncfsm {}: {} templates, {} compile, {} fail",
+            self.engine_version,
+            self.templates,
+            self.compiled,
+            self.failures.len()
+        )?;
+        writeln!(f, "platforms:")?;
+        for coverage in &self.platforms {
+            let plural = if coverage.commands == 1 { "" } else { "s" };
+            writeln!(
+                f,
+                "  {}: {} command{}",
+                coverage.platform, coverage.commands, plural
+            )?;
+        }
+        if !self.failures.is_empty() {
+            writeln!(f, "failures:")?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "  {}: {}", failure.template, failure.error)?;
+        }
+        Ok(())
+    }
 }
 
 /// A rule for matching a command to a specific row in an index table.
@@ -47,10 +196,7 @@ pub struct CliTableRow {
 }
 
 impl ParsedCliTable {
-    fn parse(fname: &Path) -> Result<Vec<CliTableRow>> {
-        use std::io::BufReader;
-        let file = std::fs::File::open(fname)?;
-        let reader = BufReader::new(file);
+    fn parse<R: std::io::Read>(reader: R) -> Result<Vec<CliTableRow>> {
         let mut rows: Vec<CliTableRow> = vec![];
         let mut rdr = csv::ReaderBuilder::new()
             .comment(Some(b'#'))
@@ -109,9 +255,15 @@ impl ParsedCliTable {
     pub fn from_file<P: AsRef<Path>>(fname: P) -> Result<Self> {
         let path = fname.as_ref();
         debug!("Loading cli table from {}", path.display());
-        let rows = Self::parse(path)?;
+        let file = std::fs::File::open(path)?;
+        Self::from_reader(path, std::io::BufReader::new(file))
+    }
+
+    /// Parses index content read from `reader`; `fname` locates the template directory.
+    pub fn from_reader<R: std::io::Read>(fname: &Path, reader: R) -> Result<Self> {
+        let rows = Self::parse(reader)?;
         Ok(ParsedCliTable {
-            fname: path.to_string_lossy().into_owned(),
+            fname: fname.to_string_lossy().into_owned(),
             rows,
         })
     }
@@ -141,7 +293,7 @@ impl CliTable {
     }
 
     /// Expands command abbreviations inside `[[ ]]` into nested optional regex groups.
-    fn expand_brackets(input: &str) -> String {
+    pub(crate) fn expand_brackets(input: &str) -> String {
         let mut result = String::with_capacity(input.len());
         let mut current_pos = 0;
 
@@ -194,10 +346,393 @@ impl CliTable {
         None
     }
 
+    /// Registers a fingerprint template used by `detect` to recognize `platform`.
+    pub fn add_fingerprint(&mut self, platform: &str, template: &str) -> Result<()> {
+        let template = TextFSMParser::from_string(template)?;
+        self.fingerprints.push(PlatformFingerprint {
+            platform: platform.to_string(),
+            template,
+        });
+        Ok(())
+    }
+
+    /// Returns the index platforms in a stable order.
+    pub fn platforms(&self) -> Vec<&str> {
+        let mut platforms: Vec<&str> = self
+            .platform_regex_rules
+            .keys()
+            .map(|p| p.as_str())
+            .collect();
+        platforms.sort_unstable();
+        platforms
+    }
+
+    /// Returns a command with its `[[abbrev]]` markers removed, e.g. `sh[[ow]] ver` -> `show ver`.
+    pub fn full_command(command: &str) -> String {
+        command.replace("[[", "").replace("]]", "")
+    }
+
+    /// Returns the full (unabbreviated) commands the index knows for `platform`, sorted.
+    pub fn commands_for_platform(&self, platform: &str) -> Vec<String> {
+        let mut commands: Vec<String> = self
+            .platform_regex_rules
+            .get(platform)
+            .into_iter()
+            .flatten()
+            .map(|rule| {
+                Self::full_command(&self.tables[rule.table_index].rows[rule.row_index].command)
+            })
+            .collect();
+        commands.sort();
+        commands.dedup();
+        commands
+    }
+
+    /// Loads the first existing template of the index row matching `platform` and `cmd`.
+    fn load_template_for_command(&self, platform: &str, cmd: &str) -> Option<TextFSMParser> {
+        self.template_paths_for_command(platform, cmd)
+            .iter()
+            .filter(|path| path.exists())
+            .find_map(|path| self.load_template(path).ok())
+    }
+
+    /// Paths of the templates listed by the index row matching `platform` and `cmd`.
+    fn template_paths_for_command(&self, platform: &str, cmd: &str) -> Vec<PathBuf> {
+        match self.get_template_for_command(platform, cmd) {
+            Some((dir, row)) => row
+                .templates
+                .iter()
+                .map(|name| Path::new(&dir).join(name))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn cached_template(&self, path: &Path) -> Option<TextFSMParser> {
+        self.template_cache.read().ok()?.get(path).cloned()
+    }
+
+    fn cache_template(&self, path: PathBuf, template: &TextFSMParser) {
+        if let Ok(mut cache) = self.template_cache.write() {
+            cache.insert(path, template.clone());
+        }
+    }
+
+    /// Compiles the template at `path`, or returns the cached copy.
+    fn load_template(&self, path: &Path) -> Result<TextFSMParser> {
+        if let Some(template) = self.cached_template(path) {
+            return Ok(template);
+        }
+        let template = TextFSMParser::from_file(path)?;
+        // Logged once per template, when it is first compiled.
+        if let Some(deprecation) = template.deprecation() {
+            let path = path.display();
+            match deprecation.severity {
+                Severity::Info => info!("template {} is {}", path, deprecation),
+                Severity::Warning => warn!("template {} is {}", path, deprecation),
+                Severity::Error => error!("template {} is {}", path, deprecation),
+            }
+        }
+        self.cache_template(path.to_path_buf(), &template);
+        Ok(template)
+    }
+
+    /// Returns the compiled template the index selects for `platform` and `cmd`.
+    pub fn template(&self, platform: &str, cmd: &str) -> Result<TextFSMParser> {
+        self.load_template_for_command(platform, cmd)
+            .ok_or_else(|| Self::template_not_found(platform, cmd))
+    }
+
+    fn template_not_found(platform: &str, cmd: &str) -> TextFsmError {
+        TextFsmError::TemplateNotFound(format!(
+            "no template in index for platform {} and command {}",
+            platform, cmd
+        ))
+    }
+
+    /// Parses `input` with the template the index selects for `platform` and `cmd`.
+    ///
+    /// See [`parse_match`](Self::parse_match) for how a row with several templates is
+    /// handled.
+    pub fn parse(&self, platform: &str, cmd: &str, input: &str) -> Result<Records> {
+        Ok(self.parse_match(platform, cmd, input)?.records)
+    }
+
+    /// Parses `input` like `parse`, also reporting which template was used.
+    ///
+    /// When the index row lists several templates, each one that exists and compiles
+    /// is tried in order and the first that yields records wins. If none does, the
+    /// first successful (empty) parse is returned, or else the first parse error.
+    pub fn parse_match(&self, platform: &str, cmd: &str, input: &str) -> Result<TemplateMatch> {
+        let candidates = self
+            .template_paths_for_command(platform, cmd)
+            .into_iter()
+            .filter(|path| path.exists())
+            .map(|path| {
+                let template = self.load_template(&path);
+                (path, template)
+            })
+            .collect();
+        Self::parse_candidates(candidates, &self.options, input)
+            .ok_or_else(|| Self::template_not_found(platform, cmd))?
+    }
+
+    /// Runs each compiled candidate over `input` until one yields records.
+    fn parse_candidates(
+        candidates: Vec<(PathBuf, Result<TextFSMParser>)>,
+        options: &TextFsmOptions,
+        input: &str,
+    ) -> Option<Result<TemplateMatch>> {
+        let mut fallback: Option<Result<TemplateMatch>> = None;
+        for (path, template) in candidates {
+            let Ok(template) = template else {
+                continue;
+            };
+            let result = Self::run_template(path, template, options, input);
+            match &result {
+                Ok(found) if !found.records.is_empty() => {
+                    debug!("Parsed with {}", found.template.display());
+                    return Some(result);
+                }
+                Ok(_) if !matches!(fallback, Some(Ok(_))) => fallback = Some(result),
+                Err(_) if fallback.is_none() => fallback = Some(result),
+                _ => {}
+            }
+        }
+        fallback
+    }
+
+    fn run_template(
+        path: PathBuf,
+        template: TextFSMParser,
+        options: &TextFsmOptions,
+        input: &str,
+    ) -> Result<TemplateMatch> {
+        let deprecation = template.deprecation();
+        let mut fsm = TextFSM::from_parser(template).with_options(options.clone());
+        let records = fsm.parse_records(input, None)?;
+        let outcome = fsm.outcome(records);
+        Ok(TemplateMatch {
+            template: path,
+            records: outcome.records,
+            warnings: outcome.warnings,
+            lines: fsm.line_number,
+            unmatched_lines: fsm.unmatched_lines,
+            deprecation,
+        })
+    }
+
+    fn fingerprint_candidates(&self) -> Vec<PlatformFingerprint> {
+        if !self.fingerprints.is_empty() {
+            return self.fingerprints.clone();
+        }
+        self.platforms()
+            .into_iter()
+            .filter_map(|platform| {
+                let template = self.load_template_for_command(platform, "show version")?;
+                Some(PlatformFingerprint {
+                    platform: platform.to_string(),
+                    template,
+                })
+            })
+            .collect()
+    }
+
+    /// Guesses the platform that produced `input` using the fingerprint templates.
+    ///
+    /// Each fingerprint is scored by the number of non-empty values it extracts; the
+    /// highest-scoring platform wins.
+    pub fn guess_platform(&self, input: &str) -> Option<String> {
+        let mut best: Option<(usize, String)> = None;
+        for fingerprint in self.fingerprint_candidates() {
+            let mut fsm = TextFSM::from_parser(fingerprint.template);
+            let Ok(records) = fsm.parse_records(input, None) else {
+                continue;
+            };
+            let score: usize = records
+                .iter()
+                .flat_map(|rec| rec.fields.values())
+                .filter(|v| match v {
+                    Value::Single(s) => !s.is_empty(),
+                    Value::List(l) => !l.is_empty(),
+                })
+                .count();
+            trace!("Fingerprint {} scored {}", &fingerprint.platform, score);
+            if score > 0 && best.as_ref().is_none_or(|(b, _)| score > *b) {
+                best = Some((score, fingerprint.platform));
+            }
+        }
+        best.map(|(_, platform)| platform)
+    }
+
+    /// Parses `input` with every template of `platform_hint` (or of every platform)
+    /// and returns the one that fits best, for captures whose command is unknown.
+    ///
+    /// A template scores the share of input lines it matched, or 0 if it produced no
+    /// records. Ties go to the template with more records, then to the earlier index
+    /// row. Templates that do not compile or fail to parse are skipped.
+    pub fn parse_best(&self, platform_hint: Option<&str>, input: &str) -> Option<BestMatch> {
+        let mut best: Option<BestMatch> = None;
+        let mut tried = std::collections::HashSet::new();
+        for table in &self.tables {
+            let dir = Self::get_directory(&table.fname).unwrap_or_default();
+            for row in &table.rows {
+                let Some(platform) = &row.platform else {
+                    continue;
+                };
+                if platform_hint.is_some_and(|hint| hint != platform) {
+                    continue;
+                }
+                for name in &row.templates {
+                    let path = Path::new(&dir).join(name);
+                    if !path.exists() || !tried.insert(path.clone()) {
+                        continue;
+                    }
+                    let Ok(template) = self.load_template(&path) else {
+                        continue;
+                    };
+                    let Ok(result) = Self::run_template(path, template, &self.options, input)
+                    else {
+                        continue;
+                    };
+                    let score = if result.records.is_empty() {
+                        0.0
+                    } else {
+                        1.0 - result.unmatched_ratio()
+                    };
+                    trace!("Template {} scored {}", result.template.display(), score);
+                    let better = best.as_ref().is_none_or(|b| {
+                        score > b.score
+                            || (score == b.score && result.records.len() > b.result.records.len())
+                    });
+                    if better {
+                        best = Some(BestMatch {
+                            platform: platform.clone(),
+                            command: Self::full_command(&row.command),
+                            score,
+                            result,
+                        });
+                    }
+                }
+            }
+        }
+        best.filter(|b| b.score > 0.0)
+    }
+
+    /// Extracts commands echoed after a device prompt (e.g. `router#show version`).
+    pub fn echoed_commands(input: &str) -> Vec<String> {
+        let prompt =
+            regex::Regex::new(r"^[\w.:@()/-]+[#>$%]\s*(\S.*?)\s*$").expect("prompt regex is valid");
+        input
+            .lines()
+            .filter_map(|line| prompt.captures(line))
+            .map(|caps| caps[1].to_string())
+            .collect()
+    }
+
+    /// Guesses the platform and command of a capture with no other hints.
+    ///
+    /// The platform is recognized with the fingerprint templates, then the first echoed
+    /// command line that matches an index entry for that platform selects the row.
+    pub fn detect(&self, input: &str) -> Option<DetectedCommand> {
+        let platform = self.guess_platform(input)?;
+        debug!("Detected platform {}", &platform);
+        Self::echoed_commands(input)
+            .into_iter()
+            .find_map(|command| {
+                let (directory, row) = self.get_template_for_command(&platform, &command)?;
+                Some(DetectedCommand {
+                    platform: platform.clone(),
+                    command,
+                    directory,
+                    row,
+                })
+            })
+    }
+
+    /// Sets the options parses through this table run with.
+    pub fn with_options(mut self, options: TextFsmOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The options templates are run with.
+    pub fn options(&self) -> &TextFsmOptions {
+        &self.options
+    }
+
     /// Loads a CLI table from an index file and compiles all command regexes.
     pub fn from_file<P: AsRef<Path>>(fname: P) -> Result<Self> {
-        let parsed_cli_table = ParsedCliTable::from_file(fname)?;
-        let tables = vec![parsed_cli_table];
+        Self::from_tables(vec![ParsedCliTable::from_file(fname)?])
+    }
+
+    /// Re-reads every index file and drops all cached templates.
+    ///
+    /// Fingerprints are kept. On error the table is left unchanged.
+    pub fn reload(&mut self) -> Result<()> {
+        let tables = self
+            .tables
+            .iter()
+            .map(|table| ParsedCliTable::from_file(&table.fname))
+            .collect::<Result<Vec<_>>>()?;
+        let reloaded = Self::from_tables(tables)?;
+        self.tables = reloaded.tables;
+        self.platform_regex_rules = reloaded.platform_regex_rules;
+        if let Ok(mut cache) = self.template_cache.write() {
+            cache.clear();
+        }
+        Ok(())
+    }
+
+    /// Drops the cached compilation of the template at `path`, if any.
+    ///
+    /// Paths are compared by file, so an absolute path matches the relative one the
+    /// index produced.
+    pub fn invalidate_template(&self, path: &Path) {
+        if let Ok(mut cache) = self.template_cache.write() {
+            cache.retain(|cached, _| !same_file(cached, path));
+        }
+    }
+
+    /// Compiles every template the index lists and reports which fail, with the
+    /// platforms and commands covered.
+    pub fn audit(&self) -> IndexAudit {
+        let mut paths = BTreeMap::new();
+        for table in &self.tables {
+            let dir = Self::get_directory(&table.fname).unwrap_or_default();
+            for template in table.rows.iter().flat_map(|row| &row.templates) {
+                let path = Path::new(&dir).join(template);
+                paths.insert(path.display().to_string(), path);
+            }
+        }
+        let failures: Vec<TemplateFailure> = paths
+            .iter()
+            .filter_map(|(name, path)| {
+                let error = self.load_template(path).err()?;
+                Some(TemplateFailure {
+                    template: name.clone(),
+                    error: error.to_string(),
+                })
+            })
+            .collect();
+        IndexAudit {
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            indexes: self.tables.iter().map(|t| t.fname.clone()).collect(),
+            templates: paths.len(),
+            compiled: paths.len() - failures.len(),
+            platforms: self
+                .platforms()
+                .into_iter()
+                .map(|platform| PlatformCoverage {
+                    platform: platform.to_string(),
+                    commands: self.commands_for_platform(platform).len(),
+                })
+                .collect(),
+            failures,
+        }
+    }
+
+    fn from_tables(tables: Vec<ParsedCliTable>) -> Result<Self> {
         let mut platform_regex_rules: HashMap<String, Vec<CliTableRegexRule>> = Default::default();
 
         for (table_index, table) in tables.iter().enumerate() {
@@ -223,7 +758,116 @@ impl CliTable {
         Ok(CliTable {
             platform_regex_rules,
             tables,
+            fingerprints: vec![],
+            template_cache: Default::default(),
+            options: TextFsmOptions::default(),
+        })
+    }
+}
+
+/// Whether two paths name the same file, which need not exist any more.
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    let parent = |p: &Path| {
+        let dir = match p.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::canonicalize(dir).ok()
+    };
+    a.file_name() == b.file_name() && parent(a).is_some() && parent(a) == parent(b)
+}
+
+#[cfg(feature = "async")]
+impl CliTable {
+    /// Loads a CLI table like `from_file`, reading the index without blocking the runtime.
+    pub async fn from_file_async<P: AsRef<Path>>(fname: P) -> Result<Self> {
+        let path = fname.as_ref();
+        debug!("Loading cli table from {}", path.display());
+        let content = tokio::fs::read(path).await?;
+        Self::from_tables(vec![ParsedCliTable::from_reader(path, content.as_slice())?])
+    }
+
+    /// Compiles the templates at `paths` concurrently on the blocking pool, keeping their order.
+    async fn load_templates_async(
+        &self,
+        paths: Vec<PathBuf>,
+    ) -> Result<Vec<Result<TextFSMParser>>> {
+        let mut results: Vec<Option<Result<TextFSMParser>>> = Vec::with_capacity(paths.len());
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, path) in paths.into_iter().enumerate() {
+            match self.cached_template(&path) {
+                Some(template) => results.push(Some(Ok(template))),
+                None => {
+                    results.push(None);
+                    tasks.spawn_blocking(move || {
+                        let template = TextFSMParser::from_file(&path);
+                        (index, path, template)
+                    });
+                }
+            }
+        }
+        while let Some(joined) = tasks.join_next().await {
+            let (index, path, template) =
+                joined.map_err(|e| TextFsmError::InternalError(e.to_string()))?;
+            if let Ok(template) = &template {
+                self.cache_template(path, template);
+            }
+            results[index] = Some(template);
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Compiles every template the index references, concurrently, into the template cache.
+    ///
+    /// Returns the number of templates compiled. Templates missing on disk are skipped; a
+    /// template that fails to compile is an error.
+    pub async fn preload_async(&self) -> Result<usize> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for table in &self.tables {
+            let dir = Self::get_directory(&table.fname).unwrap_or_default();
+            for row in &table.rows {
+                paths.extend(row.templates.iter().map(|name| Path::new(&dir).join(name)));
+            }
+        }
+        paths.retain(|path| path.exists());
+        paths.sort();
+        paths.dedup();
+        let templates = self.load_templates_async(paths).await?;
+        let count = templates.len();
+        templates.into_iter().collect::<Result<Vec<_>>>()?;
+        Ok(count)
+    }
+
+    /// Parses the input read from `reader` with the template the index selects.
+    ///
+    /// The row's candidate templates are compiled concurrently while the input is read,
+    /// then tried in order as with `parse`.
+    pub async fn parse_async<R>(&self, platform: &str, cmd: &str, mut reader: R) -> Result<Records>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let mut paths = self.template_paths_for_command(platform, cmd);
+        paths.retain(|path| path.exists());
+        let mut input = String::new();
+        let (read, templates) = tokio::join!(
+            reader.read_to_string(&mut input),
+            self.load_templates_async(paths.clone())
+        );
+        read?;
+        let candidates = paths.into_iter().zip(templates?).collect();
+        let options = self.options.clone();
+        let found = tokio::task::spawn_blocking(move || {
+            Self::parse_candidates(candidates, &options, &input)
         })
+        .await
+        .map_err(|e| TextFsmError::InternalError(e.to_string()))?
+        .ok_or_else(|| Self::template_not_found(platform, cmd))?;
+        Ok(found?.records)
     }
 }
 
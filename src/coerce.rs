@@ -0,0 +1,315 @@
+//! Turning captured text such as `1w2d` or `4.5 Gbps` into canonical numbers.
+//!
+//! A [`Coercions`] maps field names to a [`Coercion`]. Applied as
+//! `DataRecordConversion::Coerce`, it rewrites each chosen field's text to the
+//! number it stands for, written in decimal without units: seconds for durations,
+//! bits per second for rates and bytes for sizes. Plain numbers written with a
+//! locale's separators, such as `1.234,56`, are read as well. Values stay strings, so the
+//! conversion composes with everything that handles records. A value the coercion
+//! can not read is left as it was.
+//!
+//! ```
+//! use asyncfsm::{Coercion, Coercions, DataRecordConversion, TextFSM};
+//!
+//! let template = "Value UPTIME (.+?)\nValue BW (.+)\n\nStart\n  ^up ${UPTIME}, bw ${BW} -> Record\n";
+//! let coercions = Coercions::new()
+//!     .field("UPTIME", Coercion::Duration)
+//!     .field("BW", Coercion::Bandwidth);
+//! let records = TextFSM::from_string(template)?
+//!     .parse_records("up 1w2d, bw 4.5 Gbps\n", Some(DataRecordConversion::Coerce(coercions)))?;
+//! assert_eq!(records[0].fields["UPTIME"].to_string(), "777600");
+//! assert_eq!(records[0].fields["BW"].to_string(), "4500000000");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Value};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// How a field's text is read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Coercion {
+    /// A duration, in seconds: `1w2d`, `2d03h`, `5h30m10s`, `01:02:03`, `3d04:05:06`,
+    /// `1 year, 2 weeks, 3 days, 4 hours, 5 minutes`. A year counts as 365 days.
+    Duration,
+    /// A rate, in bits per second: `4.5 Gbps`, `100 Mbit/sec`, `1000 Kb/s`, `10M`.
+    /// Prefixes are decimal, so `1 Kbps` is 1000; a capital `B`, as in `10 MB/s`, is a
+    /// byte and not read.
+    Bandwidth,
+    /// A size, in bytes: `1024 KB`, `1.5 MiB`, `4G`, `512 bytes`. Prefixes are binary,
+    /// as devices use them for memory and storage, so `1 KB` is 1024.
+    Bytes,
+    /// A number with thousands separators or a decimal comma, such as `1,234,567`,
+    /// `1.234,56`, `1 234 567` or `1'234.5`, written with neither; see [`DecimalMark`].
+    Number(DecimalMark),
+    /// A point in time, written in RFC 3339 or as Unix time; see [`crate::timestamp`].
+    #[cfg(feature = "timestamps")]
+    Timestamp(crate::Timestamps),
+}
+
+impl Coercion {
+    /// The canonical number `text` stands for, or `None` if it can not be read.
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let text = text.trim();
+        match self {
+            Coercion::Duration => duration(text),
+            Coercion::Bandwidth => bandwidth(text),
+            Coercion::Bytes => bytes(text),
+            #[cfg(feature = "timestamps")]
+            Coercion::Timestamp(timestamps) => return timestamps.parse(text),
+            Coercion::Number(mark) => return number(text, *mark),
+        }
+        .map(|n| n.to_string())
+    }
+}
+
+/// Which of `.` and `,` [`Coercion::Number`] takes as the decimal mark; the other one
+/// separates thousands, as do spaces and `'`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecimalMark {
+    /// Decided from the text: the last of `.` and `,` when both appear, a mark that
+    /// appears more than once separates thousands. A single mark followed by exactly
+    /// three digits, as in `1,234`, could be either, so such a value is not read.
+    #[default]
+    Auto,
+    /// `1,234.5`
+    Point,
+    /// `1.234,5`
+    Comma,
+}
+
+/// The fields to coerce and how; see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coercions {
+    fields: Vec<(String, Coercion)>,
+}
+
+impl Coercions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coerces `field` (in any case) with `coercion`.
+    pub fn field(mut self, field: impl Into<String>, coercion: Coercion) -> Self {
+        self.fields.push((field.into(), coercion));
+        self
+    }
+
+    /// Coerces the configured fields of `record`, including list items.
+    pub fn apply(&self, record: &mut DataRecord) {
+        for (field, value) in record.fields.iter_mut() {
+            let Some((_, coercion)) = self
+                .fields
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            else {
+                continue;
+            };
+            let coerce = |text: &mut String| {
+                if let Some(number) = coercion.apply(text) {
+                    *text = number;
+                }
+            };
+            match value {
+                Value::Single(text) => coerce(text),
+                Value::List(items) => items.iter_mut().for_each(coerce),
+            }
+        }
+    }
+}
+
+/// An exact non-negative decimal: `digits / 10^scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Decimal {
+    digits: u128,
+    scale: u32,
+}
+
+impl Decimal {
+    fn parse(text: &str) -> Option<Self> {
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if whole.is_empty() || !(whole.bytes().chain(fraction.bytes())).all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Decimal {
+            digits: format!("{}{}", whole, fraction).parse().ok()?,
+            scale: u32::try_from(fraction.len()).ok()?,
+        })
+    }
+
+    fn times(self, factor: u128) -> Option<Self> {
+        Some(Decimal {
+            digits: self.digits.checked_mul(factor)?,
+            scale: self.scale,
+        })
+    }
+
+    fn plus(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: Self| d.digits.checked_mul(10u128.checked_pow(scale - d.scale)?);
+        Some(Decimal {
+            digits: widen(self)?.checked_add(widen(other)?)?,
+            scale,
+        })
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = 10u128.pow(self.scale);
+        let (whole, fraction) = (self.digits / unit, self.digits % unit);
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let fraction = format!("{:0width$}", fraction, width = self.scale as usize);
+        write!(f, "{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+fn number(text: &str, mark: DecimalMark) -> Option<String> {
+    const GROUPING: [char; 5] = [',', '.', ' ', '\'', '\u{a0}'];
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.strip_prefix('+').unwrap_or(text)),
+    };
+    let text = text.replace('\u{202f}', "\u{a0}");
+    let decimal = match mark {
+        DecimalMark::Point => Some('.'),
+        DecimalMark::Comma => Some(','),
+        DecimalMark::Auto => match text.rfind(['.', ',']) {
+            None => None,
+            Some(last) => {
+                let mark = text[last..].chars().next()?;
+                let other = if mark == '.' { ',' } else { '.' };
+                let (before, after) = (&text[..last], &text[last + 1..]);
+                if text.contains(other) {
+                    Some(mark)
+                } else if before.contains(mark) {
+                    None
+                } else if after.len() != 3 || before.trim_start_matches('0').is_empty() {
+                    Some(mark)
+                } else {
+                    return None;
+                }
+            }
+        },
+    };
+    let (whole, fraction) = match decimal.and_then(|mark| text.split_once(mark)) {
+        Some((_, "")) => return None,
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+    // Thousands are separated by a single kind of separator, every three digits.
+    let separators: Vec<char> = whole.chars().filter(|c| GROUPING.contains(c)).collect();
+    if separators.iter().any(|c| *c != separators[0]) {
+        return None;
+    }
+    let groups: Vec<&str> = whole.split(&GROUPING[..]).collect();
+    if !(1..=3).contains(&groups[0].len()) && groups.len() > 1
+        || groups[1..].iter().any(|group| group.len() != 3)
+    {
+        return None;
+    }
+    let digits = groups.concat();
+    let number = match fraction {
+        Some(fraction) => Decimal::parse(&format!("{}.{}", digits, fraction))?,
+        None => Decimal::parse(&digits)?,
+    };
+    let sign = if number.digits == 0 { "" } else { sign };
+    Some(format!("{}{}", sign, number))
+}
+
+fn duration(text: &str) -> Option<Decimal> {
+    // Longer units come first, so `2d03h` reads as `2d` then `03h`.
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token = TOKEN.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(?:,|\s|and\s)*(?:(\d+):(\d{2})(?::(\d{2}))?|(\d+(?:\.\d+)?)\s*(years?|yrs?|y|weeks?|wks?|w|days?|d|hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s))",
+        )
+        .unwrap()
+    });
+    let mut rest = text.trim_end_matches([',', ' ']);
+    let mut total = Decimal::parse("0")?;
+    while !rest.is_empty() {
+        let caps = token.captures(rest)?;
+        let seconds = match caps.get(1) {
+            Some(hours) => {
+                let part = |i: usize| caps.get(i).map_or(Some(0), |m| m.as_str().parse().ok());
+                let (h, m, s): (u128, u128, u128) =
+                    (hours.as_str().parse().ok()?, part(2)?, part(3)?);
+                Decimal::parse(&(h * 3600 + m * 60 + s).to_string())?
+            }
+            None => {
+                let factor = match caps[5].to_ascii_lowercase().trim_end_matches('s') {
+                    "y" | "yr" | "year" => 365 * 86400,
+                    "w" | "wk" | "week" => 7 * 86400,
+                    "d" | "day" => 86400,
+                    "h" | "hr" | "hour" => 3600,
+                    "m" | "min" | "minute" => 60,
+                    _ => 1,
+                };
+                Decimal::parse(&caps[4])?.times(factor)?
+            }
+        };
+        total = total.plus(seconds)?;
+        rest = &rest[caps.get(0)?.end()..];
+        // A unit must end its word: `5mo` is no duration.
+        if rest.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+    }
+    (!text.is_empty()).then_some(total)
+}
+
+/// Splits `4.5 Gbps` into the number and the unit after it, in lowercase.
+fn number_and_unit(text: &str) -> Option<(Decimal, String)> {
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let number = Decimal::parse(&text[..end])?;
+    Some((number, text[end..].trim().to_ascii_lowercase()))
+}
+
+fn bandwidth(text: &str) -> Option<Decimal> {
+    // A capital `B` is a byte, as in `MBps`.
+    if text.contains('B') {
+        return None;
+    }
+    let (number, unit) = number_and_unit(text)?;
+    let (prefix, rest) = match unit.chars().next() {
+        Some(c @ ('k' | 'm' | 'g' | 't')) => (Some(c), &unit[1..]),
+        _ => (None, unit.as_str()),
+    };
+    if !matches!(
+        rest,
+        "" | "b" | "bps" | "b/s" | "bit" | "bits" | "bit/s" | "bits/s" | "bit/sec" | "bits/sec"
+    ) {
+        return None;
+    }
+    let factor = match prefix {
+        None => 1,
+        Some('k') => 1_000,
+        Some('m') => 1_000_000,
+        Some('g') => 1_000_000_000,
+        Some(_) => 1_000_000_000_000,
+    };
+    number.times(factor)
+}
+
+fn bytes(text: &str) -> Option<Decimal> {
+    let (number, unit) = number_and_unit(text)?;
+    let (power, rest) = match unit.chars().next() {
+        Some('k') => (1, &unit[1..]),
+        Some('m') => (2, &unit[1..]),
+        Some('g') => (3, &unit[1..]),
+        Some('t') => (4, &unit[1..]),
+        Some('p') => (5, &unit[1..]),
+        _ => (0, unit.as_str()),
+    };
+    let rest = rest.strip_prefix('i').filter(|_| power > 0).unwrap_or(rest);
+    if !matches!(rest, "" | "b" | "byte" | "bytes") {
+        return None;
+    }
+    number.times(1024u128.pow(power))
+}
@@ -0,0 +1,176 @@
+//! Compressed output files.
+//!
+//! Parse results of a whole fleet are large and are compressed as soon as they are
+//! written, so a [`Compression`] wraps any `io::Write` in a gzip or zstd encoder. The
+//! [`CompressedWriter`] it returns is an `io::Write` itself, so every
+//! [sink](crate::sink) writes through it unchanged; [`finish`](CompressedWriter::finish)
+//! ends the stream, and dropping the writer finishes it too, ignoring errors the way
+//! `io::BufWriter` ignores them when dropped. [`record_sink`](crate::record_sink)
+//! compresses a file whose name ends in `.gz` or `.zst`, and finishes it when the sink
+//! is closed, returning any error.
+//!
+//! ```
+//! use asyncfsm::compress::Compression;
+//! use asyncfsm::sink::{NdjsonSink, RecordSink};
+//! use asyncfsm::TextFSM;
+//! use std::io::Read;
+//!
+//! let fsm = TextFSM::from_string("Value N (\\d+)\n\nStart\n  ^${N} -> Record\n")?;
+//! let mut sink = NdjsonSink::new(Compression::Gzip.writer(vec![])?);
+//! fsm.parse_reader("1\n2\n".as_bytes()).write_to(&mut sink, None)?;
+//! let compressed = sink.into_inner().finish()?;
+//!
+//! let mut text = String::new();
+//! Compression::Gzip.reader(compressed.as_slice())?.read_to_string(&mut text)?;
+//! assert_eq!(text, "{\"N\":\"1\"}\n{\"N\":\"2\"}\n");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{Result, TextFsmError};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// How to compress an output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The names [`Compression::from_str`] accepts.
+    pub const NAMES: [&'static str; 2] = ["gzip", "zstd"];
+
+    /// The file extension of this compression, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// The compression a file name's extension asks for, if any.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Writes compressed bytes to `out`.
+    pub fn writer<W: Write>(self, out: W) -> Result<CompressedWriter<W>> {
+        let encoder = match self {
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(out, 0)?),
+        };
+        Ok(CompressedWriter {
+            encoder: Some(encoder),
+        })
+    }
+
+    /// Reads the bytes `input` holds compressed, to check what was written.
+    pub fn reader<'a, R: Read + 'a>(self, input: R) -> Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(input)?),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+impl FromStr for Compression {
+    type Err = TextFsmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(TextFsmError::ParseError(format!(
+                "unknown compression '{}', expected one of: {}",
+                s,
+                Compression::NAMES.join(", ")
+            ))),
+        }
+    }
+}
+
+enum Encoder<W: Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+/// An `io::Write` that compresses what is written to it; see [`Compression::writer`].
+pub struct CompressedWriter<W: Write> {
+    /// `None` once finished.
+    encoder: Option<Encoder<W>>,
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Ends the compressed stream and returns the writer it went to.
+    pub fn finish(mut self) -> Result<W> {
+        self.finish_encoder()
+    }
+
+    fn finish_encoder(&mut self) -> Result<W> {
+        let mut out = match self.encoder.take() {
+            Some(Encoder::Gzip(encoder)) => encoder.finish()?,
+            Some(Encoder::Zstd(encoder)) => encoder.finish()?,
+            None => unreachable!("a CompressedWriter is finished once"),
+        };
+        out.flush()?;
+        Ok(out)
+    }
+
+    fn encoder(&mut self) -> &mut dyn Write {
+        match self.encoder.as_mut() {
+            Some(Encoder::Gzip(encoder)) => encoder,
+            Some(Encoder::Zstd(encoder)) => encoder,
+            None => unreachable!("a finished CompressedWriter is consumed"),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoder().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder().flush()
+    }
+}
+
+impl<W: Write> Drop for CompressedWriter<W> {
+    fn drop(&mut self) {
+        if self.encoder.is_some() {
+            let _ = self.finish_encoder();
+        }
+    }
+}
+
+impl<W: Write> fmt::Debug for CompressedWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let compression = match self.encoder {
+            Some(Encoder::Gzip(_)) => "gzip",
+            Some(Encoder::Zstd(_)) => "zstd",
+            None => "finished",
+        };
+        f.debug_struct("CompressedWriter")
+            .field("compression", &compression)
+            .finish()
+    }
+}
@@ -0,0 +1,272 @@
+//! Everything wrong with a template, found in one compile.
+//!
+//! Compiling stops at the first grammar error, since nothing after it can be read, but
+//! any other problem is noted where it is and compilation goes on: a bad option, a
+//! macro defined twice, a rule referencing an undeclared value or a regex that does not
+//! compile. [`TextFSMParser::diagnose`](crate::TextFSMParser::diagnose) returns them all,
+//! with the warnings that do not stop a template from compiling, and a failed compile
+//! reports them in [`TextFsmError::TemplateErrors`](crate::TextFsmError::TemplateErrors).
+//!
+//! Each [`Diagnostic`] points at the text it is about, and may carry a suggested
+//! replacement for that text.
+//!
+//! ```
+//! use asyncfsm::{Severity, TextFSMParser};
+//!
+//! let template = "Value Filldwn HOST (\\S+)\nValue PORT (\\S+)\n\nStart\n  ^${HOST} ${port}\n";
+//! let diagnostics = TextFSMParser::diagnose(template);
+//! let errors: Vec<_> = diagnostics.errors().collect();
+//! assert_eq!(errors.len(), 2);
+//! assert_eq!(errors[0].span.line, 1);
+//! assert_eq!(errors[0].suggestion.as_deref(), Some("Filldown"));
+//! assert_eq!(errors[1].to_string(), "line 5, column 12: error: ${port} is not a declared Value (did you mean `${PORT}`?)");
+//! assert_eq!(diagnostics.warnings().count(), 1);
+//! ```
+
+use crate::{Result, Severity, TextFsmError};
+use pest::iterators::Pair;
+use serde::Serialize;
+use std::fmt;
+
+/// A stretch of template text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Span {
+    /// Byte offset of the start in the template.
+    pub start: usize,
+    /// Byte offset just past the end.
+    pub end: usize,
+    /// The 1-based line of the start.
+    pub line: usize,
+    /// The 1-based column of the start, in characters.
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn of<R: pest::RuleType>(pair: &Pair<'_, R>) -> Self {
+        let span = pair.as_span();
+        let (line, column) = span.start_pos().line_col();
+        Span {
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
+
+    /// The start of the template, for problems with no better place.
+    pub(crate) fn file_start() -> Self {
+        Span {
+            line: 1,
+            column: 1,
+            ..Span::default()
+        }
+    }
+
+    /// The 1-based `line` of `content`, without its line break.
+    pub(crate) fn line(content: &str, line: usize) -> Self {
+        let start: usize = content
+            .split_inclusive('\n')
+            .take(line - 1)
+            .map(str::len)
+            .sum();
+        let len = content[start..].lines().next().map_or(0, str::len);
+        Span {
+            start,
+            end: start + len,
+            line,
+            column: 1,
+        }
+    }
+
+    /// From the start of this span to the end of `other`.
+    pub(crate) fn to(self, other: Span) -> Self {
+        Span {
+            end: other.end,
+            ..self
+        }
+    }
+
+    /// This span without the whitespace and line breaks that end `text`, its text.
+    pub(crate) fn trim_end(self, text: &str) -> Self {
+        Span {
+            end: self.start + text.trim_end().len(),
+            ..self
+        }
+    }
+
+    /// The part of this span from byte `at` on its first line, `len` bytes long.
+    pub(crate) fn sub(&self, text: &str, at: usize, len: usize) -> Self {
+        Span {
+            start: self.start + at,
+            end: self.start + at + len,
+            line: self.line,
+            column: self.column + text[..at].chars().count(),
+        }
+    }
+}
+
+/// One problem with a template.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The text the problem is about.
+    pub span: Span,
+    pub message: String,
+    /// Text to replace the span with, when there is an obvious fix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {}",
+            self.span.line, self.span.column, self.severity, self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// What to say about `error` in a diagnostic, without the kind of error in front.
+pub(crate) fn message_of(error: TextFsmError) -> String {
+    match error {
+        TextFsmError::ParseError(message) | TextFsmError::StateError(message) => message,
+        e => e.to_string(),
+    }
+}
+
+/// The one of `candidates` that `name` is most likely a misspelling of: one that
+/// differs only in case, or else in at most two letters.
+pub(crate) fn similar<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str> + Clone,
+) -> Option<&'a str> {
+    if let Some(found) = candidates
+        .clone()
+        .into_iter()
+        .find(|c| c.eq_ignore_ascii_case(name))
+    {
+        return Some(found);
+    }
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(&c.to_lowercase(), &name.to_lowercase()), c))
+        .filter(|&(distance, _)| distance <= 2 && distance * 3 <= name.len())
+        .min()
+        .map(|(_, c)| c)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// The diagnostics of one template, in template order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub(crate) fn push(
+        &mut self,
+        severity: Severity,
+        span: Span,
+        message: impl Into<String>,
+        suggestion: Option<String>,
+    ) {
+        self.0.push(Diagnostic {
+            severity,
+            span,
+            message: message.into(),
+            suggestion,
+        });
+    }
+
+    pub(crate) fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.push(Severity::Error, span, message, None);
+    }
+
+    pub(crate) fn warning(&mut self, span: Span, message: impl Into<String>) {
+        self.push(Severity::Warning, span, message, None);
+    }
+
+    /// Puts the diagnostics in template order, keeping the order of those at one place.
+    pub(crate) fn sort(&mut self) {
+        self.0.sort_by_key(|d| d.span.start);
+    }
+
+    /// Fails with the first error, for the functions that report one problem.
+    pub(crate) fn first_error(&self) -> Result<()> {
+        match self.errors().next() {
+            Some(error) => Err(TextFsmError::ParseError(error.message.clone())),
+            None => Ok(()),
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter().filter(|d| d.severity != Severity::Error)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.0 {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
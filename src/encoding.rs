@@ -0,0 +1,68 @@
+//! Decoding input that is not valid UTF-8.
+//!
+//! Device logs are often Latin-1 or carry stray bytes from a terminal. Rather than
+//! failing with a UTF-8 error part way through a file, input is decoded lossily:
+//!
+//! * with an encoding set in [`TextFsmOptions`] (feature `encoding_rs`), the input
+//!   is decoded with it, a byte order mark taking precedence;
+//! * otherwise valid UTF-8 is used as is, minus any byte order mark;
+//! * anything else is decoded as Windows-1252 (a superset of Latin-1) with the
+//!   `encoding_rs` feature, or with invalid bytes replaced by U+FFFD without it.
+
+use crate::{Result, TextFsmOptions};
+use std::path::Path;
+use tracing::warn;
+
+#[cfg(feature = "encoding_rs")]
+pub use encoding_rs::Encoding;
+
+/// Looks up an encoding by a WHATWG label such as `latin1`, `utf-16le` or `shift_jis`.
+#[cfg(feature = "encoding_rs")]
+pub fn for_label(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| crate::TextFsmError::ParseError(format!("unknown encoding '{}'", label)))
+}
+
+/// Decodes raw input into text, following the rules above.
+pub fn decode(bytes: &[u8], options: &TextFsmOptions) -> String {
+    #[cfg(feature = "encoding_rs")]
+    if let Some(encoding) = options.encoding {
+        let (text, used, had_errors) = encoding.decode(bytes);
+        if had_errors {
+            warn!("Input is not valid {}, replaced invalid bytes", used.name());
+        }
+        return text.into_owned();
+    }
+    #[cfg(not(feature = "encoding_rs"))]
+    let _ = options;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(e) => {
+            #[cfg(feature = "encoding_rs")]
+            {
+                warn!(
+                    "Input is not UTF-8 (byte {}), decoding it as windows-1252",
+                    e.valid_up_to()
+                );
+                encoding_rs::WINDOWS_1252
+                    .decode_without_bom_handling(bytes)
+                    .0
+                    .into_owned()
+            }
+            #[cfg(not(feature = "encoding_rs"))]
+            {
+                warn!(
+                    "Input is not UTF-8 (byte {}), replaced invalid bytes",
+                    e.valid_up_to()
+                );
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        }
+    }
+}
+
+/// Reads a file and [`decode`]s it.
+pub fn read_file<P: AsRef<Path>>(path: P, options: &TextFsmOptions) -> Result<String> {
+    Ok(decode(&std::fs::read(path)?, options))
+}
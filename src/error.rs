@@ -1,4 +1,5 @@
 use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Custom error type for TextFSM operations.
@@ -14,13 +15,133 @@ pub enum TextFsmError {
     /// Errors occurring during the parsing of templates or variable strings.
     #[error("Parse error: {0}")]
     ParseError(String),
+    /// A template does not follow the template grammar.
+    #[error(
+        "Syntax error in {} at line {line}, column {column}: {message}",
+        .file.as_ref().map_or("template".to_string(), |f| f.display().to_string())
+    )]
+    TemplateSyntaxError {
+        /// The template file, when compiled from one.
+        file: Option<PathBuf>,
+        /// The 1-based template line the grammar stopped at.
+        line: usize,
+        /// The 1-based column on that line.
+        column: usize,
+        /// What the grammar expected there.
+        message: String,
+    },
+    /// A template follows the grammar but does not compile. Lists every problem found,
+    /// the warnings included.
+    #[error(
+        "Errors in {}:{}",
+        .file.as_ref().map_or("template".to_string(), |f| f.display().to_string()),
+        .diagnostics.iter().map(|d| format!("\n  {}", d)).collect::<String>()
+    )]
+    TemplateErrors {
+        /// The template file, when compiled from one.
+        file: Option<PathBuf>,
+        diagnostics: crate::Diagnostics,
+    },
     /// Errors related to invalid states or state transitions.
     #[error("State error: {0}")]
     StateError(String),
+    /// No usable template was found for a platform/command pair or a name.
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+    /// A rule's `Error` action fired, aborting the parse.
+    #[error(
+        "Error action in state '{state}' at line {line_number}: {} (line: '{line}', template line {})",
+        .message.as_deref().unwrap_or("no message"),
+        .span.line
+    )]
+    TemplateErrorAction {
+        /// The message supplied by the template, if any.
+        message: Option<String>,
+        /// The state whose rule triggered the action.
+        state: String,
+        /// The 1-based number of the input line that matched.
+        line_number: usize,
+        /// The content of the input line that matched.
+        line: String,
+        /// The rule's action in the template.
+        span: crate::Span,
+    },
+    /// A rule's `Call(...)` action failed or is not registered.
+    #[error(
+        "Action '{action}' failed at line {line_number}: {message} (template line {})",
+        .span.line
+    )]
+    ActionError {
+        /// The name in `Call(...)`.
+        action: String,
+        /// The 1-based number of the input line that matched.
+        line_number: usize,
+        /// Why it failed.
+        message: String,
+        /// The rule's action in the template.
+        span: crate::Span,
+    },
+    /// A captured value was rejected by a validator in strict mode.
+    #[error("Validation error: {0}")]
+    ValidationError(crate::validate::ValidationIssue),
+    /// Errors compiling or rendering an output template.
+    #[error("Render error: {0}")]
+    RenderError(String),
+    /// Errors setting up file watching.
+    #[cfg(feature = "notify")]
+    #[error("Watch error: {0}")]
+    WatchError(#[from] notify::Error),
+    /// Errors fetching or verifying a remote template collection.
+    #[cfg(feature = "registry")]
+    #[error("Registry error: {0}")]
+    RegistryError(String),
+    /// Errors compiling or running a record script.
+    #[cfg(feature = "script")]
+    #[error("Script error: {0}")]
+    ScriptError(String),
+    /// Errors loading an output writer plugin.
+    #[cfg(feature = "plugins")]
+    #[error("Plugin error: {0}")]
+    PluginError(String),
+    /// Errors writing records to an SQLite database.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
     /// Unrecoverable internal library errors.
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+impl TextFsmError {
+    /// A stable, snake_case name for the kind of error, for machine-readable reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TextFsmError::IoError(_) => "io",
+            #[cfg(any(feature = "clitable", feature = "csv_export"))]
+            TextFsmError::CsvError(_) => "csv",
+            TextFsmError::ParseError(_) => "parse",
+            TextFsmError::TemplateSyntaxError { .. } => "template_syntax",
+            TextFsmError::TemplateErrors { .. } => "template_errors",
+            TextFsmError::StateError(_) => "state",
+            TextFsmError::TemplateNotFound(_) => "template_not_found",
+            TextFsmError::TemplateErrorAction { .. } => "template_error_action",
+            TextFsmError::ActionError { .. } => "action",
+            TextFsmError::ValidationError(_) => "validation",
+            TextFsmError::RenderError(_) => "render",
+            #[cfg(feature = "notify")]
+            TextFsmError::WatchError(_) => "watch",
+            #[cfg(feature = "registry")]
+            TextFsmError::RegistryError(_) => "registry",
+            #[cfg(feature = "script")]
+            TextFsmError::ScriptError(_) => "script",
+            #[cfg(feature = "plugins")]
+            TextFsmError::PluginError(_) => "plugin",
+            #[cfg(feature = "sqlite")]
+            TextFsmError::SqliteError(_) => "sqlite",
+            TextFsmError::InternalError(_) => "internal",
+        }
+    }
+}
+
 /// A specialized Result type for TextFSM operations.
 pub type Result<T> = std::result::Result<T, TextFsmError>;
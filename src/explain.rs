@@ -0,0 +1,54 @@
+//! Which rule matched each input line, and where in the line its values were captured.
+//!
+//! Collected in `TextFSM::matches` when `TextFsmOptions::track_matches` is set, for
+//! tools that show a template working through its input, such as `textfsm trace`.
+//!
+//! ```
+//! use asyncfsm::{TextFSM, TextFsmOptions};
+//!
+//! let template = "Value NAME (\\S+)\n\nStart\n  ^name ${NAME} -> Record\n";
+//! let mut fsm = TextFSM::from_string(template)?
+//!     .with_options(TextFsmOptions::default().track_matches(true));
+//! fsm.parse_records("name r1\nother\n", None)?;
+//! let found = &fsm.matches[0];
+//! assert_eq!((found.line_number, found.rule), (1, 1));
+//! assert_eq!(found.captured(&found.captures[0]), "r1");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use serde::Serialize;
+
+/// A rule that matched an input line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleMatch {
+    /// The 1-based input line.
+    pub line_number: usize,
+    /// The line as the rule saw it, after `TextFsmOptions::normalize_whitespace`.
+    pub line: String,
+    /// The state the rule belongs to.
+    pub state: String,
+    /// The 1-based position of the rule in its state.
+    pub rule: usize,
+    /// The 1-based line of the rule in the template.
+    pub template_line: usize,
+    /// The values the rule captured, in the order the rule names them.
+    pub captures: Vec<CaptureSpan>,
+}
+
+/// Where in [`RuleMatch::line`] a value was captured.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaptureSpan {
+    /// The value's name.
+    pub name: String,
+    /// Byte offset of the first captured byte.
+    pub start: usize,
+    /// Byte offset just past the last captured byte.
+    pub end: usize,
+}
+
+impl RuleMatch {
+    /// The text `capture` covers.
+    pub fn captured(&self, capture: &CaptureSpan) -> &str {
+        &self.line[capture.start..capture.end]
+    }
+}
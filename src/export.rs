@@ -1,4 +1,4 @@
-use crate::{DataRecord, TextFsmError};
+use crate::{DataRecord, Records, TextFsmError};
 use std::collections::BTreeSet;
 
 /// Supported output formats for parsed results.
@@ -10,7 +10,7 @@ pub enum OutputFormat {
     /// YAML format (using serde_yaml)
     #[cfg(feature = "yaml")]
     Yaml,
-    /// Comma-Separated Values (headers sorted alphabetically)
+    /// Comma-Separated Values (headers in template order for `Records`, otherwise sorted alphabetically)
     #[cfg(feature = "csv_export")]
     Csv,
     /// Simple ASCII table
@@ -29,36 +29,59 @@ pub trait TextFsmExport {
 
 impl TextFsmExport for Vec<DataRecord> {
     fn export(&self, format: OutputFormat) -> Result<String, TextFsmError> {
-        match format {
-            #[cfg(feature = "json")]
-            OutputFormat::Json => serde_json::to_string_pretty(self)
-                .map_err(|e| TextFsmError::InternalError(e.to_string())),
-            #[cfg(feature = "yaml")]
-            OutputFormat::Yaml => {
-                serde_yaml::to_string(self).map_err(|e| TextFsmError::InternalError(e.to_string()))
-            }
-            #[cfg(feature = "csv_export")]
-            OutputFormat::Csv => export_csv(self),
-            OutputFormat::Text => export_text(self),
-            OutputFormat::Html => export_html(self),
-            OutputFormat::Xml => export_xml(self),
+        export_records(self, self, &[], format)
+    }
+}
+
+impl TextFsmExport for Records {
+    fn export(&self, format: OutputFormat) -> Result<String, TextFsmError> {
+        export_records(self, self, self.schema(), format)
+    }
+}
+
+/// Exports `records`; `value` is what gets serialized for the JSON and YAML formats.
+fn export_records<T: serde::Serialize + ?Sized>(
+    value: &T,
+    records: &[DataRecord],
+    schema: &[String],
+    format: OutputFormat,
+) -> Result<String, TextFsmError> {
+    match format {
+        #[cfg(feature = "json")]
+        OutputFormat::Json => serde_json::to_string_pretty(value)
+            .map_err(|e| TextFsmError::InternalError(e.to_string())),
+        #[cfg(feature = "yaml")]
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(value).map_err(|e| TextFsmError::InternalError(e.to_string()))
         }
+        #[cfg(feature = "csv_export")]
+        OutputFormat::Csv => export_csv(records, schema),
+        OutputFormat::Text => export_text(records, schema),
+        OutputFormat::Html => export_html(records, schema),
+        OutputFormat::Xml => export_xml(records, schema),
     }
 }
 
-fn get_headers(records: &[DataRecord]) -> Vec<String> {
+/// Field names present in `records`: those in `schema` first, in schema order, then the rest sorted.
+pub(crate) fn get_headers(records: &[DataRecord], schema: &[String]) -> Vec<String> {
     let mut headers = BTreeSet::new();
     for rec in records {
         for k in rec.fields.keys() {
             headers.insert(k.clone());
         }
     }
-    headers.into_iter().collect()
+    let mut ordered: Vec<String> = schema
+        .iter()
+        .filter(|name| headers.remove(*name))
+        .cloned()
+        .collect();
+    ordered.extend(headers);
+    ordered
 }
 
 #[cfg(feature = "csv_export")]
-fn export_csv(records: &[DataRecord]) -> Result<String, TextFsmError> {
-    let headers = get_headers(records);
+fn export_csv(records: &[DataRecord], schema: &[String]) -> Result<String, TextFsmError> {
+    let headers = get_headers(records, schema);
     let mut wtr = csv::Writer::from_writer(vec![]);
 
     // Write header
@@ -87,8 +110,8 @@ fn export_csv(records: &[DataRecord]) -> Result<String, TextFsmError> {
     String::from_utf8(data).map_err(|e| TextFsmError::InternalError(e.to_string()))
 }
 
-fn export_html(records: &[DataRecord]) -> Result<String, TextFsmError> {
-    let headers = get_headers(records);
+fn export_html(records: &[DataRecord], schema: &[String]) -> Result<String, TextFsmError> {
+    let headers = get_headers(records, schema);
     let mut html = String::from("<table>\n<thead>\n<tr>");
     for h in &headers {
         html.push_str(&format!("<th>{}</th>", h));
@@ -117,8 +140,8 @@ fn export_html(records: &[DataRecord]) -> Result<String, TextFsmError> {
     Ok(html)
 }
 
-fn export_xml(records: &[DataRecord]) -> Result<String, TextFsmError> {
-    let headers = get_headers(records);
+fn export_xml(records: &[DataRecord], schema: &[String]) -> Result<String, TextFsmError> {
+    let headers = get_headers(records, schema);
     let mut xml = String::from("<results>\n");
 
     for rec in records {
@@ -147,8 +170,8 @@ fn export_xml(records: &[DataRecord]) -> Result<String, TextFsmError> {
     Ok(xml)
 }
 
-fn export_text(records: &[DataRecord]) -> Result<String, TextFsmError> {
-    let headers = get_headers(records);
+fn export_text(records: &[DataRecord], schema: &[String]) -> Result<String, TextFsmError> {
+    let headers = get_headers(records, schema);
     if headers.is_empty() {
         return Ok(String::new());
     }
@@ -0,0 +1,197 @@
+//! A normalized summary of a device, assembled from its standard `show` outputs, like
+//! the network facts Ansible gathers but from captures rather than SNMP or a login.
+//!
+//! [`DeviceFacts`] reads the fields ntc-templates templates share across platforms from
+//! a [`DeviceModel`]: `HOSTNAME`, `HARDWARE` or `MODEL`, `SERIAL` or `SN`, `VERSION`,
+//! `UPTIME` and `RUNNING_IMAGE` for the device, and for each interface its description,
+//! status, MTU, MAC address and IPv4 addresses. Interface names are canonicalized with
+//! [`InterfaceNames`], so `Gi0/1` from `show ip interface brief` and
+//! `GigabitEthernet0/1` from `show interfaces` describe one interface. Facts the
+//! outputs do not hold are left out.
+//!
+//! ```
+//! use asyncfsm::{DataRecord, DeviceFacts, DeviceModel};
+//!
+//! let mut version = DataRecord::new();
+//! version.insert("HOSTNAME".into(), "r1".into());
+//! version.insert("VERSION".into(), "15.2(4)M".into());
+//! let mut brief = DataRecord::new();
+//! brief.insert("INTF".into(), "Gi0/1".into());
+//! brief.insert("IPADDR".into(), "10.0.0.1".into());
+//! brief.insert("STATUS".into(), "up".into());
+//! let model = DeviceModel::from_commands([
+//!     ("show version".to_string(), vec![version]),
+//!     ("show ip interface brief".to_string(), vec![brief]),
+//! ]);
+//!
+//! let facts = DeviceFacts::from_model(&model);
+//! assert_eq!(facts.hostname.as_deref(), Some("r1"));
+//! assert_eq!(facts.interfaces["GigabitEthernet0/1"].ipv4, ["10.0.0.1"]);
+//! ```
+
+#[cfg(feature = "clitable")]
+use crate::{CliTable, Result};
+use crate::{DeviceModel, InterfaceNames, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "clitable")]
+use std::path::Path;
+
+/// The facts of one device; see the [module documentation](self).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct DeviceFacts {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<String>,
+    /// The software image the device booted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Interfaces keyed by their full name.
+    #[serde(default)]
+    pub interfaces: BTreeMap<String, InterfaceFacts>,
+}
+
+/// The facts of one interface.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct InterfaceFacts {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// False when the interface is administratively down.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// `up` or `down`, from the line protocol where the outputs have it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oper_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac_address: Option<String>,
+    /// Addresses as `10.0.0.1/24`, or without the prefix length when the outputs
+    /// lack it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ipv4: Vec<String>,
+}
+
+/// The texts of the first of `keys` present in `fields` (in any case) with a non-empty
+/// value.
+fn texts<'a>(fields: &'a BTreeMap<String, Value>, keys: &[&str]) -> Vec<&'a str> {
+    keys.iter()
+        .find_map(|key| {
+            let (_, value) = fields.iter().find(|(k, _)| k.eq_ignore_ascii_case(key))?;
+            let texts: Vec<&str> = match value {
+                Value::Single(text) => vec![text.as_str()],
+                Value::List(items) => items.iter().map(String::as_str).collect(),
+            };
+            let texts: Vec<&str> = texts.into_iter().filter(|t| !t.is_empty()).collect();
+            (!texts.is_empty()).then_some(texts)
+        })
+        .unwrap_or_default()
+}
+
+fn text(fields: &BTreeMap<String, Value>, keys: &[&str]) -> Option<String> {
+    texts(fields, keys).first().map(|t| t.to_string())
+}
+
+impl DeviceFacts {
+    /// The facts in `model`, with interface names in the Cisco IOS form.
+    pub fn from_model(model: &DeviceModel) -> Self {
+        Self::from_model_with(model, &InterfaceNames::default())
+    }
+
+    /// The facts in `model`, with interface names rewritten by `names`.
+    pub fn from_model_with(model: &DeviceModel, names: &InterfaceNames) -> Self {
+        let facts = &model.facts;
+        let mut out = DeviceFacts {
+            hostname: text(facts, &["HOSTNAME"]),
+            model: text(facts, &["HARDWARE", "MODEL", "PLATFORM", "PID"]),
+            serial: text(facts, &["SERIAL", "SERIAL_NUMBER", "SN"]),
+            version: text(facts, &["VERSION", "OS_VERSION", "SOFTWARE_VERSION"]),
+            uptime: text(facts, &["UPTIME"]),
+            image: text(facts, &["RUNNING_IMAGE", "SOFTWARE_IMAGE"]),
+            interfaces: BTreeMap::new(),
+        };
+        for (name, fields) in &model.interfaces {
+            let name = names.normalize(name).unwrap_or_else(|| name.clone());
+            let interface = out.interfaces.entry(name).or_default();
+            interface.merge(InterfaceFacts::from_fields(fields));
+        }
+        out
+    }
+
+    /// The facts in a directory of one device's command outputs, parsed as by
+    /// [`DeviceModel::from_dir`]. Interface names take the form of `platform`, or of
+    /// Cisco IOS if it is detected.
+    #[cfg(feature = "clitable")]
+    pub fn from_dir(
+        table: &CliTable,
+        platform: Option<&str>,
+        dir: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let model = DeviceModel::from_dir(table, platform, dir)?;
+        let names = platform.map_or_else(InterfaceNames::default, InterfaceNames::for_platform);
+        Ok(Self::from_model_with(&model, &names))
+    }
+}
+
+impl InterfaceFacts {
+    fn from_fields(fields: &BTreeMap<String, Value>) -> Self {
+        let link = text(fields, &["LINK_STATUS", "STATUS"]).map(|s| s.to_ascii_lowercase());
+        let protocol = text(fields, &["PROTOCOL_STATUS", "PROTO", "PROTOCOL"]);
+        let addresses = texts(fields, &["IP_ADDRESS", "IPADDR", "IPV4_ADDRESS"]);
+        let prefixes = texts(fields, &["PREFIX_LENGTH", "IP_PREFIX_LENGTH"]);
+        InterfaceFacts {
+            description: text(fields, &["DESCRIPTION", "DESCRIP", "DESC"]),
+            enabled: link
+                .as_deref()
+                .map(|s| !s.starts_with("admin") && !s.starts_with("disabled")),
+            // `up (connected)` for the protocol, `administratively down` for the link.
+            oper_status: protocol
+                .map(|s| s.to_ascii_lowercase().split(' ').next().map(String::from))
+                .unwrap_or_else(|| link.and_then(|s| s.rsplit(' ').next().map(String::from)))
+                .filter(|s| s == "up" || s == "down"),
+            mtu: text(fields, &["MTU"]).and_then(|mtu| {
+                let end = mtu.find(|c: char| !c.is_ascii_digit()).unwrap_or(mtu.len());
+                mtu[..end].parse().ok()
+            }),
+            mac_address: text(fields, &["MAC_ADDRESS", "MAC"]),
+            ipv4: addresses
+                .iter()
+                .enumerate()
+                .filter(|(_, address)| address.contains('.'))
+                .map(|(i, address)| match prefixes.get(i) {
+                    Some(len) if !address.contains('/') => format!("{}/{}", address, len),
+                    _ => address.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Fills the facts `self` lacks from `other`.
+    fn merge(&mut self, other: InterfaceFacts) {
+        self.description = self.description.take().or(other.description);
+        self.enabled = self.enabled.or(other.enabled);
+        self.oper_status = self.oper_status.take().or(other.oper_status);
+        self.mtu = self.mtu.or(other.mtu);
+        self.mac_address = self.mac_address.take().or(other.mac_address);
+        for address in other.ipv4 {
+            let bare = address.split('/').next().unwrap_or_default();
+            match self
+                .ipv4
+                .iter()
+                .position(|a| a.split('/').next() == Some(bare))
+            {
+                // Keep the form with a prefix length.
+                Some(i) if address.contains('/') => self.ipv4[i] = address,
+                Some(_) => {}
+                None => self.ipv4.push(address),
+            }
+        }
+    }
+}
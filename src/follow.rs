@@ -0,0 +1,146 @@
+//! Inputs that never end: a log file as it grows, and a systemd unit's journal.
+//!
+//! [`FollowFile`] reads a file like `tail -f`: from the start, and then whatever is
+//! appended, waiting for more at the end instead of reporting end of input. A file that
+//! is truncated is read again from the start, and one that is rotated away is reopened
+//! under its path. [`Journal`] reads the new entries of a unit from `journalctl`, in
+//! its classic syslog layout.
+//!
+//! Both are [`Read`], so [`TextFSM::parse_reader`](crate::TextFSM::parse_reader) turns
+//! them into a stream of records. It ends when the [`CancellationToken`] given to a
+//! `FollowFile` is cancelled, or when `journalctl` exits. With [`TextFsmOptions::line_mode`](crate::TextFsmOptions::line_mode)
+//! every log line comes out as soon as it is written.
+//!
+//! ```no_run
+//! use asyncfsm::follow::FollowFile;
+//! use asyncfsm::{TextFSM, TextFsmOptions};
+//! use std::io::BufReader;
+//!
+//! let fsm = TextFSM::from_file("syslog.textfsm")?
+//!     .with_options(TextFsmOptions::default().line_mode(true));
+//! for record in fsm.parse_reader(BufReader::new(FollowFile::open("/var/log/syslog")?)) {
+//!     println!("{:?}", record?);
+//! }
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{CancellationToken, Result, TextFsmError};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+/// A file read as it grows; see the [module documentation](self).
+#[derive(Debug)]
+pub struct FollowFile {
+    path: PathBuf,
+    file: File,
+    position: u64,
+    poll_interval: Duration,
+    cancel: Option<CancellationToken>,
+}
+
+impl FollowFile {
+    /// Opens `path`, to be read from the start.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        Ok(FollowFile {
+            path,
+            file,
+            position: 0,
+            poll_interval: Duration::from_millis(250),
+            cancel: None,
+        })
+    }
+
+    /// How long to wait at the end of the file before looking for more; 250ms by
+    /// default.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Ends the input once `token` is cancelled.
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Starts over when the file was truncated, or reopens the path when it names
+    /// another file since a rotation.
+    fn check_rotation(&mut self) -> io::Result<()> {
+        let Ok(current) = std::fs::metadata(&self.path) else {
+            // Rotated away and not created again yet.
+            return Ok(());
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if current.ino() != self.file.metadata()?.ino() {
+                self.file = File::open(&self.path)?;
+                self.position = 0;
+                return Ok(());
+            }
+        }
+        if current.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+        }
+        Ok(())
+    }
+}
+
+impl Read for FollowFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                self.position += n as u64;
+                return Ok(n);
+            }
+            if self.cancel.as_ref().is_some_and(|t| t.is_cancelled()) {
+                return Ok(0);
+            }
+            std::thread::sleep(self.poll_interval);
+            self.check_rotation()?;
+        }
+    }
+}
+
+/// The entries a systemd unit logs from now on, as `journalctl --follow` prints them.
+///
+/// Dropping it stops `journalctl`.
+#[derive(Debug)]
+pub struct Journal {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Journal {
+    /// Starts `journalctl` for `unit`. Fails if it can not be run.
+    pub fn follow(unit: &str) -> Result<Self> {
+        let mut child = Command::new("journalctl")
+            .args(["--follow", "--lines=0", "--output=short", "--unit", unit])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| TextFsmError::ParseError(format!("could not run journalctl: {}", e)))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Journal { child, stdout })
+    }
+}
+
+impl Read for Journal {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,251 @@
+//! Canonical template text, for keeping a template repository consistently formatted.
+//!
+//! [`format_template`] rewrites a template without changing what it parses: value
+//! options are sorted (the built-in ones first, then `OneOf=` and registered options in
+//! their order) and padded so names and regexes line up, rules are indented by two
+//! spaces with their transitions spelled the short way (`Next.Record` becomes `Record`),
+//! runs of blank lines become one and every state is preceded by one. Comments stay
+//! where they are.
+//!
+//! [`Template::to_textfsm_string`](crate::Template::to_textfsm_string) writes a compiled
+//! template in the same form. It only has the parsed template to go on, so the comments
+//! other than the `# Key: value` header are gone and value regexes have their macros
+//! expanded.
+//!
+//! ```
+//! let template = "Value Required,Filldown HOST (\\S+)\nValue IP (\\S+)\n\n\nStart\n ^${HOST} ${IP} -> Next.Record\n";
+//! assert_eq!(
+//!     asyncfsm::format::format_template(template)?,
+//!     "Value Filldown,Required HOST (\\S+)\nValue                   IP   (\\S+)\n\nStart\n  ^${HOST} ${IP} -> Record\n"
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{Result, TemplateAst, TextFSMParser, TextFsmError};
+
+/// The built-in options, in the order they are written.
+pub(crate) const BUILTIN_OPTIONS: [&str; 5] = ["Filldown", "Fillup", "Key", "List", "Required"];
+
+/// `options` in canonical order. Registered options keep their order, which is the
+/// order they run in.
+fn canonical_options(options: &str) -> String {
+    let mut words: Vec<&str> = options
+        .split(',')
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.sort_by_key(
+        |word| match BUILTIN_OPTIONS.iter().position(|b| b == word) {
+            Some(i) => (0, i),
+            None if word.starts_with("OneOf=") => (1, 0),
+            None => (2, 0),
+        },
+    );
+    words.join(",")
+}
+
+struct ValueLine {
+    options: Option<String>,
+    name: String,
+    regex: String,
+}
+
+impl ValueLine {
+    /// Splits a `Value [options] NAME (regex)` line.
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix("Value")?.trim_start();
+        let (first, after) = rest.split_once(char::is_whitespace)?;
+        let after = after.trim_start();
+        if after.starts_with('(') {
+            return Some(ValueLine {
+                options: None,
+                name: first.to_string(),
+                regex: after.trim_end().to_string(),
+            });
+        }
+        let (name, regex) = after.split_once(char::is_whitespace)?;
+        Some(ValueLine {
+            options: Some(canonical_options(first)),
+            name: name.to_string(),
+            regex: regex.trim().to_string(),
+        })
+    }
+}
+
+/// The `Value` lines, with options and names padded to the widest.
+fn value_lines(values: &[ValueLine]) -> Vec<String> {
+    let options_width = values
+        .iter()
+        .filter_map(|v| v.options.as_ref())
+        .map(String::len)
+        .max();
+    let name_width = values.iter().map(|v| v.name.len()).max().unwrap_or(0);
+    values
+        .iter()
+        .map(|value| {
+            let options = match options_width {
+                Some(width) => format!("{:<width$} ", value.options.as_deref().unwrap_or("")),
+                None => String::new(),
+            };
+            format!(
+                "Value {}{:<name_width$} {}",
+                options, value.name, value.regex
+            )
+        })
+        .collect()
+}
+
+/// Normalizes the space in a `Macro NAME (regex)` line.
+fn macro_line(line: &str) -> String {
+    let rest = line.trim().strip_prefix("Macro").unwrap_or(line).trim();
+    match rest.split_once(char::is_whitespace) {
+        Some((name, regex)) => format!("Macro {} {}", name, regex.trim_start()),
+        None => format!("Macro {}", rest),
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+fn is_definition(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("Value ") || line.starts_with("Macro ")
+}
+
+/// Adds `line`, after one blank line if `blank` is set and something came before.
+fn push(out: &mut Vec<String>, blank: &mut bool, line: String) {
+    if *blank && !out.is_empty() {
+        out.push(String::new());
+    }
+    *blank = false;
+    out.push(line);
+}
+
+/// `source` in canonical form; see the [module documentation](self).
+///
+/// Fails if `source` is not a valid template.
+pub fn format_template(source: &str) -> Result<String> {
+    let parser = TextFSMParser::from_string(source)?;
+    let lines: Vec<&str> = source.lines().collect();
+    let states_start = lines
+        .iter()
+        .position(|l| !l.trim().is_empty() && !is_comment(l) && !is_definition(l))
+        .unwrap_or(lines.len());
+    let values: Vec<ValueLine> = lines[..states_start]
+        .iter()
+        .filter(|l| l.trim_start().starts_with("Value "))
+        .filter_map(|l| ValueLine::parse(l))
+        .collect();
+    let mut values = value_lines(&values).into_iter();
+
+    let mut out: Vec<String> = vec![];
+    let mut blank = false;
+    // Where the comments directly above the current line start in `out`.
+    let mut comments: Option<usize> = None;
+    let mut states = parser.state_names.iter();
+    let mut rules: &[crate::StateRuleCompiled] = &[];
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            blank = true;
+        } else if is_comment(line) {
+            let indent = if i > states_start && line.starts_with(char::is_whitespace) {
+                "  "
+            } else {
+                ""
+            };
+            push(&mut out, &mut blank, format!("{}{}", indent, trimmed));
+            comments = comments.or(Some(out.len() - 1));
+            continue;
+        } else if i < states_start {
+            let formatted = if trimmed.starts_with("Macro ") {
+                Some(macro_line(line))
+            } else {
+                values.next()
+            };
+            let formatted = formatted.ok_or_else(|| {
+                TextFsmError::InternalError(format!("unexpected line {:?}", line))
+            })?;
+            push(&mut out, &mut blank, formatted);
+        } else if trimmed.starts_with('^') {
+            let (rule, rest) = rules.split_first().ok_or_else(|| {
+                TextFsmError::InternalError(format!("rule {:?} outside a state", trimmed))
+            })?;
+            rules = rest;
+            push(&mut out, &mut blank, format!("  {}", rule));
+        } else {
+            let name = states
+                .next()
+                .filter(|name| name.as_str() == trimmed)
+                .ok_or_else(|| {
+                    TextFsmError::InternalError(format!("unexpected state {:?}", trimmed))
+                })?;
+            rules = parser.states[name].rules();
+            // The blank line before a state goes above the comments on it.
+            match comments {
+                Some(at) if at > 0 && !out[at - 1].is_empty() => out.insert(at, String::new()),
+                Some(_) => {}
+                None => blank = true,
+            }
+            push(&mut out, &mut blank, name.clone());
+        }
+        comments = None;
+    }
+    let formatted = out.join("\n") + "\n";
+
+    // The formatted template must parse into the same rules.
+    let reparsed = TextFSMParser::from_string(&formatted)?;
+    if TemplateAst::new(&reparsed).states != TemplateAst::new(&parser).states {
+        return Err(TextFsmError::InternalError(
+            "formatting changed the template's rules".to_string(),
+        ));
+    }
+    Ok(formatted)
+}
+
+/// `parser`'s template in canonical form, without comments other than the metadata.
+pub(crate) fn to_textfsm_string(parser: &TextFSMParser) -> String {
+    render(parser, |state| {
+        parser.states[state]
+            .rules()
+            .iter()
+            .map(|rule| rule.to_string())
+            .collect()
+    })
+}
+
+/// As [`to_textfsm_string`], with the rule lines of each state given by `rules`.
+pub(crate) fn render(parser: &TextFSMParser, rules: impl Fn(&str) -> Vec<String>) -> String {
+    let mut out: Vec<String> = vec![];
+    let mut metadata: Vec<_> = parser.metadata.iter().collect();
+    metadata.sort();
+    for (key, value) in metadata {
+        out.push(format!("# {}: {}", key, value));
+    }
+    let mut macros: Vec<_> = parser.macros.iter().collect();
+    macros.sort();
+    for (name, regex) in macros {
+        out.push(format!("Macro {} ({})", name, regex));
+    }
+    let values: Vec<ValueLine> = parser
+        .value_names
+        .iter()
+        .map(|name| {
+            let value = &parser.values[name];
+            ValueLine {
+                options: value.options().map(canonical_options),
+                name: name.clone(),
+                regex: value.regex().to_string(),
+            }
+        })
+        .collect();
+    out.extend(value_lines(&values));
+    for name in &parser.state_names {
+        push(&mut out, &mut true, name.clone());
+        for rule in rules(name) {
+            out.push(format!("  {}", rule));
+        }
+    }
+    out.join("\n") + "\n"
+}
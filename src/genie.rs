@@ -0,0 +1,201 @@
+//! Genie-shaped output for code written against Cisco's pyATS parsers.
+//!
+//! Genie parsers return one nested document per command, keyed by the same names on
+//! every device, where ntc-templates return a list of flat records. [`to_genie`] turns
+//! the records of a supported command into the document Genie's parser for that
+//! command produces, so downstream code can move to this crate before it moves off the
+//! Genie schema. Counters become integers, as in Genie, and fields the records do not
+//! have are left out.
+//!
+//! The supported commands are listed in [`COMMANDS`]. Their records are read by the
+//! field names of the ntc-templates templates for Cisco IOS, in any case, so records
+//! with lowercase keys work too.
+//!
+//! ```
+//! use asyncfsm::TextFSM;
+//!
+//! let template = "Value VERSION (\\S+)\nValue HOSTNAME (\\S+)\n\nStart\n  ^${HOSTNAME} uptime, version ${VERSION}\n";
+//! let records = TextFSM::from_string(template)?.parse_records("r1 uptime, version 15.2(4)M\n", None)?;
+//! let genie = asyncfsm::genie::to_genie("show version", &records)?;
+//! assert_eq!(genie["version"]["hostname"], "r1");
+//! assert_eq!(genie["version"]["version"], "15.2(4)M");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Records, Result, TextFsmError, Value};
+use serde_json::{Map, Value as Json};
+
+/// The commands [`to_genie`] can convert, in full.
+pub const COMMANDS: &[&str] = &["show version", "show interfaces"];
+
+/// The Genie document for the records of `command`, which may be abbreviated as on the
+/// device, e.g. `sh ver`.
+///
+/// Fails if Genie has no parser for the command this module knows.
+pub fn to_genie(command: &str, records: &Records) -> Result<Json> {
+    let words: Vec<String> = command
+        .split_whitespace()
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+    let matched = COMMANDS.iter().find(|full| {
+        let full: Vec<&str> = full.split(' ').collect();
+        full.len() == words.len() && full.iter().zip(&words).all(|(f, w)| f.starts_with(&**w))
+    });
+    match matched {
+        Some(&"show version") => Ok(show_version(records)),
+        Some(&"show interfaces") => Ok(show_interfaces(records)),
+        _ => Err(TextFsmError::ParseError(format!(
+            "no Genie mapping for command {:?}",
+            command
+        ))),
+    }
+}
+
+/// The first non-empty text of `field` (in any case).
+fn text<'a>(record: &'a DataRecord, field: &str) -> Option<&'a str> {
+    let (_, value) = record
+        .fields
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(field))?;
+    let text = match value {
+        Value::Single(text) => text.as_str(),
+        Value::List(items) => items.first()?.as_str(),
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// The leading digits of `field`, as an integer: `1000000 Kbit` is 1000000.
+fn integer(record: &DataRecord, field: &str) -> Option<Json> {
+    let text = text(record, field)?;
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse::<u64>().ok().map(Json::from)
+}
+
+/// Inserts the entries whose value is present.
+fn insert_all(map: &mut Map<String, Json>, entries: Vec<(&str, Option<Json>)>) {
+    for (key, value) in entries {
+        if let Some(value) = value {
+            map.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn string(text: Option<&str>) -> Option<Json> {
+    text.map(Json::from)
+}
+
+fn show_version(records: &Records) -> Json {
+    let mut version = Map::new();
+    if let Some(record) = records.first() {
+        let field = |name: &str| string(text(record, name));
+        insert_all(
+            &mut version,
+            vec![
+                ("version", field("VERSION")),
+                ("rom", field("ROMMON")),
+                ("hostname", field("HOSTNAME")),
+                ("uptime", field("UPTIME")),
+                ("last_reload_reason", field("RELOAD_REASON")),
+                ("system_image", field("RUNNING_IMAGE")),
+                ("chassis", field("HARDWARE")),
+                ("chassis_sn", field("SERIAL")),
+                ("curr_config_register", field("CONFIG_REGISTER")),
+            ],
+        );
+    }
+    serde_json::json!({ "version": version })
+}
+
+fn show_interfaces(records: &Records) -> Json {
+    let mut interfaces = Map::new();
+    for record in records.iter() {
+        let Some(name) = text(record, "INTERFACE") else {
+            continue;
+        };
+        let field = |name: &str| string(text(record, name));
+        let lower = |name: &str| text(record, name).map(|t| t.to_ascii_lowercase());
+        let link = lower("LINK_STATUS");
+        let mut interface = Map::new();
+        insert_all(
+            &mut interface,
+            vec![
+                (
+                    "oper_status",
+                    link.as_deref()
+                        .map(|s| Json::from(s.rsplit(' ').next().unwrap_or(s))),
+                ),
+                (
+                    "line_protocol",
+                    lower("PROTOCOL_STATUS")
+                        .map(|s| Json::from(s.split(' ').next().unwrap_or_default())),
+                ),
+                (
+                    "enabled",
+                    link.as_deref()
+                        .map(|s| Json::from(!s.starts_with("administratively"))),
+                ),
+                ("type", field("HARDWARE_TYPE")),
+                ("mac_address", field("MAC_ADDRESS")),
+                ("phys_address", field("BIA")),
+                ("description", field("DESCRIPTION")),
+                ("mtu", integer(record, "MTU")),
+                ("bandwidth", integer(record, "BANDWIDTH")),
+                ("delay", integer(record, "DELAY")),
+                (
+                    "duplex_mode",
+                    lower("DUPLEX").map(|s| Json::from(s.trim_end_matches("-duplex"))),
+                ),
+                (
+                    "port_speed",
+                    lower("SPEED").map(|s| Json::from(s.replace("b/s", "bps"))),
+                ),
+                ("media_type", field("MEDIA_TYPE")),
+                ("last_input", field("LAST_INPUT")),
+                ("last_output", field("LAST_OUTPUT")),
+                ("output_hang", field("LAST_OUTPUT_HANG")),
+            ],
+        );
+        if let Some(encapsulation) = lower("ENCAPSULATION") {
+            interface.insert(
+                "encapsulations".into(),
+                serde_json::json!({ "encapsulation": encapsulation }),
+            );
+        }
+        if let (Some(ip), Some(len)) = (text(record, "IP_ADDRESS"), text(record, "PREFIX_LENGTH")) {
+            interface.insert(
+                "ipv4".into(),
+                serde_json::json!({ format!("{}/{}", ip, len): { "ip": ip, "prefix_length": len } }),
+            );
+        }
+        let mut rate = Map::new();
+        insert_all(
+            &mut rate,
+            vec![
+                ("in_rate", integer(record, "INPUT_RATE")),
+                ("out_rate", integer(record, "OUTPUT_RATE")),
+            ],
+        );
+        let mut counters = Map::new();
+        insert_all(
+            &mut counters,
+            vec![
+                ("in_pkts", integer(record, "INPUT_PACKETS")),
+                ("out_pkts", integer(record, "OUTPUT_PACKETS")),
+                ("in_errors", integer(record, "INPUT_ERRORS")),
+                ("in_crc_errors", integer(record, "CRC")),
+                ("in_abort", integer(record, "ABORT")),
+                ("out_errors", integer(record, "OUTPUT_ERRORS")),
+            ],
+        );
+        if !rate.is_empty() {
+            counters.insert("rate".into(), Json::Object(rate));
+        }
+        if !counters.is_empty() {
+            interface.insert("counters".into(), Json::Object(counters));
+        }
+        interfaces.insert(name.to_string(), Json::Object(interface));
+    }
+    Json::Object(interfaces)
+}
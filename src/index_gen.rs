@@ -0,0 +1,172 @@
+//! Building a CLI table index from a directory of templates.
+//!
+//! Each template's platform and command come from its `# Platform:` and
+//! `# Command:` header lines when present, and otherwise from the ntc-templates
+//! file naming convention `<platform>_<command words>.textfsm`, where the command
+//! starts at the first word that is a known command verb (`show`, `display`, ...):
+//! `cisco_ios_show_ip_route.textfsm` is `show ip route` on `cisco_ios`.
+//!
+//! Commands are written with `[[ ]]` abbreviation markers, keeping the shortest
+//! prefix (at least two characters) that tells each word apart from the other words
+//! used at the same position on the same platform, so `sh ip ro` matches as it
+//! would on the device.
+
+use crate::{Result, TextFSMParser};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Template file extensions picked up from the directory.
+const TEMPLATE_EXTENSIONS: &[&str] = &["textfsm", "template"];
+
+/// Words that start the command part of a template file name.
+const COMMAND_VERBS: &[&str] = &[
+    "show",
+    "display",
+    "get",
+    "dir",
+    "list",
+    "ping",
+    "traceroute",
+];
+
+/// Shortest abbreviation kept for any command word.
+const MIN_ABBREVIATION: usize = 2;
+
+/// One generated index row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRow {
+    /// The template file name, relative to the directory.
+    pub template: String,
+    /// The platform, e.g. `cisco_ios`.
+    pub platform: String,
+    /// The command with abbreviation markers, e.g. `sh[[ow]] ip ro[[ute]]`.
+    pub command: String,
+}
+
+/// An index generated by [`generate_index`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeneratedIndex {
+    /// The rows, sorted by platform and command.
+    pub rows: Vec<IndexRow>,
+    /// Template files whose platform or command could not be determined.
+    pub skipped: Vec<PathBuf>,
+}
+
+impl fmt::Display for GeneratedIndex {
+    /// Writes the index in the CSV layout `CliTable::from_file` reads.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Generated by textfsm index-gen.")?;
+        writeln!(f, "Template, Hostname, Platform, Command")?;
+        for row in &self.rows {
+            writeln!(f, "{}, .*, {}, {}", row.template, row.platform, row.command)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans `dir` for templates and builds the index rows for them.
+pub fn generate_index<P: AsRef<Path>>(dir: P) -> Result<GeneratedIndex> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir.as_ref())?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| {
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| TEMPLATE_EXTENSIONS.iter().any(|e| ext == *e))
+    });
+    paths.sort();
+
+    let mut index = GeneratedIndex::default();
+    let mut found: Vec<(String, String, Vec<String>)> = vec![];
+    for path in paths {
+        let metadata = TextFSMParser::parse_metadata(&std::fs::read_to_string(&path)?);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let from_name = split_file_name(&stem);
+        let platform = metadata
+            .get("Platform")
+            .cloned()
+            .or_else(|| from_name.as_ref().map(|(p, _)| p.clone()));
+        let command = metadata
+            .get("Command")
+            .map(|c| c.split_whitespace().map(str::to_string).collect())
+            .or_else(|| from_name.map(|(_, c)| c));
+        match (platform, command) {
+            (Some(platform), Some(words)) if !words.is_empty() => {
+                let template = path.file_name().unwrap_or_default().to_string_lossy();
+                found.push((template.into_owned(), platform, words));
+            }
+            _ => index.skipped.push(path),
+        }
+    }
+
+    let mut by_platform: BTreeMap<&str, Vec<&[String]>> = BTreeMap::new();
+    for (_, platform, words) in &found {
+        by_platform.entry(platform).or_default().push(words);
+    }
+    index.rows = found
+        .iter()
+        .map(|(template, platform, words)| IndexRow {
+            template: template.clone(),
+            platform: platform.clone(),
+            command: abbreviate(words, &by_platform[platform.as_str()]),
+        })
+        .collect();
+    index
+        .rows
+        .sort_by(|a, b| (&a.platform, &a.command).cmp(&(&b.platform, &b.command)));
+    Ok(index)
+}
+
+/// Splits `cisco_ios_show_ip_route` into `cisco_ios` and `show ip route`.
+fn split_file_name(stem: &str) -> Option<(String, Vec<String>)> {
+    let words: Vec<&str> = stem.split('_').collect();
+    let verb = words
+        .iter()
+        .position(|w| COMMAND_VERBS.contains(w))
+        .filter(|&i| i > 0)?;
+    Some((
+        words[..verb].join("_"),
+        words[verb..].iter().map(|w| w.to_string()).collect(),
+    ))
+}
+
+/// Marks the optional tail of each word of `words`, given all commands of its platform.
+fn abbreviate(words: &[String], commands: &[&[String]]) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if word.contains("[[") {
+                return word.clone();
+            }
+            // Words at the same position following the same earlier words.
+            let siblings = commands
+                .iter()
+                .filter(|other| other.len() > i && other[..i] == words[..i] && other[i] != *word)
+                .map(|other| other[i].as_str());
+            let chars: Vec<char> = word.chars().collect();
+            let mut keep = MIN_ABBREVIATION.min(chars.len());
+            for sibling in siblings {
+                let shared = sibling
+                    .chars()
+                    .zip(&chars)
+                    .take_while(|(a, b)| a == *b)
+                    .count();
+                keep = keep.max((shared + 1).min(chars.len()));
+            }
+            if keep == chars.len() {
+                word.clone()
+            } else {
+                let (head, tail) = chars.split_at(keep);
+                format!(
+                    "{}[[{}]]",
+                    head.iter().collect::<String>(),
+                    tail.iter().collect::<String>()
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,311 @@
+//! A human-readable outline of a template, for finding one's way around it before an
+//! edit.
+//!
+//! A [`TemplateSummary`] lists the values with their options and regexes, and each
+//! state with its number of rules and the states its rules move to, which is the
+//! template's transition graph. States no path from `Start` reaches are marked.
+//!
+//! A [`TemplateAst`] holds the whole parsed template instead, down to each rule's
+//! expanded regex and actions, and serializes for tools that read templates without
+//! parsing them: linters, documentation generators and editors.
+//!
+//! ```
+//! use asyncfsm::{TemplateSummary, TextFSMParser};
+//!
+//! let template = "Value Required NAME (\\S+)\n\nStart\n  ^Name: ${NAME} -> Detail\n\nDetail\n  ^$$ -> Record Start\n";
+//! let summary = TemplateSummary::new(&TextFSMParser::from_string(template)?);
+//! assert_eq!(summary.states[0].transitions[0].to, "Detail");
+//! assert_eq!(
+//!     summary.to_string(),
+//!     "values:\n  NAME (\\S+) [Required]\nstates:\n  Start (1 rule)\n    -> Detail (rule 1)\n  Detail (1 rule)\n    -> Start (rule 1)\n"
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{NextState, RecordAction, TextFSMParser};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// One `Value` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueSummary {
+    pub name: String,
+    /// The options as written, e.g. `Filldown,Required`.
+    pub options: Option<String>,
+    pub regex: String,
+}
+
+/// The rules of one state that move to the same state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// The state moved to, or `Error`.
+    pub to: String,
+    /// 0-based positions of the rules in their state.
+    pub rules: Vec<usize>,
+}
+
+/// One state and where its rules lead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSummary {
+    pub name: String,
+    pub rules: usize,
+    /// In the order of the first rule to each state.
+    pub transitions: Vec<Transition>,
+    /// False when no chain of transitions from `Start` leads here.
+    pub reachable: bool,
+}
+
+/// An outline of a template; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateSummary {
+    pub values: Vec<ValueSummary>,
+    /// In template order.
+    pub states: Vec<StateSummary>,
+}
+
+impl TemplateSummary {
+    pub fn new(parser: &TextFSMParser) -> Self {
+        let values = parser
+            .value_names
+            .iter()
+            .map(|name| {
+                let value = &parser.values[name];
+                ValueSummary {
+                    name: name.clone(),
+                    options: value.options().map(String::from),
+                    regex: value.regex().to_string(),
+                }
+            })
+            .collect();
+        let mut states: Vec<StateSummary> = parser
+            .state_names
+            .iter()
+            .map(|name| {
+                let rules = parser.states[name].rules();
+                let mut transitions: Vec<Transition> = vec![];
+                for (index, rule) in rules.iter().enumerate() {
+                    let to = match rule.transition().next_state() {
+                        Some(NextState::NamedState(to)) => to.clone(),
+                        Some(NextState::Error(_)) => "Error".to_string(),
+                        None => continue,
+                    };
+                    match transitions.iter_mut().find(|t| t.to == to) {
+                        Some(transition) => transition.rules.push(index),
+                        None => transitions.push(Transition {
+                            to,
+                            rules: vec![index],
+                        }),
+                    }
+                }
+                StateSummary {
+                    name: name.clone(),
+                    rules: rules.len(),
+                    transitions,
+                    reachable: false,
+                }
+            })
+            .collect();
+        let mut reached: HashSet<String> = HashSet::new();
+        let mut pending = vec!["Start".to_string()];
+        while let Some(name) = pending.pop() {
+            if !reached.insert(name.clone()) {
+                continue;
+            }
+            if let Some(state) = states.iter().find(|s| s.name == name) {
+                pending.extend(state.transitions.iter().map(|t| t.to.clone()));
+            }
+        }
+        for state in &mut states {
+            state.reachable = reached.contains(&state.name);
+        }
+        TemplateSummary { values, states }
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+impl fmt::Display for TemplateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "values:")?;
+        for value in &self.values {
+            write!(f, "  {} {}", value.name, value.regex)?;
+            match &value.options {
+                Some(options) => writeln!(f, " [{}]", options)?,
+                None => writeln!(f)?,
+            }
+        }
+        writeln!(f, "states:")?;
+        for state in &self.states {
+            write!(
+                f,
+                "  {} ({} rule{})",
+                state.name,
+                state.rules,
+                plural(state.rules)
+            )?;
+            if state.reachable {
+                writeln!(f)?;
+            } else {
+                writeln!(f, " unreachable")?;
+            }
+            for transition in &state.transitions {
+                let rules: Vec<String> = transition
+                    .rules
+                    .iter()
+                    .map(|i| (i + 1).to_string())
+                    .collect();
+                writeln!(
+                    f,
+                    "    -> {} (rule{} {})",
+                    transition.to,
+                    plural(rules.len()),
+                    rules.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed template as plain data; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateAst {
+    /// `Key: value` pairs from the comment block at the top.
+    pub metadata: BTreeMap<String, String>,
+    /// The flags every rule is compiled with, as letters such as `i`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub regex_flags: String,
+    /// `Macro` definitions, with the macros they use expanded.
+    pub macros: BTreeMap<String, String>,
+    /// In template order.
+    pub values: Vec<ValueAst>,
+    /// In template order, without the implicit `EOF`.
+    pub states: Vec<StateAst>,
+    /// Whether the template declares an `EOF` state, which stops the record being
+    /// flushed at end of input.
+    pub explicit_eof: bool,
+}
+
+/// A `Value` line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueAst {
+    pub name: String,
+    /// Including the outer parentheses.
+    pub regex: String,
+    /// E.g. `["Filldown", "OneOf=up|down"]`.
+    pub options: Vec<String>,
+}
+
+/// A state and its rules.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StateAst {
+    pub name: String,
+    pub rules: Vec<RuleAst>,
+}
+
+/// A rule and what happens when it matches.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleAst {
+    /// As written, with `${VALUE}` references.
+    pub pattern: String,
+    /// As compiled; see [`StateRuleCompiled::expanded_pattern`](crate::StateRuleCompiled::expanded_pattern).
+    pub regex: String,
+    /// The values the rule captures, in pattern order.
+    pub values: Vec<String>,
+    /// `next`, or `continue` to try the following rules on the same line.
+    pub line_action: &'static str,
+    /// `record`, `clear` or `clearall`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_action: Option<&'static str>,
+    /// The state moved to, `Error` included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_state: Option<String>,
+    /// The message of an `Error` transition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+    /// Registered actions run by `Call(...)`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<String>,
+}
+
+impl TemplateAst {
+    pub fn new(parser: &TextFSMParser) -> Self {
+        let values = parser
+            .value_names
+            .iter()
+            .map(|name| {
+                let value = &parser.values[name];
+                ValueAst {
+                    name: name.clone(),
+                    regex: value.regex().to_string(),
+                    options: value
+                        .options()
+                        .into_iter()
+                        .flat_map(|options| options.split(','))
+                        .map(|option| option.trim().to_string())
+                        .filter(|option| !option.is_empty())
+                        .collect(),
+                }
+            })
+            .collect();
+        let states = parser
+            .state_names
+            .iter()
+            .map(|name| StateAst {
+                name: name.clone(),
+                rules: parser.states[name]
+                    .rules()
+                    .iter()
+                    .map(|rule| {
+                        let transition = rule.transition();
+                        let (next_state, error_message) = match transition.next_state() {
+                            Some(NextState::NamedState(to)) => (Some(to.clone()), None),
+                            Some(NextState::Error(message)) => {
+                                (Some("Error".to_string()), message.clone())
+                            }
+                            None => (None, None),
+                        };
+                        RuleAst {
+                            pattern: rule.pattern().to_string(),
+                            regex: rule.expanded_pattern().to_string(),
+                            values: rule.captured_values().map(String::from).collect(),
+                            line_action: if transition.continues() {
+                                "continue"
+                            } else {
+                                "next"
+                            },
+                            record_action: match transition.record_action() {
+                                RecordAction::NoRecord => None,
+                                RecordAction::Record => Some("record"),
+                                RecordAction::Clear => Some("clear"),
+                                RecordAction::Clearall => Some("clearall"),
+                            },
+                            next_state,
+                            error_message,
+                            calls: transition.calls().to_vec(),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        let flags = parser.regex_flags.inline_group();
+        TemplateAst {
+            metadata: parser.metadata.clone().into_iter().collect(),
+            regex_flags: flags
+                .trim_start_matches("(?")
+                .trim_end_matches(')')
+                .to_string(),
+            macros: parser.macros.clone().into_iter().collect(),
+            values,
+            states,
+            explicit_eof: parser.explicit_eof,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Canonical interface names, so records from different commands can be joined.
+//!
+//! Devices abbreviate interface names inconsistently: `show interfaces` prints
+//! `GigabitEthernet0/1` where `show cdp neighbors` prints `Gi0/1` and some commands
+//! `Gig 0/1`. [`InterfaceNames`] rewrites them all to one form, using a table of
+//! full names and their usual abbreviation for the platform. A name matches an entry
+//! when its letters are the abbreviation or a prefix of the full name at least as long
+//! as the abbreviation, in any case; names that match no entry are left alone.
+//!
+//! ```
+//! use asyncfsm::{DataRecordConversion, InterfaceNames, TextFSM};
+//!
+//! let template = "Value PORT (\\S+)\n\nStart\n  ^${PORT} -> Record\n";
+//! let records = TextFSM::from_string(template)?.parse_records(
+//!     "Gi0/1\nPo10\nTenGigabitEthernet1/1\n",
+//!     Some(DataRecordConversion::NormalizeInterfaces(InterfaceNames::default())),
+//! )?;
+//! let ports: Vec<String> = records.iter().map(|r| r.fields["PORT"].to_string()).collect();
+//! assert_eq!(ports, ["GigabitEthernet0/1", "Port-channel10", "TenGigabitEthernet1/1"]);
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Value};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Full interface names and their abbreviations on Cisco IOS and IOS XE.
+const CISCO_IOS: &[(&str, &str)] = &[
+    ("GigabitEthernet", "Gi"),
+    ("FastEthernet", "Fa"),
+    ("TenGigabitEthernet", "Te"),
+    ("TwentyFiveGigE", "Twe"),
+    ("FortyGigabitEthernet", "Fo"),
+    ("HundredGigE", "Hu"),
+    ("AppGigabitEthernet", "Ap"),
+    ("Ethernet", "Et"),
+    ("Port-channel", "Po"),
+    ("Loopback", "Lo"),
+    ("Vlan", "Vl"),
+    ("Tunnel", "Tu"),
+    ("Serial", "Se"),
+    ("Management", "Ma"),
+];
+
+/// Cisco NX-OS.
+const CISCO_NXOS: &[(&str, &str)] = &[
+    ("Ethernet", "Eth"),
+    ("port-channel", "Po"),
+    ("loopback", "Lo"),
+    ("Vlan", "Vlan"),
+    ("mgmt", "mgmt"),
+    ("Tunnel", "Tu"),
+    ("nve", "nve"),
+];
+
+/// Cisco IOS XR.
+const CISCO_XR: &[(&str, &str)] = &[
+    ("GigabitEthernet", "Gi"),
+    ("TenGigE", "Te"),
+    ("TwentyFiveGigE", "TF"),
+    ("FortyGigE", "Fo"),
+    ("HundredGigE", "Hu"),
+    ("Bundle-Ether", "BE"),
+    ("Loopback", "Lo"),
+    ("MgmtEth", "Mg"),
+    ("tunnel-te", "tt"),
+];
+
+/// Arista EOS.
+const ARISTA_EOS: &[(&str, &str)] = &[
+    ("Ethernet", "Et"),
+    ("Port-Channel", "Po"),
+    ("Loopback", "Lo"),
+    ("Vlan", "Vl"),
+    ("Management", "Ma"),
+    ("Vxlan", "Vx"),
+    ("Tunnel", "Tu"),
+];
+
+/// The table for a platform name as used in CliTable indexes, e.g. `cisco_nxos`.
+fn table(platform: &str) -> &'static [(&'static str, &'static str)] {
+    match platform.to_ascii_lowercase().as_str() {
+        "cisco_nxos" => CISCO_NXOS,
+        "cisco_xr" => CISCO_XR,
+        "arista_eos" => ARISTA_EOS,
+        _ => CISCO_IOS,
+    }
+}
+
+/// Which form [`InterfaceNames`] rewrites names to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InterfaceStyle {
+    /// `GigabitEthernet0/1`
+    #[default]
+    Long,
+    /// `Gi0/1`
+    Short,
+}
+
+/// Rewrites interface names to one form; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceNames {
+    table: Vec<(String, String)>,
+    style: InterfaceStyle,
+    fields: Option<Vec<String>>,
+}
+
+impl Default for InterfaceNames {
+    /// Full names, with the Cisco IOS table.
+    fn default() -> Self {
+        Self::for_platform("cisco_ios")
+    }
+}
+
+impl InterfaceNames {
+    /// Full names, with the table for `platform`: `cisco_ios` (also used for
+    /// platforms without a table of their own), `cisco_nxos`, `cisco_xr` or
+    /// `arista_eos`.
+    pub fn for_platform(platform: &str) -> Self {
+        InterfaceNames {
+            table: table(platform)
+                .iter()
+                .map(|(long, short)| (long.to_string(), short.to_string()))
+                .collect(),
+            style: InterfaceStyle::Long,
+            fields: None,
+        }
+    }
+
+    /// Rewrites to `style` instead of full names.
+    pub fn style(mut self, style: InterfaceStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Adds a full name and its abbreviation, checked before the platform's entries.
+    pub fn alias(mut self, long: impl Into<String>, short: impl Into<String>) -> Self {
+        self.table.insert(0, (long.into(), short.into()));
+        self
+    }
+
+    /// Rewrites only the named fields (in any case). By default a field is rewritten
+    /// when its name contains `INTERFACE` or `PORT`, as in `LOCAL_INTERFACE`.
+    pub fn fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// The name in the configured form, or `None` if it matches no entry.
+    pub fn normalize(&self, name: &str) -> Option<String> {
+        static NAME: OnceLock<Regex> = OnceLock::new();
+        let re =
+            NAME.get_or_init(|| Regex::new(r"^([A-Za-z][A-Za-z-]*?) ?(\d[\w/:.-]*)$").unwrap());
+        let caps = re.captures(name.trim())?;
+        let (letters, rest) = (&caps[1], &caps[2]);
+        let lower = letters.to_ascii_lowercase();
+        let (long, short) = self.table.iter().find(|(long, short)| {
+            lower == short.to_ascii_lowercase()
+                || (lower.len() >= short.len() && long.to_ascii_lowercase().starts_with(&lower))
+        })?;
+        Some(match self.style {
+            InterfaceStyle::Long => format!("{}{}", long, rest),
+            InterfaceStyle::Short => format!("{}{}", short, rest),
+        })
+    }
+
+    fn applies_to(&self, field: &str) -> bool {
+        match &self.fields {
+            Some(fields) => fields.iter().any(|f| f.eq_ignore_ascii_case(field)),
+            None => {
+                let field = field.to_ascii_uppercase();
+                field.contains("INTERFACE") || field.contains("PORT")
+            }
+        }
+    }
+
+    /// Rewrites the interface names in `record`'s chosen fields, including list items.
+    pub fn apply(&self, record: &mut DataRecord) {
+        for (field, value) in record.fields.iter_mut() {
+            if !self.applies_to(field) {
+                continue;
+            }
+            match value {
+                Value::Single(name) => {
+                    if let Some(normalized) = self.normalize(name) {
+                        *name = normalized;
+                    }
+                }
+                Value::List(names) => {
+                    for name in names {
+                        if let Some(normalized) = self.normalize(name) {
+                            *name = normalized;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
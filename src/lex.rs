@@ -0,0 +1,285 @@
+//! Template text as tokens, for syntax highlighting.
+//!
+//! [`lex`] splits a template into the pieces an editor colours: comments, the `Value`
+//! and `Macro` keywords with their options, names and regexes, the `${NAME}`
+//! references in regexes, state names, and the `->` and actions of a rule. It reads the
+//! template a line at a time and never fails, so text being typed still highlights:
+//! what fits nowhere is an [`Invalid`](TokenKind::Invalid) token. Whitespace and line
+//! breaks are not tokens.
+//!
+//! The [language server](crate::lsp) uses the same tokens for highlighting and to find
+//! the name under the cursor.
+//!
+//! ```
+//! use asyncfsm::{Template, TokenKind};
+//!
+//! let template = "Value Filldown HOST (\\S+)\n\nStart\n  ^${HOST} -> Record\n";
+//! let tokens = Template::lex(template);
+//! let kinds: Vec<(TokenKind, &str)> = tokens
+//!     .iter()
+//!     .map(|t| (t.kind, &template[t.span.start..t.span.end]))
+//!     .collect();
+//! assert_eq!(
+//!     kinds,
+//!     [
+//!         (TokenKind::Keyword, "Value"),
+//!         (TokenKind::ValueOption, "Filldown"),
+//!         (TokenKind::Name, "HOST"),
+//!         (TokenKind::Regex, "(\\S+)"),
+//!         (TokenKind::State, "Start"),
+//!         (TokenKind::Regex, "^"),
+//!         (TokenKind::Reference, "${HOST}"),
+//!         (TokenKind::Arrow, "->"),
+//!         (TokenKind::Action, "Record"),
+//!     ]
+//! );
+//! ```
+
+use crate::Span;
+use serde::Serialize;
+
+/// What a [`Token`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    /// A `#` comment, to the end of its line.
+    Comment,
+    /// `Value` or `Macro`, starting a declaration.
+    Keyword,
+    /// An option of a `Value`, such as `Filldown` or `OneOf=up|down`.
+    ValueOption,
+    /// The name a `Value` or `Macro` declares.
+    Name,
+    /// A regex, or the part of a rule's regex between references.
+    Regex,
+    /// A `${NAME}` or `$NAME` in a regex.
+    Reference,
+    /// A state's name, in its header or in a transition.
+    State,
+    /// The `->` between a rule's regex and its actions.
+    Arrow,
+    /// `Next`, `Continue`, `Record`, `NoRecord`, `Clear`, `Clearall`, `Error` or
+    /// `Call(NAME)`.
+    Action,
+    /// The message of an `Error` action.
+    Message,
+    /// A `,` between options or a `.` between actions.
+    Punctuation,
+    /// Text that fits nowhere, such as a second word on a state's line.
+    Invalid,
+}
+
+/// A piece of template text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// The actions that may come before a `.` or stand alone, other than `Error`.
+const ACTIONS: [&str; 6] = [
+    "Next", "Continue", "NoRecord", "Record", "Clearall", "Clear",
+];
+
+/// One line of the template, collecting its tokens.
+struct Line<'a> {
+    text: &'a str,
+    /// Byte offset of the line in the template.
+    start: usize,
+    number: usize,
+    tokens: &'a mut Vec<Token>,
+}
+
+impl<'a> Line<'a> {
+    /// Adds the bytes `from..to` of the line as a token.
+    fn push(&mut self, kind: TokenKind, from: usize, to: usize) {
+        if from < to {
+            self.tokens.push(Token {
+                kind,
+                span: Span {
+                    start: self.start + from,
+                    end: self.start + to,
+                    line: self.number,
+                    column: self.text[..from].chars().count() + 1,
+                },
+            });
+        }
+    }
+
+    /// The words at or after byte `from`, with their offsets in the line.
+    fn words(&self, from: usize) -> Vec<(usize, &'a str)> {
+        let mut words = vec![];
+        let mut at = from;
+        for word in self.text[from..].split(char::is_whitespace) {
+            if !word.is_empty() {
+                words.push((at, word));
+            }
+            at += word.len() + 1;
+        }
+        words
+    }
+
+    /// Where the text after byte `from` starts, skipping whitespace.
+    fn skip_space(&self, from: usize) -> usize {
+        from + (self.text[from..].len() - self.text[from..].trim_start().len())
+    }
+
+    /// The end of the line's text, without trailing whitespace.
+    fn end(&self) -> usize {
+        self.text.trim_end().len()
+    }
+
+    /// A regex in the bytes `from..end`, split around its references.
+    fn regex(&mut self, from: usize, end: usize) {
+        let bytes = self.text.as_bytes();
+        let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+        let (mut text_start, mut i) = (from, from);
+        while i < end {
+            if bytes[i] != b'$' {
+                i += 1;
+                continue;
+            }
+            let reference_end = match bytes.get(i + 1) {
+                Some(b'$') => None,
+                Some(b'{') => self.text[i..end].find('}').map(|close| i + close + 1),
+                Some(&b) if is_word(b) => {
+                    let len = bytes[i + 1..end]
+                        .iter()
+                        .take_while(|&&b| is_word(b))
+                        .count();
+                    Some(i + 1 + len)
+                }
+                _ => None,
+            };
+            match reference_end {
+                Some(reference_end) => {
+                    self.push(TokenKind::Regex, text_start, i);
+                    self.push(TokenKind::Reference, i, reference_end);
+                    text_start = reference_end;
+                    i = reference_end;
+                }
+                // `$$` is a literal `$`, and a lone `$` the end of the line.
+                None => {
+                    i += if bytes.get(i + 1) == Some(&b'$') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+        }
+        self.push(TokenKind::Regex, text_start, end);
+    }
+
+    /// A `Value [options] NAME (regex)` or `Macro NAME (regex)` line, after the keyword.
+    fn declaration(&mut self, from: usize, has_options: bool) {
+        let words = self.words(from);
+        let Some(&(first, word)) = words.first() else {
+            return;
+        };
+        if word.starts_with('(') {
+            return self.regex(first, self.end());
+        }
+        let (name, name_len) = match words.get(1) {
+            Some(&(second, next)) if has_options && !next.starts_with('(') => {
+                let mut at = first;
+                for option in word.split(',') {
+                    self.push(TokenKind::ValueOption, at, at + option.len());
+                    at += option.len();
+                    if at < first + word.len() {
+                        self.push(TokenKind::Punctuation, at, at + 1);
+                        at += 1;
+                    }
+                }
+                (second, next.len())
+            }
+            _ => (first, word.len()),
+        };
+        self.push(TokenKind::Name, name, name + name_len);
+        let regex = self.skip_space(name + name_len);
+        if self.text[regex..].starts_with('(') {
+            self.regex(regex, self.end());
+        } else {
+            self.push(TokenKind::Invalid, regex, self.end());
+        }
+    }
+
+    /// A rule, starting with its `^` at byte `from`.
+    fn rule(&mut self, from: usize) {
+        let Some(arrow) = self.text[from..].find(" -> ").map(|i| from + i + 1) else {
+            return self.regex(from, self.end());
+        };
+        self.regex(from, arrow - 1);
+        self.push(TokenKind::Arrow, arrow, arrow + 2);
+        for (at, word) in self.words(arrow + 2) {
+            if word == "Error" {
+                self.push(TokenKind::Action, at, at + word.len());
+                let message = self.skip_space(at + word.len());
+                self.push(TokenKind::Message, message, self.end());
+                return;
+            }
+            if word.starts_with("Call(") {
+                self.push(TokenKind::Action, at, at + word.len());
+                continue;
+            }
+            let mut part_start = at;
+            for part in word.split('.') {
+                let kind = if ACTIONS.contains(&part) {
+                    TokenKind::Action
+                } else {
+                    TokenKind::State
+                };
+                self.push(kind, part_start, part_start + part.len());
+                part_start += part.len();
+                if part_start < at + word.len() {
+                    self.push(TokenKind::Punctuation, part_start, part_start + 1);
+                    part_start += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The tokens of `template`, in order; see the [module documentation](self).
+pub fn lex(template: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut in_states = false;
+    let mut start = 0;
+    for (i, raw) in template.split_inclusive('\n').enumerate() {
+        let text = raw.trim_end_matches(['\n', '\r']);
+        let mut line = Line {
+            text,
+            start,
+            number: i + 1,
+            tokens: &mut tokens,
+        };
+        start += raw.len();
+        let indent = line.skip_space(0);
+        let trimmed = text[indent..].trim_end();
+        let end = indent + trimmed.len();
+        let keyword = trimmed
+            .split(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+        if trimmed.is_empty() {
+            continue;
+        } else if trimmed.starts_with('#') {
+            line.push(TokenKind::Comment, indent, end);
+        } else if !in_states && (keyword == "Value" || keyword == "Macro") {
+            line.push(TokenKind::Keyword, indent, indent + keyword.len());
+            line.declaration(indent + keyword.len(), keyword == "Value");
+        } else if trimmed.starts_with('^') {
+            in_states = true;
+            line.rule(indent);
+        } else {
+            in_states = true;
+            let name = trimmed
+                .find(char::is_whitespace)
+                .map_or(end, |i| indent + i);
+            line.push(TokenKind::State, indent, name);
+            let rest = line.skip_space(name);
+            line.push(TokenKind::Invalid, rest, end);
+        }
+    }
+    tokens
+}
@@ -1,42 +1,142 @@
-#![deny(
-    warnings,
-    clippy::all,
-    clippy::pedantic,
-    clippy::nursery,
-    clippy::perf,
-    clippy::cargo,
-    clippy::alloc_instead_of_core,
-    clippy::std_instead_of_core,
-    clippy::std_instead_of_alloc,
-    clippy::get_unwrap,
-    clippy::panic_in_result_fn,
-    clippy::pub_use,
-    clippy::question_mark_used,
-    clippy::rc_buffer,
-    clippy::rest_pat_in_fully_bound_structs,
-    clippy::same_name_method,
-    clippy::string_add,
-    clippy::unnecessary_self_imports,
-    clippy::unneeded_field_pattern,
-    clippy::use_debug,
-    clippy::verbose_file_reads
-)]
 pub mod error;
 pub mod record;
-use crate::error::{Result, TextFsmError};
-use crate::record::*;
-use log::{debug, trace, warn};
+pub mod session;
+use crate::diagnostics::{message_of, similar};
+pub use crate::error::{Result, TextFsmError};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 use regex::Regex;
 use std::collections::{HashMap, VecDeque};
-
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, trace, trace_span, warn};
+
+pub mod action;
+pub mod addresses;
+pub mod anonymize;
+pub mod batch;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod cancel;
 #[cfg(feature = "clitable")]
 pub mod cli_table;
+pub mod coerce;
+#[cfg(feature = "compress")]
+pub mod compress;
+pub mod diagnostics;
+pub mod encoding;
+pub mod explain;
 pub mod export;
+pub mod facts;
+#[cfg(feature = "follow")]
+pub mod follow;
+pub mod format;
+#[cfg(feature = "genie")]
+pub mod genie;
+#[cfg(feature = "clitable")]
+pub mod index_gen;
+pub mod inspect;
+pub mod interfaces;
+pub mod lex;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod macros;
+#[cfg(all(feature = "json", feature = "yaml"))]
+pub mod mapping;
+pub mod metrics;
+pub mod model;
+#[cfg(all(feature = "json", feature = "yaml"))]
+pub mod netbox;
+pub mod optimize;
+pub mod output;
+pub mod patterns;
+pub mod pipeline;
+pub mod pluck;
+pub mod publish;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod render;
+#[cfg(feature = "report")]
+pub mod report;
+pub mod resolver;
+pub mod schema;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod sink;
+pub mod tdiff;
+pub mod template;
+#[cfg(feature = "timestamps")]
+pub mod timestamp;
+pub mod validate;
+pub mod value_option;
 pub mod varsubst;
+pub mod warnings;
+#[cfg(feature = "notify")]
+pub mod watch;
 
+pub use crate::action::{ActionContext, RuleAction};
+pub use crate::addresses::{AddressFormats, Ipv6Format, MacFormat};
+pub use crate::anonymize::Anonymizer;
+#[cfg(feature = "clitable")]
+pub use crate::batch::Inventory;
+pub use crate::batch::{DirFile, DirOptions, DirParser, DirProgress, DirResult, ResultEnvelope};
+#[cfg(feature = "bundle")]
+pub use crate::bundle::{Bundle, BundleOptions, BundleOutput};
+#[cfg(feature = "cache")]
+pub use crate::cache::{CacheKey, CachedParse, ResultCache};
+pub use crate::cancel::CancellationToken;
+#[cfg(feature = "clitable")]
+pub use crate::cli_table::{
+    BestMatch, CliTable, CliTableRow, IndexAudit, PlatformCoverage, TemplateCache, TemplateFailure,
+    TemplateMatch,
+};
+pub use crate::coerce::{Coercion, Coercions, DecimalMark};
+pub use crate::diagnostics::{Diagnostic, Diagnostics, Span};
+pub use crate::explain::{CaptureSpan, RuleMatch};
+pub use crate::export::{OutputFormat, TextFsmExport};
+pub use crate::facts::{DeviceFacts, InterfaceFacts};
+#[cfg(feature = "clitable")]
+pub use crate::index_gen::{generate_index, GeneratedIndex, IndexRow};
+pub use crate::inspect::{TemplateAst, TemplateSummary};
+pub use crate::interfaces::{InterfaceNames, InterfaceStyle};
+pub use crate::lex::{Token, TokenKind};
+pub use crate::macros::{register_macro, BUILTIN_MACROS};
+pub use crate::model::DeviceModel;
+pub use crate::output::{
+    output_writer, output_writer_names, register_output_writer, write_records, OutputWriter,
+};
+pub use crate::pipeline::{NestedRecord, NestedValue, Nesting, Pipeline};
+pub use crate::pluck::{pluck, Plucked};
+pub use crate::record::{DataRecord, DataRecordConversion, RawText, Records, Value};
+#[cfg(feature = "registry")]
+pub use crate::registry::{GitSource, RegistryEntry, TemplateRegistry};
+pub use crate::render::LineTemplate;
+#[cfg(feature = "report")]
+pub use crate::report::Report;
+pub use crate::resolver::TemplateResolver;
+pub use crate::schema::{FieldChange, FieldType, SchemaDiff};
+#[cfg(feature = "script")]
+pub use crate::script::RecordScript;
+pub use crate::session::SessionState;
+pub use crate::sink::{record_sink, RecordSink};
+pub use crate::tdiff::TemplateDiff;
+pub use crate::template::Template;
+#[cfg(feature = "timestamps")]
+pub use crate::timestamp::{TimestampOutput, Timestamps};
+pub use crate::validate::{ValidationIssue, Validator};
+pub use crate::value_option::{
+    register_value_option, CustomValueOption, ValueOptionHandler, ValueOptionUse,
+};
+pub use crate::warnings::{
+    Deprecation, DropReason, DroppedRecord, ParseOutcome, ParseWarning, Severity, WarningKind,
+    Warnings,
+};
+#[cfg(feature = "notify")]
+pub use crate::watch::WatchedCliTable;
 
 /// An iterator that parses input line-by-line and yields `DataRecord`s.
 pub struct TextFsmIter<R> {
@@ -44,12 +144,80 @@ pub struct TextFsmIter<R> {
     lines: std::io::Lines<R>,
     eof_processed: bool,
     current_line: Option<String>,
+    emitted: usize,
+    started: Option<Instant>,
+}
+
+impl<R> TextFsmIter<R> {
+    /// Returns true if iteration stopped early because a record or time limit was hit
+    /// or the parse was cancelled.
+    pub fn is_truncated(&self) -> bool {
+        self.fsm.truncated
+    }
+
+    /// Returns true if iteration stopped because the cancel token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.fsm.cancelled
+    }
+
+    /// Fraction of the lines read so far that matched no rule; see [`TextFSM::unmatched_ratio`].
+    pub fn unmatched_ratio(&self) -> f64 {
+        self.fsm.unmatched_ratio()
+    }
+
+    /// The engine driving the iteration, e.g. for its line counters and warnings.
+    pub fn fsm(&self) -> &TextFSM {
+        &self.fsm
+    }
+}
+
+impl<R: std::io::BufRead> TextFsmIter<R> {
+    /// Parses the rest of the input into `sink`, writing each record as it is parsed,
+    /// and closes the sink. Returns the number of records written.
+    pub fn write_to(
+        mut self,
+        sink: &mut dyn RecordSink,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<usize> {
+        let schema = self.fsm.parser.value_names.clone();
+        let fields = Records::new(vec![], schema.clone()).convert(conversion.clone());
+        sink.open(fields.schema())?;
+        let mut written = 0;
+        for record in self.by_ref() {
+            let records = Records::new(vec![record?], schema.clone()).convert(conversion.clone());
+            sink.write(&records)?;
+            written += 1;
+        }
+        sink.close()?;
+        Ok(written)
+    }
 }
 
 impl<R: std::io::BufRead> Iterator for TextFsmIter<R> {
     type Item = Result<DataRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if self.fsm.check_limits(self.emitted, started) {
+            return None;
+        }
+        let first_line = self.fsm.line_number;
+        let item = self.next_record(started);
+        let lines = self.fsm.line_number - first_line;
+        match item {
+            Some(Ok(_)) => {
+                self.emitted += 1;
+                metrics::stream_progress(lines, 1, true);
+            }
+            Some(Err(_)) => metrics::stream_progress(lines, 0, false),
+            None => metrics::stream_progress(lines, 0, true),
+        }
+        item
+    }
+}
+
+impl<R: std::io::BufRead> TextFsmIter<R> {
+    fn next_record(&mut self, started: Instant) -> Option<Result<DataRecord>> {
         // If we have accumulated records from previous lines (e.g. from 'Record' actions), return them first.
         if !self.fsm.records.is_empty() {
             return Some(Ok(self.fsm.records.pop_front().unwrap()));
@@ -60,79 +228,54 @@ impl<R: std::io::BufRead> Iterator for TextFsmIter<R> {
         }
 
         loop {
+            if self.fsm.check_limits(self.emitted, started) {
+                return None;
+            }
             let line = if let Some(ref l) = self.current_line {
                 l.clone()
             } else {
                 match self.lines.next() {
-                    Some(Ok(l)) => l,
+                    Some(Ok(l)) => {
+                        self.fsm.start_line();
+                        l
+                    }
                     Some(Err(e)) => return Some(Err(TextFsmError::IoError(e))),
                     None => {
                         // End of input. Handle EOF state transition logic.
-                        if self.fsm.curr_state != "End" {
-                            if let Err(e) = self.fsm.set_curr_state("EOF") {
-                                return Some(Err(e));
-                            }
-                            if let Err(e) = self.fsm.parse_line("") {
-                                return Some(Err(e));
-                            }
-                            if !self.fsm.records.is_empty() {
-                                // Don't return None yet, next call will return None after popping records
-                                return Some(Ok(self.fsm.records.pop_front().unwrap()));
-                            }
-                            if let Err(e) = self.fsm.set_curr_state("End") {
-                                return Some(Err(e));
-                            }
-                        }
                         self.eof_processed = true;
-                        return None;
+                        if let Err(e) = self.fsm.process_eof() {
+                            return Some(Err(e));
+                        }
+                        // Any flushed record is returned now, the next call returns None.
+                        return self.fsm.records.pop_front().map(Ok);
                     }
                 }
             };
 
-            // trace!("LINE: '{}'", &line);
+            trace!("LINE: '{}'", &line);
             match self.fsm.parse_line(&line) {
                 Ok(ParseStatus::NextLine(maybe_next_state)) => {
                     self.current_line = None;
-                    if let Some(next_state) = maybe_next_state {
-                        match next_state {
-                            NextState::Error(msg) => {
-                                return Some(Err(TextFsmError::StateError(format!(
-                                    "Error state reached! msg: {:?}",
-                                    msg
-                                ))));
-                            }
-                            NextState::NamedState(name) => {
-                                if let Err(e) = self.fsm.set_curr_state(&name) {
-                                    return Some(Err(e));
-                                }
-                            }
-                        }
+                    self.fsm.finish_line();
+                    if let Err(e) = self.fsm.apply_next_state(maybe_next_state, &line) {
+                        return Some(Err(e));
                     }
                 }
                 Ok(ParseStatus::SameLine(maybe_next_state)) => {
                     // Keep current_line set
                     self.current_line = Some(line.clone());
-                    if let Some(next_state) = maybe_next_state {
-                        match next_state {
-                            NextState::Error(msg) => {
-                                return Some(Err(TextFsmError::StateError(format!(
-                                    "Error state reached! msg: {:?}",
-                                    msg
-                                ))));
-                            }
-                            NextState::NamedState(name) => {
-                                if let Err(e) = self.fsm.set_curr_state(&name) {
-                                    return Some(Err(e));
-                                }
-                            }
-                        }
+                    if let Err(e) = self.fsm.apply_next_state(maybe_next_state, &line) {
+                        return Some(Err(e));
                     }
                 }
                 Err(e) => return Some(Err(e)),
             }
 
-            if self.fsm.curr_state == "EOF" || self.fsm.curr_state == "End" {
+            if self.fsm.is_finished() {
                 self.eof_processed = true;
+                if let Err(e) = self.fsm.process_eof() {
+                    return Some(Err(e));
+                }
                 if !self.fsm.records.is_empty() {
                     return Some(Ok(self.fsm.records.pop_front().unwrap()));
                 }
@@ -155,31 +298,517 @@ pub struct TextFSMParser {
     pub values: HashMap<String, ValueDefinition>,
     /// List of value names that are marked as 'Required'.
     pub mandatory_values: Vec<String>,
+    /// All value names, in the order the template declares them.
+    pub value_names: Vec<String>,
     /// Compiled state machine states.
     pub states: HashMap<String, StateCompiled>,
+    /// Names of the states the template declares, in order (without the implicit `EOF`).
+    pub state_names: Vec<String>,
+    /// Whether the template declares its own `EOF` state, which suppresses the
+    /// implicit record flush at end of input.
+    pub explicit_eof: bool,
+    /// `Key: value` pairs from the comment block at the top of the template.
+    pub metadata: HashMap<String, String>,
+    /// Flags every rule regex is compiled with.
+    pub regex_flags: RegexFlags,
+    /// The template's `Macro` definitions, with the macros they use expanded.
+    pub macros: HashMap<String, String>,
+}
+
+/// Regex flags applied to every rule of a template.
+///
+/// Set them for a whole template with a `# RegexFlags: i` header line (letters `i`,
+/// `m` and `s`), or with [`TextFSMParser::with_regex_flags`]. A single rule can use
+/// an inline group instead, e.g. `^(?i)interface ${NAME}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegexFlags {
+    /// `i`: letters match regardless of case.
+    pub case_insensitive: bool,
+    /// `m`: `^` and `$` match at line boundaries within the text.
+    pub multi_line: bool,
+    /// `s`: `.` also matches a newline.
+    pub dot_matches_new_line: bool,
+}
+
+impl RegexFlags {
+    /// Parses flag letters such as `"i"` or `"is"`.
+    pub fn parse(letters: &str) -> Result<Self> {
+        let mut flags = RegexFlags::default();
+        for c in letters.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                'i' => flags.case_insensitive = true,
+                'm' => flags.multi_line = true,
+                's' => flags.dot_matches_new_line = true,
+                c => {
+                    return Err(TextFsmError::ParseError(format!(
+                        "Unknown regex flag '{}', expected i, m or s",
+                        c
+                    )))
+                }
+            }
+        }
+        Ok(flags)
+    }
+
+    /// The inline group that enables the flags, e.g. `(?i)`, or `""` for none.
+    pub fn inline_group(&self) -> String {
+        let letters: String = [
+            (self.case_insensitive, 'i'),
+            (self.multi_line, 'm'),
+            (self.dot_matches_new_line, 's'),
+        ]
+        .iter()
+        .filter_map(|(on, c)| on.then_some(*c))
+        .collect();
+        if letters.is_empty() {
+            letters
+        } else {
+            format!("(?{})", letters)
+        }
+    }
 }
 
 /// Options for controlling TextFSM parsing behavior.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct TextFsmOptions {
     /// If true, missing fields in a record will be populated with empty strings (or empty lists).
     /// Default is true, matching standard TextFSM behavior.
     pub fill_missing_fields: bool,
+    /// State to begin parsing in. Defaults to `Start` when unset.
+    pub start_state: Option<String>,
+    /// Stop parsing once this many records have been emitted.
+    pub max_records: Option<usize>,
+    /// Stop parsing once this much time has elapsed.
+    pub stop_after: Option<Duration>,
+    /// Stop parsing once this token is cancelled.
+    pub cancel: Option<CancellationToken>,
+    /// Overrides whether the current record is flushed at end of input.
+    ///
+    /// When unset, the Python TextFSM rules apply: the record is flushed unless the template
+    /// declares an `EOF` state or the `End` state was reached. `Some(true)` flushes even with an
+    /// explicit `EOF` state, `Some(false)` never flushes. Reaching `End` always skips the flush.
+    pub flush_on_eof: Option<bool>,
+    /// Maximum number of rules evaluated against a single input line before giving up.
+    /// Guards against `Continue` transitions that cycle between states forever.
+    pub max_rule_evaluations: usize,
+    /// Extra validators per value name, applied on top of any `OneOf=` template options.
+    pub validators: HashMap<String, Vec<Validator>>,
+    /// Fail the parse on the first rejected capture instead of collecting it.
+    pub strict_validation: bool,
+    /// Attach the input lines each record was parsed from as `DataRecord::raw`.
+    pub with_raw: bool,
+    /// Actions rules can run with `Call(Name)`.
+    pub actions: HashMap<String, RuleAction>,
+    /// Clean-ups applied to every input line before it is matched.
+    pub normalize_whitespace: WhitespaceNormalization,
+    /// Most items a `List` value keeps in one record; later captures are dropped.
+    pub max_list_len: Option<usize>,
+    /// Most bytes one value holds in a record: longer captures are cut at a character
+    /// boundary, and `List` items that would exceed it are dropped.
+    pub max_field_bytes: Option<usize>,
+    /// Which duplicate records to drop as they are emitted.
+    pub dedupe: Dedupe,
+    /// Number records in `DataRecord::index` and hash their content into `DataRecord::id`.
+    pub with_ids: bool,
+    /// Drop this many records as they are emitted, before any are kept.
+    pub skip_records: usize,
+    /// Keep only this share of the emitted records, from 0.0 to 1.0.
+    pub sample: Option<f64>,
+    /// When `Filldown` values are forgotten.
+    pub filldown_reset: FilldownReset,
+    /// Keep the parse open between `parse_records` and `parse_file_records` calls.
+    pub carry_over: bool,
+    /// Collect the records the engine discards in `TextFSM::dropped`.
+    pub track_dropped: bool,
+    /// Collect the rules that matched each line in `TextFSM::matches`.
+    pub track_matches: bool,
+    /// Parse each line as a record of its own; see [`TextFsmOptions::line_mode`].
+    pub line_mode: bool,
+    /// Encoding of input files; see [`encoding::decode`] for what happens without one.
+    #[cfg(feature = "encoding_rs")]
+    pub encoding: Option<&'static encoding::Encoding>,
+}
+
+/// Duplicate records to drop while parsing; see [`TextFsmOptions::dedupe`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dedupe {
+    /// Keep every record.
+    #[default]
+    Off,
+    /// Drop a record whose fields equal those of the record emitted just before it.
+    Consecutive,
+    /// Drop a record whose `Key` values match any earlier record's. Records without
+    /// `Key` values are always kept.
+    Key,
+}
+
+/// When `Filldown` values stop being carried into new records; see
+/// [`TextFsmOptions::filldown_reset`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilldownReset {
+    /// On `Clearall` only, as Python TextFSM does.
+    #[default]
+    Clearall,
+    /// On `Clearall` and whenever a rule moves to another state. A value captured on the
+    /// line that makes the move is kept, so `^hostname ${HOST} -> Interfaces` still
+    /// carries `HOST` through the `Interfaces` state.
+    StateChange,
+    /// Never: `Clearall` clears like `Clear` and keeps the `Filldown` values.
+    Never,
+}
+
+/// Input line clean-ups, for captures made on another platform than the template.
+///
+/// Windows captures carry carriage returns and some devices align columns with tabs,
+/// while templates are usually written against Unix captures with spaces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WhitespaceNormalization {
+    /// Remove carriage returns.
+    pub strip_cr: bool,
+    /// Remove whitespace at the end of the line.
+    pub trim_trailing: bool,
+    /// Expand tabs to spaces, up to the next multiple of this many columns.
+    pub expand_tabs: Option<usize>,
+}
+
+impl WhitespaceNormalization {
+    /// Every clean-up, with tab stops every 8 columns.
+    pub fn all() -> Self {
+        Self {
+            strip_cr: true,
+            trim_trailing: true,
+            expand_tabs: Some(8),
+        }
+    }
+
+    /// Whether any clean-up is enabled.
+    pub fn is_active(&self) -> bool {
+        self.strip_cr || self.trim_trailing || self.expand_tabs.is_some()
+    }
+
+    /// Applies the enabled clean-ups to one line.
+    pub fn apply<'a>(&self, line: &'a str) -> std::borrow::Cow<'a, str> {
+        use std::borrow::Cow;
+        let mut line = Cow::Borrowed(line);
+        if self.strip_cr && line.contains('\r') {
+            line = Cow::Owned(line.replace('\r', ""));
+        }
+        if let Some(width) = self.expand_tabs.filter(|_| line.contains('\t')) {
+            let mut expanded = String::with_capacity(line.len() + width);
+            let mut column = 0;
+            for c in line.chars() {
+                if c == '\t' {
+                    let spaces = width.max(1) - column % width.max(1);
+                    expanded.extend(std::iter::repeat_n(' ', spaces));
+                    column += spaces;
+                } else {
+                    expanded.push(c);
+                    column += 1;
+                }
+            }
+            line = Cow::Owned(expanded);
+        }
+        if self.trim_trailing {
+            let trimmed = line.trim_end().len();
+            if trimmed < line.len() {
+                line.to_mut().truncate(trimmed);
+            }
+        }
+        line
+    }
 }
 
 impl Default for TextFsmOptions {
     fn default() -> Self {
         Self {
             fill_missing_fields: true,
+            start_state: None,
+            max_records: None,
+            stop_after: None,
+            cancel: None,
+            flush_on_eof: None,
+            max_rule_evaluations: 10_000,
+            validators: HashMap::new(),
+            strict_validation: false,
+            with_raw: false,
+            actions: HashMap::new(),
+            normalize_whitespace: WhitespaceNormalization::default(),
+            max_list_len: None,
+            max_field_bytes: None,
+            dedupe: Dedupe::Off,
+            with_ids: false,
+            skip_records: 0,
+            sample: None,
+            filldown_reset: FilldownReset::Clearall,
+            carry_over: false,
+            track_dropped: false,
+            track_matches: false,
+            line_mode: false,
+            #[cfg(feature = "encoding_rs")]
+            encoding: None,
         }
     }
 }
 
+impl TextFsmOptions {
+    /// Begins parsing in the named state instead of `Start`.
+    ///
+    /// Useful when the input is a fragment of a larger document and the
+    /// template models several sections.
+    pub fn start_state(mut self, name: &str) -> Self {
+        self.start_state = Some(name.to_string());
+        self
+    }
+
+    /// Stops parsing after `n` records, marking the result as truncated.
+    pub fn max_records(mut self, n: usize) -> Self {
+        self.max_records = Some(n);
+        self
+    }
+
+    /// Stops parsing once `limit` has elapsed, marking the result as truncated.
+    pub fn stop_after(mut self, limit: Duration) -> Self {
+        self.stop_after = Some(limit);
+        self
+    }
+
+    /// Stops parsing before the next line once `token` is cancelled, marking the result
+    /// as truncated and cancelled. The records emitted so far are returned as usual.
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Forces the implicit end-of-input record flush on or off.
+    pub fn flush_on_eof(mut self, flush: bool) -> Self {
+        self.flush_on_eof = Some(flush);
+        self
+    }
+
+    /// Sets the per-line rule evaluation cap.
+    pub fn max_rule_evaluations(mut self, n: usize) -> Self {
+        self.max_rule_evaluations = n;
+        self
+    }
+
+    /// Checks every capture of the value `name` with `validator`.
+    pub fn validator(mut self, name: &str, validator: Validator) -> Self {
+        self.validators
+            .entry(name.to_string())
+            .or_default()
+            .push(validator);
+        self
+    }
+
+    /// Turns rejected captures into a `ValidationError` instead of collected issues.
+    pub fn strict_validation(mut self, strict: bool) -> Self {
+        self.strict_validation = strict;
+        self
+    }
+
+    /// Keeps the input lines behind each record in `DataRecord::raw`.
+    ///
+    /// A record's block runs from the line of its first capture (or the line that
+    /// recorded it, if it captured nothing) to the line that recorded it.
+    pub fn with_raw(mut self, with_raw: bool) -> Self {
+        self.with_raw = with_raw;
+        self
+    }
+
+    /// Registers `action` under `name`, for rules that say `-> Call(name)`.
+    pub fn action<F>(mut self, name: &str, action: F) -> Self
+    where
+        F: Fn(&mut ActionContext<'_>) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        self.actions
+            .insert(name.to_string(), RuleAction::new(action));
+        self
+    }
+
+    /// Cleans up each input line before matching; see [`WhitespaceNormalization`].
+    pub fn normalize_whitespace(mut self, normalize: WhitespaceNormalization) -> Self {
+        self.normalize_whitespace = normalize;
+        self
+    }
+
+    /// Caps `List` values at `n` items per record.
+    ///
+    /// Guards long-running parses against templates that append to a list on every line.
+    pub fn max_list_len(mut self, n: usize) -> Self {
+        self.max_list_len = Some(n);
+        self
+    }
+
+    /// Drops duplicate records as they are emitted, e.g. the repeated blocks some devices
+    /// print when output is refreshed mid-capture. Dropped records are counted in
+    /// `TextFSM::duplicate_records`.
+    pub fn dedupe(mut self, dedupe: Dedupe) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// Keeps a [`DroppedRecord`] in `TextFSM::dropped` (and [`ParseOutcome::dropped`])
+    /// for each record the engine discards: one missing a `Required` value, values a
+    /// `Clear` or `Clearall` throws away before they were recorded, and duplicates
+    /// removed by [`dedupe`](Self::dedupe). Each is also logged at debug level.
+    pub fn track_dropped(mut self, track: bool) -> Self {
+        self.track_dropped = track;
+        self
+    }
+
+    /// Keeps a [`RuleMatch`] in `TextFSM::matches` for each rule that matches an input
+    /// line, with the spans of the values it captured. Lines parsed in
+    /// [`line_mode`](Self::line_mode) are not kept.
+    pub fn track_matches(mut self, track: bool) -> Self {
+        self.track_matches = track;
+        self
+    }
+
+    /// Gives each record an `_index`, counting the records this engine emitted from 0,
+    /// and an `_id` hashed from its content (see [`DataRecord::content_id`]).
+    ///
+    /// Indexes continue across `parse_chunk` calls and restart after `reset`.
+    pub fn with_ids(mut self, with_ids: bool) -> Self {
+        self.with_ids = with_ids;
+        self
+    }
+
+    /// Drops the first `n` records the engine emits. Combined with
+    /// [`max_records`](Self::max_records), which counts the records kept, it pages
+    /// through a large input, and `skip_records(100).max_records(10)` stops parsing
+    /// after the 110th record.
+    pub fn skip_records(mut self, n: usize) -> Self {
+        self.skip_records = n;
+        self
+    }
+
+    /// Keeps about `rate` (0.0 to 1.0) of the records emitted, to preview the shape of
+    /// a large result. Records are picked by a hash of their position among those
+    /// emitted, so the same input gives the same sample on every run. Skipped records
+    /// are not sampled, and `_index` still counts every record emitted.
+    pub fn sample(mut self, rate: f64) -> Self {
+        self.sample = Some(rate);
+        self
+    }
+
+    /// Chooses when `Filldown` values are cleared. The default matches Python TextFSM;
+    /// the others are for templates written against different clearing rules.
+    pub fn filldown_reset(mut self, reset: FilldownReset) -> Self {
+        self.filldown_reset = reset;
+        self
+    }
+
+    /// Treats successive inputs as one: `parse_records` and `parse_file_records` leave the state,
+    /// the record in progress and the `Filldown` values in place and skip the `EOF`
+    /// processing, so a record that spans two inputs comes out whole. Each call returns
+    /// the records completed during it; call [`TextFSM::finish`] after the last input.
+    pub fn carry_over(mut self, carry_over: bool) -> Self {
+        self.carry_over = carry_over;
+        self
+    }
+
+    /// Parses single-line inputs such as syslog or flow logs, where every line that
+    /// matches is one record. The template must have only a `Start` state. The first
+    /// rule that matches a line fills a fresh record, which is emitted at once, as if
+    /// the rule ended in `Record`, unless the rule only captures `Filldown` values.
+    /// `Continue`, `Clear` and the rest of the per-line bookkeeping are skipped, which
+    /// makes long logs parse faster. `Filldown` values still carry over, and `Error` and
+    /// `EOF` transitions still apply.
+    pub fn line_mode(mut self, line_mode: bool) -> Self {
+        self.line_mode = line_mode;
+        self
+    }
+
+    /// Caps every value at `n` bytes per record, counting all items of a `List`.
+    pub fn max_field_bytes(mut self, n: usize) -> Self {
+        self.max_field_bytes = Some(n);
+        self
+    }
+
+    /// Applies `max_list_len` and `max_field_bytes` to `value`; true if it was cut.
+    fn limit_value(&self, value: &mut Value) -> bool {
+        let mut limited = false;
+        match value {
+            Value::Single(s) => {
+                if let Some(max) = self.max_field_bytes.filter(|&max| s.len() > max) {
+                    let mut end = max;
+                    while !s.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    s.truncate(end);
+                    limited = true;
+                }
+            }
+            Value::List(items) => {
+                if let Some(max) = self.max_list_len.filter(|&max| items.len() > max) {
+                    items.truncate(max);
+                    limited = true;
+                }
+                if let Some(max) = self.max_field_bytes {
+                    let mut total = 0;
+                    let keep = items
+                        .iter()
+                        .take_while(|item| {
+                            total += item.len();
+                            total <= max
+                        })
+                        .count();
+                    if keep < items.len() {
+                        items.truncate(keep);
+                        limited = true;
+                    }
+                }
+            }
+        }
+        limited
+    }
+
+    /// Decodes input files with `encoding` instead of detecting it.
+    #[cfg(feature = "encoding_rs")]
+    pub fn encoding(mut self, encoding: &'static encoding::Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Returns the name of the state parsing begins in.
+    pub fn initial_state(&self) -> &str {
+        self.start_state.as_deref().unwrap_or("Start")
+    }
+}
+
 /// The runtime engine for TextFSM parsing.
+///
+/// The compiled template is held behind an [`Arc`] and never modified by parsing, so
+/// cloning a `TextFSM` or building one per input with [`TextFSM::from_parser`] only copies
+/// the parse state. [`Template::session`] does this for a template and its options; with a
+/// bare compiled parser it looks like this:
+///
+/// ```
+/// use asyncfsm::{TextFSM, TextFSMParser};
+/// use std::sync::Arc;
+///
+/// let template = Arc::new(TextFSMParser::from_string("Value N (\\d+)\n\nStart\n  ^${N} -> Record\n")?);
+/// let handles: Vec<_> = ["1\n2\n", "3\n"]
+///     .into_iter()
+///     .map(|input| {
+///         let template = Arc::clone(&template);
+///         std::thread::spawn(move || TextFSM::from_parser(template).parse_records(input, None))
+///     })
+///     .collect();
+/// let counts: Vec<usize> = handles
+///     .into_iter()
+///     .map(|h| h.join().unwrap().map(|records| records.len()))
+///     .collect::<asyncfsm::Result<_>>()?;
+/// assert_eq!(counts, vec![2, 1]);
+/// # Ok::<(), asyncfsm::TextFsmError>(())
+/// ```
 #[derive(Debug, Default, Clone)]
 pub struct TextFSM {
-    /// The underlying compiled parser.
-    pub parser: TextFSMParser,
+    /// The underlying compiled parser, shared with every engine built from it.
+    pub parser: Arc<TextFSMParser>,
     /// The current state of the engine.
     pub curr_state: String,
     /// The record currently being populated.
@@ -190,6 +819,126 @@ pub struct TextFSM {
     pub records: VecDeque<DataRecord>,
     /// Options for this FSM instance.
     pub options: TextFsmOptions,
+    /// Trailing input without a newline, held back by `parse_chunk`.
+    pub partial_line: String,
+    /// Set when parsing stopped early because of `max_records`, `stop_after` or a
+    /// cancelled token.
+    pub truncated: bool,
+    /// Set when parsing stopped because the `cancel_token` was cancelled.
+    pub cancelled: bool,
+    /// Number of input lines consumed so far (1-based once parsing starts).
+    pub line_number: usize,
+    /// Number of input lines that matched no rule in the state they were read in.
+    pub unmatched_lines: usize,
+    /// Number of records dropped by `TextFsmOptions::dedupe`.
+    pub duplicate_records: usize,
+    /// Number of records emitted so far, the next `_index` under `with_ids`.
+    pub records_emitted: usize,
+    /// Captures rejected by a validator, in input order (empty in strict mode).
+    pub validation_issues: Vec<ValidationIssue>,
+    /// Non-fatal anomalies noticed so far, other than validation issues.
+    pub warnings: Warnings,
+    /// Records discarded so far, when `TextFsmOptions::track_dropped` is set.
+    pub dropped: Vec<DroppedRecord>,
+    /// Rules that matched so far, when `TextFsmOptions::track_matches` is set.
+    pub matches: Vec<RuleMatch>,
+    /// Set once any rule matched the current input line.
+    line_matched: bool,
+    /// Rules evaluated against the current input line so far.
+    line_rule_evaluations: usize,
+    /// `(state, rule index)` of each `Continue` transition taken on the current line.
+    continue_chain: Vec<(String, usize)>,
+    /// Input lines of the record in progress, kept when `with_raw` is set.
+    raw_block: RawBlock,
+    /// Values of the record in progress cut by `max_list_len` or `max_field_bytes`.
+    limited_fields: std::collections::HashSet<String>,
+    /// Where the action of the rule that matched last is in the template, for the
+    /// errors it raises.
+    matched_action: Span,
+    /// What `TextFsmOptions::dedupe` compares new records against.
+    seen: SeenRecords,
+    /// Line each `Filldown` value was last captured on, for `FilldownReset::StateChange`.
+    filldown_lines: HashMap<String, usize>,
+}
+
+/// The records kept so far, as far as duplicate suppression needs to know them.
+#[derive(Debug, Default, Clone)]
+struct SeenRecords {
+    /// Fields of the last record kept, for `Dedupe::Consecutive`.
+    last_fields: Option<HashMap<String, Value>>,
+    /// Keys of the records kept so far, for `Dedupe::Key`.
+    keys: std::collections::HashSet<String>,
+}
+
+impl SeenRecords {
+    /// Drops the record emitted since `emitted` records if it is a duplicate and returns it.
+    fn suppress(
+        &mut self,
+        dedupe: Dedupe,
+        records: &mut VecDeque<DataRecord>,
+        emitted: usize,
+    ) -> Option<DataRecord> {
+        if dedupe == Dedupe::Off || records.len() <= emitted {
+            return None;
+        }
+        let rec = records.back()?;
+        let duplicate = match dedupe {
+            Dedupe::Off => false,
+            Dedupe::Consecutive => self.last_fields.as_ref() == Some(&rec.fields),
+            Dedupe::Key => rec
+                .record_key
+                .as_ref()
+                .is_some_and(|key| !self.keys.insert(key.clone())),
+        };
+        if duplicate {
+            return records.pop_back();
+        }
+        if dedupe == Dedupe::Consecutive {
+            self.last_fields = Some(rec.fields.clone());
+        }
+        None
+    }
+}
+
+/// The input lines seen since the current record's first capture.
+#[derive(Debug, Default, Clone)]
+struct RawBlock {
+    first_line: Option<usize>,
+    last_line: usize,
+    text: String,
+}
+
+impl RawBlock {
+    /// Adds `line` unless it is already part of the block.
+    fn extend(&mut self, line_number: usize, line: &str) {
+        if self.first_line.is_some() && self.last_line != line_number {
+            self.text.push_str(line);
+            self.text.push('\n');
+            self.last_line = line_number;
+        }
+    }
+
+    /// Starts the block at `line` if it has not started yet.
+    fn start(&mut self, line_number: usize, line: &str) {
+        if self.first_line.is_none() {
+            self.first_line = Some(line_number);
+            self.last_line = line_number;
+            self.text = format!("{}\n", line);
+        }
+    }
+
+    /// Takes the block for a record emitted at `line_number`.
+    fn take(&mut self, line_number: usize, line: Option<&str>) -> RawText {
+        if let Some(line) = line {
+            self.start(line_number, line);
+        }
+        let block = std::mem::take(self);
+        RawText {
+            first_line: block.first_line.unwrap_or(line_number),
+            last_line: block.last_line.max(block.first_line.unwrap_or(line_number)),
+            text: block.text,
+        }
+    }
 }
 
 /// Action to take regarding the current line of input.
@@ -240,6 +989,8 @@ pub enum NextState {
 pub struct RuleTransition {
     line_action: LineAction,
     record_action: RecordAction,
+    /// Names of the registered actions to run, from `Call(...)`.
+    calls: Vec<String>,
 }
 
 /// A single rule within a TextFSM state.
@@ -249,6 +1000,10 @@ pub struct StateRule {
     rule_match: String,
     /// The transition to perform if the rule matches.
     transition: RuleTransition,
+    /// The rule in the template, from `^` to the end of its action.
+    span: Span,
+    /// The part after ` -> `, if the rule has an action.
+    action_span: Option<Span>,
 }
 
 /// Metadata and regex definition for an extracted value.
@@ -270,6 +1025,12 @@ pub struct ValueDefinition {
     regex_pattern: String,
     /// Original raw options string.
     options: Option<String>,
+    /// Validators declared with the `OneOf=` option.
+    validators: Vec<Validator>,
+    /// Registered custom options, in template order.
+    custom_options: Vec<CustomValueOption>,
+    /// The `Value` line in the template.
+    span: Span,
 }
 
 /// Wrapper for different regex engines (standard or fancy for lookarounds).
@@ -289,6 +1050,8 @@ pub struct CapturedVariable {
     pub is_key: bool,
     pub is_filldown: bool,
     pub is_fillup: bool,
+    pub validators: Vec<Validator>,
+    pub custom_options: Vec<CustomValueOption>,
 }
 
 /// A compiled version of a `StateRule` ready for execution.
@@ -302,6 +1065,10 @@ pub struct StateRuleCompiled {
     maybe_regex: Option<MultiRegex>,
     /// The transition to perform.
     transition: RuleTransition,
+    /// The rule in the template; empty for the implicit `EOF` rule.
+    span: Span,
+    /// The part of the rule after ` -> `.
+    action_span: Option<Span>,
 }
 
 /// A compiled state containing a list of rules.
@@ -311,6 +1078,168 @@ pub struct StateCompiled {
     name: String,
     /// Rules belonging to this state.
     rules: Vec<StateRuleCompiled>,
+    /// The state's name in the template; empty for the implicit `EOF` state.
+    span: Span,
+}
+
+impl StateCompiled {
+    /// The state's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The state's rules, in template order.
+    pub fn rules(&self) -> &[StateRuleCompiled] {
+        &self.rules
+    }
+
+    /// Where the state is declared in the template.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl StateRuleCompiled {
+    /// The rule's regex as written, with `${VALUE}` references.
+    pub fn pattern(&self) -> &str {
+        &self._rule_match
+    }
+
+    /// The regex the rule is compiled from: the pattern with each `${VALUE}` replaced
+    /// by a named group and the template's regex flags in front.
+    pub fn expanded_pattern(&self) -> &str {
+        &self._expanded_rule_match
+    }
+
+    /// What happens when the rule matches.
+    pub fn transition(&self) -> &RuleTransition {
+        &self.transition
+    }
+
+    /// Names of the values the rule captures, in pattern order.
+    pub fn captured_values(&self) -> impl Iterator<Item = &str> {
+        self.captured_vars.iter().map(|v| v.name.as_str())
+    }
+
+    /// Where the rule is in the template, from `^` to the end of its action.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Where the rule's action is in the template, if it has one.
+    pub fn action_span(&self) -> Option<Span> {
+        self.action_span
+    }
+}
+
+/// Formats the rule as a template line without indentation, e.g. `^${A} -> Record`.
+impl std::fmt::Display for StateRuleCompiled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self._rule_match)?;
+        if self.transition != RuleTransition::default() {
+            write!(f, " -> {}", self.transition)?;
+        }
+        Ok(())
+    }
+}
+
+impl RuleTransition {
+    /// The state to move to, if the rule names one; `Error` included.
+    pub fn next_state(&self) -> Option<&NextState> {
+        match &self.line_action {
+            LineAction::Continue(next) | LineAction::Next(next) => next.as_ref(),
+        }
+    }
+
+    /// True for `Continue`, which tries the following rules on the same line.
+    pub fn continues(&self) -> bool {
+        matches!(self.line_action, LineAction::Continue(_))
+    }
+
+    /// What happens to the current record.
+    pub fn record_action(&self) -> &RecordAction {
+        &self.record_action
+    }
+
+    /// Names of the registered actions the rule calls, in order.
+    pub fn calls(&self) -> &[String] {
+        &self.calls
+    }
+}
+
+impl ValueDefinition {
+    /// The value's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The value's regex, including its outer parentheses.
+    pub fn regex(&self) -> &str {
+        &self.regex_pattern
+    }
+
+    /// The options as written, e.g. `Filldown,Required`.
+    pub fn options(&self) -> Option<&str> {
+        self.options.as_deref()
+    }
+
+    /// Where the `Value` line is in the template.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Formats the definition as a template line, e.g. `Value Filldown HOST (\S+)`.
+impl std::fmt::Display for ValueDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.options {
+            Some(options) => write!(f, "Value {} {} {}", options, self.name, self.regex_pattern),
+            None => write!(f, "Value {} {}", self.name, self.regex_pattern),
+        }
+    }
+}
+
+/// Formats the part after `->`, e.g. `Continue.Record Detail` or `Error "bad input"`.
+///
+/// The default transition (`Next`, no record action, same state) formats as `Next`.
+impl std::fmt::Display for RuleTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = self.calls.iter().map(|c| format!("Call({})", c)).collect();
+        let (continues, next) = match &self.line_action {
+            LineAction::Continue(next) => (true, next),
+            LineAction::Next(next) => (false, next),
+        };
+        let record = match self.record_action {
+            RecordAction::NoRecord => None,
+            RecordAction::Record => Some("Record"),
+            RecordAction::Clear => Some("Clear"),
+            RecordAction::Clearall => Some("Clearall"),
+        };
+        match (continues, record) {
+            (true, Some(record)) => parts.push(format!("Continue.{}", record)),
+            (true, None) => parts.push("Continue".to_string()),
+            (false, Some(record)) => parts.push(record.to_string()),
+            (false, None) => {}
+        }
+        match next {
+            Some(NextState::NamedState(name)) => parts.push(name.clone()),
+            Some(NextState::Error(Some(message))) => parts.push(format!("Error \"{}\"", message)),
+            Some(NextState::Error(None)) => parts.push("Error".to_string()),
+            None => {}
+        }
+        if parts.is_empty() {
+            parts.push("Next".to_string());
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+/// The span of the first `kind` in `pair`, or of `pair` if it has none.
+fn span_of(pair: &Pair<'_, Rule>, kind: Rule) -> Span {
+    pair.clone()
+        .into_inner()
+        .find(|p| p.as_rule() == kind)
+        .map_or_else(|| Span::of(pair), |p| Span::of(&p))
 }
 
 /// The runtime engine for TextFSM parsing.
@@ -324,7 +1253,7 @@ impl TextFSMParser {
             Self::_log_pair(indent + 2, &p);
         }
     }
-    pub fn parse_state_rule_transition(pair: &Pair<'_, Rule>) -> RuleTransition {
+    pub fn parse_state_rule_transition(pair: &Pair<'_, Rule>) -> Result<RuleTransition> {
         let mut record_action: RecordAction = Default::default();
         let mut line_action: LineAction = Default::default();
         // Self::print_pair(5, pair);
@@ -336,21 +1265,38 @@ impl TextFSMParser {
                         "NoRecord" => RecordAction::NoRecord,
                         "Clear" => RecordAction::Clear,
                         "Clearall" => RecordAction::Clearall,
-                        x => panic!("Record action {} not supported", x),
+                        x => {
+                            return Err(TextFsmError::ParseError(format!(
+                                "Record action {} not supported",
+                                x
+                            )))
+                        }
                     };
                 }
                 Rule::line_action => {
                     line_action = match pair.as_str() {
                         "Continue" => LineAction::Continue(None),
                         "Next" => LineAction::Next(None),
-                        x => panic!("Record action {} not supported", x),
+                        x => {
+                            return Err(TextFsmError::ParseError(format!(
+                                "Line action {} not supported",
+                                x
+                            )))
+                        }
                     };
                 }
                 Rule::err_state => {
                     let mut maybe_err_msg: Option<String> = None;
                     for p in pair.clone().into_inner() {
                         if p.as_rule() == Rule::err_msg {
-                            maybe_err_msg = Some(p.as_str().to_string());
+                            let msg = p.as_str();
+                            let msg = msg
+                                .strip_prefix('"')
+                                .and_then(|m| m.strip_suffix('"'))
+                                .unwrap_or(msg);
+                            if !msg.is_empty() {
+                                maybe_err_msg = Some(msg.to_string());
+                            }
                         }
                     }
                     let next_state = NextState::Error(maybe_err_msg);
@@ -366,112 +1312,99 @@ impl TextFSMParser {
                             line_action = LineAction::Continue(Some(next_state));
                         }
                         _ => {
-                            panic!(
+                            return Err(TextFsmError::ParseError(format!(
                                 "Line action {:?} does not support next state (attempted {:?})",
                                 &line_action,
                                 pair.as_str()
-                            );
+                            )));
                         }
                     }
                 }
                 x => {
-                    panic!("Rule {:?} not supported!", &x);
+                    return Err(TextFsmError::ParseError(format!(
+                        "Rule {:?} not supported!",
+                        &x
+                    )));
                 }
             }
         }
-        RuleTransition {
+        Ok(RuleTransition {
             record_action,
             line_action,
-        }
+            calls: vec![],
+        })
     }
-    pub fn parse_state_rule(pair: &Pair<'_, Rule>) -> StateRule {
+    pub fn parse_state_rule(pair: &Pair<'_, Rule>) -> Result<StateRule> {
         let mut rule_match: Option<String> = None;
         // println!("----- state rule ---");
         // Self::print_pair(10, pair);
         // println!("--------");
         let mut transition: RuleTransition = Default::default();
+        let mut calls = vec![];
         let mut has_action = false;
+        let mut span = Span::of(pair);
+        let mut action_span: Option<Span> = None;
         let spaces = "";
         for pair in pair.clone().into_inner() {
+            if pair.as_rule() != Rule::rule_match {
+                let start = action_span.unwrap_or_else(|| Span::of(&pair));
+                action_span = Some(start.to(Span::of(&pair)));
+            }
             match pair.as_rule() {
                 Rule::rule_match => {
                     rule_match = Some(pair.as_str().to_string());
+                    span = Span::of(&pair).to(span);
                 }
                 Rule::transition_action => {
                     has_action = true;
-                    transition = Self::parse_state_rule_transition(&pair);
+                    transition = Self::parse_state_rule_transition(&pair)?;
                     // println!("TRANSITION: {:?}", &transition);
                 }
+                Rule::call_action => {
+                    has_action = true;
+                    calls.extend(pair.into_inner().map(|p| p.as_str().to_string()));
+                }
                 x => {
-                    println!("{}state Rule:    {:?}", spaces, pair.as_rule());
-                    println!("{}Span:    {:?}", spaces, pair.as_span());
-                    println!("{}Text:    {}", spaces, pair.as_str());
-                    panic!("state rule {:?} not supported", &x);
+                    debug!("{}state Rule:    {:?}", spaces, pair.as_rule());
+                    debug!("{}Span:    {:?}", spaces, pair.as_span());
+                    debug!("{}Text:    {}", spaces, pair.as_str());
+                    return Err(TextFsmError::ParseError(format!(
+                        "state rule {:?} not supported",
+                        &x
+                    )));
                 }
             }
         }
-        let mut rule_match = rule_match.expect("rule_match must be always set");
+        let mut rule_match = rule_match.ok_or_else(|| {
+            TextFsmError::ParseError(format!("state rule {:?} has no match", pair.as_str()))
+        })?;
         if (rule_match.ends_with(" ") || rule_match.ends_with("\t")) && !has_action {
-            println!(
-                "WARNING: '{}' has trailing spaces without transition action!",
+            warn!(
+                "'{}' has trailing spaces without transition action!",
                 &rule_match
             );
             rule_match = rule_match.trim_end().to_string();
         }
         if rule_match.contains(r#"\<"#) {
-            println!("WARNING: replacing \\< with < in '{}'", &rule_match);
+            warn!("replacing \\< with < in '{}'", &rule_match);
             rule_match = rule_match.replace("\\<", "<");
         }
         if rule_match.contains(r#"\>"#) {
-            println!("WARNING: replacing \\> with > in '{}'", &rule_match);
+            warn!("replacing \\> with > in '{}'", &rule_match);
             rule_match = rule_match.replace("\\>", ">");
         }
-        StateRule {
+        transition.calls = calls;
+        Ok(StateRule {
             rule_match,
             transition,
-        }
+            span,
+            action_span,
+        })
     }
 
-    pub fn compile_state_rule(
-        rule: &StateRule,
-        values: &HashMap<String, ValueDefinition>,
-    ) -> Result<StateRuleCompiled> {
-        let mut expanded_rule_match: String = String::new();
-        let rule_match = rule.rule_match.clone();
-        let mut captured_vars: Vec<CapturedVariable> = vec![];
-        let varsubst = varsubst::VariableParser::parse_dollar_string(&rule_match)
-            .map_err(|e| TextFsmError::ParseError(e.to_string()))?;
-        // println!("DOLLAR STR: {:?}", &varsubst);
-        {
-            use varsubst::ParseChunk;
-            for i in &varsubst {
-                match i {
-                    ParseChunk::DollarDollar => expanded_rule_match.push('$'),
-                    ParseChunk::Text(s) => expanded_rule_match.push_str(s),
-                    ParseChunk::Variable(v) => match values.get(v) {
-                        Some(val) => {
-                            let v_out = format!("(?P<{}>{})", v, val.regex_pattern);
-                            expanded_rule_match.push_str(&v_out);
-                            captured_vars.push(CapturedVariable {
-                                name: v.clone(),
-                                is_list: val.is_list,
-                                is_key: val.is_key,
-                                is_filldown: val.is_filldown,
-                                is_fillup: val.is_fillup,
-                            });
-                        }
-                        None => {
-                            return Err(TextFsmError::ParseError(format!(
-                                "Can not find variable '{}' while parsing rule_match '{}'",
-                                &v, &rule.rule_match
-                            )));
-                        }
-                    },
-                }
-            }
-        }
-        // println!("OUT_STR: {}", expanded_rule_match);
-
+    /// Compiles a rule regex, falling back to `fancy_regex` for look-around and
+    /// back-references. Returns the pattern as compiled, after any fix-ups.
+    fn compile_regex(mut expanded_rule_match: String) -> Result<(MultiRegex, String)> {
         let regex_val = match Regex::new(&expanded_rule_match) {
             Ok(r) => MultiRegex::Classic(r),
             Err(_e) => {
@@ -485,15 +1418,14 @@ impl TextFSMParser {
                             break x;
                         }
                         Err(Error::ParseError(pos, e)) => {
-                            println!("STR:{}", &expanded_rule_match[0..pos + 1]);
-                            println!("ERR:{}^", " ".repeat(pos));
+                            debug!("STR:{}", &expanded_rule_match[0..pos + 1]);
+                            debug!("ERR:{}^", " ".repeat(pos));
                             match e {
                                 ParseError::TargetNotRepeatable => {
                                     if let Some(char_index) =
                                         expanded_rule_match.char_indices().nth(pos)
                                     {
-                                        println!(
-                                            "WARNING: repeat quantifier on a lookahead, lookbehind or other zero-width item"
+                                        warn!("repeat quantifier on a lookahead, lookbehind or other zero-width item"
                                         );
                                         expanded_rule_match.remove(char_index.0);
                                     } else {
@@ -504,20 +1436,72 @@ impl TextFSMParser {
                                 }
                                 e => {
                                     return Err(TextFsmError::ParseError(format!(
-                                        "Error: {:?}",
+                                        "invalid regex: {}",
                                         &e
                                     )));
                                 }
                             }
                         }
-                        x => {
-                            return Err(TextFsmError::ParseError(format!("Error: {:?}", &x)));
+                        Err(e) => {
+                            return Err(TextFsmError::ParseError(format!("invalid regex: {}", &e)));
                         }
                     }
                 };
                 MultiRegex::Fancy(freg)
             }
         };
+        Ok((regex_val, expanded_rule_match))
+    }
+
+    pub fn compile_state_rule(
+        rule: &StateRule,
+        values: &HashMap<String, ValueDefinition>,
+        macros: &HashMap<String, String>,
+    ) -> Result<StateRuleCompiled> {
+        let mut expanded_rule_match: String = String::new();
+        let rule_match = rule.rule_match.clone();
+        let mut captured_vars: Vec<CapturedVariable> = vec![];
+        let varsubst = varsubst::VariableParser::parse_dollar_string(&rule_match)
+            .map_err(|e| TextFsmError::ParseError(e.to_string()))?;
+        // println!("DOLLAR STR: {:?}", &varsubst);
+        {
+            use varsubst::ParseChunk;
+            for i in &varsubst {
+                match i {
+                    ParseChunk::DollarDollar => expanded_rule_match.push('$'),
+                    ParseChunk::Text(s) => expanded_rule_match.push_str(s),
+                    ParseChunk::Variable(v) => match values.get(v) {
+                        Some(val) => {
+                            let v_out = format!("(?P<{}>{})", v, val.regex_pattern);
+                            expanded_rule_match.push_str(&v_out);
+                            captured_vars.push(CapturedVariable {
+                                name: v.clone(),
+                                is_list: val.is_list,
+                                is_key: val.is_key,
+                                is_filldown: val.is_filldown,
+                                is_fillup: val.is_fillup,
+                                validators: val.validators.clone(),
+                                custom_options: val.custom_options.clone(),
+                            });
+                        }
+                        None => match macros::lookup(v, macros) {
+                            Some(pattern) => {
+                                expanded_rule_match.push_str(&format!("(?:{})", pattern));
+                            }
+                            None => {
+                                return Err(TextFsmError::ParseError(format!(
+                                    "Can not find variable '{}' while parsing rule_match '{}'",
+                                    &v, &rule.rule_match
+                                )));
+                            }
+                        },
+                    },
+                }
+            }
+        }
+        // println!("OUT_STR: {}", expanded_rule_match);
+
+        let (regex_val, expanded_rule_match) = Self::compile_regex(expanded_rule_match)?;
         let maybe_regex = Some(regex_val);
         let transition = rule.transition.clone();
         let _rule_match = rule_match;
@@ -529,13 +1513,132 @@ impl TextFSMParser {
             captured_vars,
             maybe_regex,
             transition,
+            span: rule.span,
+            action_span: rule.action_span,
         })
     }
+    /// Checks that every `${NAME}` in `rule_match` is a value or a macro, and that the
+    /// rule does not capture a name twice, through two references or a named group.
+    /// Returns false if it reported a problem.
+    fn check_captures(
+        rule_match: &Pair<'_, Rule>,
+        values: &HashMap<String, ValueDefinition>,
+        macros: &HashMap<String, String>,
+        diagnostics: &mut Diagnostics,
+    ) -> bool {
+        use varsubst::ParseChunk;
+        static NAMED_GROUP: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+        let named_group = NAMED_GROUP
+            .get_or_init(|| Regex::new(r"\(\?P?<([A-Za-z_][A-Za-z0-9_]*)>").expect("valid regex"));
+        let groups = |text: &str| -> Vec<String> {
+            named_group
+                .captures_iter(text)
+                .map(|c| c[1].to_string())
+                .collect()
+        };
+        let text = rule_match.as_str();
+        let span = Span::of(rule_match);
+        // A rule varsubst can not read is reported when it is compiled.
+        let Ok(chunks) = varsubst::VariableParser::parse_dollar_string(text) else {
+            return true;
+        };
+        let errors = diagnostics.errors().count();
+        let mut captured: Vec<String> = vec![];
+        let mut offset = 0;
+        for chunk in &chunks {
+            match chunk {
+                ParseChunk::DollarDollar => offset += 2,
+                ParseChunk::Text(s) => {
+                    captured.extend(groups(s));
+                    offset += s.len();
+                }
+                ParseChunk::Variable(name) => {
+                    let braced = format!("${{{}}}", name);
+                    let (at, len) = match text[offset..].find(&braced) {
+                        Some(i) => (offset + i, braced.len()),
+                        None => (offset, name.len() + 1),
+                    };
+                    offset = at + len;
+                    if let Some(value) = values.get(name) {
+                        captured.push(name.clone());
+                        captured.extend(groups(&value.regex_pattern));
+                    } else if macros::lookup(name, macros).is_none() {
+                        diagnostics.push(
+                            Severity::Error,
+                            span.sub(text, at, len),
+                            format!("${{{}}} is not a declared Value", name),
+                            similar(name, values.keys().map(String::as_str))
+                                .map(|value| format!("${{{}}}", value)),
+                        );
+                    }
+                }
+            }
+        }
+        let mut reported: Vec<&String> = vec![];
+        for (i, name) in captured.iter().enumerate() {
+            if captured[..i].contains(name) && !reported.contains(&name) {
+                reported.push(name);
+                diagnostics.error(
+                    span,
+                    format!("{} is captured more than once in this rule", name),
+                );
+            }
+        }
+        diagnostics.errors().count() == errors
+    }
+
+    /// Warns about the parts of a rule that are not read as written.
+    fn check_rule_text(rule: &Pair<'_, Rule>, diagnostics: &mut Diagnostics) {
+        let Some(rule_match) = rule
+            .clone()
+            .into_inner()
+            .find(|p| p.as_rule() == Rule::rule_match)
+        else {
+            return;
+        };
+        let text = rule_match.as_str();
+        let span = Span::of(&rule_match);
+        let has_action = rule.clone().into_inner().count() > 1;
+        if !has_action && text != text.trim_end() {
+            diagnostics.push(
+                Severity::Warning,
+                span,
+                "trailing space without an action is ignored",
+                Some(text.trim_end().to_string()),
+            );
+        }
+        let fixed = text.replace("\\<", "<").replace("\\>", ">");
+        if fixed != text {
+            diagnostics.push(
+                Severity::Warning,
+                span,
+                "`\\<` and `\\>` are read as `<` and `>`",
+                Some(fixed),
+            );
+        }
+    }
+
     pub fn parse_and_compile_state_definition(
         pair: &Pair<'_, Rule>,
         values: &HashMap<String, ValueDefinition>,
+        macros: &HashMap<String, String>,
+    ) -> Result<StateCompiled> {
+        let mut diagnostics = Diagnostics::default();
+        let state = Self::compile_state_definition(pair, values, macros, &mut diagnostics)?;
+        diagnostics.first_error()?;
+        Ok(state)
+    }
+
+    /// Compiles a state, reporting the rules that do not compile in `diagnostics` and
+    /// leaving them out.
+    fn compile_state_definition(
+        pair: &Pair<'_, Rule>,
+        values: &HashMap<String, ValueDefinition>,
+        macros: &HashMap<String, String>,
+        diagnostics: &mut Diagnostics,
     ) -> Result<StateCompiled> {
         let mut name: Option<String> = None;
+        let span = span_of(pair, Rule::state_header);
         // Self::print_pair(20, pair);
         let mut rules: Vec<StateRuleCompiled> = vec![];
 
@@ -547,17 +1650,36 @@ impl TextFSMParser {
                 }
                 Rule::rules => {
                     for pair in pair.clone().into_inner() {
-                        let rule = Self::parse_state_rule(&pair);
+                        let rule_match = span_of(&pair, Rule::rule_match);
+                        if let Some(rule_match) = pair
+                            .clone()
+                            .into_inner()
+                            .find(|p| p.as_rule() == Rule::rule_match)
+                        {
+                            if !Self::check_captures(&rule_match, values, macros, diagnostics) {
+                                continue;
+                            }
+                        }
+                        Self::check_rule_text(&pair, diagnostics);
+                        let rule = match Self::parse_state_rule(&pair) {
+                            Ok(rule) => rule,
+                            Err(e) => {
+                                diagnostics.error(rule_match, message_of(e));
+                                continue;
+                            }
+                        };
                         trace!("PARSED RULE [{:?}]: {:#?}", &name, &rule);
-                        let compiled_rule = Self::compile_state_rule(&rule, values)?;
-                        rules.push(compiled_rule);
+                        match Self::compile_state_rule(&rule, values, macros) {
+                            Ok(compiled_rule) => rules.push(compiled_rule),
+                            Err(e) => diagnostics.error(rule_match, message_of(e)),
+                        }
                     }
                 }
                 x => {
                     let spaces = "";
-                    println!("{}state def Rule:    {:?}", spaces, pair.as_rule());
-                    println!("{}Span:    {:?}", spaces, pair.as_span());
-                    println!("{}Text:    {}", spaces, pair.as_str());
+                    debug!("{}state def Rule:    {:?}", spaces, pair.as_rule());
+                    debug!("{}Span:    {:?}", spaces, pair.as_span());
+                    debug!("{}Text:    {}", spaces, pair.as_str());
                     return Err(TextFsmError::ParseError(format!(
                         "Rule not supported in state definition: {:?}",
                         &x
@@ -567,10 +1689,22 @@ impl TextFSMParser {
         }
         let name =
             name.ok_or_else(|| TextFsmError::InternalError("state must have a name".to_string()))?;
-        Ok(StateCompiled { name, rules })
+        Ok(StateCompiled { name, rules, span })
     }
 
     pub fn parse_value_definition(pair: &Pair<'_, Rule>) -> Result<ValueDefinition> {
+        let mut diagnostics = Diagnostics::default();
+        let value = Self::value_definition(pair, &mut diagnostics)?;
+        diagnostics.first_error()?;
+        Ok(value)
+    }
+
+    /// Reads a `Value` line, reporting unknown options in `diagnostics` and leaving them
+    /// out.
+    fn value_definition(
+        pair: &Pair<'_, Rule>,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<ValueDefinition> {
         // println!("value definition");
         let mut name: Option<String> = None;
         let mut regex_pattern: Option<String> = None;
@@ -580,6 +1714,10 @@ impl TextFSMParser {
         let mut is_required = false;
         let mut is_list = false;
         let mut is_fillup = false;
+        let mut validators = vec![];
+        let mut custom_options = vec![];
+        let options_span = span_of(pair, Rule::options);
+        let regex_span = span_of(pair, Rule::regex_pattern);
 
         for p in pair.clone().into_inner() {
             match p.as_rule() {
@@ -599,29 +1737,45 @@ impl TextFSMParser {
         }
         if let (Some(name), Some(mut regex_pattern)) = (name.clone(), regex_pattern.clone()) {
             if let Some(ref opts) = options {
-                let opts = opts.split(",");
-                for word in opts {
+                let mut at = 0;
+                for word in opts.split(",") {
                     match word {
                         "Filldown" => is_filldown = true,
                         "Key" => is_key = true,
                         "Required" => is_required = true,
                         "List" => is_list = true,
                         "Fillup" => is_fillup = true,
-                        x => {
-                            return Err(TextFsmError::ParseError(format!(
-                                "Unknown option {:?}",
-                                &x
-                            )));
+                        x if x.starts_with("OneOf=") => {
+                            validators.push(Validator::one_of(x["OneOf=".len()..].split('|')))
+                        }
+                        x if value_option::lookup(x).is_some() => {
+                            custom_options.extend(value_option::lookup(x))
                         }
+                        x => diagnostics.push(
+                            Severity::Error,
+                            options_span.sub(opts, at, x.len()),
+                            format!("Unknown option {:?}", &x),
+                            similar(x, crate::format::BUILTIN_OPTIONS).map(String::from),
+                        ),
                     }
+                    at += word.len() + 1;
                 }
             }
+            let fixed = regex_pattern.replace("\\<", "<").replace("\\>", ">");
+            if fixed != regex_pattern {
+                diagnostics.push(
+                    Severity::Warning,
+                    regex_span,
+                    "`\\<` and `\\>` are read as `<` and `>`",
+                    Some(fixed),
+                );
+            }
             if regex_pattern.contains(r#"\<"#) {
-                println!("WARNING: replacing \\< with < in value '{}'", &name);
+                warn!("replacing \\< with < in value '{}'", &name);
                 regex_pattern = regex_pattern.replace("\\<", "<");
             }
             if regex_pattern.contains(r#"\>"#) {
-                println!("WARNING: replacing \\> with > in value '{}'", &name);
+                warn!("replacing \\> with > in value '{}'", &name);
                 regex_pattern = regex_pattern.replace("\\>", ">");
             }
             Ok(ValueDefinition {
@@ -633,6 +1787,9 @@ impl TextFSMParser {
                 is_list,
                 is_fillup,
                 options,
+                validators,
+                custom_options,
+                span: Span::of(pair).trim_end(pair.as_str()),
             })
         } else {
             Err(TextFsmError::ParseError(format!(
@@ -658,8 +1815,136 @@ impl TextFSMParser {
         Ok((vals, mandatory_values))
     }
 
+    /// Collects the `Macro` definitions among the value definitions, in order, so each
+    /// can use the ones before it.
+    pub fn parse_macro_defs(pair: &Pair<'_, Rule>) -> Result<HashMap<String, String>> {
+        let mut diagnostics = Diagnostics::default();
+        let macros = Self::macro_definitions(pair, &mut diagnostics)?;
+        diagnostics.first_error()?;
+        Ok(macros)
+    }
+
+    /// As [`parse_macro_defs`](Self::parse_macro_defs), reporting the macros that can
+    /// not be defined in `diagnostics` and leaving them out.
+    fn macro_definitions(
+        pair: &Pair<'_, Rule>,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<HashMap<String, String>> {
+        let mut macros = HashMap::new();
+        for pair in pair.clone().into_inner() {
+            if Rule::macro_definition != pair.as_rule() {
+                continue;
+            }
+            let mut inner = pair.into_inner();
+            let (Some(name), Some(pattern)) = (inner.next(), inner.next()) else {
+                return Err(TextFsmError::InternalError(
+                    "macro must have a name and a pattern".to_string(),
+                ));
+            };
+            let name_span = Span::of(&name);
+            let name = name.as_str().to_string();
+            if macros::is_builtin(&name) || macros.contains_key(&name) {
+                diagnostics.error(name_span, format!("Macro {} already defined", &name));
+                continue;
+            }
+            // The outer parentheses only delimit the pattern, as in a value definition.
+            let pattern_span = Span::of(&pattern);
+            let pattern = pattern.as_str();
+            match macros::expand(&pattern[1..pattern.len() - 1], &macros) {
+                Ok(pattern) => {
+                    macros.insert(name, pattern);
+                }
+                Err(e) => diagnostics.error(pattern_span, message_of(e)),
+            }
+        }
+        Ok(macros)
+    }
+
+    /// Extracts `# Key: value` pairs from the leading comment lines of a template.
+    ///
+    /// Scanning stops at the first line that is neither blank nor a comment.
+    pub fn parse_metadata(content: &str) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = line.strip_prefix('#') else {
+                break;
+            };
+            if let Some((key, value)) = comment.split_once(':') {
+                let key = key.trim();
+                if !key.is_empty() && !key.contains(char::is_whitespace) {
+                    metadata.insert(key.to_string(), value.trim().to_string());
+                }
+            }
+        }
+        metadata
+    }
+
+    /// Returns the metadata declared in the template's leading comment header.
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// Returns the template's `# Deprecated:` header, if it has one.
+    pub fn deprecation(&self) -> Option<Deprecation> {
+        Deprecation::from_metadata(&self.metadata)
+    }
+
+    /// Recompiles every rule regex with `flags`, replacing the flags set before.
+    pub fn with_regex_flags(mut self, flags: RegexFlags) -> Result<Self> {
+        let previous = self.regex_flags.inline_group();
+        for state in self.states.values_mut() {
+            for rule in &mut state.rules {
+                let bare = rule
+                    ._expanded_rule_match
+                    .strip_prefix(&previous)
+                    .unwrap_or(&rule._expanded_rule_match);
+                let (regex, pattern) =
+                    Self::compile_regex(format!("{}{}", flags.inline_group(), bare))?;
+                rule.maybe_regex = Some(regex);
+                rule._expanded_rule_match = pattern;
+            }
+        }
+        self.regex_flags = flags;
+        Ok(self)
+    }
+
+    /// A copy of this template with rule `index` of `state` removed, or `None` if there
+    /// is no such rule. Used to check which rules the fixtures depend on.
+    pub fn without_rule(&self, state: &str, index: usize) -> Option<Self> {
+        let mut mutant = self.clone();
+        let rules = &mut mutant.states.get_mut(state)?.rules;
+        if index >= rules.len() {
+            return None;
+        }
+        rules.remove(index);
+        Some(mutant)
+    }
+
     /// Parses and compiles a TextFSM template from a string.
     pub fn from_string(content: &str) -> Result<Self> {
+        let result = Self::compile(content, &mut Diagnostics::default());
+        metrics::template_compiled(result.is_ok());
+        result
+    }
+
+    /// Every problem with `content` as a template, the warnings included; see the
+    /// [`diagnostics`](crate::diagnostics) module.
+    pub fn diagnose(content: &str) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        if let Err(e) = Self::compile(content, &mut diagnostics) {
+            if !diagnostics.has_errors() {
+                diagnostics.error(Span::file_start(), message_of(e));
+            }
+        }
+        diagnostics
+    }
+
+    fn compile(content: &str, diagnostics: &mut Diagnostics) -> Result<Self> {
+        let _span = debug_span!("compile").entered();
         let mut template = content.to_string();
         // pad with newlines, because dealing with a missing one within grammar is a PITA
         if !template.ends_with('\n') {
@@ -671,6 +1956,14 @@ impl TextFSMParser {
         let mut values: HashMap<String, ValueDefinition> = HashMap::new();
         let mut states: HashMap<String, StateCompiled> = HashMap::new();
         let mut mandatory_values: Vec<String> = vec![];
+        let mut value_names: Vec<String> = vec![];
+        let mut state_names: Vec<String> = vec![];
+        let mut explicit_eof = false;
+        let mut macros: HashMap<String, String> = HashMap::new();
+        let mut value_spans: HashMap<String, Span> = HashMap::new();
+        let mut state_spans: Vec<(String, Span)> = vec![];
+        let mut referenced: std::collections::HashSet<String> = Default::default();
+        let mut transitions: Vec<(String, Span)> = vec![];
 
         let end_state = NextState::NamedState("End".to_string());
         let eof_rule = StateRule {
@@ -678,14 +1971,17 @@ impl TextFSMParser {
             transition: RuleTransition {
                 line_action: LineAction::Next(Some(end_state)),
                 record_action: RecordAction::Record,
+                calls: vec![],
             },
+            ..Default::default()
         };
 
-        let compiled_eof_rule = Self::compile_state_rule(&eof_rule, &values)?;
+        let compiled_eof_rule = Self::compile_state_rule(&eof_rule, &values, &HashMap::new())?;
 
         let eof_state = StateCompiled {
             name: "EOF".to_string(),
             rules: vec![compiled_eof_rule],
+            span: Span::default(),
         };
         states.insert(eof_state.name.clone(), eof_state);
 
@@ -694,25 +1990,86 @@ impl TextFSMParser {
                 for pair in pairs.clone() {
                     match pair.as_rule() {
                         Rule::value_definitions => {
-                            (values, mandatory_values) = Self::parse_value_defs(&pair)?;
+                            macros = Self::macro_definitions(&pair, diagnostics)?;
+                            for pair in pair.clone().into_inner() {
+                                if pair.as_rule() != Rule::value_definition {
+                                    continue;
+                                }
+                                let mut value = Self::value_definition(&pair, diagnostics)?;
+                                let span = span_of(&pair, Rule::identifier);
+                                if macros.contains_key(&value.name) {
+                                    diagnostics.error(
+                                        span,
+                                        format!("Macro {} has the name of a value", &value.name),
+                                    );
+                                }
+                                if value_spans.insert(value.name.clone(), span).is_some() {
+                                    diagnostics.error(
+                                        span,
+                                        format!("Value {} already defined", &value.name),
+                                    );
+                                    continue;
+                                }
+                                match macros::expand(&value.regex_pattern, &macros) {
+                                    Ok(pattern) => value.regex_pattern = pattern,
+                                    Err(e) => diagnostics
+                                        .error(span_of(&pair, Rule::regex_pattern), message_of(e)),
+                                }
+                                if value.is_required {
+                                    mandatory_values.push(value.name.clone());
+                                }
+                                value_names.push(value.name.clone());
+                                values.insert(value.name.clone(), value);
+                            }
                         }
                         Rule::state_definitions => {
+                            for pair in pair.clone().into_inner().flatten() {
+                                match pair.as_rule() {
+                                    Rule::rule_match => {
+                                        referenced.extend(
+                                            varsubst::VariableParser::parse_dollar_string(
+                                                pair.as_str(),
+                                            )
+                                            .into_iter()
+                                            .flatten()
+                                            .filter_map(|chunk| match chunk {
+                                                varsubst::ParseChunk::Variable(name) => Some(name),
+                                                _ => None,
+                                            }),
+                                        )
+                                    }
+                                    Rule::next_state => transitions
+                                        .push((pair.as_str().to_string(), Span::of(&pair))),
+                                    _ => {}
+                                }
+                            }
                             for pair in pair.clone().into_inner() {
                                 match pair.as_rule() {
                                     Rule::state_definition => {
                                         trace!("STATE DEFINITION");
                                         Self::_log_pair(0, &pair);
-                                        let state = Self::parse_and_compile_state_definition(
-                                            &pair, &values,
+                                        let state = Self::compile_state_definition(
+                                            &pair,
+                                            &values,
+                                            &macros,
+                                            diagnostics,
                                         )?;
                                         trace!("STATE DEFINITION END: {:?}", &state);
-                                        if &state.name != "EOF" && states.contains_key(&state.name)
-                                        {
-                                            return Err(TextFsmError::StateError(format!(
-                                                "State {} already defined in the file!",
-                                                &state.name
-                                            )));
+                                        let span = span_of(&pair, Rule::state_header);
+                                        if &state.name == "EOF" {
+                                            explicit_eof = true;
+                                        } else if states.contains_key(&state.name) {
+                                            diagnostics.error(
+                                                span,
+                                                format!(
+                                                    "State {} already defined in the file!",
+                                                    &state.name
+                                                ),
+                                            );
+                                            continue;
                                         }
+                                        state_spans.push((state.name.clone(), span));
+                                        state_names.push(state.name.clone());
                                         states.insert(state.name.clone(), state);
                                     }
                                     x => {
@@ -738,31 +2095,130 @@ impl TextFSMParser {
                 }
 
                 if !seen_eoi {
-                    println!("WARNING: EOI token not seen");
+                    warn!("EOI token not seen");
                 }
 
+                for name in &value_names {
+                    if !referenced.contains(name) {
+                        diagnostics.warning(
+                            value_spans[name],
+                            format!("Value {} is never captured", name),
+                        );
+                    }
+                }
+                for (to, span) in transitions {
+                    if to != "End" && !states.contains_key(&to) {
+                        diagnostics.push(
+                            Severity::Error,
+                            span,
+                            format!("State {} is not defined", to),
+                            similar(&to, state_names.iter().map(String::as_str)).map(String::from),
+                        );
+                    }
+                }
                 if !states.contains_key("Start") {
-                    return Err(TextFsmError::StateError(
-                        "Start state not found".to_string(),
-                    ));
+                    match state_spans
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("Start"))
+                    {
+                        Some((_, span)) => diagnostics.push(
+                            Severity::Error,
+                            *span,
+                            "Start state not found",
+                            Some("Start".to_string()),
+                        ),
+                        None => diagnostics.error(Span::file_start(), "Start state not found"),
+                    }
                 }
 
-                Ok(TextFSMParser {
+                let metadata = Self::parse_metadata(content);
+                let regex_flags = match metadata.get("RegexFlags") {
+                    Some(letters) => RegexFlags::parse(letters).unwrap_or_else(|e| {
+                        let line = content
+                            .lines()
+                            .position(|line| line.contains("RegexFlags"))
+                            .map_or(1, |i| i + 1);
+                        diagnostics.error(Span::line(content, line), message_of(e));
+                        RegexFlags::default()
+                    }),
+                    None => RegexFlags::default(),
+                };
+                diagnostics.sort();
+                if diagnostics.has_errors() {
+                    return Err(TextFsmError::TemplateErrors {
+                        file: None,
+                        diagnostics: diagnostics.clone(),
+                    });
+                }
+                let parser = TextFSMParser {
                     values,
                     mandatory_values,
+                    value_names,
                     states,
+                    state_names,
+                    explicit_eof,
+                    metadata,
+                    regex_flags: RegexFlags::default(),
+                    macros,
+                };
+                if regex_flags == RegexFlags::default() {
+                    Ok(parser)
+                } else {
+                    parser.with_regex_flags(regex_flags)
+                }
+            }
+            Err(e) => {
+                let (line, column) = match e.line_col {
+                    pest::error::LineColLocation::Pos(pos) => pos,
+                    pest::error::LineColLocation::Span(start, _) => start,
+                };
+                let (start, end) = match e.location {
+                    pest::error::InputLocation::Pos(pos) => (pos, pos),
+                    pest::error::InputLocation::Span(span) => span,
+                };
+                let message = e.variant.message().into_owned();
+                diagnostics.error(
+                    Span {
+                        start,
+                        end,
+                        line,
+                        column,
+                    },
+                    message.clone(),
+                );
+                Err(TextFsmError::TemplateSyntaxError {
+                    file: None,
+                    line,
+                    column,
+                    message,
                 })
             }
-            Err(e) => Err(TextFsmError::ParseError(format!("Error: {}", e))),
         }
     }
 
     /// Parses and compiles a TextFSM template from a file.
     pub fn from_file<P: AsRef<std::path::Path>>(fname: P) -> Result<Self> {
         let path = fname.as_ref();
+        let _span = debug_span!("compile_template", file = %path.display()).entered();
         let content = std::fs::read_to_string(path)?;
-        Self::from_string(&content)
-            .map_err(|e| TextFsmError::ParseError(format!("file {} Error: {}", path.display(), e)))
+        Self::from_string(&content).map_err(|e| match e {
+            TextFsmError::TemplateSyntaxError {
+                line,
+                column,
+                message,
+                ..
+            } => TextFsmError::TemplateSyntaxError {
+                file: Some(path.to_path_buf()),
+                line,
+                column,
+                message,
+            },
+            TextFsmError::TemplateErrors { diagnostics, .. } => TextFsmError::TemplateErrors {
+                file: Some(path.to_path_buf()),
+                diagnostics,
+            },
+            e => TextFsmError::ParseError(format!("file {} Error: {}", path.display(), e)),
+        })
     }
 }
 
@@ -771,30 +2227,48 @@ impl TextFSM {
     pub fn new(template: &str) -> Result<Self> {
         let parser = TextFSMParser::from_string(template)?;
         Ok(TextFSM {
-            parser,
+            parser: Arc::new(parser),
             curr_state: "Start".to_string(),
             ..Default::default()
         })
     }
 
+    /// Creates a new `TextFSM` instance from an already compiled template.
+    ///
+    /// Passing an `Arc<TextFSMParser>` shares the template instead of moving it in.
+    pub fn from_parser(parser: impl Into<Arc<TextFSMParser>>) -> Self {
+        TextFSM {
+            parser: parser.into(),
+            curr_state: "Start".to_string(),
+            ..Default::default()
+        }
+    }
+
     /// Alias for `new`.
     pub fn from_string(template: &str) -> Result<Self> {
         Self::new(template)
     }
 
+    /// Returns the metadata declared in the template's leading comment header.
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        self.parser.metadata()
+    }
+
     /// Sets the options for this `TextFSM` instance.
+    ///
+    /// The engine is moved to the configured start state.
     pub fn with_options(mut self, options: TextFsmOptions) -> Self {
         self.options = options;
+        self.curr_state = self.options.initial_state().to_string();
         self
     }
 
-
     /// Creates a new `TextFSM` instance from a template file.
     pub fn from_file<P: AsRef<std::path::Path>>(fname: P) -> Result<Self> {
         let parser = TextFSMParser::from_file(fname)?;
         let curr_state = "Start".to_string();
         Ok(TextFSM {
-            parser,
+            parser: Arc::new(parser),
             curr_state,
             ..Default::default()
         })
@@ -803,10 +2277,51 @@ impl TextFSM {
     /// Resets the engine to its initial state, clearing all records and resetting variables.
     /// This allows reusing the parsed template for a new file.
     pub fn reset(&mut self) {
-        self.curr_state = "Start".to_string();
+        self.curr_state = self.options.initial_state().to_string();
         self.curr_record = DataRecord::default();
         self.filldown_record = DataRecord::default();
         self.records.clear();
+        self.partial_line.clear();
+        self.truncated = false;
+        self.cancelled = false;
+        self.line_number = 0;
+        self.unmatched_lines = 0;
+        self.duplicate_records = 0;
+        self.records_emitted = 0;
+        self.seen = SeenRecords::default();
+        self.filldown_lines.clear();
+        self.validation_issues.clear();
+        self.warnings.clear();
+        self.dropped.clear();
+        self.matches.clear();
+        self.raw_block = RawBlock::default();
+        self.limited_fields.clear();
+        self.end_line();
+    }
+
+    /// Clears the per-line bookkeeping once the engine moves on to the next line.
+    fn end_line(&mut self) {
+        self.line_rule_evaluations = 0;
+        self.continue_chain.clear();
+    }
+
+    fn rule_evaluation_limit_error(&self, aline: &str, rule_idx: usize) -> TextFsmError {
+        let chain: Vec<String> = self
+            .continue_chain
+            .iter()
+            .rev()
+            .take(10)
+            .rev()
+            .map(|(state, idx)| format!("{}[rule {}]", state, idx))
+            .collect();
+        TextFsmError::StateError(format!(
+            "Rule evaluation limit ({}) exceeded in state '{}' at rule {} on line '{}'; Continue chain: {}",
+            self.options.max_rule_evaluations,
+            &self.curr_state,
+            rule_idx,
+            aline,
+            chain.join(" -> ")
+        ))
     }
 
     /// Sets the current state of the engine.
@@ -817,6 +2332,14 @@ impl TextFSM {
                 state_name
             )));
         }
+        if state_name != self.curr_state {
+            trace!(
+                from = %self.curr_state,
+                to = state_name,
+                line = self.line_number,
+                "state transition"
+            );
+        }
         self.curr_state = state_name.to_string();
         Ok(())
     }
@@ -853,6 +2376,12 @@ impl TextFSM {
 
         let ins_value = if let Some(value) = maybe_value {
             trace!("SET VAR '{}' = '{}'", name, value);
+            let value = &var_info
+                .custom_options
+                .iter()
+                .fold(value.to_string(), |value, option| {
+                    option.assign(name, &value)
+                });
 
             if var_info.is_list {
                 Value::List(vec![value.to_string()])
@@ -891,15 +2420,57 @@ impl TextFSM {
         Ok(())
     }
 
+    /// Runs the template and option validators of `var` against a captured string.
+    fn validate_capture(
+        options: &TextFsmOptions,
+        issues: &mut Vec<ValidationIssue>,
+        var: &CapturedVariable,
+        value: &str,
+        line_number: usize,
+    ) -> Result<()> {
+        let extra = options.validators.get(&var.name).map(Vec::as_slice);
+        for validator in var.validators.iter().chain(extra.unwrap_or_default()) {
+            if let Err(message) = validator.check(value) {
+                let issue = ValidationIssue {
+                    value: var.name.clone(),
+                    captured: value.to_string(),
+                    line_number,
+                    message,
+                };
+                if options.strict_validation {
+                    return Err(TextFsmError::ValidationError(issue));
+                }
+                warn!("Validation failed: {}", issue);
+                issues.push(issue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a rule's record action. Returns what was discarded, when
+    /// `TextFsmOptions::track_dropped` is set and the action discarded something.
+    #[allow(clippy::too_many_arguments)]
     fn process_record_action(
         curr_record: &mut DataRecord,
         filldown_record: &mut DataRecord,
         records: &mut VecDeque<DataRecord>,
-        mandatory_values: &[String],
-        values: &HashMap<String, ValueDefinition>,
+        parser: &TextFSMParser,
         action: RecordAction,
-        options: TextFsmOptions,
-    ) -> Result<()> {
+        options: &TextFsmOptions,
+        warnings: &mut Warnings,
+        line_number: usize,
+    ) -> Result<Option<(DropReason, HashMap<String, Value>)>> {
+        let mandatory_values = &parser.mandatory_values;
+        let values = &parser.values;
+        // The captures a Clear or Clearall throws away before they were recorded.
+        let cleared = |record: &DataRecord| -> Option<(DropReason, HashMap<String, Value>)> {
+            let fields: HashMap<String, Value> = record
+                .iter()
+                .filter(|(k, _)| values.get(*k).is_none_or(|val| !val.is_filldown))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            (options.track_dropped && !fields.is_empty()).then_some((DropReason::Cleared, fields))
+        };
         match action {
             RecordAction::Record => {
                 let mut mandatory_count = 0;
@@ -915,7 +2486,22 @@ impl TextFSM {
                         let mut new_rec: DataRecord = filldown_record.clone();
                         /* swap with the current record */
                         std::mem::swap(&mut new_rec, curr_record);
-                        
+
+                        let mut unset_filldown: Vec<&String> = values
+                            .values()
+                            .filter(|v| v.is_filldown && new_rec.get(&v.name).is_none())
+                            .map(|v| &v.name)
+                            .collect();
+                        unset_filldown.sort();
+                        for name in unset_filldown {
+                            warnings.push(
+                                line_number,
+                                WarningKind::FilldownWithoutValue {
+                                    value: name.clone(),
+                                },
+                            );
+                        }
+
                         if options.fill_missing_fields {
                             for v in values.values() {
                                 if new_rec.get(&v.name).is_none() {
@@ -929,10 +2515,33 @@ impl TextFSM {
                                 }
                             }
                         }
+                        for name in &parser.value_names {
+                            for option in values.get(name).map_or(&[][..], |v| &v.custom_options) {
+                                option.record(name, &mut new_rec);
+                            }
+                        }
+                        Self::clear_custom_options(parser, false);
                         trace!("RECORD: {:?}", &new_rec);
                         records.push_back(new_rec);
                     } else {
                         trace!("RECORD: no required fields set");
+                        let missing: Vec<String> = mandatory_values
+                            .iter()
+                            .filter(|k| curr_record.get(k).is_none())
+                            .cloned()
+                            .collect();
+                        warnings.push(
+                            line_number,
+                            WarningKind::RecordDropped {
+                                missing: missing.clone(),
+                            },
+                        );
+                        if options.track_dropped {
+                            return Ok(Some((
+                                DropReason::MissingRequired { missing },
+                                curr_record.fields.clone(),
+                            )));
+                        }
                     }
                 } else {
                     trace!("RECORD: record is empty, not dumping");
@@ -940,46 +2549,145 @@ impl TextFSM {
             }
             RecordAction::NoRecord => {} // Do nothing
             RecordAction::Clear => {
+                let dropped = cleared(curr_record);
                 let mut rem_keys: Vec<String> = vec![];
                 for (ref k, _v) in curr_record.iter() {
-                    if let Some(val) = values.get(*k) {
-                        if !val.is_filldown {
-                            rem_keys.push(k.to_string());
-                        }
-                    } else {
-                        return Err(TextFsmError::InternalError(format!(
-                            "is_filldown_value for {} failed",
-                            k
-                        )));
+                    // Fields set by actions are not template values and are cleared too.
+                    if values.get(*k).is_none_or(|val| !val.is_filldown) {
+                        rem_keys.push(k.to_string());
                     }
                 }
                 for k in rem_keys {
                     curr_record.remove(&k);
                 }
+                Self::clear_custom_options(parser, false);
+                return Ok(dropped);
+            }
+            RecordAction::Clearall if options.filldown_reset == FilldownReset::Never => {
+                let dropped = cleared(curr_record);
+                Self::clear_custom_options(parser, true);
+                curr_record
+                    .fields
+                    .retain(|k, _| values.get(k).is_some_and(|val| val.is_filldown));
+                return Ok(dropped);
             }
             RecordAction::Clearall => {
+                let dropped = cleared(curr_record);
+                Self::clear_custom_options(parser, true);
                 // reset the current record
                 *curr_record = Default::default();
                 *filldown_record = Default::default();
+                return Ok(dropped);
             }
         }
+        Ok(None)
+    }
+
+    /// Keeps a record the engine discarded and logs it.
+    fn note_dropped(
+        dropped: &mut Vec<DroppedRecord>,
+        line_number: usize,
+        state: &str,
+        (reason, fields): (DropReason, HashMap<String, Value>),
+    ) {
+        let record = DroppedRecord {
+            line_number,
+            state: state.to_string(),
+            reason,
+            fields,
+        };
+        debug!("DROPPED: {}", record);
+        dropped.push(record);
+    }
+
+    /// Runs the `Call(...)` actions of a matching rule on the current record.
+    fn run_actions(
+        options: &TextFsmOptions,
+        rule: &StateRuleCompiled,
+        mut context: ActionContext<'_>,
+    ) -> Result<()> {
+        let line_number = context.line_number;
+        for name in &rule.transition.calls {
+            let error = |message: String| TextFsmError::ActionError {
+                action: name.clone(),
+                line_number,
+                message,
+                span: rule.action_span.unwrap_or(rule.span),
+            };
+            let Some(action) = options.actions.get(name) else {
+                return Err(error("no such action registered".to_string()));
+            };
+            action.call(&mut context).map_err(error)?;
+        }
         Ok(())
     }
 
+    /// Runs the `on_clear` hooks of custom options, skipping `Filldown` values unless `all`.
+    fn clear_custom_options(parser: &TextFSMParser, all: bool) {
+        for name in &parser.value_names {
+            if let Some(v) = parser.values.get(name).filter(|v| all || !v.is_filldown) {
+                for option in &v.custom_options {
+                    option.clear(name);
+                }
+            }
+        }
+    }
+
+    /// Attaches the raw block to a record just emitted, or restarts it when the
+    /// record in progress was cleared.
+    fn track_raw(
+        raw_block: &mut RawBlock,
+        records: &mut VecDeque<DataRecord>,
+        curr_record: &DataRecord,
+        action: &RecordAction,
+        emitted: usize,
+        line_number: usize,
+        line: Option<&str>,
+    ) {
+        match action {
+            RecordAction::Record if records.len() > emitted => {
+                let raw = raw_block.take(line_number, line);
+                if let Some(record) = records.back_mut() {
+                    record.raw = Some(raw);
+                }
+            }
+            // A dropped record keeps its values, and so its lines.
+            RecordAction::Record if !curr_record.fields.is_empty() => {}
+            RecordAction::NoRecord => {}
+            _ => *raw_block = RawBlock::default(),
+        }
+    }
+
     /// Processes a single line of input against the current state's rules.
     pub fn parse_line(&mut self, aline: &str) -> Result<ParseStatus> {
+        if self.options.line_mode {
+            return self.parse_single_line(aline);
+        }
         // Reuse these record structures to avoid reallocating on every rule match
         let mut tmp_datarec = DataRecord::new();
         let mut tmp_filldown_rec = DataRecord::new();
         // Keep track of which fields are fillup to avoid lookups later
         let mut fillup_fields: Vec<String> = vec![];
+        let track = self.options.track_matches;
+        let mut spans: Vec<CaptureSpan> = vec![];
 
+        let _span =
+            trace_span!("state", name = %self.curr_state, line = self.line_number).entered();
+        if self.options.with_raw {
+            self.raw_block.extend(self.line_number, aline);
+        }
+        let normalized = self.options.normalize_whitespace.apply(aline);
+        let aline: &str = &normalized;
         let state_name = &self.curr_state;
         let state_def = self.parser.states.get(state_name);
 
         if let Some(curr_state) = state_def {
             trace!("CURR STATE: {:?}", &curr_state);
-            for rule in &curr_state.rules {
+            for (rule_idx, rule) in curr_state.rules.iter().enumerate() {
+                self.line_rule_evaluations += 1;
+                if self.line_rule_evaluations > self.options.max_rule_evaluations {
+                    return Err(self.rule_evaluation_limit_error(aline, rule_idx));
+                }
                 let mut transition = RuleTransition {
                     line_action: LineAction::Continue(None),
                     ..Default::default()
@@ -990,6 +2698,7 @@ impl TextFSM {
                 tmp_datarec.record_key = None;
                 tmp_filldown_rec.fields.clear();
                 fillup_fields.clear();
+                spans.clear();
 
                 match &rule.maybe_regex {
                     Some(MultiRegex::Classic(rx)) => {
@@ -997,6 +2706,22 @@ impl TextFSM {
                         if let Some(caps) = rx.captures(aline) {
                             for var in &rule.captured_vars {
                                 let maybe_value = caps.name(&var.name).map(|x| x.as_str());
+                                if let Some(m) = caps.name(&var.name).filter(|_| track) {
+                                    spans.push(CaptureSpan {
+                                        name: var.name.clone(),
+                                        start: m.start(),
+                                        end: m.end(),
+                                    });
+                                }
+                                if let Some(value) = maybe_value {
+                                    Self::validate_capture(
+                                        &self.options,
+                                        &mut self.validation_issues,
+                                        var,
+                                        value,
+                                        self.line_number,
+                                    )?;
+                                }
                                 self.insert_value_optimized(
                                     &mut tmp_datarec,
                                     &mut tmp_filldown_rec,
@@ -1016,6 +2741,22 @@ impl TextFSM {
                         if let Ok(Some(caps)) = rx.captures(aline) {
                             for var in &rule.captured_vars {
                                 let maybe_value = caps.name(&var.name).map(|x| x.as_str());
+                                if let Some(m) = caps.name(&var.name).filter(|_| track) {
+                                    spans.push(CaptureSpan {
+                                        name: var.name.clone(),
+                                        start: m.start(),
+                                        end: m.end(),
+                                    });
+                                }
+                                if let Some(value) = maybe_value {
+                                    Self::validate_capture(
+                                        &self.options,
+                                        &mut self.validation_issues,
+                                        var,
+                                        value,
+                                        self.line_number,
+                                    )?;
+                                }
                                 self.insert_value_optimized(
                                     &mut tmp_datarec,
                                     &mut tmp_filldown_rec,
@@ -1038,8 +2779,21 @@ impl TextFSM {
                     }
                 }
                 if capture_matched {
+                    self.line_matched = true;
+                    if self.options.with_raw
+                        && !(tmp_datarec.fields.is_empty() && tmp_filldown_rec.fields.is_empty())
+                    {
+                        self.raw_block.start(self.line_number, aline);
+                    }
                     trace!("TMP_REC: {:?}", &tmp_datarec);
                     trace!("TMP_FILLDOWN: {:?}", &tmp_filldown_rec);
+                    let captures = (!rule.transition.calls.is_empty()).then(|| {
+                        let mut captures = tmp_datarec.clone();
+                        for (name, v) in &tmp_filldown_rec.fields {
+                            captures.fields.entry(name.clone()).or_insert(v.clone());
+                        }
+                        captures
+                    });
                     for (name, v) in tmp_datarec.fields.drain() {
                         if fillup_fields.contains(&name) {
                             let name_ref = &name;
@@ -1061,7 +2815,32 @@ impl TextFSM {
                                 fillup_record.fields.insert(name.clone(), v.clone());
                             }
                         }
-                        self.curr_record.append_value(name, v);
+                        if matches!(
+                            (self.curr_record.get(&name), &v),
+                            (Some(Value::Single(_)), Value::List(_))
+                        ) {
+                            self.warnings.push(
+                                self.line_number,
+                                WarningKind::ListPromoted {
+                                    value: name.clone(),
+                                },
+                            );
+                        }
+                        if !self.curr_record.fields.contains_key(&name) {
+                            self.limited_fields.remove(&name);
+                        } else if matches!(v, Value::List(_)) && self.limited_fields.contains(&name)
+                        {
+                            // The list already hit a limit in this record; keep it closed.
+                            continue;
+                        }
+                        self.curr_record.append_value(name.clone(), v);
+                        let value = self.curr_record.fields.get_mut(&name).unwrap();
+                        if self.options.limit_value(value)
+                            && self.limited_fields.insert(name.clone())
+                        {
+                            self.warnings
+                                .push(self.line_number, WarningKind::FieldLimited { value: name });
+                        }
                     }
                     trace!("TMP KEY: {:?}", &tmp_datarec.record_key);
                     self.curr_record.record_key = tmp_datarec.record_key;
@@ -1069,27 +2848,100 @@ impl TextFSM {
                     // self.filldown_record.overwrite_from(tmp_filldown_rec);
                     // This is correct:
                     for (name, v) in tmp_filldown_rec.fields.drain() {
-                        self.filldown_record.append_value(name, v);
+                        if self.options.filldown_reset == FilldownReset::StateChange {
+                            self.filldown_lines.insert(name.clone(), self.line_number);
+                        }
+                        self.filldown_record.append_value(name.clone(), v);
+                        if let Some(value) = self.filldown_record.fields.get_mut(&name) {
+                            self.options.limit_value(value);
+                        }
                     }
                     transition = rule.transition.clone();
+                    self.matched_action = rule.action_span.unwrap_or(rule.span);
+                    if track {
+                        self.matches.push(RuleMatch {
+                            line_number: self.line_number,
+                            line: aline.to_string(),
+                            state: state_name.clone(),
+                            rule: rule_idx + 1,
+                            template_line: rule.span.line,
+                            captures: std::mem::take(&mut spans),
+                        });
+                    }
+                    trace!(
+                        state = %state_name,
+                        rule = rule_idx,
+                        template_line = rule.span.line,
+                        "rule matched"
+                    );
+                    if let Some(captures) = captures {
+                        let context = ActionContext {
+                            state: state_name,
+                            line_number: self.line_number,
+                            line: aline,
+                            captures: &captures,
+                            record: &mut self.curr_record,
+                        };
+                        Self::run_actions(&self.options, rule, context)?;
+                    }
                 }
-                // println!("TRANS: {:?}", &transition);
+                trace!("TRANS: {:?}", &transition);
 
-                Self::process_record_action(
+                let emitted = self.records.len();
+                if let Some(dropped) = Self::process_record_action(
                     &mut self.curr_record,
                     &mut self.filldown_record,
                     &mut self.records,
-                    &self.parser.mandatory_values,
-                    &self.parser.values,
+                    &self.parser,
                     transition.record_action.clone(),
-                    self.options,
-                )?;
-
+                    &self.options,
+                    &mut self.warnings,
+                    self.line_number,
+                )? {
+                    Self::note_dropped(&mut self.dropped, self.line_number, state_name, dropped);
+                }
+                if self.options.with_raw {
+                    Self::track_raw(
+                        &mut self.raw_block,
+                        &mut self.records,
+                        &self.curr_record,
+                        &transition.record_action,
+                        emitted,
+                        self.line_number,
+                        Some(aline),
+                    );
+                }
+                if let Some(duplicate) =
+                    self.seen
+                        .suppress(self.options.dedupe, &mut self.records, emitted)
+                {
+                    self.duplicate_records += 1;
+                    if self.options.track_dropped {
+                        let dropped = (DropReason::Duplicate, duplicate.fields);
+                        Self::note_dropped(
+                            &mut self.dropped,
+                            self.line_number,
+                            state_name,
+                            dropped,
+                        );
+                    }
+                } else if self.records.len() > emitted {
+                    Self::select_record(
+                        &mut self.records,
+                        &self.options,
+                        &mut self.records_emitted,
+                    );
+                }
 
                 match transition.line_action {
-                    LineAction::Next(x) => return Ok(ParseStatus::NextLine(x)),
+                    LineAction::Next(x) => {
+                        self.end_line();
+                        return Ok(ParseStatus::NextLine(x));
+                    }
                     LineAction::Continue(maybe_next_state) => {
                         if let Some(next_state) = maybe_next_state {
+                            self.continue_chain
+                                .push((self.curr_state.clone(), rule_idx));
                             return Ok(ParseStatus::SameLine(Some(next_state)));
                         }
                     } // Do nothing
@@ -1101,9 +2953,164 @@ impl TextFSM {
                 &self.curr_state
             )));
         }
+        self.end_line();
+        Ok(ParseStatus::NextLine(None))
+    }
+
+    /// The fast path of `TextFsmOptions::line_mode`: the first rule that matches the
+    /// line fills the record, which is then emitted.
+    fn parse_single_line(&mut self, aline: &str) -> Result<ParseStatus> {
+        if self.line_number <= 1 {
+            if let Some(state) = self
+                .parser
+                .state_names
+                .iter()
+                .find(|name| !matches!(name.as_str(), "Start" | "EOF" | "End"))
+            {
+                return Err(TextFsmError::StateError(format!(
+                    "line mode needs a template with only a Start state, found {}",
+                    state
+                )));
+            }
+        }
+        if self.options.with_raw {
+            self.raw_block.extend(self.line_number, aline);
+        }
+        let normalized = self.options.normalize_whitespace.apply(aline);
+        let aline: &str = &normalized;
+        let parser = Arc::clone(&self.parser);
+        let Some(state) = parser.states.get(&self.curr_state) else {
+            return Err(TextFsmError::StateError(format!(
+                "State {} not found!",
+                &self.curr_state
+            )));
+        };
+        for rule in &state.rules {
+            let captured: Option<Vec<Option<&str>>> = match &rule.maybe_regex {
+                Some(MultiRegex::Classic(rx)) => rx.captures(aline).map(|caps| {
+                    rule.captured_vars
+                        .iter()
+                        .map(|var| caps.name(&var.name).map(|m| m.as_str()))
+                        .collect()
+                }),
+                Some(MultiRegex::Fancy(rx)) => rx.captures(aline).ok().flatten().map(|caps| {
+                    rule.captured_vars
+                        .iter()
+                        .map(|var| caps.name(&var.name).map(|m| m.as_str()))
+                        .collect()
+                }),
+                None => None,
+            };
+            let Some(captured) = captured else {
+                continue;
+            };
+            self.line_matched = true;
+            self.matched_action = rule.action_span.unwrap_or(rule.span);
+            if self.options.with_raw {
+                self.raw_block.start(self.line_number, aline);
+            }
+            let mut tmp_datarec = DataRecord::new();
+            let mut tmp_filldown_rec = DataRecord::new();
+            for (var, maybe_value) in rule.captured_vars.iter().zip(captured) {
+                if let Some(value) = maybe_value {
+                    Self::validate_capture(
+                        &self.options,
+                        &mut self.validation_issues,
+                        var,
+                        value,
+                        self.line_number,
+                    )?;
+                }
+                self.insert_value_optimized(
+                    &mut tmp_datarec,
+                    &mut tmp_filldown_rec,
+                    var,
+                    maybe_value,
+                    aline,
+                )?;
+            }
+            for (name, mut v) in tmp_datarec.fields.drain() {
+                if self.options.limit_value(&mut v) {
+                    self.warnings.push(
+                        self.line_number,
+                        WarningKind::FieldLimited {
+                            value: name.clone(),
+                        },
+                    );
+                }
+                self.curr_record.fields.insert(name, v);
+            }
+            self.curr_record.record_key = tmp_datarec.record_key;
+            self.filldown_record
+                .fields
+                .extend(tmp_filldown_rec.fields.drain());
+            if !rule.transition.calls.is_empty() {
+                let captures = self.curr_record.clone();
+                let context = ActionContext {
+                    state: &state.name,
+                    line_number: self.line_number,
+                    line: aline,
+                    captures: &captures,
+                    record: &mut self.curr_record,
+                };
+                Self::run_actions(&self.options, rule, context)?;
+            }
+            // A line that only sets `Filldown` values, such as a header, is no record.
+            if rule.captured_vars.iter().any(|var| !var.is_filldown) {
+                self.emit_record(Some(aline))?;
+            }
+            let next_state = match &rule.transition.line_action {
+                LineAction::Next(next_state) | LineAction::Continue(next_state) => next_state,
+            };
+            return Ok(ParseStatus::NextLine(next_state.clone()));
+        }
         Ok(ParseStatus::NextLine(None))
     }
 
+    /// Runs the `Record` action on the current record, as at the end of a line or of
+    /// the input (`line` is `None`), and numbers or drops what it emits.
+    fn emit_record(&mut self, line: Option<&str>) -> Result<()> {
+        let emitted = self.records.len();
+        if let Some(dropped) = Self::process_record_action(
+            &mut self.curr_record,
+            &mut self.filldown_record,
+            &mut self.records,
+            &self.parser,
+            RecordAction::Record,
+            &self.options,
+            &mut self.warnings,
+            self.line_number,
+        )? {
+            let state = self.curr_state.clone();
+            Self::note_dropped(&mut self.dropped, self.line_number, &state, dropped);
+        }
+        if self.options.with_raw {
+            Self::track_raw(
+                &mut self.raw_block,
+                &mut self.records,
+                &self.curr_record,
+                &RecordAction::Record,
+                emitted,
+                self.line_number,
+                line,
+            );
+        }
+        if let Some(duplicate) = self
+            .seen
+            .suppress(self.options.dedupe, &mut self.records, emitted)
+        {
+            self.duplicate_records += 1;
+            if self.options.track_dropped {
+                let state = self.curr_state.clone();
+                let dropped = (DropReason::Duplicate, duplicate.fields);
+                Self::note_dropped(&mut self.dropped, self.line_number, &state, dropped);
+            }
+        } else if self.records.len() > emitted {
+            Self::select_record(&mut self.records, &self.options, &mut self.records_emitted);
+        }
+        Ok(())
+    }
+
     /// Returns a new vector of records with all field names converted to lowercase.
     pub fn lowercase_keys(src: &VecDeque<DataRecord>) -> Vec<DataRecord> {
         let mut out = vec![];
@@ -1111,6 +3118,7 @@ impl TextFSM {
         for irec in src {
             let mut hm = DataRecord::new();
             hm.record_key = irec.record_key.clone();
+            hm.raw = irec.raw.clone();
             for (k, v) in irec.iter() {
                 let kl = k.to_lowercase();
                 hm.fields.insert(kl, v.clone());
@@ -1131,71 +3139,285 @@ impl TextFSM {
             lines: reader.lines(),
             eof_processed: false,
             current_line: None,
+            emitted: 0,
+            started: None,
+        }
+    }
+
+    /// Builds the error returned when a rule's `Error` action fires on `aline`.
+    fn error_action(&self, maybe_msg: Option<String>, aline: &str) -> TextFsmError {
+        TextFsmError::TemplateErrorAction {
+            message: maybe_msg,
+            state: self.curr_state.clone(),
+            line_number: self.line_number,
+            line: aline.to_string(),
+            span: self.matched_action,
+        }
+    }
+
+    /// Applies an optional state transition returned by `parse_line`.
+    fn apply_next_state(&mut self, maybe_next_state: Option<NextState>, aline: &str) -> Result<()> {
+        match maybe_next_state {
+            Some(NextState::Error(maybe_msg)) => Err(self.error_action(maybe_msg, aline)),
+            Some(NextState::NamedState(name)) => {
+                if self.options.filldown_reset == FilldownReset::StateChange
+                    && name != self.curr_state
+                {
+                    self.forget_filldown();
+                }
+                self.set_curr_state(&name)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Drops the `Filldown` values not captured on the current line.
+    fn forget_filldown(&mut self) {
+        let line_number = self.line_number;
+        let stale: Vec<String> = self
+            .filldown_lines
+            .iter()
+            .filter(|(_, &line)| line != line_number)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in stale {
+            self.filldown_lines.remove(&name);
+            self.filldown_record.remove(&name);
+            self.curr_record.remove(&name);
+        }
+    }
+
+    /// Runs a single input line through the rules until a `Next` action is taken.
+    fn process_input_line(&mut self, aline: &str) -> Result<()> {
+        self.start_line();
+        loop {
+            match self.parse_line(aline)? {
+                ParseStatus::NextLine(maybe_next_state) => {
+                    self.finish_line();
+                    return self.apply_next_state(maybe_next_state, aline);
+                }
+                ParseStatus::SameLine(maybe_next_state) => {
+                    self.apply_next_state(maybe_next_state, aline)?;
+                }
+            }
+        }
+    }
+
+    /// Counts a newly read input line, which no rule has matched yet.
+    fn start_line(&mut self) {
+        self.line_number += 1;
+        self.line_matched = false;
+    }
+
+    /// Counts the current line as unmatched if no rule matched it before moving on.
+    fn finish_line(&mut self) {
+        if !self.line_matched {
+            self.unmatched_lines += 1;
+        }
+    }
+
+    /// Checks the configured record and time limits and the cancel token, marking the
+    /// parse as truncated once one is hit.
+    pub(crate) fn check_limits(&mut self, emitted: usize, started: Instant) -> bool {
+        let over_records = self.options.max_records.is_some_and(|max| emitted >= max);
+        let over_time = self
+            .options
+            .stop_after
+            .is_some_and(|limit| started.elapsed() >= limit);
+        if self
+            .options
+            .cancel
+            .as_ref()
+            .is_some_and(|t| t.is_cancelled())
+        {
+            self.cancelled = true;
+        }
+        if over_records || over_time || self.cancelled {
+            self.truncated = true;
+        }
+        self.truncated
+    }
+
+    /// Fraction (0.0 to 1.0) of the input lines read so far that matched no rule.
+    ///
+    /// A high ratio on input the template is meant for usually means the template no
+    /// longer fits the device output.
+    pub fn unmatched_ratio(&self) -> f64 {
+        if self.line_number == 0 {
+            0.0
+        } else {
+            self.unmatched_lines as f64 / self.line_number as f64
+        }
+    }
+
+    /// Returns true once the engine has reached the `EOF` or `End` state.
+    fn is_finished(&self) -> bool {
+        self.curr_state == "EOF" || self.curr_state == "End"
+    }
+
+    /// Processes the `EOF` state unless `End` has already been reached.
+    ///
+    /// Like Python TextFSM, the rules of an explicit `EOF` state are not run against input;
+    /// declaring the state only suppresses the implicit record flush.
+    fn process_eof(&mut self) -> Result<()> {
+        if self.curr_state != "End" {
+            let flush = self
+                .options
+                .flush_on_eof
+                .unwrap_or(!self.parser.explicit_eof);
+            // In line mode every record was emitted with its line.
+            if flush && !self.options.line_mode {
+                self.emit_record(None)?;
+            }
+            self.set_curr_state("End")?;
+        }
+        Ok(())
+    }
+
+    /// Counts the record just emitted and keeps it unless `skip_records` or `sample`
+    /// drop it.
+    fn select_record(
+        records: &mut VecDeque<DataRecord>,
+        options: &TextFsmOptions,
+        emitted: &mut usize,
+    ) {
+        let index = *emitted;
+        *emitted += 1;
+        let sampled_out = options.sample.is_some_and(|rate| {
+            // SplitMix64 of the position, so the sample is the same on every run.
+            let mut z = (index as u64)
+                .wrapping_add(1)
+                .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            ((z ^ (z >> 31)) >> 11) as f64 / (1u64 << 53) as f64 >= rate
+        });
+        if index < options.skip_records || sampled_out {
+            records.pop_back();
+        } else {
+            Self::number_record(records, options, index);
+        }
+    }
+
+    /// Fills in `_index` and `_id` of the record just emitted when `with_ids` is set.
+    fn number_record(records: &mut VecDeque<DataRecord>, options: &TextFsmOptions, index: usize) {
+        if let Some(rec) = records.back_mut().filter(|_| options.with_ids) {
+            rec.index = Some(index);
+            rec.id = Some(rec.content_id());
         }
     }
 
+    /// Copies the collected records out, applying `conversion` to them and to the schema.
+    fn convert_records(&self, conversion: Option<DataRecordConversion>) -> Records {
+        Records::new(self.records.clone().into(), self.parser.value_names.clone())
+            .convert(conversion)
+    }
+
     /// Parses input from a string.
     ///
+    /// If `max_records` or `stop_after` is hit, or the `cancel_token` is cancelled, the
+    /// records parsed so far are returned and `truncated` (and `cancelled`) is set.
+    ///
     /// # Arguments
     /// * `input` - The input string to parse.
     /// * `conversion` - Optional transformation to apply to the results.
-    pub fn parse_string(
+    pub fn parse_records(
         &mut self,
         input: &str,
         conversion: Option<DataRecordConversion>,
-    ) -> Result<Vec<DataRecord>> {
+    ) -> Result<Records> {
+        let _span = debug_span!("parse").entered();
+        let started = Instant::now();
+        let first_line = self.line_number;
+        let first_record = self.records.len();
+        let result = self.run_input(input, started);
+        metrics::parse_finished(
+            started.elapsed(),
+            self.line_number - first_line,
+            self.records.len().saturating_sub(first_record),
+            result.is_ok(),
+        );
+        result?;
+        let records = self.convert_records(conversion);
+        if self.options.carry_over {
+            self.records.clear();
+        }
+        Ok(records)
+    }
+
+    /// Parses the inputs in order as if they were one, e.g. a log rotated into several
+    /// files, and returns all their records. Line numbers count on across the files.
+    pub fn parse_files<P: AsRef<std::path::Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Records> {
+        let started = Instant::now();
+        for path in paths {
+            let input = encoding::read_file(path, &self.options)?;
+            self.run_lines(&input, started)?;
+            if self.truncated || self.is_finished() {
+                break;
+            }
+        }
+        self.end_input()?;
+        Ok(self.convert_records(conversion))
+    }
+
+    /// Parses input from a string like `parse_records`, also returning the warnings raised.
+    pub fn parse_outcome(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<ParseOutcome> {
+        let records = self.parse_records(input, conversion)?;
+        Ok(self.outcome(records))
+    }
+
+    /// Pairs `records` with the warnings and validation issues collected so far.
+    pub fn outcome(&self, records: Records) -> ParseOutcome {
+        ParseOutcome::new(
+            records,
+            &self.warnings,
+            &self.validation_issues,
+            &self.dropped,
+        )
+    }
+
+    fn run_input(&mut self, input: &str, started: Instant) -> Result<()> {
+        self.run_lines(input, started)?;
+        if self.options.carry_over {
+            return Ok(());
+        }
+        self.end_input()
+    }
+
+    fn run_lines(&mut self, input: &str, started: Instant) -> Result<()> {
         for (_lineno, aline) in input.lines().enumerate() {
-            debug!("LINE:#{}: '{}'", _lineno + 1, &aline);
-            loop {
-                let status = self.parse_line(aline)?;
-                match status {
-                    ParseStatus::NextLine(maybe_next_state) => {
-                        if let Some(next_state) = maybe_next_state {
-                            match next_state {
-                                NextState::Error(maybe_msg) => {
-                                    return Err(TextFsmError::StateError(format!(
-                                        "Error state reached! msg: {:?}",
-                                        &maybe_msg
-                                    )));
-                                }
-                                NextState::NamedState(name) => {
-                                    self.set_curr_state(&name)?;
-                                }
-                            }
-                        }
-                        break;
-                    }
-                    ParseStatus::SameLine(maybe_next_state) => {
-                        if let Some(next_state) = maybe_next_state {
-                            match next_state {
-                                NextState::Error(maybe_msg) => {
-                                    return Err(TextFsmError::StateError(format!(
-                                        "Error state reached! msg: {:?}",
-                                        &maybe_msg
-                                    )));
-                                }
-                                NextState::NamedState(name) => {
-                                    self.set_curr_state(&name)?;
-                                }
-                            }
-                        }
-                    }
-                }
+            if self.check_limits(self.records.len(), started) {
+                break;
             }
-            if &self.curr_state == "EOF" || &self.curr_state == "End" {
+            debug!("LINE:#{}: '{}'", _lineno + 1, &aline);
+            self.process_input_line(aline)?;
+            if self.is_finished() {
                 break;
             }
         }
-        if &self.curr_state != "End" {
-            self.set_curr_state("EOF")?;
-            self.parse_line("")?;
-            // FIXME: Can EOF state transition into something else ? Presumably not.
-            self.set_curr_state("End")?;
+        Ok(())
+    }
+
+    /// Runs the `EOF` processing and applies `max_records`.
+    fn end_input(&mut self) -> Result<()> {
+        if !self.truncated {
+            self.process_eof()?;
         }
-        match conversion {
-            None => Ok(self.records.clone().into()),
-            Some(DataRecordConversion::LowercaseKeys) => Ok(Self::lowercase_keys(&self.records)),
+        if let Some(max) = self.options.max_records {
+            if self.records.len() > max {
+                self.records.truncate(max);
+                self.truncated = true;
+            }
         }
+        Ok(())
     }
 
     /// Parses an entire file and returns the extracted records.
@@ -1203,12 +3425,52 @@ impl TextFSM {
     /// # Arguments
     /// * `fname` - Path to the data file to parse.
     /// * `conversion` - Optional transformation to apply to the results.
+    pub fn parse_file_records<P: AsRef<std::path::Path>>(
+        &mut self,
+        fname: P,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Records> {
+        let path = fname.as_ref();
+        let _span = debug_span!("parse_file", file = %path.display()).entered();
+        let input = encoding::read_file(path, &self.options)?;
+        self.parse_records(&input, conversion)
+    }
+
+    /// Parses input from a string like `parse_records`, returning the records as a `Vec`.
+    #[deprecated(note = "use `parse_records`, which returns `Records`")]
+    pub fn parse_string(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Vec<DataRecord>> {
+        self.parse_records(input, conversion).map(Records::into_vec)
+    }
+
+    /// Parses a file like `parse_file_records`, returning the records as a `Vec`.
+    #[deprecated(note = "use `parse_file_records`, which returns `Records`")]
     pub fn parse_file<P: AsRef<std::path::Path>>(
         &mut self,
         fname: P,
         conversion: Option<DataRecordConversion>,
     ) -> Result<Vec<DataRecord>> {
-        let input = std::fs::read_to_string(fname)?;
-        self.parse_string(&input, conversion)
+        self.parse_file_records(fname, conversion)
+            .map(Records::into_vec)
     }
 }
+
+// Compiled templates, engines and their results are handed between threads and tasks;
+// these fail to compile if a change makes any of them lose `Send` or `Sync`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<TextFSMParser>();
+    assert_send_sync::<TextFSM>();
+    assert_send_sync::<Template>();
+    assert_send_sync::<TextFsmOptions>();
+    assert_send_sync::<Records>();
+    assert_send_sync::<TextFsmError>();
+    assert_send_sync::<TemplateResolver>();
+    #[cfg(feature = "clitable")]
+    assert_send_sync::<CliTable>();
+    #[cfg(feature = "registry")]
+    assert_send_sync::<TemplateRegistry>();
+};
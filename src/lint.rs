@@ -0,0 +1,179 @@
+//! Rules anchored loosely enough to match lines they were not written for.
+//!
+//! Every rule starts with `^`, but that does not always anchor it: [`lint`] reports a
+//! `|` outside any group, which leaves the alternatives after the first free to match
+//! anywhere in the line, a regex that starts with `.*` or `.+`, and a rule made of
+//! values alone that does not end with `$$`, which matches any line that starts the
+//! way its values do. These are the usual source of records built from the wrong
+//! lines in community templates.
+//!
+//! The first and last have a fix that keeps the rule's intent: grouping the
+//! alternatives after the `^`, and allowing only trailing space after the values.
+//! [`fix`] applies them to the template text, leaving everything else as written.
+//!
+//! ```
+//! use asyncfsm::lint::{fix, lint, LintKind};
+//!
+//! let template = "Value A (\\S+)\nValue B (\\S+)\n\nStart\n  ^${A}\\s+${B} -> Record\n  ^up|down\n";
+//! let issues = lint(template)?;
+//! assert_eq!(issues[0].kind, LintKind::OpenEnd);
+//! assert_eq!(issues[0].line, 5);
+//! assert_eq!(issues[1].kind, LintKind::UnanchoredAlternative);
+//! assert_eq!(
+//!     fix(template, &issues),
+//!     "Value A (\\S+)\nValue B (\\S+)\n\nStart\n  ^${A}\\s+${B}\\s*$$ -> Record\n  ^(?:up|down)\n"
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::optimize::has_top_level_alternation;
+use crate::{Result, TextFSMParser};
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// What is wrong with a rule's anchoring; see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    /// A `|` outside any group: `^a|b` matches every line containing `b`.
+    UnanchoredAlternative,
+    /// The regex starts with `.*` or `.+`, so what follows may match anywhere.
+    LeadingWildcard,
+    /// The rule has no literal text and no `$$` at the end.
+    OpenEnd,
+}
+
+impl LintKind {
+    /// What is wrong, in a few words.
+    pub fn description(&self) -> &'static str {
+        match self {
+            LintKind::UnanchoredAlternative => {
+                "the alternatives after the first `|` are not anchored"
+            }
+            LintKind::LeadingWildcard => "a leading `.*` or `.+` lets the rest match anywhere",
+            LintKind::OpenEnd => "a rule of values alone should end with `$$`",
+        }
+    }
+
+    /// `pattern` with this problem fixed, if it has a safe fix.
+    fn fix(&self, pattern: &str) -> Option<String> {
+        match self {
+            LintKind::UnanchoredAlternative => Some(format!(
+                "^(?:{})",
+                pattern.strip_prefix('^').unwrap_or(pattern)
+            )),
+            LintKind::LeadingWildcard => None,
+            LintKind::OpenEnd => Some(format!("{}\\s*$$", pattern)),
+        }
+    }
+}
+
+/// A rule with an anchoring problem.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintIssue {
+    /// 1-based line of the rule in the template.
+    pub line: usize,
+    pub state: String,
+    /// 0-based position of the rule in its state.
+    pub rule: usize,
+    /// The rule's regex as written.
+    pub pattern: String,
+    pub kind: LintKind,
+    /// The regex with this problem fixed, if there is a safe fix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} rule {}: {}: {}",
+            self.line,
+            self.state,
+            self.rule + 1,
+            self.kind.description(),
+            self.pattern
+        )?;
+        if let Some(fix) = &self.fix {
+            write!(f, " (fix: {})", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// True if `pattern` has no literal letters or digits outside value references,
+/// escapes and character classes.
+fn has_no_literal_text(pattern: &str) -> bool {
+    static REFERENCES: OnceLock<Regex> = OnceLock::new();
+    let references = REFERENCES.get_or_init(|| {
+        Regex::new(r"\$\{\w+\}|\\.|\[(?:\\.|[^\]])*\]|\(\?[a-zA-Z-]*[:)]").expect("valid regex")
+    });
+    !references
+        .replace_all(pattern, "")
+        .chars()
+        .any(|c| c.is_alphanumeric())
+}
+
+/// The anchoring problems of the rules of `source`, in template order.
+///
+/// Fails if `source` is not a valid template.
+pub fn lint(source: &str) -> Result<Vec<LintIssue>> {
+    let parser = TextFSMParser::from_string(source)?;
+    let mut issues = vec![];
+    for state in &parser.state_names {
+        for (index, rule) in parser.states[state].rules().iter().enumerate() {
+            let line = rule.span().line;
+            let pattern = rule.pattern();
+            let body = pattern.strip_prefix('^').unwrap_or(pattern);
+            let mut kinds = vec![];
+            if has_top_level_alternation(pattern) {
+                kinds.push(LintKind::UnanchoredAlternative);
+            }
+            if (body.starts_with(".*") || body.starts_with(".+")) && body.len() > 2 {
+                kinds.push(LintKind::LeadingWildcard);
+            }
+            if rule.captured_values().next().is_some()
+                && !pattern.ends_with('$')
+                && has_no_literal_text(pattern)
+            {
+                kinds.push(LintKind::OpenEnd);
+            }
+            issues.extend(kinds.into_iter().map(|kind| LintIssue {
+                line,
+                state: state.clone(),
+                rule: index,
+                pattern: pattern.to_string(),
+                kind,
+                fix: kind.fix(pattern),
+            }));
+        }
+    }
+    Ok(issues)
+}
+
+/// `source` with the safe fixes of `issues` applied to the rules they name.
+pub fn fix(source: &str, issues: &[LintIssue]) -> String {
+    let mut lines: Vec<String> = source.lines().map(String::from).collect();
+    for issue in issues.iter().filter(|issue| issue.fix.is_some()) {
+        let Some(line) = issue.line.checked_sub(1).and_then(|i| lines.get_mut(i)) else {
+            continue;
+        };
+        // Issues of one rule come in the order their fixes compose.
+        let indent = line.len() - line.trim_start().len();
+        let (pattern, rest) = match line[indent..].find(" -> ") {
+            Some(at) => line[indent..].split_at(at),
+            None => (&line[indent..], ""),
+        };
+        if let Some(fixed) = issue.kind.fix(pattern) {
+            *line = format!("{}{}{}", &line[..indent], fixed, rest);
+        }
+    }
+    let mut fixed = lines.join("\n");
+    if source.ends_with('\n') {
+        fixed.push('\n');
+    }
+    fixed
+}
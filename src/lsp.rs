@@ -0,0 +1,449 @@
+//! A language server for templates, so editors can check and navigate them.
+//!
+//! [`serve`] speaks the Language Server Protocol over a pair of streams, usually the
+//! standard input and output an editor starts `textfsm lsp` with. For each open
+//! template it publishes the problems [`TextFSMParser::diagnose`] and the
+//! [`lint`](crate::lint) find, shows a value's or macro's regex on hover over a
+//! `${NAME}`, goes from a `${NAME}` to the line declaring it and from a transition to
+//! its state, lists the states as the document's symbols and highlights the template
+//! with the tokens of [`lex`](crate::lex).
+//!
+//! Documents are synchronized in full on every change. Navigation needs the template
+//! to compile; diagnostics and highlighting are there either way.
+//!
+//! ```
+//! let request = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#;
+//! let input = format!("Content-Length: {}\r\n\r\n{}", request.len(), request);
+//! let mut output = vec![];
+//! asyncfsm::lsp::serve(input.as_bytes(), &mut output)?;
+//! assert!(String::from_utf8_lossy(&output).contains(r#""hoverProvider":true"#));
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{
+    macros, Result, Severity, Span, TextFSMParser, TextFsmError, Token, TokenKind, ValueDefinition,
+};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// Reads one message, or `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length
+        .ok_or_else(|| TextFsmError::ParseError("message without a Content-Length".to_string()))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| TextFsmError::ParseError(format!("invalid message: {}", e)))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// The position of byte `offset` of `text`: a 0-based line and UTF-16 column.
+fn position(text: &str, offset: usize) -> Json {
+    let before = &text[..offset.min(text.len())];
+    let start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[start..].encode_utf16().count(),
+    })
+}
+
+fn range(text: &str, span: Span) -> Json {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+/// The byte offset of an LSP position in `text`.
+fn offset(text: &str, position: &Json) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let start = match line {
+        0 => 0,
+        n => text.match_indices('\n').nth(n - 1)?.0 + 1,
+    };
+    let line_text = text[start..].split('\n').next().unwrap_or_default();
+    let mut units = 0;
+    for (i, c) in line_text.char_indices() {
+        if units >= character {
+            return Some(start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(start + line_text.len())
+}
+
+fn severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+    }
+}
+
+/// The semantic token types, in the order [`token_type`] numbers them.
+const TOKEN_TYPES: [&str; 8] = [
+    "comment",
+    "keyword",
+    "modifier",
+    "variable",
+    "regexp",
+    "namespace",
+    "operator",
+    "string",
+];
+
+fn token_type(kind: TokenKind) -> Option<usize> {
+    let name = match kind {
+        TokenKind::Comment => "comment",
+        TokenKind::Keyword | TokenKind::Action => "keyword",
+        TokenKind::ValueOption => "modifier",
+        TokenKind::Name | TokenKind::Reference => "variable",
+        TokenKind::Regex => "regexp",
+        TokenKind::State => "namespace",
+        TokenKind::Arrow | TokenKind::Punctuation => "operator",
+        TokenKind::Message => "string",
+        TokenKind::Invalid => return None,
+    };
+    TOKEN_TYPES.iter().position(|&t| t == name)
+}
+
+/// What a name in the template refers to.
+enum Target<'a> {
+    Value(&'a ValueDefinition),
+    Macro {
+        name: &'a str,
+        regex: String,
+        span: Option<Span>,
+    },
+    State(&'a crate::StateCompiled),
+}
+
+/// An open template.
+struct Document {
+    text: String,
+    tokens: Vec<Token>,
+    /// `None` while the template does not compile.
+    parser: Option<TextFSMParser>,
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let parser = TextFSMParser::from_string(&text).ok();
+        let tokens = crate::lex::lex(&text);
+        Document {
+            text,
+            tokens,
+            parser,
+        }
+    }
+
+    fn text_of(&self, token: &Token) -> &str {
+        &self.text[token.span.start..token.span.end]
+    }
+
+    fn diagnostics(&self) -> Vec<Json> {
+        let mut diagnostics: Vec<Json> = TextFSMParser::diagnose(&self.text)
+            .iter()
+            .map(|d| {
+                let mut message = d.message.clone();
+                if let Some(suggestion) = &d.suggestion {
+                    message.push_str(&format!(" (did you mean `{}`?)", suggestion));
+                }
+                json!({
+                    "range": range(&self.text, d.span),
+                    "severity": severity(d.severity),
+                    "source": "asyncfsm",
+                    "message": message,
+                })
+            })
+            .collect();
+        for issue in crate::lint::lint(&self.text).unwrap_or_default() {
+            let line = Span::line(&self.text, issue.line);
+            let at = self.text[line.start..line.end]
+                .find(&issue.pattern)
+                .unwrap_or(0);
+            let span = Span {
+                start: line.start + at,
+                end: line.start + at + issue.pattern.len(),
+                ..line
+            };
+            let mut message = issue.kind.description().to_string();
+            if let Some(fix) = &issue.fix {
+                message.push_str(&format!(" (fix: `{}`)", fix));
+            }
+            diagnostics.push(json!({
+                "range": range(&self.text, span),
+                "severity": severity(Severity::Warning),
+                "source": "asyncfsm-lint",
+                "message": message,
+            }));
+        }
+        diagnostics
+    }
+
+    /// The line of the `Macro` declaring `name`, if this template has it.
+    fn macro_declaration(&self, name: &str) -> Option<Span> {
+        self.tokens
+            .windows(2)
+            .find(|pair| {
+                pair[0].kind == TokenKind::Keyword
+                    && self.text_of(&pair[0]) == "Macro"
+                    && self.text_of(&pair[1]) == name
+            })
+            .map(|pair| Span::line(&self.text, pair[1].span.line))
+    }
+
+    /// The name at `position` and what it refers to.
+    fn target(&self, position: &Json) -> Option<(Target<'_>, Span)> {
+        let parser = self.parser.as_ref()?;
+        let at = offset(&self.text, position)?;
+        // A cursor just past a name is still on it.
+        let token = self
+            .tokens
+            .iter()
+            .rfind(|t| t.span.start <= at && at <= t.span.end)?;
+        let text = self.text_of(token);
+        let target = match token.kind {
+            TokenKind::Reference => {
+                let name = text.trim_start_matches(['$', '{']).trim_end_matches('}');
+                match parser.values.get(name) {
+                    Some(value) => Target::Value(value),
+                    None => Target::Macro {
+                        name,
+                        regex: macros::lookup(name, &parser.macros)?,
+                        span: self.macro_declaration(name),
+                    },
+                }
+            }
+            TokenKind::Name => match parser.values.get(text) {
+                Some(value) => Target::Value(value),
+                None => Target::Macro {
+                    name: text,
+                    regex: parser.macros.get(text)?.clone(),
+                    span: self.macro_declaration(text),
+                },
+            },
+            TokenKind::State => {
+                let state = parser.states.get(text)?;
+                if state.span() == Span::default() {
+                    return None;
+                }
+                Target::State(state)
+            }
+            _ => return None,
+        };
+        Some((target, token.span))
+    }
+
+    fn hover(&self, position: &Json) -> Option<Json> {
+        let (target, span) = self.target(position)?;
+        let contents = match target {
+            Target::Value(value) => format!("```textfsm\n{}\n```", value),
+            Target::Macro { name, regex, .. } => {
+                format!("```textfsm\nMacro {} ({})\n```", name, regex)
+            }
+            Target::State(state) => {
+                let rules = state.rules().len();
+                format!(
+                    "State `{}`: {} rule{}",
+                    state.name(),
+                    rules,
+                    if rules == 1 { "" } else { "s" }
+                )
+            }
+        };
+        Some(json!({
+            "contents": { "kind": "markdown", "value": contents },
+            "range": range(&self.text, span),
+        }))
+    }
+
+    fn definition(&self, position: &Json) -> Option<Span> {
+        match self.target(position)?.0 {
+            Target::Value(value) => Some(value.span()),
+            Target::Macro { span, .. } => span,
+            Target::State(state) => Some(state.span()),
+        }
+    }
+
+    /// The tokens as LSP semantic tokens: five numbers each, the line and start relative
+    /// to the previous token, the length, the type and no modifiers.
+    fn semantic_tokens(&self) -> Vec<usize> {
+        let mut data = vec![];
+        let (mut line, mut character) = (0, 0);
+        for token in &self.tokens {
+            let Some(kind) = token_type(token.kind) else {
+                continue;
+            };
+            let token_line = token.span.line - 1;
+            let line_start = self.text[..token.span.start]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let token_character = self.text[line_start..token.span.start]
+                .encode_utf16()
+                .count();
+            if token_line != line {
+                character = 0;
+            }
+            let length = self.text_of(token).encode_utf16().count();
+            data.extend([
+                token_line - line,
+                token_character - character,
+                length,
+                kind,
+                0,
+            ]);
+            (line, character) = (token_line, token_character);
+        }
+        data
+    }
+
+    fn symbols(&self) -> Vec<Json> {
+        let Some(parser) = &self.parser else {
+            return vec![];
+        };
+        parser
+            .state_names
+            .iter()
+            .map(|name| {
+                let state = &parser.states[name];
+                let header = state.span();
+                let end = state.rules().last().map_or(header, |rule| rule.span());
+                json!({
+                    "name": name,
+                    "detail": format!("{} rules", state.rules().len()),
+                    // Namespace
+                    "kind": 3,
+                    "range": range(&self.text, header.to(end)),
+                    "selectionRange": range(&self.text, header),
+                })
+            })
+            .collect()
+    }
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// Serves the templates an editor opens, reading requests from `input` and writing
+/// responses and diagnostics to `output`, until the editor sends `exit` or closes
+/// `input`.
+///
+/// Fails if a stream fails or a message can not be read.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let mut documents: HashMap<String, Document> = HashMap::new();
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // Full document sync
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentSymbolProvider": true,
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "asyncfsm", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => Json::Null,
+            "exit" => return Ok(()),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = match method {
+                    "textDocument/didOpen" => &params["textDocument"]["text"],
+                    _ => params["contentChanges"]
+                        .as_array()
+                        .and_then(|changes| changes.last())
+                        .map_or(&Json::Null, |change| &change["text"]),
+                };
+                let document = Document::new(text.as_str().unwrap_or_default().to_string());
+                let diagnostics = json!({ "uri": uri, "diagnostics": document.diagnostics() });
+                write_message(
+                    &mut output,
+                    &notification("textDocument/publishDiagnostics", diagnostics),
+                )?;
+                documents.insert(uri, document);
+                continue;
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                let diagnostics = json!({ "uri": uri, "diagnostics": [] });
+                write_message(
+                    &mut output,
+                    &notification("textDocument/publishDiagnostics", diagnostics),
+                )?;
+                continue;
+            }
+            "textDocument/hover" => documents
+                .get(&uri)
+                .and_then(|document| document.hover(&params["position"]))
+                .unwrap_or(Json::Null),
+            "textDocument/definition" => documents
+                .get(&uri)
+                .and_then(|document| {
+                    let span = document.definition(&params["position"])?;
+                    Some(json!({ "uri": uri, "range": range(&document.text, span) }))
+                })
+                .unwrap_or(Json::Null),
+            "textDocument/semanticTokens/full" => json!({
+                "data": documents
+                    .get(&uri)
+                    .map(Document::semantic_tokens)
+                    .unwrap_or_default(),
+            }),
+            "textDocument/documentSymbol" => {
+                json!(documents
+                    .get(&uri)
+                    .map(Document::symbols)
+                    .unwrap_or_default())
+            }
+            _ => {
+                // Notifications need no answer.
+                if let Some(id) = message.get("id") {
+                    let error = json!({ "code": -32601, "message": format!("{} is not supported", method) });
+                    write_message(
+                        &mut output,
+                        &json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+                    )?;
+                }
+                continue;
+            }
+        };
+        if let Some(id) = message.get("id") {
+            write_message(
+                &mut output,
+                &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Named regexes that templates reuse in Value patterns and rules.
+//!
+//! A template declares macros next to its values, and uses them as `${NAME}` inside a
+//! value's regex or a rule, where they expand to a non-capturing group:
+//!
+//! ```text
+//! Macro _PORT_ ((?:Gi|Te)\d+/\d+)
+//! Value PORT (${_PORT_})
+//! Value ADDRESS (${_IPV4_PREFIX_})
+//!
+//! Start
+//!   ^interface ${PORT}
+//!   ^ ip address ${ADDRESS} -> Record
+//!   ^ shutdown on ${_PORT_}
+//! ```
+//!
+//! [`BUILTIN_MACROS`] names the [`patterns`](crate::patterns), and [`register_macro`] adds more
+//! for every template compiled afterwards. A macro can use the macros declared before
+//! it. In a rule, a value of the same name is substituted first, so templates declare
+//! their own macros with names that are not values; the built-in names start and end
+//! with `_` to keep them apart.
+
+use crate::{patterns, Result, TextFsmError};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// The macros every template can use, with their patterns.
+pub const BUILTIN_MACROS: &[(&str, &str)] = patterns::PATTERNS;
+
+fn registry() -> &'static RwLock<HashMap<String, String>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Makes `name` usable as `${name}` in templates compiled from now on.
+///
+/// `pattern` may use the macros available so far. Registering a name again replaces
+/// its pattern; built-in names are refused.
+pub fn register_macro(name: &str, pattern: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || is_builtin(name) {
+        return Err(TextFsmError::ParseError(format!(
+            "Can not register macro {:?}",
+            name
+        )));
+    }
+    let pattern = expand(pattern, &HashMap::new())?;
+    Regex::new(&pattern).map_err(|e| {
+        TextFsmError::ParseError(format!("invalid pattern for macro {}: {}", name, e))
+    })?;
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), pattern);
+    Ok(())
+}
+
+pub(crate) fn is_builtin(name: &str) -> bool {
+    patterns::by_macro_name(name).is_some()
+}
+
+/// The expanded pattern of macro `name`: declared by the template (`local`),
+/// registered, or built in.
+pub(crate) fn lookup(name: &str, local: &HashMap<String, String>) -> Option<String> {
+    if let Some(pattern) = local.get(name) {
+        return Some(pattern.clone());
+    }
+    if let Some(pattern) = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+    {
+        return Some(pattern.clone());
+    }
+    patterns::by_macro_name(name).map(str::to_string)
+}
+
+/// Replaces every `${NAME}` in `pattern` with the macro's pattern in a non-capturing
+/// group.
+pub(crate) fn expand(pattern: &str, local: &HashMap<String, String>) -> Result<String> {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let reference = REFERENCE.get_or_init(|| Regex::new(r"\$\{(\w+)\}").unwrap());
+    let mut out = String::with_capacity(pattern.len());
+    let mut last = 0;
+    for caps in reference.captures_iter(pattern) {
+        let whole = caps.get(0).unwrap();
+        let name = &caps[1];
+        let expansion = lookup(name, local)
+            .ok_or_else(|| TextFsmError::ParseError(format!("unknown macro ${{{}}}", name)))?;
+        out.push_str(&pattern[last..whole.start()]);
+        out.push_str(&format!("(?:{})", expansion));
+        last = whole.end();
+    }
+    out.push_str(&pattern[last..]);
+    Ok(out)
+}
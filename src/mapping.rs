@@ -0,0 +1,313 @@
+//! Translating records into model-shaped JSON, such as OpenConfig, with declarative
+//! mapping files.
+//!
+//! A [`Mapping`] names the list that receives one entry per record and, for each leaf
+//! of an entry, the record field it is read from:
+//!
+//! ```yaml
+//! path: openconfig-interfaces:interfaces/interface
+//! fields:
+//!   name: INTERFACE
+//!   config/mtu: { field: MTU, type: integer }
+//!   state/admin-status:
+//!     field: LINK_STATUS
+//!     values: { up: UP, administratively down: DOWN }
+//!   config/type: { value: "iana-if-type:ethernetCsmacd" }
+//! ```
+//!
+//! Paths are separated by `/`. A leaf is a field name, or a rule with the `field` to
+//! read (in any case), the `type` to write it as (`string`, the default, `integer` or
+//! `boolean`) and `values` that replace whole texts first; keys match in any case. A
+//! rule with `value` writes that constant instead, and one with `format`, such as
+//! `"{IP_ADDRESS}/{PREFIX_LENGTH}"`, fills in the text of each named field, the first
+//! item of a `List`. Leaves whose field is missing or empty, or does not read as the
+//! type, are left out, and `List` fields become arrays; a record missing a leaf marked
+//! `required: true` is left out entirely. An empty `path` makes the document the list
+//! itself.
+//!
+//! Mappings are looked up by name with [`mapping`]; `openconfig-interfaces` and the
+//! NetBox payloads of [`netbox`](crate::netbox) are built in, and [`register_mapping`]
+//! adds others.
+//!
+//! ```
+//! use asyncfsm::mapping::Mapping;
+//! use asyncfsm::TextFSM;
+//!
+//! let mapping = Mapping::from_yaml_str("path: interfaces/interface\nfields:\n  name: NAME\n  mtu: { field: MTU, type: integer }\n")?;
+//! let template = "Value NAME (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^${NAME} mtu ${MTU} -> Record\n";
+//! let records = TextFSM::from_string(template)?.parse_records("Gi1 mtu 1500\n", None)?;
+//! assert_eq!(
+//!     mapping.apply(&records),
+//!     serde_json::json!({ "interfaces": { "interface": [{ "name": "Gi1", "mtu": 1500 }] } })
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Records, Result, TextFsmError, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+/// What a leaf is written as; see [`FieldRule`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafType {
+    #[default]
+    String,
+    /// The leading digits of the text, with an optional `-`.
+    Integer,
+    /// `true`, `yes`, `up`, `enabled` or `1`, and their opposites, in any case.
+    Boolean,
+}
+
+/// How one leaf is filled; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldSource {
+    /// The text of a field, as a string.
+    Field(String),
+    Rule(FieldRule),
+}
+
+/// A leaf read from `field` and converted, or the constant `value`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Json>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, rename = "type")]
+    pub leaf_type: LeafType,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+    /// Leave out records without this leaf.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A translation of records into a model's JSON; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    /// The path of the list that gets one entry per record, or empty for the list alone.
+    #[serde(default)]
+    pub path: String,
+    /// Each leaf's path within an entry, and how it is filled.
+    pub fields: BTreeMap<String, FieldSource>,
+}
+
+impl Mapping {
+    /// Reads a mapping from YAML, or JSON, which is also YAML.
+    pub fn from_yaml_str(content: &str) -> Result<Self> {
+        serde_yaml::from_str(content).map_err(|e| TextFsmError::ParseError(e.to_string()))
+    }
+
+    /// Loads a mapping file.
+    pub fn from_file<P: AsRef<Path>>(fname: P) -> Result<Self> {
+        Self::from_yaml_str(&std::fs::read_to_string(fname)?)
+    }
+
+    /// The document holding one list entry per record. Records that fill no leaf, or
+    /// miss a required one, are left out.
+    pub fn apply(&self, records: &Records) -> Json {
+        let entries: Vec<Json> = records
+            .iter()
+            .filter_map(|record| self.entry(record))
+            .filter(|entry| !entry.is_empty())
+            .map(Json::Object)
+            .collect();
+        self.path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .rev()
+            .fold(Json::Array(entries), |inner, segment| {
+                let mut outer = Map::new();
+                outer.insert(segment.to_string(), inner);
+                Json::Object(outer)
+            })
+    }
+
+    fn entry(&self, record: &DataRecord) -> Option<Map<String, Json>> {
+        let mut entry = Map::new();
+        for (path, source) in &self.fields {
+            match source.read(record) {
+                Some(leaf) => insert_at(&mut entry, path, leaf),
+                None if matches!(source, FieldSource::Rule(rule) if rule.required) => return None,
+                None => {}
+            }
+        }
+        Some(entry)
+    }
+}
+
+impl FieldSource {
+    fn read(&self, record: &DataRecord) -> Option<Json> {
+        let rule = match self {
+            FieldSource::Field(field) => {
+                return read_field(record, field, &FieldRule::default());
+            }
+            FieldSource::Rule(rule) => rule,
+        };
+        match (&rule.value, &rule.format, &rule.field) {
+            (Some(value), _, _) => Some(value.clone()),
+            (None, Some(format), _) => convert(&fill(record, format)?, rule),
+            (None, None, Some(field)) => read_field(record, field, rule),
+            (None, None, None) => None,
+        }
+    }
+}
+
+/// `format` with each `{FIELD}` replaced by the field's text, or `None` if one is
+/// missing or empty.
+fn fill(record: &DataRecord, format: &str) -> Option<String> {
+    let mut filled = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        let field = &rest[start + 1..end];
+        let (_, value) = record
+            .fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))?;
+        let text = match value {
+            Value::Single(text) => text.as_str(),
+            Value::List(items) => items.first()?.as_str(),
+        };
+        if text.is_empty() {
+            return None;
+        }
+        filled.push_str(&rest[..start]);
+        filled.push_str(text);
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    Some(filled)
+}
+
+fn read_field(record: &DataRecord, field: &str, rule: &FieldRule) -> Option<Json> {
+    let (_, value) = record
+        .fields
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(field))?;
+    match value {
+        Value::Single(text) => convert(text, rule),
+        Value::List(items) => Some(Json::Array(
+            items
+                .iter()
+                .filter_map(|item| convert(item, rule))
+                .collect(),
+        )),
+    }
+}
+
+fn convert(text: &str, rule: &FieldRule) -> Option<Json> {
+    let text = rule
+        .values
+        .iter()
+        .find(|(from, _)| from.eq_ignore_ascii_case(text))
+        .map_or(text, |(_, to)| to.as_str());
+    if text.is_empty() {
+        return None;
+    }
+    match rule.leaf_type {
+        LeafType::String => Some(Json::from(text)),
+        LeafType::Integer => {
+            let digits = text.strip_prefix('-').unwrap_or(text);
+            let end = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+            let number = &text[..end + text.len() - digits.len()];
+            number.parse::<i64>().ok().map(Json::from)
+        }
+        LeafType::Boolean => match text.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "up" | "enabled" | "1" => Some(Json::Bool(true)),
+            "false" | "no" | "down" | "disabled" | "0" => Some(Json::Bool(false)),
+            _ => None,
+        },
+    }
+}
+
+/// Sets `leaf` at the `/`-separated `path` under `entry`, creating the containers.
+fn insert_at(entry: &mut Map<String, Json>, path: &str, leaf: Json) {
+    let mut segments: Vec<&str> = path.split('/').collect();
+    let last = segments.pop().unwrap_or_default();
+    let mut container = entry;
+    for segment in segments {
+        let child = container
+            .entry(segment.to_string())
+            .or_insert_with(|| Json::Object(Map::new()));
+        if !child.is_object() {
+            *child = Json::Object(Map::new());
+        }
+        container = child.as_object_mut().expect("just made an object");
+    }
+    container.insert(last.to_string(), leaf);
+}
+
+type Registry = RwLock<HashMap<String, Mapping>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let builtin = [
+            (
+                "openconfig-interfaces",
+                include_str!("mappings/openconfig-interfaces.yaml"),
+            ),
+            (
+                "netbox-interfaces",
+                include_str!("mappings/netbox-interfaces.yaml"),
+            ),
+            (
+                "netbox-ip-addresses",
+                include_str!("mappings/netbox-ip-addresses.yaml"),
+            ),
+            (
+                "netbox-inventory-items",
+                include_str!("mappings/netbox-inventory-items.yaml"),
+            ),
+        ];
+        let mappings = builtin
+            .into_iter()
+            .map(|(name, content)| {
+                let mapping = Mapping::from_yaml_str(content).expect("built-in mappings are valid");
+                (name.to_string(), mapping)
+            })
+            .collect();
+        RwLock::new(mappings)
+    })
+}
+
+/// Registers `mapping` under `name`, replacing any mapping of that name.
+pub fn register_mapping(name: &str, mapping: Mapping) {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), mapping);
+}
+
+/// The mapping registered as `name`.
+pub fn mapping(name: &str) -> Result<Mapping> {
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    match registry.get(name) {
+        Some(mapping) => Ok(mapping.clone()),
+        None => {
+            let mut names: Vec<&String> = registry.keys().collect();
+            names.sort();
+            Err(TextFsmError::ParseError(format!(
+                "unknown mapping '{}', expected one of: {}",
+                name,
+                names
+                    .iter()
+                    .map(|n| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )))
+        }
+    }
+}
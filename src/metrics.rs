@@ -0,0 +1,59 @@
+//! Observability hooks for template compilation and parsing.
+//!
+//! With the `metrics` feature enabled, the engine reports through the
+//! [`metrics`](https://docs.rs/metrics) facade, so any installed recorder (Prometheus,
+//! StatsD, ...) picks the values up. Without the feature these hooks compile to nothing.
+
+use std::time::Duration;
+
+/// Counter of templates compiled, labelled `result=ok|error`.
+pub const TEMPLATES_COMPILED: &str = "textfsm_templates_compiled_total";
+/// Counter of input lines run through the state machine.
+pub const LINES_PARSED: &str = "textfsm_lines_parsed_total";
+/// Counter of records emitted.
+pub const RECORDS_EMITTED: &str = "textfsm_records_emitted_total";
+/// Counter of parse operations that failed.
+pub const PARSE_ERRORS: &str = "textfsm_parse_errors_total";
+/// Histogram of `parse_records`/`parse_file_records` durations in seconds.
+pub const PARSE_DURATION: &str = "textfsm_parse_duration_seconds";
+
+#[cfg(feature = "metrics")]
+fn result_label(ok: bool) -> &'static str {
+    if ok {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+pub(crate) fn template_compiled(ok: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(TEMPLATES_COMPILED, "result" => result_label(ok)).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = ok;
+}
+
+pub(crate) fn parse_finished(elapsed: Duration, lines: usize, records: usize, ok: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(PARSE_DURATION).record(elapsed.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = elapsed;
+    stream_progress(lines, records, ok);
+}
+
+pub(crate) fn stream_progress(lines: usize, records: usize, ok: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        if lines > 0 {
+            ::metrics::counter!(LINES_PARSED).increment(lines as u64);
+        }
+        if records > 0 {
+            ::metrics::counter!(RECORDS_EMITTED).increment(records as u64);
+        }
+        if !ok {
+            ::metrics::counter!(PARSE_ERRORS).increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (lines, records, ok);
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "clitable")]
+use crate::{CliTable, Result, TextFsmError};
+use crate::{DataRecord, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "clitable")]
+use std::path::{Path, PathBuf};
+
+/// Field names that identify an interface, in order of preference.
+const INTERFACE_KEYS: &[&str] = &["INTERFACE", "INTF", "PORT", "LOCAL_INTERFACE", "NAME"];
+
+/// A single device document assembled from the output of several commands.
+///
+/// Records are classified by the command that produced them: `show version`-like output
+/// becomes `facts`, interface output is merged per interface name, neighbor tables
+/// (CDP, LLDP, BGP, ...) are collected in `neighbors`, and anything else is kept verbatim
+/// under `commands`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct DeviceModel {
+    /// Device-wide facts such as version, model and serial number.
+    pub facts: BTreeMap<String, Value>,
+    /// Interfaces keyed by name, with fields merged from every interface command.
+    pub interfaces: BTreeMap<String, BTreeMap<String, Value>>,
+    /// Neighbor entries, each tagged with the command that produced it.
+    pub neighbors: Vec<BTreeMap<String, Value>>,
+    /// Records of commands that did not fit any of the above sections.
+    pub commands: BTreeMap<String, Vec<BTreeMap<String, Value>>>,
+}
+
+/// Returns the value of the first well-known field present in `rec`, compared case-insensitively.
+fn find_key(rec: &DataRecord, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| {
+        rec.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+fn to_map(rec: DataRecord) -> BTreeMap<String, Value> {
+    rec.fields.into_iter().collect()
+}
+
+impl DeviceModel {
+    /// Creates an empty model.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Assembles a model from a mapping of command to the records it produced.
+    pub fn from_commands<I, R>(outputs: I) -> Self
+    where
+        I: IntoIterator<Item = (String, R)>,
+        R: IntoIterator<Item = DataRecord>,
+    {
+        let mut model = Self::new();
+        for (command, records) in outputs {
+            model.add_command(&command, records);
+        }
+        model
+    }
+
+    /// Parses a directory of one device's command outputs with the templates `table`
+    /// selects. Each file is named after the command it holds, e.g. `show_version.txt`;
+    /// without a platform, it is guessed from each file.
+    #[cfg(feature = "clitable")]
+    pub fn from_dir(
+        table: &CliTable,
+        platform: Option<&str>,
+        dir: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.sort();
+
+        let mut model = Self::new();
+        for path in paths.iter().filter(|p| p.is_file()) {
+            let Some(stem) = path.file_stem() else {
+                continue;
+            };
+            let command = stem.to_string_lossy().replace('_', " ");
+            let content = crate::encoding::read_file(path, table.options())?;
+            let platform = match platform {
+                Some(platform) => platform.to_string(),
+                None => table.guess_platform(&content).ok_or_else(|| {
+                    TextFsmError::ParseError(format!(
+                        "could not detect platform of {}",
+                        path.display()
+                    ))
+                })?,
+            };
+            let records = table.parse(&platform, &command, &content)?;
+            model.add_command(&command, records);
+        }
+        Ok(model)
+    }
+
+    /// Merges the records of one command into the model.
+    pub fn add_command<R: IntoIterator<Item = DataRecord>>(&mut self, command: &str, records: R) {
+        let cmd = command.to_lowercase();
+        if cmd.contains("version") || cmd.contains("inventory") {
+            for rec in records {
+                for (k, v) in rec.fields {
+                    self.facts.entry(k).or_insert(v);
+                }
+            }
+        } else if ["neighbor", "cdp", "lldp", "bgp"]
+            .iter()
+            .any(|w| cmd.contains(w))
+        {
+            for rec in records {
+                let mut entry = to_map(rec);
+                entry.insert("COMMAND".to_string(), Value::Single(command.to_string()));
+                self.neighbors.push(entry);
+            }
+        } else if cmd.contains("interface") {
+            for rec in records {
+                match find_key(&rec, INTERFACE_KEYS) {
+                    Some(name) => self.interfaces.entry(name).or_default().extend(rec.fields),
+                    None => self.push_command(command, rec),
+                }
+            }
+        } else {
+            for rec in records {
+                self.push_command(command, rec);
+            }
+        }
+    }
+
+    fn push_command(&mut self, command: &str, rec: DataRecord) {
+        self.commands
+            .entry(command.to_string())
+            .or_default()
+            .push(to_map(rec));
+    }
+}
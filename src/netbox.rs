@@ -0,0 +1,126 @@
+//! NetBox API payloads for syncing parsed device facts into NetBox.
+//!
+//! [`Netbox::payloads`] turns records into the JSON list a `POST` to a NetBox endpoint
+//! takes, one object per record. The kind of object is detected from the record
+//! fields, see [`NetboxObject::detect`], and each kind has a built-in
+//! [`Mapping`] from the ntc-templates field names, registered as `netbox-interfaces`,
+//! `netbox-ip-addresses` and `netbox-inventory-items`. Templates with other field
+//! names, or a NetBox with custom fields, use their own mapping file instead.
+//!
+//! NetBox accepts related objects by their attributes, so with [`Netbox::device`] every
+//! interface and inventory item names its device as `{"device": {"name": ...}}`.
+//!
+//! ```
+//! use asyncfsm::netbox::{Netbox, NetboxObject};
+//! use asyncfsm::TextFSM;
+//!
+//! let template = "Value INTERFACE (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^${INTERFACE} mtu ${MTU} -> Record\n";
+//! let records = TextFSM::from_string(template)?.parse_records("Gi1 mtu 1500\n", None)?;
+//! assert_eq!(NetboxObject::detect(&records), Some(NetboxObject::Interface));
+//! assert_eq!(
+//!     Netbox::new().device("r1").payloads(&records)?,
+//!     serde_json::json!([{ "device": { "name": "r1" }, "name": "Gi1", "type": "other", "mtu": 1500 }])
+//! );
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::mapping::{mapping, Mapping};
+use crate::{Records, Result, TextFsmError};
+use serde_json::Value as Json;
+
+/// The kinds of NetBox object with a built-in mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetboxObject {
+    Interface,
+    IpAddress,
+    InventoryItem,
+}
+
+impl NetboxObject {
+    /// The kind the records describe: inventory items if they have a `PID` or `SN`
+    /// field, interfaces if they have `INTERFACE` and one of `MTU`, `LINK_STATUS` or
+    /// `MAC_ADDRESS`, and IP addresses if they have `IP_ADDRESS` and `PREFIX_LENGTH`.
+    pub fn detect(records: &Records) -> Option<Self> {
+        let record = records.first()?;
+        let has = |name: &str| record.fields.keys().any(|f| f.eq_ignore_ascii_case(name));
+        if has("PID") || has("SN") {
+            Some(NetboxObject::InventoryItem)
+        } else if has("INTERFACE") && (has("MTU") || has("LINK_STATUS") || has("MAC_ADDRESS")) {
+            Some(NetboxObject::Interface)
+        } else if has("IP_ADDRESS") && has("PREFIX_LENGTH") {
+            Some(NetboxObject::IpAddress)
+        } else {
+            None
+        }
+    }
+
+    /// The API endpoint the payloads are posted to, under `/api/`.
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            NetboxObject::Interface => "dcim/interfaces",
+            NetboxObject::IpAddress => "ipam/ip-addresses",
+            NetboxObject::InventoryItem => "dcim/inventory-items",
+        }
+    }
+
+    /// The name the built-in mapping is registered under.
+    pub fn mapping_name(&self) -> &'static str {
+        match self {
+            NetboxObject::Interface => "netbox-interfaces",
+            NetboxObject::IpAddress => "netbox-ip-addresses",
+            NetboxObject::InventoryItem => "netbox-inventory-items",
+        }
+    }
+}
+
+/// Settings for building payloads; see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Netbox {
+    mapping: Option<Mapping>,
+    device: Option<String>,
+}
+
+impl Netbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `mapping` instead of the built-in mapping of the detected kind.
+    pub fn mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = Some(mapping);
+        self
+    }
+
+    /// Names `device` in every payload except IP addresses.
+    pub fn device(mut self, device: &str) -> Self {
+        self.device = Some(device.to_string());
+        self
+    }
+
+    /// The payloads for `records`.
+    ///
+    /// Fails if no mapping was given and the kind of object can not be detected.
+    pub fn payloads(&self, records: &Records) -> Result<Json> {
+        let (mut payloads, object) = match &self.mapping {
+            Some(custom) => (custom.apply(records), None),
+            None => {
+                let object = NetboxObject::detect(records).ok_or_else(|| {
+                    TextFsmError::ParseError(
+                        "records are not interfaces, IP addresses or inventory items; \
+                         give a mapping for them"
+                            .into(),
+                    )
+                })?;
+                (mapping(object.mapping_name())?.apply(records), Some(object))
+            }
+        };
+        if let (Some(device), false) = (&self.device, object == Some(NetboxObject::IpAddress)) {
+            for payload in payloads.as_array_mut().into_iter().flatten() {
+                if let Some(payload) = payload.as_object_mut() {
+                    payload.insert("device".into(), serde_json::json!({ "name": device }));
+                }
+            }
+        }
+        Ok(payloads)
+    }
+}
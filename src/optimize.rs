@@ -0,0 +1,256 @@
+//! Dead and duplicated rules, and the template without them.
+//!
+//! [`optimize`] reads the rules of each state in order, as the engine tries them:
+//!
+//! - a rule is *shadowed* when an earlier rule of its state that does not `Continue`
+//!   matches every line it does, because it has the same regex or matches any line.
+//!   It never runs, and is removed.
+//! - adjacent rules that capture nothing and do the same thing are merged, so
+//!   `^a -> Record` and `^b -> Record` become `^(?:a|b) -> Record`.
+//! - a rule that matches every line, or can not match any, is reported. It is kept: it
+//!   is as likely to be a mistake in the regex as it is to be meant.
+//!
+//! The optimized template is written in the form of
+//! [`Template::to_textfsm_string`](crate::Template::to_textfsm_string), and parses
+//! every input into the records the original does.
+//!
+//! ```
+//! use asyncfsm::optimize::{optimize, Finding};
+//! use asyncfsm::TextFSMParser;
+//!
+//! let template = "Value A (\\d+)\n\nStart\n  ^a ${A} -> Record\n  ^a ${A} -> Record\n  ^x -> Next\n  ^y\n";
+//! let optimized = optimize(&TextFSMParser::from_string(template)?)?;
+//! assert_eq!(
+//!     optimized.findings[0],
+//!     Finding::Shadowed { state: "Start".into(), rule: 1, by: 0 }
+//! );
+//! assert_eq!(optimized.template, "Value A (\\d+)\n\nStart\n  ^a ${A} -> Record\n  ^(?:x|y)\n");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{Result, RuleTransition, StateRuleCompiled, TextFSMParser};
+use regex::Regex;
+use regex_syntax::hir::Look;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Something [`optimize`] found. Rules are 0-based positions in the original state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Finding {
+    /// The rule never runs because rule `by` matches its lines first; it was removed.
+    Shadowed {
+        state: String,
+        rule: usize,
+        by: usize,
+    },
+    /// The rules were merged into one.
+    Merged { state: String, rules: Vec<usize> },
+    /// The rule matches every line.
+    AlwaysMatches { state: String, rule: usize },
+    /// The rule can not match any line.
+    NeverMatches { state: String, rule: usize },
+}
+
+impl Finding {
+    /// The rule found, or the first of the merged rules.
+    fn rule(&self) -> usize {
+        match self {
+            Finding::Shadowed { rule, .. }
+            | Finding::AlwaysMatches { rule, .. }
+            | Finding::NeverMatches { rule, .. } => *rule,
+            Finding::Merged { rules, .. } => rules[0],
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Shadowed { state, rule, by } => write!(
+                f,
+                "{} rule {}: shadowed by rule {}, removed",
+                state,
+                rule + 1,
+                by + 1
+            ),
+            Finding::Merged { state, rules } => {
+                let rules: Vec<String> = rules.iter().map(|i| (i + 1).to_string()).collect();
+                write!(f, "{} rules {}: merged", state, rules.join(", "))
+            }
+            Finding::AlwaysMatches { state, rule } => {
+                write!(f, "{} rule {}: matches every line", state, rule + 1)
+            }
+            Finding::NeverMatches { state, rule } => {
+                write!(f, "{} rule {}: can never match", state, rule + 1)
+            }
+        }
+    }
+}
+
+/// The result of [`optimize`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Optimized {
+    /// In state and rule order.
+    pub findings: Vec<Finding>,
+    /// The optimized template.
+    pub template: String,
+}
+
+impl Optimized {
+    /// True if no rule was removed or merged.
+    pub fn is_unchanged(&self) -> bool {
+        !self
+            .findings
+            .iter()
+            .any(|f| matches!(f, Finding::Shadowed { .. } | Finding::Merged { .. }))
+    }
+}
+
+/// `Some(true)` for a regex that matches every line, `Some(false)` for one that matches
+/// none, `None` when it depends on the line or the regex is beyond `regex-syntax`, such
+/// as a look-around.
+fn matches_all(regex: &str) -> Option<bool> {
+    let hir = regex_syntax::Parser::new().parse(regex).ok()?;
+    let properties = hir.properties();
+    match properties.minimum_len() {
+        None => Some(false),
+        // Matching the empty string with no anchor but the start matches any line.
+        Some(0)
+            if properties
+                .look_set()
+                .iter()
+                .all(|look| matches!(look, Look::Start | Look::StartLF | Look::StartCRLF)) =>
+        {
+            Some(true)
+        }
+        Some(_) => None,
+    }
+}
+
+/// Matches a flag group that applies to the rest of its group, such as `(?i)`.
+fn inline_flags() -> &'static Regex {
+    static INLINE_FLAGS: OnceLock<Regex> = OnceLock::new();
+    INLINE_FLAGS.get_or_init(|| Regex::new(r"\(\?[a-zA-Z-]+\)").expect("valid regex"))
+}
+
+/// True if `pattern` has a `|` outside any group, as in `^a|b`, which only anchors `a`.
+pub(crate) fn has_top_level_alternation(pattern: &str) -> bool {
+    let (mut depth, mut in_class, mut chars) = (0usize, false, pattern.chars());
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => depth = depth.saturating_sub(1),
+            '|' if !in_class && depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// True if `rule` can be merged with its neighbours: merging a rule that captures or
+/// continues would change what runs, an inline flag group would spread to them and a
+/// bare `|` would take them into its alternatives.
+fn mergeable(rule: &StateRuleCompiled) -> bool {
+    !rule.transition().continues()
+        && rule.captured_values().next().is_none()
+        && !inline_flags().is_match(rule.pattern())
+        && !has_top_level_alternation(rule.pattern())
+}
+
+fn rule_line(pattern: String, transition: &RuleTransition) -> String {
+    if *transition == RuleTransition::default() {
+        pattern
+    } else {
+        format!("{} -> {}", pattern, transition)
+    }
+}
+
+/// Finds shadowed, mergeable and suspicious rules; see the [module documentation](self).
+pub fn optimize(parser: &TextFSMParser) -> Result<Optimized> {
+    let mut findings = vec![];
+    let mut lines: HashMap<&str, Vec<String>> = HashMap::new();
+    for name in &parser.state_names {
+        let first_finding = findings.len();
+        let rules = parser.states[name].rules();
+        let kinds: Vec<Option<bool>> = rules
+            .iter()
+            .map(|rule| matches_all(rule.expanded_pattern()))
+            .collect();
+        let mut live = vec![];
+        for (i, rule) in rules.iter().enumerate() {
+            match kinds[i] {
+                Some(true) => findings.push(Finding::AlwaysMatches {
+                    state: name.clone(),
+                    rule: i,
+                }),
+                Some(false) => findings.push(Finding::NeverMatches {
+                    state: name.clone(),
+                    rule: i,
+                }),
+                None => {}
+            }
+            let by = live.iter().copied().find(|&j: &usize| {
+                !rules[j].transition().continues()
+                    && (kinds[j] == Some(true)
+                        || rules[j].expanded_pattern() == rule.expanded_pattern())
+            });
+            match by {
+                Some(by) => findings.push(Finding::Shadowed {
+                    state: name.clone(),
+                    rule: i,
+                    by,
+                }),
+                None => live.push(i),
+            }
+        }
+
+        let mut state_lines = vec![];
+        let mut rest = live.as_slice();
+        while let Some((&first, _)) = rest.split_first() {
+            let group = if mergeable(&rules[first]) {
+                rest.iter()
+                    .take_while(|&&i| {
+                        mergeable(&rules[i]) && rules[i].transition() == rules[first].transition()
+                    })
+                    .count()
+            } else {
+                1
+            };
+            let (merged, after) = rest.split_at(group);
+            rest = after;
+            if group == 1 {
+                state_lines.push(rules[first].to_string());
+                continue;
+            }
+            let patterns: Vec<&str> = merged
+                .iter()
+                .map(|&i| {
+                    let pattern = rules[i].pattern();
+                    pattern.strip_prefix('^').unwrap_or(pattern)
+                })
+                .collect();
+            state_lines.push(rule_line(
+                format!("^(?:{})", patterns.join("|")),
+                rules[first].transition(),
+            ));
+            findings.push(Finding::Merged {
+                state: name.clone(),
+                rules: merged.to_vec(),
+            });
+        }
+        findings[first_finding..].sort_by_key(Finding::rule);
+        lines.insert(name, state_lines);
+    }
+    let template = crate::format::render(parser, |state| lines[state].clone());
+    // The rules were rewritten as text; make sure they still compile.
+    TextFSMParser::from_string(&template)?;
+    Ok(Optimized { findings, template })
+}
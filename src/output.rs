@@ -0,0 +1,256 @@
+//! Pluggable output formats.
+//!
+//! An [`OutputWriter`] receives the field names once, then each record, then a
+//! final call, and writes to any `io::Write`. Writers are registered by name with
+//! [`register_output_writer`]; the CLI's `--writer` flag picks one, so a program
+//! embedding this crate can offer its own formats next to the built-in `csv`,
+//! `text`, `html`, `xml` and `jsonl`.
+//!
+//! With the `plugins` feature, [`load_plugin`] adds writers from a shared library,
+//! which is how the stock CLI picks up formats it was not built with.
+
+use crate::export::{get_headers, OutputFormat, TextFsmExport};
+use crate::{DataRecord, Records, Result, TextFsmError};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Writes records in one output format.
+pub trait OutputWriter {
+    /// Called once before any record, with the field names in output order.
+    fn header(&mut self, out: &mut dyn Write, fields: &[String]) -> io::Result<()> {
+        let _ = (out, fields);
+        Ok(())
+    }
+
+    /// Called for each record.
+    fn record(
+        &mut self,
+        out: &mut dyn Write,
+        fields: &[String],
+        record: &DataRecord,
+    ) -> io::Result<()>;
+
+    /// Called once after the last record.
+    fn footer(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let _ = out;
+        Ok(())
+    }
+}
+
+/// Runs `writer` over `records`: header, every record, footer.
+pub fn write_records(
+    writer: &mut dyn OutputWriter,
+    out: &mut dyn Write,
+    records: &Records,
+) -> Result<()> {
+    let fields = get_headers(records, records.schema());
+    writer.header(out, &fields)?;
+    for record in records.iter() {
+        writer.record(out, &fields, record)?;
+    }
+    writer.footer(out)?;
+    Ok(())
+}
+
+/// Makes a fresh writer for each output.
+pub type WriterFactory = Arc<dyn Fn() -> Box<dyn OutputWriter> + Send + Sync>;
+
+/// Adapts an [`OutputFormat`] of [`TextFsmExport`], which needs all records at once.
+struct ExportWriter {
+    format: OutputFormat,
+    fields: Vec<String>,
+    records: Vec<DataRecord>,
+}
+
+impl OutputWriter for ExportWriter {
+    fn header(&mut self, _out: &mut dyn Write, fields: &[String]) -> io::Result<()> {
+        self.fields = fields.to_vec();
+        Ok(())
+    }
+
+    fn record(
+        &mut self,
+        _out: &mut dyn Write,
+        _fields: &[String],
+        record: &DataRecord,
+    ) -> io::Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn footer(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let records = Records::new(std::mem::take(&mut self.records), self.fields.clone());
+        let text = records.export(self.format).map_err(io::Error::other)?;
+        writeln!(out, "{}", text.trim_end())
+    }
+}
+
+/// One JSON object per line, fields in output order.
+#[cfg(feature = "json")]
+struct JsonLinesWriter;
+
+#[cfg(feature = "json")]
+impl OutputWriter for JsonLinesWriter {
+    fn record(
+        &mut self,
+        out: &mut dyn Write,
+        fields: &[String],
+        record: &DataRecord,
+    ) -> io::Result<()> {
+        write_json_line(out, fields, record)
+    }
+}
+
+/// Writes `record` as one line of JSON, with its `fields` in order.
+#[cfg(feature = "json")]
+pub(crate) fn write_json_line(
+    out: &mut dyn Write,
+    fields: &[String],
+    record: &DataRecord,
+) -> io::Result<()> {
+    let mut first = true;
+    out.write_all(b"{")?;
+    for name in fields {
+        let Some(value) = record.get(name) else {
+            continue;
+        };
+        if !first {
+            out.write_all(b",")?;
+        }
+        first = false;
+        serde_json::to_writer(&mut *out, name)?;
+        out.write_all(b":")?;
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    out.write_all(b"}")?;
+    writeln!(out)
+}
+
+type Registry = RwLock<BTreeMap<String, WriterFactory>>;
+
+fn export_factory(format: OutputFormat) -> WriterFactory {
+    Arc::new(move || {
+        Box::new(ExportWriter {
+            format,
+            fields: vec![],
+            records: vec![],
+        })
+    })
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut writers = BTreeMap::new();
+        #[cfg(feature = "csv_export")]
+        writers.insert("csv".to_string(), export_factory(OutputFormat::Csv));
+        writers.insert("text".to_string(), export_factory(OutputFormat::Text));
+        writers.insert("html".to_string(), export_factory(OutputFormat::Html));
+        writers.insert("xml".to_string(), export_factory(OutputFormat::Xml));
+        #[cfg(feature = "json")]
+        writers.insert(
+            "jsonl".to_string(),
+            Arc::new(|| Box::new(JsonLinesWriter) as Box<dyn OutputWriter>) as WriterFactory,
+        );
+        RwLock::new(writers)
+    })
+}
+
+/// Registers a writer under `name`, replacing any writer of that name.
+pub fn register_output_writer<F, W>(name: &str, factory: F)
+where
+    F: Fn() -> W + Send + Sync + 'static,
+    W: OutputWriter + 'static,
+{
+    let factory: WriterFactory = Arc::new(move || Box::new(factory()));
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), factory);
+}
+
+/// A new writer for the format called `name`.
+pub fn output_writer(name: &str) -> Result<Box<dyn OutputWriter>> {
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    match registry.get(name) {
+        Some(factory) => Ok(factory()),
+        None => Err(TextFsmError::ParseError(format!(
+            "unknown output writer '{}', expected one of: {}",
+            name,
+            registry.keys().cloned().collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+/// The names of all registered writers, sorted.
+pub fn output_writer_names() -> Vec<String> {
+    registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// What a plugin's entry point registers its writers with.
+#[cfg(feature = "plugins")]
+#[derive(Default)]
+pub struct PluginRegistrar {
+    writers: Vec<(String, WriterFactory)>,
+}
+
+#[cfg(feature = "plugins")]
+impl PluginRegistrar {
+    /// Offers a writer under `name`.
+    pub fn output_writer<F, W>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> W + Send + Sync + 'static,
+        W: OutputWriter + 'static,
+    {
+        self.writers
+            .push((name.to_string(), Arc::new(move || Box::new(factory()))));
+    }
+}
+
+/// The symbol [`load_plugin`] calls, with the signature of [`PluginEntry`].
+#[cfg(feature = "plugins")]
+pub const PLUGIN_ENTRY: &str = "asyncfsm_register_plugin";
+
+/// A plugin's entry point, exported as
+/// `#[no_mangle] pub fn asyncfsm_register_plugin(registrar: &mut PluginRegistrar)`.
+#[cfg(feature = "plugins")]
+pub type PluginEntry = fn(&mut PluginRegistrar);
+
+/// Loads a shared library and registers the writers its entry point offers.
+///
+/// The entry point uses the Rust ABI, so the plugin has to be built with the same
+/// compiler and the same version of this crate as the program loading it.
+///
+/// # Safety
+///
+/// Loading runs the library's initialisers and its entry point, which can do
+/// anything; only load plugins you trust. The library stays loaded for the rest of
+/// the process.
+#[cfg(feature = "plugins")]
+pub unsafe fn load_plugin<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let library =
+        libloading::Library::new(path).map_err(|e| TextFsmError::PluginError(e.to_string()))?;
+    let entry = *library
+        .get::<PluginEntry>(PLUGIN_ENTRY.as_bytes())
+        .map_err(|e| TextFsmError::PluginError(format!("{}: {}", path.display(), e)))?;
+    let mut registrar = PluginRegistrar::default();
+    entry(&mut registrar);
+    // Writers point into the library's code, so it must never be unloaded.
+    std::mem::forget(library);
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    Ok(registrar
+        .writers
+        .into_iter()
+        .map(|(name, factory)| {
+            registry.insert(name.clone(), factory);
+            name
+        })
+        .collect())
+}
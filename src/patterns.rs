@@ -0,0 +1,93 @@
+//! Regexes for the values network output is full of.
+//!
+//! Each pattern is a bare regex with no capturing groups, safe to embed anywhere in a
+//! larger one. It does not anchor itself, so `IPV4` matches the start of
+//! `10.0.0.300`, where it takes `10.0.0.30`; follow it with `\b`, `\s` or `$` where
+//! that matters.
+//!
+//! Templates reach the same patterns through the built-in macros listed in
+//! [`PATTERNS`], e.g. `Value ADDRESS (${_IPV4_PREFIX_})`.
+//!
+//! ```
+//! use asyncfsm::patterns;
+//!
+//! let re = regex::Regex::new(&format!("^{}$", patterns::MAC)).unwrap();
+//! assert!(re.is_match("0011.2233.4455"));
+//! assert!(re.is_match("00:11:22:33:44:55"));
+//! assert!(!re.is_match("00:11:22:33:44"));
+//! ```
+
+macro_rules! ipv4 {
+    () => {
+        r"(?:25[0-5]|2[0-4]\d|1?\d?\d)(?:\.(?:25[0-5]|2[0-4]\d|1?\d?\d)){3}"
+    };
+}
+
+macro_rules! ipv6 {
+    () => {
+        concat!(
+            r"(?:[0-9A-Fa-f]{0,4}:){2,7}(?:",
+            ipv4!(),
+            r"|[0-9A-Fa-f]{0,4})"
+        )
+    };
+}
+
+macro_rules! vlan {
+    () => {
+        r"(?:409[0-4]|40[0-8]\d|[1-3]\d{3}|[1-9]\d{0,2})"
+    };
+}
+
+/// A dotted-quad IPv4 address, each octet 0-255: `10.0.0.1`.
+pub const IPV4: &str = ipv4!();
+
+/// An IPv4 address with a prefix length: `10.0.0.0/8`.
+pub const IPV4_PREFIX: &str = concat!(ipv4!(), r"/(?:3[0-2]|[12]?\d)");
+
+/// An IPv6 address, loosely: `2001:db8::1`, `::` and `::ffff:10.0.0.1` match, and so
+/// do a few strings that are not addresses.
+pub const IPV6: &str = ipv6!();
+
+/// An IPv6 address with a prefix length: `2001:db8::/32`.
+pub const IPV6_PREFIX: &str = concat!(ipv6!(), r"/(?:12[0-8]|1[01]\d|[1-9]?\d)");
+
+/// A MAC address: `00:11:22:33:44:55`, `00-11-22-33-44-55` or `0011.2233.4455`.
+pub const MAC: &str =
+    r"(?:[0-9A-Fa-f]{2}(?:[:-][0-9A-Fa-f]{2}){5}|[0-9A-Fa-f]{4}(?:\.[0-9A-Fa-f]{4}){2})";
+
+/// A VLAN ID, 1-4094.
+pub const VLAN: &str = vlan!();
+
+/// A list of VLAN IDs and ranges as switches print them: `1,10-20,4094`.
+pub const VLAN_RANGE: &str = concat!(
+    vlan!(),
+    "(?:-",
+    vlan!(),
+    ")?(?:,",
+    vlan!(),
+    "(?:-",
+    vlan!(),
+    ")?)*"
+);
+
+/// An interface name, full or abbreviated: `GigabitEthernet0/1`, `Gi0/1.100`,
+/// `Port-channel10`, `xe-0/0/0`, `Vlan10`.
+pub const INTERFACE: &str = r"[A-Za-z][A-Za-z-]*\d+(?:[/:.]\d+)*";
+
+/// Every pattern with the name of the built-in macro that expands to it.
+pub const PATTERNS: &[(&str, &str)] = &[
+    ("_IPV4_", IPV4),
+    ("_IPV4_PREFIX_", IPV4_PREFIX),
+    ("_IPV6_", IPV6),
+    ("_IPV6_PREFIX_", IPV6_PREFIX),
+    ("_MAC_", MAC),
+    ("_VLAN_", VLAN),
+    ("_VLAN_RANGE_", VLAN_RANGE),
+    ("_INTERFACE_", INTERFACE),
+];
+
+/// The pattern the built-in macro `name` expands to.
+pub fn by_macro_name(name: &str) -> Option<&'static str> {
+    PATTERNS.iter().find(|(n, _)| *n == name).map(|(_, p)| *p)
+}
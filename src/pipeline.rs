@@ -0,0 +1,263 @@
+//! Parsing the sections of a document with further templates.
+//!
+//! A [`Pipeline`] parses a document with one template, then parses the text captured in
+//! some of its values with other templates. A section is a `Single` value's text or, for
+//! a `List` value, its items as lines, which is how a template usually collects a block
+//! such as the body of one interface. The section's value is replaced by the records
+//! parsed from it, so the output nests instead of repeating the parent's fields with
+//! `Filldown`. A section's template can be a pipeline of its own.
+//!
+//! A one-template parse can nest too. A [`Nesting`] groups flat records by their parent
+//! fields, typically the `Filldown` ones, which a template can declare in its comment
+//! header: `# Nest: VRF -> NEIGHBORS; NEIGHBOR, REMOTE_AS -> PREFIXES` puts one record
+//! per VRF with its neighbors under `NEIGHBORS`, and each neighbor's remaining fields
+//! under `PREFIXES`.
+//!
+//! ```
+//! use asyncfsm::pipeline::{NestedValue, Pipeline};
+//! use asyncfsm::Template;
+//!
+//! let blocks = Template::new(
+//!     "Value NAME (\\S+)\nValue List BODY (.+)\n\nStart\n  ^interface -> Continue.Record\n  ^interface ${NAME}\n  ^ ${BODY}\n",
+//! )?;
+//! let body = Template::new("Value IP (\\S+)\n\nStart\n  ^ip address ${IP} -> Record\n")?;
+//! let pipeline = Pipeline::new(blocks).section("BODY", body);
+//!
+//! let input = "interface Gi1\n ip address 10.0.0.1\n ip address 10.0.0.2\ninterface Gi2\n";
+//! let records = pipeline.parse_string(input)?;
+//! assert_eq!(records.len(), 2);
+//! let Some(NestedValue::Records(addresses)) = records[0].get("BODY") else { panic!() };
+//! assert_eq!(addresses.len(), 2);
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecord, Records, Result, Template, TextFSMParser, TextFsmError, Value};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::path::Path;
+
+/// A value of a [`NestedRecord`]: captured text, or the records parsed from a section.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(untagged)]
+pub enum NestedValue {
+    Value(Value),
+    Records(Vec<NestedRecord>),
+}
+
+/// A record whose section values have been replaced by the records parsed from them.
+///
+/// Fields keep the template's declaration order, followed by any fields the template
+/// does not declare, sorted by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NestedRecord {
+    pub fields: Vec<(String, NestedValue)>,
+}
+
+impl NestedRecord {
+    /// The value named `name`.
+    pub fn get(&self, name: &str) -> Option<&NestedValue> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    fn from_record(record: DataRecord, schema: &[String]) -> Self {
+        let mut fields = record.fields;
+        let mut extra: Vec<String> = fields
+            .keys()
+            .filter(|k| !schema.contains(k))
+            .cloned()
+            .collect();
+        extra.sort();
+        NestedRecord {
+            fields: schema
+                .iter()
+                .chain(&extra)
+                .filter_map(|name| {
+                    let value = fields.remove(name)?;
+                    Some((name.clone(), NestedValue::Value(value)))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Serialize for NestedRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for (name, value) in &self.fields {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// A template whose records have sections parsed by further templates.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    template: Template,
+    sections: Vec<(String, Pipeline)>,
+}
+
+impl Pipeline {
+    /// A pipeline that parses documents with `template`.
+    pub fn new(template: Template) -> Self {
+        Pipeline {
+            template,
+            sections: vec![],
+        }
+    }
+
+    /// Parses the text of value `name` with `stage`, a [`Template`] or a [`Pipeline`].
+    pub fn section(mut self, name: impl Into<String>, stage: impl Into<Pipeline>) -> Self {
+        self.sections.push((name.into(), stage.into()));
+        self
+    }
+
+    /// The template the document is parsed with.
+    pub fn template(&self) -> &Template {
+        &self.template
+    }
+
+    /// Parses `input` and then each record's sections.
+    pub fn parse_string(&self, input: &str) -> Result<Vec<NestedRecord>> {
+        let records = self.template.parse_records(input, None)?;
+        let schema = records.schema().to_vec();
+        records
+            .into_vec()
+            .into_iter()
+            .map(|record| {
+                let mut nested = NestedRecord::from_record(record, &schema);
+                for (name, stage) in &self.sections {
+                    let Some((_, value)) = nested.fields.iter_mut().find(|(n, _)| n == name) else {
+                        continue;
+                    };
+                    let text = match value {
+                        NestedValue::Value(Value::Single(text)) => format!("{}\n", text),
+                        NestedValue::Value(Value::List(lines)) => {
+                            lines.iter().map(|l| format!("{}\n", l)).collect()
+                        }
+                        NestedValue::Records(_) => continue,
+                    };
+                    *value = NestedValue::Records(stage.parse_string(&text)?);
+                }
+                Ok(nested)
+            })
+            .collect()
+    }
+
+    /// Reads and parses a file.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<NestedRecord>> {
+        let input = crate::encoding::read_file(path, self.template.options())?;
+        self.parse_string(&input)
+    }
+}
+
+impl From<Template> for Pipeline {
+    fn from(template: Template) -> Self {
+        Pipeline::new(template)
+    }
+}
+
+/// Parent/child levels for turning flat records into nested ones.
+///
+/// Each level names the fields that identify a parent and the field its children go
+/// under. Records with the same parent values are grouped in order of first appearance;
+/// the fields no level names form the innermost records. Field names match
+/// case-insensitively, so lowercased records nest the same way.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Nesting {
+    levels: Vec<(Vec<String>, String)>,
+}
+
+impl Nesting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a level below the existing ones.
+    pub fn level<S: Into<String>>(
+        mut self,
+        fields: impl IntoIterator<Item = S>,
+        children: impl Into<String>,
+    ) -> Self {
+        self.levels.push((
+            fields.into_iter().map(Into::into).collect(),
+            children.into(),
+        ));
+        self
+    }
+
+    /// Reads levels written as `VRF -> NEIGHBORS; NEIGHBOR, REMOTE_AS -> PREFIXES`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid =
+            |why: &str| TextFsmError::ParseError(format!("invalid nesting `{}`: {}", spec, why));
+        let mut nesting = Nesting::new();
+        for level in spec.split(';').map(str::trim).filter(|l| !l.is_empty()) {
+            let (fields, children) = level
+                .split_once("->")
+                .ok_or_else(|| invalid("each level needs `FIELDS -> NAME`"))?;
+            let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+            let children = children.trim();
+            if fields.iter().any(|f| f.is_empty()) || children.is_empty() {
+                return Err(invalid("empty field or level name"));
+            }
+            nesting = nesting.level(fields, children);
+        }
+        if nesting.levels.is_empty() {
+            return Err(invalid("no levels"));
+        }
+        Ok(nesting)
+    }
+
+    /// The nesting declared by a `# Nest:` line in the template's comment header.
+    pub fn from_template(parser: &TextFSMParser) -> Option<Result<Self>> {
+        parser.metadata().get("Nest").map(|spec| Self::parse(spec))
+    }
+
+    /// Nests `records`, whose fields are ordered by their schema.
+    pub fn apply(&self, records: &Records) -> Vec<NestedRecord> {
+        let rows = records
+            .iter()
+            .map(|record| NestedRecord::from_record(record.clone(), records.schema()))
+            .collect();
+        group(rows, &self.levels)
+    }
+}
+
+fn is_empty(value: &NestedValue) -> bool {
+    match value {
+        NestedValue::Value(Value::Single(s)) => s.is_empty(),
+        NestedValue::Value(Value::List(items)) => items.is_empty(),
+        NestedValue::Records(records) => records.is_empty(),
+    }
+}
+
+fn group(rows: Vec<NestedRecord>, levels: &[(Vec<String>, String)]) -> Vec<NestedRecord> {
+    let Some(((fields, children), inner)) = levels.split_first() else {
+        // A record only carrying parent fields has no child of its own.
+        return rows
+            .into_iter()
+            .filter(|row| !row.fields.iter().all(|(_, v)| is_empty(v)))
+            .collect();
+    };
+    let mut groups: Vec<(NestedRecord, Vec<NestedRecord>)> = vec![];
+    for row in rows {
+        let (parent, rest): (Vec<_>, Vec<_>) = row
+            .fields
+            .into_iter()
+            .partition(|(name, _)| fields.iter().any(|f| f.eq_ignore_ascii_case(name)));
+        let child = NestedRecord { fields: rest };
+        match groups.iter_mut().find(|(p, _)| p.fields == parent) {
+            Some((_, members)) => members.push(child),
+            None => groups.push((NestedRecord { fields: parent }, vec![child])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(mut parent, members)| {
+            parent.fields.push((
+                children.clone(),
+                NestedValue::Records(group(members, inner)),
+            ));
+            parent
+        })
+        .collect()
+}
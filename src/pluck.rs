@@ -0,0 +1,172 @@
+//! Picking fields out of records with a small path language.
+//!
+//! Paths look like `records[*].INTERFACE`:
+//!
+//! * `records` names the record list and may be omitted;
+//! * `[*]` selects every record, `[2]` the third, and `[?STATUS==down]` or
+//!   `[?STATUS!=up]` the records whose value compares as given;
+//! * `.NAME` (or a bare `NAME` at the start) selects a value of each record;
+//! * after a value, `[*]` and `[N]` select the elements of a `List` value.
+//!
+//! So `records[?STATUS==down].INTERFACE` lists the interfaces that are down.
+
+use crate::{DataRecord, Result, TextFsmError, Value};
+use serde::Serialize;
+
+/// Something selected by a path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Plucked<'a> {
+    /// A whole record, when the path selects no value.
+    Record(&'a DataRecord),
+    /// A value of a record.
+    Value(&'a Value),
+    /// One element of a `List` value.
+    Item(&'a str),
+}
+
+impl Plucked<'_> {
+    /// The selected text, for single values and list elements.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Plucked::Value(Value::Single(s)) => Some(s),
+            Plucked::Item(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    All,
+    Index(usize),
+    Filter {
+        field: String,
+        value: String,
+        equal: bool,
+    },
+    Field(String),
+}
+
+fn invalid(path: &str, why: &str) -> TextFsmError {
+    TextFsmError::ParseError(format!("invalid path `{}`: {}", path, why))
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+    let mut rest = path.trim();
+    rest = rest.strip_prefix("records").unwrap_or(rest);
+    let mut segments = vec![];
+    let mut first = true;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| invalid(path, "missing `]`"))?;
+            let inner = after[..end].trim();
+            segments.push(if inner == "*" {
+                Segment::All
+            } else if let Some(filter) = inner.strip_prefix('?') {
+                let (field, value, equal) = if let Some((f, v)) = filter.split_once("!=") {
+                    (f, v, false)
+                } else if let Some((f, v)) = filter.split_once("==") {
+                    (f, v, true)
+                } else {
+                    return Err(invalid(path, "filters need `==` or `!=`"));
+                };
+                Segment::Filter {
+                    field: field.trim().to_string(),
+                    value: value.trim().trim_matches(['\'', '"']).to_string(),
+                    equal,
+                }
+            } else {
+                Segment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| invalid(path, "index must be `*` or a number"))?,
+                )
+            });
+            rest = &after[end + 1..];
+        } else {
+            let name = match rest.strip_prefix('.') {
+                Some(name) => name,
+                None if first => rest,
+                None => return Err(invalid(path, "expected `.` or `[`")),
+            };
+            let end = name.find(['.', '[']).unwrap_or(name.len());
+            if end == 0 {
+                return Err(invalid(path, "empty field name"));
+            }
+            segments.push(Segment::Field(name[..end].to_string()));
+            rest = &name[end..];
+        }
+        first = false;
+    }
+    Ok(segments)
+}
+
+/// Selects the parts of `records` that `path` names.
+pub fn pluck<'a>(records: &'a [DataRecord], path: &str) -> Result<Vec<Plucked<'a>>> {
+    let mut selected: Vec<Plucked<'a>> = records.iter().map(Plucked::Record).collect();
+    // Indexes apply to the record list until a field has been selected.
+    let mut at_records = true;
+    for segment in parse_path(path)? {
+        selected = match segment {
+            Segment::All if at_records => selected,
+            Segment::Index(i) if at_records => selected.into_iter().skip(i).take(1).collect(),
+            Segment::Filter {
+                field,
+                value,
+                equal,
+            } => {
+                if !at_records {
+                    return Err(invalid(path, "filters apply to records only"));
+                }
+                selected
+                    .into_iter()
+                    .filter(|p| match p {
+                        Plucked::Record(rec) => {
+                            let matches = match rec.get(&field) {
+                                Some(Value::Single(s)) => *s == value,
+                                Some(Value::List(items)) => items.contains(&value),
+                                None => false,
+                            };
+                            matches == equal
+                        }
+                        _ => false,
+                    })
+                    .collect()
+            }
+            Segment::Field(name) => {
+                if !at_records {
+                    return Err(invalid(path, "values have no fields"));
+                }
+                at_records = false;
+                selected
+                    .into_iter()
+                    .filter_map(|p| match p {
+                        Plucked::Record(rec) => rec.get(&name).map(Plucked::Value),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            Segment::All => selected
+                .into_iter()
+                .flat_map(|p| match p {
+                    Plucked::Value(Value::List(items)) => {
+                        items.iter().map(|s| Plucked::Item(s)).collect()
+                    }
+                    other => vec![other],
+                })
+                .collect(),
+            Segment::Index(i) => selected
+                .into_iter()
+                .filter_map(|p| match p {
+                    Plucked::Value(Value::List(items)) => items.get(i).map(|s| Plucked::Item(s)),
+                    other if i == 0 => Some(other),
+                    _ => None,
+                })
+                .collect(),
+        };
+    }
+    Ok(selected)
+}
@@ -0,0 +1,132 @@
+use crate::varsubst::{ParseChunk, VariableParser};
+use crate::{DataRecord, Result, TextFsmError};
+
+/// A `${FIELD}` template expanded against the fields of a record.
+///
+/// Used to derive message topics and keys, e.g. `interfaces.${HOSTNAME}`.
+/// Fields missing from the record expand to an empty string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTemplate {
+    chunks: Vec<ParseChunk>,
+}
+
+impl FieldTemplate {
+    /// Parses a template string containing `$FIELD` or `${FIELD}` references.
+    pub fn new(template: &str) -> Result<Self> {
+        let chunks = VariableParser::parse_dollar_string(template)
+            .map_err(|e| TextFsmError::ParseError(e.to_string()))?;
+        Ok(FieldTemplate { chunks })
+    }
+
+    /// Expands the template with the values of `rec`.
+    pub fn render(&self, rec: &DataRecord) -> String {
+        let mut out = String::new();
+        for chunk in &self.chunks {
+            match chunk {
+                ParseChunk::DollarDollar => out.push('$'),
+                ParseChunk::Text(s) => out.push_str(s),
+                ParseChunk::Variable(name) => {
+                    if let Some(v) = rec.get(name) {
+                        out.push_str(&v.to_string());
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A transport that delivers encoded records to an event bus (Kafka, AMQP, ...).
+pub trait Publisher {
+    /// Sends one message. `key` is `None` when no key template is configured.
+    fn publish(&mut self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<()>;
+
+    /// Flushes any buffered messages.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Publishes each emitted `DataRecord` as JSON through a `Publisher`.
+#[cfg(feature = "json")]
+pub struct RecordPublisher<P> {
+    publisher: P,
+    topic: FieldTemplate,
+    key: Option<FieldTemplate>,
+}
+
+#[cfg(feature = "json")]
+impl<P: Publisher> RecordPublisher<P> {
+    /// Creates a sink publishing to the topic given by `topic_template`.
+    pub fn new(publisher: P, topic_template: &str) -> Result<Self> {
+        Ok(RecordPublisher {
+            publisher,
+            topic: FieldTemplate::new(topic_template)?,
+            key: None,
+        })
+    }
+
+    /// Derives each message key from the record with `key_template`.
+    pub fn with_key(mut self, key_template: &str) -> Result<Self> {
+        self.key = Some(FieldTemplate::new(key_template)?);
+        Ok(self)
+    }
+
+    /// Encodes and publishes a single record.
+    pub fn send(&mut self, rec: &DataRecord) -> Result<()> {
+        let payload =
+            serde_json::to_vec(rec).map_err(|e| TextFsmError::InternalError(e.to_string()))?;
+        let topic = self.topic.render(rec);
+        let key = self.key.as_ref().map(|k| k.render(rec));
+        self.publisher.publish(&topic, key.as_deref(), &payload)
+    }
+
+    /// Publishes every record yielded by `records`, e.g. a `TextFsmIter`, then flushes.
+    ///
+    /// Returns the number of records published.
+    pub fn send_all<I>(&mut self, records: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = Result<DataRecord>>,
+    {
+        let mut count = 0;
+        for rec in records {
+            self.send(&rec?)?;
+            count += 1;
+        }
+        self.publisher.flush()?;
+        Ok(count)
+    }
+
+    /// Returns the underlying publisher.
+    pub fn into_inner(self) -> P {
+        self.publisher
+    }
+}
+
+/// A `Publisher` backed by a Kafka producer.
+#[cfg(feature = "kafka")]
+pub struct KafkaPublisher {
+    producer: kafka::producer::Producer,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaPublisher {
+    /// Connects to the given bootstrap brokers (e.g. `localhost:9092`).
+    pub fn connect(hosts: Vec<String>) -> Result<Self> {
+        let producer = kafka::producer::Producer::from_hosts(hosts)
+            .with_required_acks(kafka::producer::RequiredAcks::One)
+            .create()
+            .map_err(|e| TextFsmError::InternalError(e.to_string()))?;
+        Ok(KafkaPublisher { producer })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl Publisher for KafkaPublisher {
+    fn publish(&mut self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<()> {
+        let record = kafka::producer::Record::from_key_value(topic, key.unwrap_or(""), payload);
+        self.producer
+            .send(&record)
+            .map_err(|e| TextFsmError::InternalError(e.to_string()))
+    }
+}
@@ -1,12 +1,20 @@
-use serde::{Deserialize, Serialize};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::{Deref, DerefMut, Index, IndexMut};
 
 /// Transformation options for extracted records.
 #[derive(Debug, Clone)]
 pub enum DataRecordConversion {
     /// Convert all field names to lowercase.
     LowercaseKeys,
+    /// Rewrite interface names to one form, e.g. `Gi0/1` to `GigabitEthernet0/1`.
+    NormalizeInterfaces(crate::InterfaceNames),
+    /// Rewrite MAC and IPv6 addresses in chosen fields to one form.
+    NormalizeAddresses(crate::AddressFormats),
+    /// Rewrite chosen fields to the numbers they stand for, e.g. `1w2d` to seconds.
+    Coerce(crate::Coercions),
 }
 
 /// Represents a single row of extracted data from a TextFSM template.
@@ -18,6 +26,28 @@ pub struct DataRecord {
     /// An optional key used to identify the record, constructed from fields marked as 'Key'.
     #[serde(skip_deserializing)]
     pub record_key: Option<String>,
+    /// The input lines the record was parsed from, when `TextFsmOptions::with_raw` is set.
+    #[serde(rename = "_raw", default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawText>,
+    /// The 0-based position of the record among those the engine emitted, when
+    /// `TextFsmOptions::with_ids` is set.
+    #[serde(rename = "_index", default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// A hash of the record's fields, equal for records with equal content, when
+    /// `TextFsmOptions::with_ids` is set; see [`DataRecord::content_id`].
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+/// A block of input lines, as attached to records by `TextFsmOptions::with_raw`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RawText {
+    /// The 1-based number of the first line of the block.
+    pub first_line: usize,
+    /// The 1-based number of the last line of the block.
+    pub last_line: usize,
+    /// The lines themselves, each followed by a newline.
+    pub text: String,
 }
 
 impl DataRecord {
@@ -92,6 +122,8 @@ impl DataRecord {
     }
 
     /// Appends a `Value` to the record.
+    ///
+    /// Appending a list to a single value promotes it to a list holding both.
     pub fn append_value(&mut self, name: String, value: Value) {
         if let Some(old_value) = self.fields.get_mut(&name) {
             match old_value {
@@ -100,19 +132,17 @@ impl DataRecord {
                         *old_value = Value::Single(val);
                     }
                     Value::List(lst) => {
-                        panic!(
-                            "can not append list {:?} to single {:?} in var {}",
-                            &lst, &old_str_ref, &name
-                        );
+                        let mut promoted = vec![std::mem::take(old_str_ref)];
+                        promoted.extend(lst);
+                        *old_value = Value::List(promoted);
                     }
                 },
                 Value::List(list) => match value {
                     Value::Single(val) => {
                         list.push(val);
                     }
-                    Value::List(mut l) => {
-                        l.extend(list.iter().cloned());
-                        *old_value = Value::List(l);
+                    Value::List(l) => {
+                        list.extend(l);
                     }
                 },
             }
@@ -121,6 +151,39 @@ impl DataRecord {
         }
     }
 
+    /// A stable identifier for the record's content: 16 hex digits of a 64-bit FNV-1a
+    /// hash over the fields in name order.
+    ///
+    /// It does not depend on field order, the record key or the Rust version, so it can be
+    /// stored and compared across runs, e.g. as an upsert key.
+    pub fn content_id(&self) -> String {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for b in bytes {
+                hash = (hash ^ u64::from(*b)).wrapping_mul(PRIME);
+            }
+        };
+        let mut names: Vec<&String> = self.fields.keys().collect();
+        names.sort();
+        for name in names {
+            feed(name.as_bytes());
+            feed(&[0x1f]);
+            match &self.fields[name] {
+                Value::Single(s) => feed(s.as_bytes()),
+                Value::List(items) => {
+                    for item in items {
+                        feed(item.as_bytes());
+                        feed(&[0x1e]);
+                    }
+                }
+            }
+            feed(&[0x1d]);
+        }
+        format!("{:016x}", hash)
+    }
+
     /// Removes a field from the record.
     pub fn remove(&mut self, key: &str) {
         self.fields.remove(key);
@@ -142,6 +205,219 @@ impl DataRecord {
     }
 }
 
+/// The records produced by a parse, together with the template's value names.
+///
+/// Iterating, indexing and the slice methods (through `Deref`) behave like the
+/// `Vec<DataRecord>` earlier versions returned; code that needs the vector itself can
+/// call [`Records::into_vec`]. Serialization writes each record's fields in template
+/// declaration order rather than hash order, followed by any fields the template
+/// does not declare, sorted by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Records {
+    records: Vec<DataRecord>,
+    schema: Vec<String>,
+}
+
+impl Records {
+    /// Wraps `records` parsed with a template declaring the values `schema`, in order.
+    pub fn new(records: Vec<DataRecord>, schema: Vec<String>) -> Self {
+        Records { records, schema }
+    }
+
+    /// The template's value names, in declaration order; empty when unknown.
+    pub fn schema(&self) -> &[String] {
+        &self.schema
+    }
+
+    /// Unwraps the records.
+    pub fn into_vec(self) -> Vec<DataRecord> {
+        self.records
+    }
+
+    /// Puts the records in the order of the input lines they start on, by
+    /// [`raw`](DataRecord::raw). The sort is stable, so records starting on one line keep
+    /// their order; records without `raw` count as starting on line 0.
+    pub fn sort_by_line(&mut self) {
+        self.records
+            .sort_by_key(|record| record.raw.as_ref().map_or(0, |raw| raw.first_line));
+    }
+
+    /// Selects fields with a path such as `records[*].INTERFACE`; see [`crate::pluck`].
+    pub fn pluck(&self, path: &str) -> crate::Result<Vec<crate::Plucked<'_>>> {
+        crate::pluck(&self.records, path)
+    }
+
+    /// Applies `conversion` to every record and to the schema.
+    pub fn convert(self, conversion: Option<DataRecordConversion>) -> Self {
+        match conversion {
+            None => self,
+            Some(DataRecordConversion::LowercaseKeys) => Records {
+                records: self
+                    .records
+                    .into_iter()
+                    .map(|rec| DataRecord {
+                        fields: rec
+                            .fields
+                            .into_iter()
+                            .map(|(k, v)| (k.to_lowercase(), v))
+                            .collect(),
+                        record_key: rec.record_key,
+                        raw: rec.raw,
+                        index: rec.index,
+                        id: rec.id,
+                    })
+                    .collect(),
+                schema: self.schema.iter().map(|n| n.to_lowercase()).collect(),
+            },
+            Some(DataRecordConversion::NormalizeInterfaces(names)) => {
+                let mut records = self;
+                for record in records.records.iter_mut() {
+                    names.apply(record);
+                }
+                records
+            }
+            Some(DataRecordConversion::NormalizeAddresses(formats)) => {
+                let mut records = self;
+                for record in records.records.iter_mut() {
+                    formats.apply(record);
+                }
+                records
+            }
+            Some(DataRecordConversion::Coerce(coercions)) => {
+                let mut records = self;
+                for record in records.records.iter_mut() {
+                    coercions.apply(record);
+                }
+                records
+            }
+        }
+    }
+}
+
+impl Deref for Records {
+    type Target = [DataRecord];
+
+    fn deref(&self) -> &[DataRecord] {
+        &self.records
+    }
+}
+
+impl DerefMut for Records {
+    fn deref_mut(&mut self) -> &mut [DataRecord] {
+        &mut self.records
+    }
+}
+
+impl Index<usize> for Records {
+    type Output = DataRecord;
+
+    fn index(&self, index: usize) -> &DataRecord {
+        &self.records[index]
+    }
+}
+
+impl IndexMut<usize> for Records {
+    fn index_mut(&mut self, index: usize) -> &mut DataRecord {
+        &mut self.records[index]
+    }
+}
+
+impl IntoIterator for Records {
+    type Item = DataRecord;
+    type IntoIter = std::vec::IntoIter<DataRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Records {
+    type Item = &'a DataRecord;
+    type IntoIter = std::slice::Iter<'a, DataRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.iter()
+    }
+}
+
+impl From<Vec<DataRecord>> for Records {
+    fn from(records: Vec<DataRecord>) -> Self {
+        Records {
+            records,
+            schema: vec![],
+        }
+    }
+}
+
+impl From<Records> for Vec<DataRecord> {
+    fn from(records: Records) -> Self {
+        records.records
+    }
+}
+
+impl PartialEq<Vec<DataRecord>> for Records {
+    fn eq(&self, other: &Vec<DataRecord>) -> bool {
+        &self.records == other
+    }
+}
+
+/// One record serialized with its fields in schema order.
+pub(crate) struct OrderedRecord<'a> {
+    pub(crate) record: &'a DataRecord,
+    pub(crate) schema: &'a [String],
+}
+
+impl Serialize for OrderedRecord<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = &self.record.fields;
+        let mut extra: Vec<&String> = fields.keys().filter(|k| !self.schema.contains(k)).collect();
+        extra.sort();
+        let record = self.record;
+        // The fields, `record_key`, and whichever of `_raw`, `_index` and `_id` are set.
+        let len = fields.len()
+            + 1
+            + usize::from(record.raw.is_some())
+            + usize::from(record.index.is_some())
+            + usize::from(record.id.is_some());
+        let mut map = serializer.serialize_map(Some(len))?;
+        for name in self.schema.iter().chain(extra) {
+            if let Some(value) = fields.get(name) {
+                map.serialize_entry(name, value)?;
+            }
+        }
+        map.serialize_entry("record_key", &self.record.record_key)?;
+        if let Some(raw) = &self.record.raw {
+            map.serialize_entry("_raw", raw)?;
+        }
+        if let Some(index) = self.record.index {
+            map.serialize_entry("_index", &index)?;
+        }
+        if let Some(id) = &self.record.id {
+            map.serialize_entry("_id", id)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Records {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.records.len()))?;
+        for record in &self.records {
+            seq.serialize_element(&OrderedRecord {
+                record,
+                schema: &self.schema,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Records {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<DataRecord>::deserialize(deserializer).map(Records::from)
+    }
+}
+
 /// Represents an extracted value, which can be either a single string or a list of strings.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -159,4 +435,4 @@ impl fmt::Display for Value {
             Value::List(l) => write!(f, "{:?}", l),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,400 @@
+//! Fetching template collections from Git.
+//!
+//! Deployments can point at a template repository instead of vendoring its files:
+//!
+//! ```no_run
+//! # use asyncfsm::CliTable;
+//! let table = CliTable::from_git("https://github.com/networktocode/ntc-templates", "v5.1.0")?;
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+//!
+//! A checkout is made with the `git` command into a cache directory, one directory
+//! per URL and ref, and reused on later loads. Pin a [`checksum`] with
+//! [`GitSource::checksum`] to make sure the templates are the ones that were reviewed.
+//!
+//! Organizations with templates in several places can combine them in a
+//! [`TemplateRegistry`], which looks templates up by platform, command and version.
+
+use crate::cli_table::ParsedCliTable;
+use crate::{CliTable, Records, Result, TextFSM, TextFSMParser, TextFsmError};
+use fancy_regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+/// Index locations tried, in order, when none is configured.
+const INDEX_CANDIDATES: &[&str] = &["ntc_templates/templates/index", "templates/index", "index"];
+
+/// A template repository at a given branch or tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSource {
+    url: String,
+    reference: String,
+    cache_dir: Option<PathBuf>,
+    checksum: Option<String>,
+    index: Option<PathBuf>,
+}
+
+impl GitSource {
+    /// A repository `url` at branch or tag `reference`.
+    pub fn new(url: &str, reference: &str) -> Self {
+        GitSource {
+            url: url.to_string(),
+            reference: reference.to_string(),
+            cache_dir: None,
+            checksum: None,
+            index: None,
+        }
+    }
+
+    /// Keeps checkouts under `dir` instead of [`default_cache_dir`].
+    pub fn cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Requires the template directory to have this SHA-256 [`checksum`] (hex).
+    pub fn checksum(mut self, sha256: &str) -> Self {
+        self.checksum = Some(sha256.to_ascii_lowercase());
+        self
+    }
+
+    /// The index file, relative to the repository root.
+    pub fn index<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.index = Some(path.into());
+        self
+    }
+
+    /// The directory this source is checked out to.
+    pub fn checkout_dir(&self) -> PathBuf {
+        let key: String = format!("{}@{}", self.url, self.reference)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.cache_dir
+            .clone()
+            .unwrap_or_else(default_cache_dir)
+            .join(key)
+    }
+
+    /// Checks the repository out if needed, verifies it, and returns the index path.
+    pub fn fetch(&self) -> Result<PathBuf> {
+        let dir = self.checkout_dir();
+        if !dir.join(".git").is_dir() {
+            self.clone_into(&dir)?;
+        } else {
+            debug!("Using cached checkout {}", dir.display());
+        }
+        let index = match &self.index {
+            Some(index) => dir.join(index),
+            None => INDEX_CANDIDATES
+                .iter()
+                .map(|candidate| dir.join(candidate))
+                .find(|path| path.is_file())
+                .ok_or_else(|| {
+                    TextFsmError::RegistryError(format!("no index file in {}", self.url))
+                })?,
+        };
+        if let Some(expected) = &self.checksum {
+            let actual = checksum(index.parent().unwrap_or(&dir))?;
+            if &actual != expected {
+                return Err(TextFsmError::RegistryError(format!(
+                    "checksum mismatch for {}@{}: expected {}, got {}",
+                    self.url, self.reference, expected, actual
+                )));
+            }
+        }
+        Ok(index)
+    }
+
+    /// Fetches the repository and loads its index.
+    pub fn load(&self) -> Result<CliTable> {
+        CliTable::from_file(self.fetch()?)
+    }
+
+    fn clone_into(&self, dir: &Path) -> Result<()> {
+        debug!(
+            "Cloning {}@{} into {}",
+            self.url,
+            self.reference,
+            dir.display()
+        );
+        if let Some(parent) = dir.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Clone next to the final directory and rename, so an interrupted clone is
+        // never mistaken for a complete one.
+        let partial = dir.with_extension("partial");
+        let _ = std::fs::remove_dir_all(&partial);
+        let output = Command::new("git")
+            .args(["clone", "--quiet", "--depth", "1", "--branch"])
+            .arg(&self.reference)
+            .arg(&self.url)
+            .arg(&partial)
+            .output()?;
+        if !output.status.success() {
+            let _ = std::fs::remove_dir_all(&partial);
+            return Err(TextFsmError::RegistryError(format!(
+                "git clone of {}@{} failed: {}",
+                self.url,
+                self.reference,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        std::fs::rename(&partial, dir)?;
+        Ok(())
+    }
+}
+
+/// `$XDG_CACHE_HOME/asyncfsm/templates`, falling back to `~/.cache/asyncfsm/templates`.
+pub fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("asyncfsm")
+        .join("templates")
+}
+
+/// The SHA-256 of a template directory, as lowercase hex.
+///
+/// Every regular file directly in `dir` is hashed in name order, each as its name, a
+/// NUL, its contents and another NUL. Subdirectories are not included.
+pub fn checksum(dir: &Path) -> Result<String> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    files.retain(|path| path.is_file());
+    files.sort();
+    let mut hasher = Sha256::new();
+    for path in files {
+        hasher.update(path.file_name().unwrap_or_default().as_encoded_bytes());
+        hasher.update([0]);
+        hasher.update(std::fs::read(&path)?);
+        hasher.update([0]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+impl CliTable {
+    /// Loads the index of a template repository at `reference`, checking it out into
+    /// the default cache on first use. See [`GitSource`] for more control.
+    pub fn from_git(url: &str, reference: &str) -> Result<Self> {
+        GitSource::new(url, reference).load()
+    }
+}
+
+/// The template a registry entry stands for.
+#[derive(Debug, Clone)]
+enum TemplateBody {
+    Embedded(Cow<'static, str>),
+    File(PathBuf),
+}
+
+/// One template known to a [`TemplateRegistry`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryEntry {
+    /// The platform the template is for, e.g. `cisco_ios`.
+    pub platform: String,
+    /// The command as written in the index, e.g. `sh[[ow]] ver[[sion]]`.
+    pub command: String,
+    /// The template's `# Version:` header, else the version of its source.
+    pub version: Option<String>,
+    /// The template's file name, or the name it was embedded under.
+    pub name: String,
+    /// Where the template came from: `embedded`, an index path, or `url#ref`.
+    pub source: String,
+    #[serde(skip)]
+    command_regex: Regex,
+    #[serde(skip)]
+    body: TemplateBody,
+}
+
+impl RegistryEntry {
+    fn new(
+        platform: &str,
+        command: &str,
+        name: &str,
+        source: &str,
+        version: Option<String>,
+        body: TemplateBody,
+    ) -> Result<Self> {
+        let pattern = format!("^{}$", CliTable::expand_brackets(command));
+        let command_regex =
+            Regex::new(&pattern).map_err(|e| TextFsmError::ParseError(e.to_string()))?;
+        Ok(RegistryEntry {
+            platform: platform.to_string(),
+            command: command.to_string(),
+            version,
+            name: name.to_string(),
+            source: source.to_string(),
+            command_regex,
+            body,
+        })
+    }
+
+    /// Whether this entry serves `command` (abbreviations allowed) on `platform`.
+    pub fn matches(&self, platform: &str, command: &str) -> bool {
+        self.platform == platform && self.command_regex.is_match(command).unwrap_or(false)
+    }
+
+    /// The template file, unless the template is embedded.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.body {
+            TemplateBody::File(path) => Some(path),
+            TemplateBody::Embedded(_) => None,
+        }
+    }
+
+    /// Compiles the template.
+    pub fn load(&self) -> Result<TextFSMParser> {
+        match &self.body {
+            TemplateBody::Embedded(content) => TextFSMParser::from_string(content),
+            TemplateBody::File(path) => TextFSMParser::from_file(path),
+        }
+    }
+}
+
+/// Templates from several sources, looked up by platform, command and version.
+///
+/// Sources take precedence in the order they were added: when two sources have a
+/// template for the same platform and command, the one added first is used. Asking
+/// for a specific version skips entries with other versions, so an older template can
+/// be kept around for devices that still need it.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateRegistry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl TemplateRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a template compiled into the program, e.g. with `include_str!`.
+    pub fn add_embedded(
+        &mut self,
+        platform: &str,
+        command: &str,
+        name: &str,
+        content: impl Into<Cow<'static, str>>,
+    ) -> Result<()> {
+        let content = content.into();
+        let version = TextFSMParser::parse_metadata(&content).remove("Version");
+        let body = TemplateBody::Embedded(content);
+        self.entries.push(RegistryEntry::new(
+            platform, command, name, "embedded", version, body,
+        )?);
+        Ok(())
+    }
+
+    /// Adds every template listed by the index file at `path`.
+    pub fn add_index<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.add_table(
+            &ParsedCliTable::from_file(path)?,
+            &path.display().to_string(),
+            None,
+        )
+    }
+
+    /// Fetches a template repository and adds the templates of its index. Templates
+    /// without a version header take the source's ref as their version.
+    pub fn add_git(&mut self, source: &GitSource) -> Result<()> {
+        let index = source.fetch()?;
+        let label = format!("{}#{}", source.url, source.reference);
+        self.add_table(
+            &ParsedCliTable::from_file(&index)?,
+            &label,
+            Some(&source.reference),
+        )
+    }
+
+    fn add_table(
+        &mut self,
+        table: &ParsedCliTable,
+        source: &str,
+        default_version: Option<&str>,
+    ) -> Result<()> {
+        let dir = Path::new(&table.fname)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        for row in &table.rows {
+            let Some(platform) = &row.platform else {
+                continue;
+            };
+            for name in &row.templates {
+                let path = dir.join(name);
+                let version = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|content| TextFSMParser::parse_metadata(&content).remove("Version"))
+                    .or_else(|| default_version.map(str::to_string));
+                self.entries.push(RegistryEntry::new(
+                    platform,
+                    &row.command,
+                    name,
+                    source,
+                    version,
+                    TemplateBody::File(path),
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    /// All entries, highest precedence first.
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries
+    }
+
+    /// The entries with template file or embedded name `name`.
+    pub fn by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a RegistryEntry> {
+        self.entries.iter().filter(move |entry| entry.name == name)
+    }
+
+    /// The template to use for `command` on `platform`, optionally of one `version`.
+    pub fn lookup(
+        &self,
+        platform: &str,
+        command: &str,
+        version: Option<&str>,
+    ) -> Option<&RegistryEntry> {
+        self.entries.iter().find(|entry| {
+            entry.matches(platform, command)
+                && version.is_none_or(|v| entry.version.as_deref() == Some(v))
+        })
+    }
+
+    /// Parses `input` with the template [`lookup`](Self::lookup) selects.
+    pub fn parse(
+        &self,
+        platform: &str,
+        command: &str,
+        version: Option<&str>,
+        input: &str,
+    ) -> Result<Records> {
+        let entry = self.lookup(platform, command, version).ok_or_else(|| {
+            TextFsmError::TemplateNotFound(format!(
+                "no template in registry for platform {} and command {}{}",
+                platform,
+                command,
+                version
+                    .map(|v| format!(" version {}", v))
+                    .unwrap_or_default()
+            ))
+        })?;
+        TextFSM::from_parser(entry.load()?).parse_records(input, None)
+    }
+}
@@ -0,0 +1,98 @@
+//! Regenerates text from records, the reverse of parsing.
+//!
+//! A [`LineTemplate`] is plain text with `${FIELD}` references, rendered once per
+//! record. It is meant for round trips: parse device output, edit the records, and
+//! emit configuration again, e.g.
+//!
+//! ```text
+//! interface ${INTERFACE}
+//!  description ${DESCRIPTION}
+//!  ip address ${IP_ADDRESS} ${NETMASK}
+//! ```
+//!
+//! Each template line follows two rules:
+//!
+//! * A line referencing `List` values is repeated once per list element, with all
+//!   lists on the line advancing together.
+//! * A line whose references are all missing or empty is left out, so optional
+//!   settings such as a description are only emitted when present.
+
+use crate::varsubst::{ParseChunk, VariableParser};
+use crate::{DataRecord, Result, TextFsmError, Value};
+use std::path::Path;
+
+/// A line-oriented `${FIELD}` template that turns records back into text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTemplate {
+    lines: Vec<Vec<ParseChunk>>,
+}
+
+impl LineTemplate {
+    /// Parses a template; each line may contain `$FIELD` or `${FIELD}` references.
+    pub fn new(template: &str) -> Result<Self> {
+        let lines = template
+            .lines()
+            .map(|line| {
+                VariableParser::parse_dollar_string(line)
+                    .map_err(|e| TextFsmError::ParseError(e.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(LineTemplate { lines })
+    }
+
+    /// Reads and parses a template file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(&std::fs::read_to_string(path)?)
+    }
+
+    /// Renders every record in turn and concatenates the results.
+    pub fn render(&self, records: &[DataRecord]) -> String {
+        records.iter().map(|rec| self.render_record(rec)).collect()
+    }
+
+    /// Renders the template for a single record.
+    pub fn render_record(&self, rec: &DataRecord) -> String {
+        let mut out = String::new();
+        for chunks in &self.lines {
+            let values: Vec<Option<&Value>> = chunks
+                .iter()
+                .filter_map(|chunk| match chunk {
+                    ParseChunk::Variable(name) => Some(rec.get(name)),
+                    _ => None,
+                })
+                .collect();
+            let is_blank = |v: &Option<&Value>| match v {
+                None => true,
+                Some(Value::Single(s)) => s.is_empty(),
+                Some(Value::List(items)) => items.iter().all(|s| s.is_empty()),
+            };
+            if !values.is_empty() && values.iter().all(is_blank) {
+                continue;
+            }
+            let repeats = values
+                .iter()
+                .filter_map(|v| match v {
+                    Some(Value::List(items)) => Some(items.len()),
+                    _ => None,
+                })
+                .max();
+            for index in 0..repeats.unwrap_or(1) {
+                for chunk in chunks {
+                    match chunk {
+                        ParseChunk::DollarDollar => out.push('$'),
+                        ParseChunk::Text(s) => out.push_str(s),
+                        ParseChunk::Variable(name) => match rec.get(name) {
+                            Some(Value::Single(s)) => out.push_str(s),
+                            Some(Value::List(items)) => {
+                                out.push_str(items.get(index).map_or("", |s| s.as_str()))
+                            }
+                            None => {}
+                        },
+                    }
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
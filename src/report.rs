@@ -0,0 +1,89 @@
+//! Renders parsed records through a user-supplied Handlebars template.
+//!
+//! The template sees the records as `records`, so a report can be as simple as
+//! `{{#each records}}{{INTERFACE}} {{STATUS}}\n{{/each}}`. Two helpers are registered
+//! on top of the Handlebars built-ins:
+//!
+//! * `group_by list "FIELD"` returns an object mapping each distinct value of `FIELD`
+//!   to the records holding it, for use with `{{#each (group_by records "VRF")}}`.
+//! * `sort_by list "FIELD"` returns `list` ordered by `FIELD`, comparing numerically
+//!   when both values are numbers.
+//!
+//! Output is not HTML-escaped.
+
+use crate::{DataRecord, Result, TextFsmError};
+use handlebars::{handlebars_helper, no_escape, Handlebars};
+use serde_json::{json, Map, Value as JsonValue};
+use std::cmp::Ordering;
+use std::path::Path;
+
+const TEMPLATE_NAME: &str = "report";
+
+/// Text form of `field` in a serialized record, used for grouping and sorting.
+fn field_text(record: &JsonValue, field: &str) -> String {
+    match record.get(field) {
+        Some(JsonValue::String(s)) => s.clone(),
+        Some(JsonValue::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+handlebars_helper!(group_by: |list: array, field: str| {
+    let mut groups: Map<String, JsonValue> = Map::new();
+    for record in list {
+        let entry = groups
+            .entry(field_text(record, field))
+            .or_insert_with(|| JsonValue::Array(vec![]));
+        if let JsonValue::Array(members) = entry {
+            members.push(record.clone());
+        }
+    }
+    JsonValue::Object(groups)
+});
+
+handlebars_helper!(sort_by: |list: array, field: str| {
+    let mut sorted = list.clone();
+    sorted.sort_by(|a, b| compare_text(&field_text(a, field), &field_text(b, field)));
+    JsonValue::Array(sorted)
+});
+
+/// A compiled report template.
+pub struct Report {
+    registry: Handlebars<'static>,
+}
+
+impl Report {
+    /// Compiles a report template from a string.
+    pub fn from_string(template: &str) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(no_escape);
+        registry.register_helper("group_by", Box::new(group_by));
+        registry.register_helper("sort_by", Box::new(sort_by));
+        registry
+            .register_template_string(TEMPLATE_NAME, template)
+            .map_err(|e| TextFsmError::RenderError(e.to_string()))?;
+        Ok(Report { registry })
+    }
+
+    /// Compiles a report template from a file, e.g. `report.hbs`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        Self::from_string(&content)
+            .map_err(|e| TextFsmError::RenderError(format!("template {}: {}", path.display(), e)))
+    }
+
+    /// Renders `records` into text.
+    pub fn render(&self, records: &[DataRecord]) -> Result<String> {
+        self.registry
+            .render(TEMPLATE_NAME, &json!({ "records": records }))
+            .map_err(|e| TextFsmError::RenderError(e.to_string()))
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{Result, TextFSMParser, TextFsmError};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// File extensions tried, in order, when a template name is looked up in a directory.
+const TEMPLATE_EXTENSIONS: &[&str] = &["textfsm", "template"];
+
+/// Finds templates by name in a list of search directories.
+///
+/// A name that already points at an existing file is used as is. Otherwise each
+/// directory is searched in the order it was added, first for the exact name and
+/// then for the name with a `.textfsm` or `.template` extension, so
+/// `cisco_ios_show_version` finds `templates/cisco_ios_show_version.textfsm`.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateResolver {
+    dirs: Vec<PathBuf>,
+}
+
+impl TemplateResolver {
+    /// Creates a resolver with no search directories.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends a search directory and returns the resolver.
+    pub fn with_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.add_dir(dir);
+        self
+    }
+
+    /// Appends a search directory.
+    pub fn add_dir<P: Into<PathBuf>>(&mut self, dir: P) {
+        self.dirs.push(dir.into());
+    }
+
+    /// The search directories, in lookup order.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Returns the path of the template called `name`.
+    pub fn resolve<P: AsRef<Path>>(&self, name: P) -> Result<PathBuf> {
+        let name = name.as_ref();
+        if name.is_file() {
+            return Ok(name.to_path_buf());
+        }
+        for dir in &self.dirs {
+            let exact = dir.join(name);
+            let candidates =
+                std::iter::once(exact.clone()).chain(TEMPLATE_EXTENSIONS.iter().map(|ext| {
+                    let mut file = exact.clone().into_os_string();
+                    file.push(".");
+                    file.push(ext);
+                    PathBuf::from(file)
+                }));
+            for candidate in candidates {
+                if candidate.is_file() {
+                    debug!(
+                        "Resolved template {} to {}",
+                        name.display(),
+                        candidate.display()
+                    );
+                    return Ok(candidate);
+                }
+            }
+        }
+        Err(TextFsmError::TemplateNotFound(name.display().to_string()))
+    }
+
+    /// Resolves and compiles the template called `name`.
+    pub fn load<P: AsRef<Path>>(&self, name: P) -> Result<TextFSMParser> {
+        TextFSMParser::from_file(self.resolve(name)?)
+    }
+}
@@ -0,0 +1,174 @@
+//! Comparing the records two templates produce.
+//!
+//! Code that consumes parsed records depends on the field names and on whether each
+//! field holds one string or a list, not on the rules that fill them. A [`SchemaDiff`]
+//! lists the changes to those: fields added, removed, renamed or changed between
+//! `Single` and `List`. A removed field and an added one with the same regex are
+//! reported as a rename.
+//!
+//! ```
+//! use asyncfsm::{FieldChange, SchemaDiff, TextFSMParser};
+//!
+//! let old = TextFSMParser::from_string("Value PORT (\\S+)\n\nStart\n  ^${PORT}\n")?;
+//! let new = TextFSMParser::from_string("Value List INTERFACE (\\S+)\n\nStart\n  ^${INTERFACE}\n")?;
+//! let diff = SchemaDiff::between(&old, &new);
+//! assert!(diff.is_breaking());
+//! let renamed = FieldChange::Renamed { old: "PORT".into(), new: "INTERFACE".into() };
+//! assert_eq!(diff.fields[0], renamed);
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::TextFSMParser;
+use serde::Serialize;
+use std::fmt;
+
+/// Whether a field holds one string or a list of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Single,
+    List,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldType::Single => "single",
+            FieldType::List => "list",
+        })
+    }
+}
+
+/// A difference in the fields of the records.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum FieldChange {
+    Added {
+        name: String,
+        field_type: FieldType,
+    },
+    Removed {
+        name: String,
+        field_type: FieldType,
+    },
+    /// A field was removed and one with the same regex added.
+    Renamed {
+        old: String,
+        new: String,
+    },
+    /// The field, under its new name if it was renamed, holds the other type.
+    TypeChanged {
+        name: String,
+        old: FieldType,
+        new: FieldType,
+    },
+}
+
+/// The differences between the records of two templates; see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SchemaDiff {
+    pub fields: Vec<FieldChange>,
+}
+
+impl SchemaDiff {
+    /// Compares the records of `old` with those of `new`.
+    pub fn between(old: &TextFSMParser, new: &TextFSMParser) -> Self {
+        let field_type = |parser: &TextFSMParser, name: &str| match parser.values[name].is_list {
+            true => FieldType::List,
+            false => FieldType::Single,
+        };
+        let mut removed: Vec<&String> = old
+            .value_names
+            .iter()
+            .filter(|name| !new.values.contains_key(*name))
+            .collect();
+        let mut added: Vec<&String> = new
+            .value_names
+            .iter()
+            .filter(|name| !old.values.contains_key(*name))
+            .collect();
+        let mut fields = vec![];
+        // Pairs of (old name, new name) for fields in both, renamed or not.
+        let mut kept: Vec<(&String, &String)> = old
+            .value_names
+            .iter()
+            .filter(|name| new.values.contains_key(*name))
+            .map(|name| (name, name))
+            .collect();
+        removed.retain(|old_name| {
+            let regex = old.values[*old_name].regex();
+            match added.iter().position(|n| new.values[*n].regex() == regex) {
+                Some(i) => {
+                    let new_name = added.remove(i);
+                    fields.push(FieldChange::Renamed {
+                        old: old_name.to_string(),
+                        new: new_name.clone(),
+                    });
+                    kept.push((old_name, new_name));
+                    false
+                }
+                None => true,
+            }
+        });
+        for (old_name, new_name) in kept {
+            let (before, after) = (field_type(old, old_name), field_type(new, new_name));
+            if before != after {
+                fields.push(FieldChange::TypeChanged {
+                    name: new_name.clone(),
+                    old: before,
+                    new: after,
+                });
+            }
+        }
+        for name in removed {
+            fields.push(FieldChange::Removed {
+                name: name.clone(),
+                field_type: field_type(old, name),
+            });
+        }
+        for name in added {
+            fields.push(FieldChange::Added {
+                name: name.clone(),
+                field_type: field_type(new, name),
+            });
+        }
+        SchemaDiff { fields }
+    }
+
+    /// True if both templates produce records with the same fields and types.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// True if code reading the old records may break: a field was removed, renamed or
+    /// changed type. Added fields are not breaking.
+    pub fn is_breaking(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|change| !matches!(change, FieldChange::Added { .. }))
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "records have the same fields");
+        }
+        for change in &self.fields {
+            match change {
+                FieldChange::Added { name, field_type } => {
+                    writeln!(f, "+ {} ({})", name, field_type)?
+                }
+                FieldChange::Removed { name, field_type } => {
+                    writeln!(f, "- {} ({})", name, field_type)?
+                }
+                FieldChange::Renamed { old, new } => writeln!(f, "~ {} renamed to {}", old, new)?,
+                FieldChange::TypeChanged { name, old, new } => {
+                    writeln!(f, "~ {}: {} -> {}", name, old, new)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
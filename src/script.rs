@@ -0,0 +1,110 @@
+//! Post-processing records with a Rhai script.
+//!
+//! The script runs once per record with the record's fields in a `record` map:
+//! single values as strings and `List` values as arrays of strings. Whatever the
+//! script leaves in `record` replaces the record, so it can add, rename or drop
+//! fields; setting `record = ()` drops the record altogether.
+//!
+//! ```text
+//! record.BANDWIDTH_PCT = (parse_float(record.RATE) / parse_float(record.BW) * 100.0).to_string();
+//! record.INTERFACE.replace("GigabitEthernet", "Gi");
+//! ```
+//!
+//! Numbers and other values the script stores are turned back into strings.
+
+use crate::{DataRecord, Records, Result, TextFsmError, Value};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::path::Path;
+
+/// A compiled record transformation.
+pub struct RecordScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl std::fmt::Debug for RecordScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RecordScript(..)")
+    }
+}
+
+fn script_error(e: impl std::fmt::Display) -> TextFsmError {
+    TextFsmError::ScriptError(e.to_string())
+}
+
+fn to_dynamic(value: &Value) -> Dynamic {
+    match value {
+        Value::Single(s) => s.clone().into(),
+        Value::List(items) => items
+            .iter()
+            .cloned()
+            .map(Dynamic::from)
+            .collect::<Array>()
+            .into(),
+    }
+}
+
+fn from_dynamic(value: Dynamic) -> Value {
+    if value.is_array() {
+        let items = value.cast::<Array>();
+        Value::List(items.into_iter().map(|item| item.to_string()).collect())
+    } else {
+        Value::Single(value.to_string())
+    }
+}
+
+impl RecordScript {
+    /// Compiles a script.
+    pub fn new(source: &str) -> Result<Self> {
+        let engine = Engine::new();
+        let ast = engine.compile(source).map_err(script_error)?;
+        Ok(RecordScript { engine, ast })
+    }
+
+    /// Reads and compiles a script file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(&std::fs::read_to_string(path)?)
+    }
+
+    /// Runs the script on one record; `None` when the script dropped it.
+    pub fn apply_record(&self, record: &DataRecord) -> Result<Option<DataRecord>> {
+        let map: Map = record
+            .iter()
+            .map(|(name, value)| (name.as_str().into(), to_dynamic(value)))
+            .collect();
+        let mut scope = Scope::new();
+        scope.push("record", map);
+        self.engine
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(script_error)?;
+        let result = scope
+            .get_value::<Dynamic>("record")
+            .unwrap_or(Dynamic::UNIT);
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let Some(map) = result.try_cast::<Map>() else {
+            return Err(script_error("`record` must be a map or ()"));
+        };
+        let mut out = DataRecord {
+            record_key: record.record_key.clone(),
+            raw: record.raw.clone(),
+            index: record.index,
+            ..Default::default()
+        };
+        for (name, value) in map {
+            out.fields.insert(name.to_string(), from_dynamic(value));
+        }
+        Ok(Some(out))
+    }
+
+    /// Runs the script on every record, keeping the template's field order.
+    pub fn apply(&self, records: Records) -> Result<Records> {
+        let schema = records.schema().to_vec();
+        let mut out = vec![];
+        for record in records.iter() {
+            out.extend(self.apply_record(record)?);
+        }
+        Ok(Records::new(out, schema))
+    }
+}
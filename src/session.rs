@@ -0,0 +1,271 @@
+use crate::{
+    DataRecord, DataRecordConversion, RawBlock, RawText, Records, Result, TextFSM, TextFsmError,
+    ValidationIssue, Value, Warnings,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A serializable snapshot of a record in progress.
+///
+/// `DataRecord` flattens its fields when serialized, which loses the record key on the way
+/// back in, so snapshots keep both explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RecordState {
+    /// Map of value names to their extracted values.
+    pub fields: HashMap<String, Value>,
+    /// The record key built from 'Key' values, if any.
+    pub record_key: Option<String>,
+    /// The record's `_index`, if `with_ids` numbered it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// The record's `_id`, if `with_ids` numbered it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The input lines the record came from, if `with_raw` kept them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawText>,
+}
+
+impl From<&DataRecord> for RecordState {
+    fn from(rec: &DataRecord) -> Self {
+        RecordState {
+            fields: rec.fields.clone(),
+            record_key: rec.record_key.clone(),
+            index: rec.index,
+            id: rec.id.clone(),
+            raw: rec.raw.clone(),
+        }
+    }
+}
+
+impl From<RecordState> for DataRecord {
+    fn from(state: RecordState) -> Self {
+        DataRecord {
+            fields: state.fields,
+            record_key: state.record_key,
+            index: state.index,
+            id: state.id,
+            raw: state.raw,
+        }
+    }
+}
+
+/// The runtime progress of a `TextFSM` parse, detached from the compiled template.
+///
+/// Obtained via `TextFSM::checkpoint` and restored with `TextFSM::resume`, so collectors that
+/// receive device output in chunks can persist parser progress across reconnects.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SessionState {
+    /// Name of the state the engine is in.
+    pub curr_state: String,
+    /// The record currently being populated.
+    pub curr_record: RecordState,
+    /// Values carried forward by 'Filldown'.
+    pub filldown_record: RecordState,
+    /// Records emitted but not yet collected.
+    pub records: Vec<RecordState>,
+    /// Trailing input without a newline, not yet processed.
+    pub partial_line: String,
+    /// Number of input lines consumed so far.
+    pub line_number: usize,
+    /// Number of records emitted so far, so `with_ids` numbering carries on after resuming.
+    #[serde(default)]
+    pub records_emitted: usize,
+    /// Fields of the last record kept, so `Dedupe::Consecutive` carries on after resuming.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fields: Option<HashMap<String, Value>>,
+    /// Keys of the records kept so far, sorted, so `Dedupe::Key` carries on after resuming.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+    /// The input lines of the record in progress, so `with_raw` blocks span the resume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_block: Option<RawText>,
+    /// Line each `Filldown` value was last captured on, for `FilldownReset::StateChange`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub filldown_lines: HashMap<String, usize>,
+    /// Values of the record in progress already cut by a field limit, sorted, so each is
+    /// reported once per record across the resume.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub limited_fields: Vec<String>,
+    /// Number of input lines so far that matched no rule.
+    #[serde(default)]
+    pub unmatched_lines: usize,
+    /// Number of records `TextFsmOptions::dedupe` dropped so far.
+    #[serde(default)]
+    pub duplicate_records: usize,
+    /// Whether a limit or the cancel token stopped the parse.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Warnings raised so far.
+    #[serde(default)]
+    pub warnings: Warnings,
+    /// Captures a validator rejected so far.
+    #[serde(default)]
+    pub validation_issues: Vec<ValidationIssue>,
+}
+
+impl TextFSM {
+    /// Captures the runtime state of the engine.
+    pub fn checkpoint(&self) -> SessionState {
+        SessionState {
+            curr_state: self.curr_state.clone(),
+            curr_record: RecordState::from(&self.curr_record),
+            filldown_record: RecordState::from(&self.filldown_record),
+            records: self.records.iter().map(RecordState::from).collect(),
+            partial_line: self.partial_line.clone(),
+            line_number: self.line_number,
+            records_emitted: self.records_emitted,
+            last_fields: self.seen.last_fields.clone(),
+            keys: {
+                let mut keys: Vec<String> = self.seen.keys.iter().cloned().collect();
+                keys.sort();
+                keys
+            },
+            raw_block: self.raw_block.first_line.map(|first_line| RawText {
+                first_line,
+                last_line: self.raw_block.last_line,
+                text: self.raw_block.text.clone(),
+            }),
+            filldown_lines: self.filldown_lines.clone(),
+            limited_fields: {
+                let mut fields: Vec<String> = self.limited_fields.iter().cloned().collect();
+                fields.sort();
+                fields
+            },
+            unmatched_lines: self.unmatched_lines,
+            duplicate_records: self.duplicate_records,
+            truncated: self.truncated,
+            warnings: self.warnings.clone(),
+            validation_issues: self.validation_issues.clone(),
+        }
+    }
+
+    /// Restores runtime state previously captured with `checkpoint`.
+    ///
+    /// Fails if the saved state does not exist in the loaded template.
+    pub fn resume(&mut self, state: SessionState) -> Result<()> {
+        if !self.is_known_state(&state.curr_state) {
+            return Err(TextFsmError::StateError(format!(
+                "Can not resume in state '{}': not found in template",
+                &state.curr_state
+            )));
+        }
+        self.curr_state = state.curr_state;
+        self.curr_record = state.curr_record.into();
+        self.filldown_record = state.filldown_record.into();
+        self.records = state.records.into_iter().map(DataRecord::from).collect();
+        self.partial_line = state.partial_line;
+        self.line_number = state.line_number;
+        self.records_emitted = state.records_emitted;
+        self.seen.last_fields = state.last_fields;
+        self.seen.keys = state.keys.into_iter().collect();
+        self.raw_block = state
+            .raw_block
+            .map(|raw| RawBlock {
+                first_line: Some(raw.first_line),
+                last_line: raw.last_line,
+                text: raw.text,
+            })
+            .unwrap_or_default();
+        self.filldown_lines = state.filldown_lines;
+        self.limited_fields = state.limited_fields.into_iter().collect();
+        self.unmatched_lines = state.unmatched_lines;
+        self.duplicate_records = state.duplicate_records;
+        self.truncated = state.truncated;
+        self.warnings = state.warnings;
+        self.validation_issues = state.validation_issues;
+        Ok(())
+    }
+
+    fn is_known_state(&self, name: &str) -> bool {
+        name == "End" || self.parser.states.contains_key(name)
+    }
+
+    /// Feeds a chunk of input without finalizing the parse.
+    ///
+    /// Complete lines are processed immediately; a trailing fragment without a newline is
+    /// held until the next chunk or `finish`. Returns (and removes) the records emitted so far.
+    pub fn parse_chunk(
+        &mut self,
+        chunk: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Records> {
+        let mut buffer = std::mem::take(&mut self.partial_line);
+        buffer.push_str(chunk);
+        let complete_len = buffer.rfind('\n').map_or(0, |pos| pos + 1);
+        self.partial_line = buffer.split_off(complete_len);
+
+        for aline in buffer.lines() {
+            if self.is_finished() {
+                break;
+            }
+            self.process_input_line(aline)?;
+        }
+        Ok(self.drain_records(conversion))
+    }
+
+    /// Processes any held-back input and the `EOF` state, returning the remaining records.
+    pub fn finish(&mut self, conversion: Option<DataRecordConversion>) -> Result<Records> {
+        let rest = std::mem::take(&mut self.partial_line);
+        if !rest.is_empty() && !self.is_finished() {
+            self.process_input_line(rest.trim_end_matches('\r'))?;
+        }
+        self.process_eof()?;
+        Ok(self.drain_records(conversion))
+    }
+
+    fn drain_records(&mut self, conversion: Option<DataRecordConversion>) -> Records {
+        let out = self.convert_records(conversion);
+        self.records.clear();
+        out
+    }
+}
+
+#[cfg(feature = "async")]
+impl TextFSM {
+    /// Parses lines from `reader` and sends each record to `sender` as soon as it is emitted.
+    ///
+    /// Sending waits while the channel is full, and no more input is read meanwhile, so a
+    /// slow consumer slows the parse down instead of letting records pile up in memory.
+    /// The `EOF` processing runs at the end of the input; record and time limits and the
+    /// cancel token are checked before each line. Stops early, without an error,
+    /// when the receiver is dropped. Returns the number of records sent; `sender` is
+    /// dropped on return, so the channel closes once no other sender is left.
+    pub async fn parse_to_channel<R>(
+        &mut self,
+        reader: R,
+        sender: tokio::sync::mpsc::Sender<DataRecord>,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<usize>
+    where
+        R: tokio::io::AsyncBufRead + Unpin,
+    {
+        use tokio::io::AsyncBufReadExt;
+
+        let started = std::time::Instant::now();
+        let mut lines = reader.lines();
+        let mut sent = 0;
+        loop {
+            if self.check_limits(sent, started) {
+                return Ok(sent);
+            }
+            let records = match lines.next_line().await? {
+                Some(line) if !self.is_finished() => {
+                    self.process_input_line(&line)?;
+                    self.drain_records(conversion.clone())
+                }
+                _ => self.finish(conversion.clone())?,
+            };
+            let done = self.curr_state == "End";
+            for record in records.into_vec() {
+                if self.check_limits(sent, started) || sender.send(record).await.is_err() {
+                    return Ok(sent);
+                }
+                sent += 1;
+            }
+            if done {
+                return Ok(sent);
+            }
+        }
+    }
+}
@@ -0,0 +1,672 @@
+//! Destinations for parsed records.
+//!
+//! A [`RecordSink`] is opened with the field names, written batches of records and
+//! closed, so one implementation serves records parsed all at once and records
+//! streamed from [`TextFsmIter::write_to`](crate::TextFsmIter::write_to) as they are
+//! parsed. The built-in sinks write JSON, YAML, CSV and NDJSON to any `io::Write` and,
+//! with the `sqlite` feature, rows of an SQLite table; [`WriterSink`] writes through an
+//! [`OutputWriter`], to standard output as a [`StdoutSink`]. [`record_sink`] picks one
+//! by name, as the CLI's `--writer` does, so a new destination is one impl of the
+//! trait. A [`PartitionedSink`] spreads records over a sink per value of a field.
+//!
+//! ```
+//! use asyncfsm::sink::{NdjsonSink, RecordSink};
+//! use asyncfsm::TextFSM;
+//!
+//! let fsm = TextFSM::from_string("Value N (\\d+)\n\nStart\n  ^${N} -> Record\n")?;
+//! let mut sink = NdjsonSink::new(vec![]);
+//! let written = fsm.parse_reader("1\n2\n".as_bytes()).write_to(&mut sink, None)?;
+//! assert_eq!(written, 2);
+//! assert_eq!(sink.into_inner(), b"{\"N\":\"1\"}\n{\"N\":\"2\"}\n");
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::export::get_headers;
+use crate::output::{output_writer, output_writer_names, OutputWriter};
+use crate::{DataRecord, Records, Result, TextFsmError};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+#[cfg(feature = "compress")]
+use {crate::compress::CompressedWriter, std::cell::RefCell, std::rc::Rc};
+
+/// Where records go; see the [module documentation](self).
+pub trait RecordSink {
+    /// Called once before any record, with the field names in output order.
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        let _ = fields;
+        Ok(())
+    }
+
+    /// Writes a batch of records, in order.
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()>;
+
+    /// Makes what was written so far reach the destination.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once after the last batch, to finish the output.
+    fn close(&mut self) -> Result<()> {
+        self.flush()
+    }
+
+    /// Opens the sink for `records`, writes them as one batch and closes it.
+    fn write_all(&mut self, records: &Records) -> Result<()> {
+        self.open(&get_headers(records, records.schema()))?;
+        self.write(records)?;
+        self.close()
+    }
+}
+
+/// Writes through an [`OutputWriter`], such as one of [`output_writer`].
+pub struct WriterSink<W> {
+    writer: Box<dyn OutputWriter>,
+    out: W,
+    fields: Vec<String>,
+}
+
+/// A [`WriterSink`] to standard output.
+pub type StdoutSink = WriterSink<io::Stdout>;
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(writer: Box<dyn OutputWriter>, out: W) -> Self {
+        WriterSink {
+            writer,
+            out,
+            fields: vec![],
+        }
+    }
+
+    /// The destination, with everything written.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl StdoutSink {
+    pub fn stdout(writer: Box<dyn OutputWriter>) -> Self {
+        WriterSink::new(writer, io::stdout())
+    }
+}
+
+impl<W: Write> RecordSink for WriterSink<W> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        self.writer.header(&mut self.out, fields)?;
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        for record in batch {
+            self.writer.record(&mut self.out, &self.fields, record)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.writer.footer(&mut self.out)?;
+        self.flush()
+    }
+}
+
+/// A JSON array of records, written as in the CLI's `--format json` but one record to
+/// a line.
+#[cfg(feature = "json")]
+pub struct JsonSink<W> {
+    out: W,
+    fields: Vec<String>,
+    written: usize,
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> JsonSink<W> {
+    pub fn new(out: W) -> Self {
+        JsonSink {
+            out,
+            fields: vec![],
+            written: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> RecordSink for JsonSink<W> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        self.written = 0;
+        self.out.write_all(b"[")?;
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        for record in batch {
+            let separator: &[u8] = if self.written == 0 { b"\n  " } else { b",\n  " };
+            self.out.write_all(separator)?;
+            let record = crate::record::OrderedRecord {
+                record,
+                schema: &self.fields,
+            };
+            serde_json::to_writer(&mut self.out, &record)
+                .map_err(|e| TextFsmError::InternalError(e.to_string()))?;
+            self.written += 1;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let end: &[u8] = if self.written == 0 { b"]\n" } else { b"\n]\n" };
+        self.out.write_all(end)?;
+        self.flush()
+    }
+}
+
+/// One JSON object of fields per line, as the `jsonl` output writer writes them.
+#[cfg(feature = "json")]
+pub struct NdjsonSink<W> {
+    out: W,
+    fields: Vec<String>,
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> NdjsonSink<W> {
+    pub fn new(out: W) -> Self {
+        NdjsonSink {
+            out,
+            fields: vec![],
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> RecordSink for NdjsonSink<W> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        for record in batch {
+            crate::output::write_json_line(&mut self.out, &self.fields, record)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// A YAML sequence of records, as in the CLI's `--format yaml`.
+#[cfg(feature = "yaml")]
+pub struct YamlSink<W> {
+    out: W,
+    fields: Vec<String>,
+    written: usize,
+}
+
+#[cfg(feature = "yaml")]
+impl<W: Write> YamlSink<W> {
+    pub fn new(out: W) -> Self {
+        YamlSink {
+            out,
+            fields: vec![],
+            written: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl<W: Write> RecordSink for YamlSink<W> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        self.written = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        // Each batch is a sequence; written one after another they make one sequence.
+        let records: Vec<_> = batch
+            .iter()
+            .map(|record| crate::record::OrderedRecord {
+                record,
+                schema: &self.fields,
+            })
+            .collect();
+        if !records.is_empty() {
+            serde_yaml::to_writer(&mut self.out, &records)
+                .map_err(|e| TextFsmError::InternalError(e.to_string()))?;
+            self.written += records.len();
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.written == 0 {
+            self.out.write_all(b"[]\n")?;
+        }
+        self.flush()
+    }
+}
+
+/// CSV with a header row, as the `csv` output writer writes it.
+#[cfg(feature = "csv_export")]
+pub struct CsvSink<W: Write> {
+    out: csv::Writer<W>,
+    fields: Vec<String>,
+}
+
+#[cfg(feature = "csv_export")]
+impl<W: Write> CsvSink<W> {
+    pub fn new(out: W) -> Self {
+        CsvSink {
+            out: csv::Writer::from_writer(out),
+            fields: vec![],
+        }
+    }
+
+    /// The destination, with everything written; fails if flushing it fails.
+    pub fn into_inner(self) -> Result<W> {
+        self.out
+            .into_inner()
+            .map_err(|e| TextFsmError::IoError(e.into_error()))
+    }
+}
+
+#[cfg(feature = "csv_export")]
+impl<W: Write> RecordSink for CsvSink<W> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        Ok(self.out.write_record(fields)?)
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        for record in batch {
+            self.out.write_record(
+                self.fields
+                    .iter()
+                    .map(|name| record.get(name).map(|v| v.to_string()).unwrap_or_default()),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// Rows of a table in an SQLite database, one `TEXT` column per field and lists as
+/// JSON arrays.
+///
+/// The table is created when the sink is opened if it does not exist. Rows are
+/// written in a transaction that each [`flush`](RecordSink::flush) and the
+/// [`close`](RecordSink::close) commit.
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    connection: rusqlite::Connection,
+    table: String,
+    fields: Vec<String>,
+}
+
+#[cfg(feature = "sqlite")]
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    /// Writes to `table` of the database at `path`, creating the file if needed.
+    pub fn create<P: AsRef<std::path::Path>>(path: P, table: &str) -> Result<Self> {
+        Ok(Self::new(rusqlite::Connection::open(path)?, table))
+    }
+
+    pub fn new(connection: rusqlite::Connection, table: &str) -> Self {
+        SqliteSink {
+            connection,
+            table: table.to_string(),
+            fields: vec![],
+        }
+    }
+
+    pub fn into_inner(self) -> rusqlite::Connection {
+        self.connection
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl RecordSink for SqliteSink {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        let columns: Vec<String> = fields
+            .iter()
+            .map(|f| format!("{} TEXT", quote(f)))
+            .collect();
+        self.connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}); BEGIN;",
+            quote(&self.table),
+            columns.join(", ")
+        ))?;
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        let columns: Vec<String> = self.fields.iter().map(|f| quote(f)).collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+        let mut insert = self.connection.prepare_cached(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(&self.table),
+            columns.join(", "),
+            placeholders.join(", ")
+        ))?;
+        for record in batch {
+            let row = self.fields.iter().map(|name| match record.get(name) {
+                Some(crate::Value::Single(s)) => Some(s.clone()),
+                Some(crate::Value::List(l)) => serde_json::to_string(l).ok(),
+                None => None,
+            });
+            insert.execute(rusqlite::params_from_iter(row))?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.connection.execute_batch("COMMIT; BEGIN;")?;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.connection.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+}
+
+/// Routes records to one sink per value of a field, so a large run ends up in many
+/// files of a usable size rather than one.
+///
+/// The sink of each value is made by `make` when its first record arrives, and opened
+/// with the fields this sink was opened with. Records without the field go to the
+/// partition `""`.
+pub struct PartitionedSink<F> {
+    field: String,
+    make: F,
+    fields: Vec<String>,
+    sinks: BTreeMap<String, Box<dyn RecordSink>>,
+}
+
+impl<F: FnMut(&str) -> Result<Box<dyn RecordSink>>> PartitionedSink<F> {
+    pub fn new(field: &str, make: F) -> Self {
+        PartitionedSink {
+            field: field.to_string(),
+            make,
+            fields: vec![],
+            sinks: BTreeMap::new(),
+        }
+    }
+
+    /// Writes `batch` to the partition `value`, whatever the records hold, e.g. for
+    /// partitions by where the records came from.
+    pub fn write_partition(&mut self, value: &str, batch: &[DataRecord]) -> Result<()> {
+        if !self.sinks.contains_key(value) {
+            let mut sink = (self.make)(value)?;
+            sink.open(&self.fields)?;
+            self.sinks.insert(value.to_string(), sink);
+        }
+        self.sinks
+            .get_mut(value)
+            .expect("inserted above")
+            .write(batch)
+    }
+
+    /// The partitions written to so far, sorted.
+    pub fn partitions(&self) -> impl Iterator<Item = &str> {
+        self.sinks.keys().map(String::as_str)
+    }
+}
+
+impl<F: FnMut(&str) -> Result<Box<dyn RecordSink>>> RecordSink for PartitionedSink<F> {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.fields = fields.to_vec();
+        Ok(())
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        let field = self.field.clone();
+        let value = |record: &DataRecord| {
+            record
+                .get(&field)
+                .map(|v| v.to_string())
+                .unwrap_or_default()
+        };
+        // Runs of records of one partition are written as one batch.
+        let mut rest = batch;
+        while let Some(first) = rest.first() {
+            let partition = value(first);
+            let run = rest.iter().take_while(|r| value(r) == partition).count();
+            let (run, after) = rest.split_at(run);
+            self.write_partition(&partition, run)?;
+            rest = after;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sinks.values_mut().try_for_each(|sink| sink.flush())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.sinks.values_mut().try_for_each(|sink| sink.close())
+    }
+}
+
+/// File names for partitions, as the CLI's `--partition-by` writes them.
+///
+/// A name keeps the value's letters, digits, `-`, `_` and `.`, with `_` for anything
+/// else, such as the separators of a path, and `_none` for an empty value. Values that
+/// come out the same, such as `a/b` and `a:b`, must not share a file, so every value
+/// after the first to claim a name gets a hash of the value added to it.
+#[derive(Debug, Default)]
+pub struct PartitionFileNames {
+    /// The value each name was given to.
+    taken: HashMap<String, String>,
+}
+
+impl PartitionFileNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The file name, without extension, of the partition `value`; the same for the
+    /// same value every time.
+    pub fn name(&mut self, value: &str) -> String {
+        let base: String = value
+            .trim_start_matches(['/', '\\'])
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || "-_.".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let base = match base {
+            base if base.is_empty() => "_none".to_string(),
+            base if base.starts_with('.') => format!("_{}", base),
+            base => base,
+        };
+        let mut name = base.clone();
+        let mut salt = 0u32;
+        while let Some(owner) = self.taken.get(&name) {
+            if owner == value {
+                return name;
+            }
+            // FNV-1a, so a value gets the same name on every run.
+            let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+            for b in value.bytes().chain(salt.to_le_bytes()) {
+                hash = (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+            name = format!("{}-{:08x}", base, hash >> 32);
+            salt += 1;
+        }
+        self.taken.insert(name.clone(), value.to_string());
+        name
+    }
+}
+
+/// The names [`record_sink`] knows besides those of the output writers.
+const SINK_NAMES: &[&str] = &[
+    #[cfg(feature = "csv_export")]
+    "csv",
+    #[cfg(feature = "json")]
+    "json",
+    #[cfg(feature = "json")]
+    "ndjson",
+    #[cfg(feature = "sqlite")]
+    "sqlite",
+    #[cfg(feature = "yaml")]
+    "yaml",
+];
+
+/// The sink `spec` names: `NAME` to write to standard output, or `NAME:PATH` to write
+/// to a file. `NAME` is `json`, `yaml`, `csv`, `ndjson` or `sqlite` (which needs a
+/// `PATH` and writes the table `records`), or else the name of an output writer.
+/// With the `compress` feature, a `PATH` other than an SQLite database that ends in
+/// `.gz` or `.zst` is compressed, and closing the sink ends the compressed stream.
+pub fn record_sink(spec: &str) -> Result<Box<dyn RecordSink>> {
+    let (name, path) = match spec.split_once(':') {
+        Some((name, path)) => (name, Some(path)),
+        None => (spec, None),
+    };
+    #[cfg(feature = "compress")]
+    let mut compressed = None;
+    #[cfg_attr(not(feature = "compress"), allow(unused_mut))]
+    let mut out = || -> Result<Box<dyn Write>> {
+        let Some(path) = path else {
+            return Ok(Box::new(io::stdout()));
+        };
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        #[cfg(feature = "compress")]
+        if let Some(compression) = crate::compress::Compression::from_path(path) {
+            let file = SharedFile(Rc::new(RefCell::new(Some(compression.writer(file)?))));
+            compressed = Some(Rc::clone(&file.0));
+            return Ok(Box::new(file));
+        }
+        Ok(Box::new(file))
+    };
+    let sink: Box<dyn RecordSink> = match name {
+        #[cfg(feature = "csv_export")]
+        "csv" => Box::new(CsvSink::new(out()?)),
+        #[cfg(feature = "json")]
+        "json" => Box::new(JsonSink::new(out()?)),
+        #[cfg(feature = "json")]
+        "ndjson" => Box::new(NdjsonSink::new(out()?)),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let path = path.ok_or_else(|| {
+                TextFsmError::ParseError("the sqlite writer needs a file, as sqlite:PATH".into())
+            })?;
+            Box::new(SqliteSink::create(path, "records")?)
+        }
+        #[cfg(feature = "yaml")]
+        "yaml" => Box::new(YamlSink::new(out()?)),
+        name => match output_writer(name) {
+            Ok(writer) => Box::new(WriterSink::new(writer, out()?)),
+            Err(_) => {
+                let mut names: Vec<String> = output_writer_names();
+                names.extend(SINK_NAMES.iter().map(|n| n.to_string()));
+                names.sort();
+                names.dedup();
+                return Err(TextFsmError::ParseError(format!(
+                    "unknown output writer '{}', expected one of: {}",
+                    name,
+                    names.join(", ")
+                )));
+            }
+        },
+    };
+    #[cfg(feature = "compress")]
+    if let Some(file) = compressed {
+        return Ok(Box::new(CompressedSink { inner: sink, file }));
+    }
+    Ok(sink)
+}
+
+/// A compressed file of [`record_sink`], shared by the sink writing to it and the
+/// [`CompressedSink`] that finishes it.
+#[cfg(feature = "compress")]
+type CompressedFile = Rc<RefCell<Option<CompressedWriter<io::BufWriter<std::fs::File>>>>>;
+
+#[cfg(feature = "compress")]
+struct SharedFile(CompressedFile);
+
+#[cfg(feature = "compress")]
+impl Write for SharedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.0.borrow_mut().as_mut() {
+            Some(file) => file.write(buf),
+            None => Err(io::Error::other("the compressed file is already finished")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.borrow_mut().as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Closes `inner`, then ends the compressed stream it wrote, so errors writing the end
+/// of the file are returned rather than lost when the writer is dropped.
+#[cfg(feature = "compress")]
+struct CompressedSink {
+    inner: Box<dyn RecordSink>,
+    file: CompressedFile,
+}
+
+#[cfg(feature = "compress")]
+impl RecordSink for CompressedSink {
+    fn open(&mut self, fields: &[String]) -> Result<()> {
+        self.inner.open(fields)
+    }
+
+    fn write(&mut self, batch: &[DataRecord]) -> Result<()> {
+        self.inner.write(batch)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.inner.close()?;
+        if let Some(file) = self.file.borrow_mut().take() {
+            file.finish()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,411 @@
+//! Structural comparison of two templates.
+//!
+//! A line diff of a template shows which lines moved; a [`TemplateDiff`] shows what the
+//! change does: values added, removed or given other options or regexes, states added or
+//! removed, and rules added, removed or changed within each state. Rules are aligned by
+//! a longest-common-subsequence match, so inserting one rule at the top of a state does
+//! not report every following rule as changed.
+
+use crate::{StateCompiled, TextFSMParser};
+use serde::Serialize;
+use std::fmt;
+
+/// A difference in the `Value` lines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ValueChange {
+    Added {
+        name: String,
+        definition: String,
+    },
+    Removed {
+        name: String,
+        definition: String,
+    },
+    /// The value exists in both; `options` and `regex` hold `[old, new]` when they differ.
+    Changed {
+        name: String,
+        options: Option<[String; 2]>,
+        regex: Option<[String; 2]>,
+    },
+    /// The values both templates declare appear in another order, which reorders the
+    /// output columns.
+    Reordered {
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+}
+
+/// A difference between the rules of a state present in both templates.
+///
+/// Indexes are 0-based positions in the old or new state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum RuleChange {
+    Added {
+        index: usize,
+        rule: String,
+    },
+    Removed {
+        index: usize,
+        rule: String,
+    },
+    Changed {
+        old_index: usize,
+        new_index: usize,
+        old: String,
+        new: String,
+    },
+}
+
+/// A difference in one state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum StateChange {
+    Added {
+        name: String,
+        rules: Vec<String>,
+    },
+    Removed {
+        name: String,
+        rules: Vec<String>,
+    },
+    Changed {
+        name: String,
+        rules: Vec<RuleChange>,
+    },
+}
+
+/// The structural differences between two templates.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TemplateDiff {
+    pub values: Vec<ValueChange>,
+    pub states: Vec<StateChange>,
+}
+
+impl TemplateDiff {
+    /// Compares `old` with `new`.
+    pub fn between(old: &TextFSMParser, new: &TextFSMParser) -> Self {
+        TemplateDiff {
+            values: diff_values(old, new),
+            states: diff_states(old, new),
+        }
+    }
+
+    /// True if the templates have the same values and rules.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.states.is_empty()
+    }
+}
+
+fn diff_values(old: &TextFSMParser, new: &TextFSMParser) -> Vec<ValueChange> {
+    let mut changes = vec![];
+    for name in &old.value_names {
+        let before = &old.values[name];
+        match new.values.get(name) {
+            None => changes.push(ValueChange::Removed {
+                name: name.clone(),
+                definition: before.to_string(),
+            }),
+            Some(after) => {
+                let pair = |a: &str, b: &str| (a != b).then(|| [a.to_string(), b.to_string()]);
+                let options = pair(
+                    before.options().unwrap_or_default(),
+                    after.options().unwrap_or_default(),
+                );
+                let regex = pair(before.regex(), after.regex());
+                if options.is_some() || regex.is_some() {
+                    changes.push(ValueChange::Changed {
+                        name: name.clone(),
+                        options,
+                        regex,
+                    });
+                }
+            }
+        }
+    }
+    for name in &new.value_names {
+        if !old.values.contains_key(name) {
+            changes.push(ValueChange::Added {
+                name: name.clone(),
+                definition: new.values[name].to_string(),
+            });
+        }
+    }
+    let common = |names: &[String], other: &TextFSMParser| -> Vec<String> {
+        names
+            .iter()
+            .filter(|n| other.values.contains_key(*n))
+            .cloned()
+            .collect()
+    };
+    let (old_order, new_order) = (common(&old.value_names, new), common(&new.value_names, old));
+    if old_order != new_order {
+        changes.push(ValueChange::Reordered {
+            old: old_order,
+            new: new_order,
+        });
+    }
+    changes
+}
+
+fn rule_lines(state: &StateCompiled) -> Vec<String> {
+    state.rules().iter().map(|r| r.to_string()).collect()
+}
+
+fn diff_states(old: &TextFSMParser, new: &TextFSMParser) -> Vec<StateChange> {
+    let mut changes = vec![];
+    for name in &old.state_names {
+        let before = rule_lines(&old.states[name]);
+        match new.state_names.contains(name) {
+            false => changes.push(StateChange::Removed {
+                name: name.clone(),
+                rules: before,
+            }),
+            true => {
+                let rules = diff_rules(&before, &rule_lines(&new.states[name]));
+                if !rules.is_empty() {
+                    changes.push(StateChange::Changed {
+                        name: name.clone(),
+                        rules,
+                    });
+                }
+            }
+        }
+    }
+    for name in &new.state_names {
+        if !old.state_names.contains(name) {
+            changes.push(StateChange::Added {
+                name: name.clone(),
+                rules: rule_lines(&new.states[name]),
+            });
+        }
+    }
+    changes
+}
+
+/// Aligns the rules on their longest common subsequence. Between two matched rules,
+/// removed and added rules are paired up as changes, in order.
+fn diff_rules(old: &[String], new: &[String]) -> Vec<RuleChange> {
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut changes = vec![];
+    let (mut removed, mut added) = (vec![], vec![]);
+    let flush =
+        |changes: &mut Vec<RuleChange>, removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+            let paired = removed.len().min(added.len());
+            for (&i, &j) in removed.iter().zip(added.iter()) {
+                changes.push(RuleChange::Changed {
+                    old_index: i,
+                    new_index: j,
+                    old: old[i].clone(),
+                    new: new[j].clone(),
+                });
+            }
+            for &i in &removed[paired..] {
+                changes.push(RuleChange::Removed {
+                    index: i,
+                    rule: old[i].clone(),
+                });
+            }
+            for &j in &added[paired..] {
+                changes.push(RuleChange::Added {
+                    index: j,
+                    rule: new[j].clone(),
+                });
+            }
+            removed.clear();
+            added.clear();
+        };
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            flush(&mut changes, &mut removed, &mut added);
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            removed.push(i);
+            i += 1;
+        } else {
+            added.push(j);
+            j += 1;
+        }
+    }
+    flush(&mut changes, &mut removed, &mut added);
+    changes
+}
+
+/// Where two strings differ: the 1-based column and the differing middle parts.
+fn inline_change(old: &str, new: &str) -> (usize, String, String) {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .take_while(|((_, a), b)| a == b)
+        .last()
+        .map_or(0, |((i, c), _)| i + c.len_utf8());
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix = old_rest
+        .chars()
+        .rev()
+        .zip(new_rest.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+    (
+        old[..prefix].chars().count() + 1,
+        old_rest[..old_rest.len() - suffix].to_string(),
+        new_rest[..new_rest.len() - suffix].to_string(),
+    )
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+impl fmt::Display for TemplateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "templates are equivalent");
+        }
+        if !self.values.is_empty() {
+            writeln!(f, "values:")?;
+        }
+        for change in &self.values {
+            match change {
+                ValueChange::Added { definition, .. } => writeln!(f, "  + {}", definition)?,
+                ValueChange::Removed { definition, .. } => writeln!(f, "  - {}", definition)?,
+                ValueChange::Changed {
+                    name,
+                    options,
+                    regex,
+                } => {
+                    if let Some([a, b]) = options {
+                        let show = |s: &str| {
+                            if s.is_empty() {
+                                "(none)".to_string()
+                            } else {
+                                s.to_string()
+                            }
+                        };
+                        writeln!(f, "  ~ {}: options {} -> {}", name, show(a), show(b))?;
+                    }
+                    if let Some([a, b]) = regex {
+                        writeln!(f, "  ~ {}: regex {} -> {}", name, a, b)?;
+                    }
+                }
+                ValueChange::Reordered { old, new } => {
+                    writeln!(f, "  ~ order: {} -> {}", old.join(", "), new.join(", "))?
+                }
+            }
+        }
+        if !self.states.is_empty() {
+            writeln!(f, "states:")?;
+        }
+        for change in &self.states {
+            match change {
+                StateChange::Added { name, rules } => {
+                    writeln!(
+                        f,
+                        "  + {} ({} rule{})",
+                        name,
+                        rules.len(),
+                        plural(rules.len())
+                    )?;
+                    for rule in rules {
+                        writeln!(f, "      {}", rule)?;
+                    }
+                }
+                StateChange::Removed { name, rules } => writeln!(
+                    f,
+                    "  - {} ({} rule{})",
+                    name,
+                    rules.len(),
+                    plural(rules.len())
+                )?,
+                StateChange::Changed { name, rules } => {
+                    writeln!(f, "  ~ {}", name)?;
+                    for rule in rules {
+                        match rule {
+                            RuleChange::Added { index, rule } => {
+                                writeln!(f, "      + rule {}: {}", index + 1, rule)?
+                            }
+                            RuleChange::Removed { index, rule } => {
+                                writeln!(f, "      - rule {}: {}", index + 1, rule)?
+                            }
+                            RuleChange::Changed {
+                                old_index,
+                                new_index,
+                                old,
+                                new,
+                            } => {
+                                let (column, a, b) = inline_change(old, new);
+                                if old_index == new_index {
+                                    writeln!(f, "      ~ rule {}:", old_index + 1)?;
+                                } else {
+                                    writeln!(
+                                        f,
+                                        "      ~ rule {} (now {}):",
+                                        old_index + 1,
+                                        new_index + 1
+                                    )?;
+                                }
+                                writeln!(f, "          - {}", old)?;
+                                writeln!(f, "          + {}", new)?;
+                                writeln!(f, "          at column {}: {:?} -> {:?}", column, a, b)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_aligned_before_pairing() {
+        let old: Vec<String> = ["^a", "^b", "^c"].map(String::from).into();
+        let new: Vec<String> = ["^new", "^a", "^b2", "^c"].map(String::from).into();
+        assert_eq!(
+            diff_rules(&old, &new),
+            vec![
+                RuleChange::Added {
+                    index: 0,
+                    rule: "^new".into()
+                },
+                RuleChange::Changed {
+                    old_index: 1,
+                    new_index: 2,
+                    old: "^b".into(),
+                    new: "^b2".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_change_finds_the_differing_middle() {
+        assert_eq!(
+            inline_change("^${A} is ${B}", "^${A} is now ${B}"),
+            (10, "".into(), "now ".into())
+        );
+        assert_eq!(inline_change("x", "x"), (2, "".into(), "".into()));
+    }
+}
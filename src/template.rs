@@ -0,0 +1,138 @@
+//! Compiled templates and the sessions that parse with them.
+//!
+//! A [`Template`] is the immutable half of a parse: the compiled states and values and
+//! the [`TextFsmOptions`] every parse should use. A session, [`TextFSM`], is the mutable
+//! half, holding the current state, the record in progress and the records emitted so far.
+//! One template serves any number of sessions, on any number of threads:
+//!
+//! ```
+//! use asyncfsm::Template;
+//!
+//! let template = Template::new("Value N (\\d+)\n\nStart\n  ^${N} -> Record\n")?;
+//! let counts: Vec<usize> = std::thread::scope(|scope| {
+//!     let handles: Vec<_> = ["1\n2\n", "3\n"]
+//!         .into_iter()
+//!         .map(|input| scope.spawn(|| template.session().parse_records(input, None)))
+//!         .collect();
+//!     handles.into_iter().map(|h| h.join().unwrap().unwrap().len()).collect()
+//! });
+//! assert_eq!(counts, vec![2, 1]);
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{DataRecordConversion, Records, Result, TextFSM, TextFSMParser, TextFsmOptions, Token};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A compiled template together with the options its sessions start with.
+///
+/// Cloning only bumps a reference count on the compiled form and copies the options.
+#[derive(Debug, Clone, Default)]
+pub struct Template {
+    parser: Arc<TextFSMParser>,
+    options: TextFsmOptions,
+}
+
+impl Template {
+    /// Compiles a template from its text.
+    pub fn new(template: &str) -> Result<Self> {
+        Ok(Self::from_parser(TextFSMParser::from_string(template)?))
+    }
+
+    /// Reads and compiles a template file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::from_parser(TextFSMParser::from_file(path)?))
+    }
+
+    /// Wraps an already compiled template, sharing it when given an `Arc`.
+    pub fn from_parser(parser: impl Into<Arc<TextFSMParser>>) -> Self {
+        Template {
+            parser: parser.into(),
+            options: TextFsmOptions::default(),
+        }
+    }
+
+    /// Sets the options every new session starts with.
+    pub fn with_options(mut self, options: TextFsmOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The compiled form, e.g. for its values and states.
+    pub fn parser(&self) -> &TextFSMParser {
+        &self.parser
+    }
+
+    /// The template as text in the canonical form of [`format`](crate::format).
+    ///
+    /// Comments other than the `# Key: value` header are not kept, and macros are
+    /// expanded in value regexes.
+    pub fn to_textfsm_string(&self) -> String {
+        crate::format::to_textfsm_string(&self.parser)
+    }
+
+    /// The tokens of `template` for syntax highlighting; see [`lex`](crate::lex).
+    ///
+    /// This reads the text only, so it works on templates that do not compile.
+    pub fn lex(template: &str) -> Vec<Token> {
+        crate::lex::lex(template)
+    }
+
+    /// The options sessions start with.
+    pub fn options(&self) -> &TextFsmOptions {
+        &self.options
+    }
+
+    /// The template's value names, in declaration order.
+    pub fn value_names(&self) -> &[String] {
+        &self.parser.value_names
+    }
+
+    /// Returns the metadata declared in the template's leading comment header.
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        self.parser.metadata()
+    }
+
+    /// Starts a new parse in the configured start state.
+    ///
+    /// The session is cheap to create, parses one input (or one stream of chunks) and is
+    /// then dropped or [`reset`](TextFSM::reset).
+    pub fn session(&self) -> TextFSM {
+        TextFSM::from_parser(Arc::clone(&self.parser)).with_options(self.options.clone())
+    }
+
+    /// Parses `input` in a fresh session and returns its records.
+    pub fn parse_records(
+        &self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Records> {
+        self.session().parse_records(input, conversion)
+    }
+
+    /// Parses a file in a fresh session and returns its records.
+    pub fn parse_file_records<P: AsRef<Path>>(
+        &self,
+        path: P,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Records> {
+        self.session().parse_file_records(path, conversion)
+    }
+}
+
+impl From<TextFSMParser> for Template {
+    fn from(parser: TextFSMParser) -> Self {
+        Template::from_parser(parser)
+    }
+}
+
+impl From<&TextFSM> for Template {
+    /// The template and options of an existing engine, without its parse state.
+    fn from(fsm: &TextFSM) -> Self {
+        Template {
+            parser: Arc::clone(&fsm.parser),
+            options: fsm.options.clone(),
+        }
+    }
+}
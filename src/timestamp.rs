@@ -0,0 +1,215 @@
+//! Reading vendor timestamps into RFC 3339 or Unix time.
+//!
+//! Devices print times in many layouts, most without a zone and syslog ones without a
+//! year. [`Timestamps`] recognizes the common ones and fills in what is missing from
+//! its settings: the zone assumed for times that name none (UTC by default) and the
+//! year assumed for times without one (the current year by default). It is used as
+//! [`Coercion::Timestamp`](crate::Coercion::Timestamp) or on its own.
+//!
+//! Recognized layouts, after a leading `*` or `.` (Cisco's clock-sync markers) is
+//! dropped and runs of spaces are collapsed:
+//!
+//! ```text
+//! 2006-01-02T15:04:05Z, 2006-01-02 15:04:05.123 +02:00   ISO 8601
+//! Mon Jan 2 15:04:05 2006, Mon Jan 2 15:04:05 UTC 2006   ctime
+//! Jan 2 2006 15:04:05, Mar 1 00:01:02.123                syslog, with or without year
+//! 15:04:05.123 UTC Mon Jan 2 2006                        Cisco `show clock`
+//! 01/02/2006 15:04:05                                    month/day/year
+//! ```
+//!
+//! `UTC`, `GMT`, `Z` and numeric offsets are honored; other zone abbreviations such
+//! as `PST` are ambiguous and the assumed zone is used instead.
+//!
+//! ```
+//! use asyncfsm::{TimestampOutput, Timestamps};
+//!
+//! let timestamps = Timestamps::new().zone("+01:00")?;
+//! assert_eq!(
+//!     timestamps.parse("Mon Jan  2 15:04:05 2006").as_deref(),
+//!     Some("2006-01-02T15:04:05+01:00")
+//! );
+//! let epoch = Timestamps::new().year(2024).output(TimestampOutput::EpochSeconds);
+//! assert_eq!(epoch.parse("*Mar  1 00:01:02.123").as_deref(), Some("1709251262"));
+//! # Ok::<(), asyncfsm::TextFsmError>(())
+//! ```
+
+use crate::{Result, TextFsmError};
+use jiff::civil::DateTime;
+use jiff::tz::{Offset, TimeZone};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// How [`Timestamps`] writes a time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampOutput {
+    /// `2006-01-02T15:04:05.123-07:00`, in the zone the time was read in.
+    #[default]
+    Rfc3339,
+    /// Seconds since 1970-01-01 UTC.
+    EpochSeconds,
+    /// Milliseconds since 1970-01-01 UTC.
+    EpochMillis,
+}
+
+/// Settings for reading timestamps; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timestamps {
+    zone: TimeZone,
+    year: Option<i16>,
+    output: TimestampOutput,
+}
+
+impl Default for Timestamps {
+    fn default() -> Self {
+        Timestamps {
+            zone: TimeZone::UTC,
+            year: None,
+            output: TimestampOutput::Rfc3339,
+        }
+    }
+}
+
+/// The fields of a timestamp, as read from the text.
+struct Parts<'a> {
+    year: Option<&'a str>,
+    month: &'a str,
+    day: &'a str,
+    hour: &'a str,
+    minute: &'a str,
+    second: &'a str,
+    fraction: Option<&'a str>,
+    zone: Option<&'a str>,
+}
+
+impl Timestamps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assumes `zone` for times that name none: an IANA name such as
+    /// `Europe/Berlin`, `UTC` or an offset such as `+02:00`.
+    pub fn zone(mut self, zone: &str) -> Result<Self> {
+        self.zone = match fixed_offset(zone) {
+            Some(offset) => TimeZone::fixed(offset),
+            None => TimeZone::get(zone).map_err(|e| {
+                TextFsmError::ParseError(format!("unknown time zone {:?}: {}", zone, e))
+            })?,
+        };
+        Ok(self)
+    }
+
+    /// Assumes `year` for times without one, instead of the current year.
+    pub fn year(mut self, year: i16) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Writes times as `output`.
+    pub fn output(mut self, output: TimestampOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// `text` written as configured, or `None` if it is not a recognized timestamp.
+    pub fn parse(&self, text: &str) -> Option<String> {
+        let text = text.trim().trim_start_matches(['*', '.']);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let parts = parts(&text)?;
+        let year = match parts.year {
+            Some(year) => year.parse().ok()?,
+            None => self
+                .year
+                .unwrap_or_else(|| jiff::Timestamp::now().to_zoned(self.zone.clone()).year()),
+        };
+        let month = match parts.month.parse::<i8>() {
+            Ok(month) => month,
+            Err(_) => month_number(parts.month)?,
+        };
+        let nanos = match parts.fraction {
+            Some(digits) => format!("{:0<9}", digits).get(..9)?.parse().ok()?,
+            None => 0,
+        };
+        let datetime = DateTime::new(
+            year,
+            month,
+            parts.day.parse().ok()?,
+            parts.hour.parse().ok()?,
+            parts.minute.parse().ok()?,
+            parts.second.parse().ok()?,
+            nanos,
+        )
+        .ok()?;
+        let zone = match parts.zone {
+            Some("UTC" | "GMT" | "Z") => TimeZone::UTC,
+            Some(zone) => fixed_offset(zone).map_or_else(|| self.zone.clone(), TimeZone::fixed),
+            None => self.zone.clone(),
+        };
+        let zoned = datetime.to_zoned(zone).ok()?;
+        let timestamp = zoned.timestamp();
+        Some(match self.output {
+            TimestampOutput::Rfc3339 => timestamp.display_with_offset(zoned.offset()).to_string(),
+            TimestampOutput::EpochSeconds => timestamp.as_second().to_string(),
+            TimestampOutput::EpochMillis => timestamp.as_millisecond().to_string(),
+        })
+    }
+}
+
+/// `+02:00`, `+0200` or `-07`.
+fn fixed_offset(text: &str) -> Option<Offset> {
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = text[1..].chars().filter(|c| *c != ':').collect();
+    if !matches!(digits.len(), 2 | 4) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits.get(2..).map_or(Some(0), |m| m.parse().ok())?;
+    Offset::from_seconds(sign * (hours * 3600 + minutes * 60)).ok()
+}
+
+fn month_number(name: &str) -> Option<i8> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let name = name.get(..3)?.to_ascii_lowercase();
+    MONTHS.iter().position(|m| *m == name).map(|i| i as i8 + 1)
+}
+
+fn parts(text: &str) -> Option<Parts<'_>> {
+    const TIME: &str =
+        r"(?P<hour>\d{1,2}):(?P<minute>\d{2}):(?P<second>\d{2})(?:\.(?P<fraction>\d+))?";
+    const ZONE: &str = r"(?P<zone>Z|UTC|GMT|[+-]\d{2}(?::?\d{2})?|[A-Z]{3,5})";
+    static LAYOUTS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let layouts = LAYOUTS.get_or_init(|| {
+        [
+            format!(
+                r"^(?P<year>\d{{4}})-(?P<month>\d{{2}})-(?P<day>\d{{2}})[T ]{TIME} ?{ZONE}?$"
+            ),
+            format!(
+                r"^(?:[A-Za-z]{{3}},? )?(?P<month>[A-Za-z]{{3}}) (?P<day>\d{{1,2}}),? (?:(?P<year>\d{{4}}) )?{TIME}(?: {ZONE})?(?: (?P<year2>\d{{4}}))?$"
+            ),
+            format!(
+                r"^{TIME} (?:{ZONE} )?(?:[A-Za-z]{{3}} )?(?P<month>[A-Za-z]{{3}}) (?P<day>\d{{1,2}}) (?P<year>\d{{4}})$"
+            ),
+            format!(r"^(?P<month>\d{{1,2}})/(?P<day>\d{{1,2}})/(?P<year>\d{{4}}) {TIME}$"),
+        ]
+        .iter()
+        .map(|layout| Regex::new(layout).unwrap())
+        .collect()
+    });
+    let caps = layouts.iter().find_map(|re| re.captures(text))?;
+    let get = |name: &str| caps.name(name).map(|m| m.as_str());
+    Some(Parts {
+        year: get("year").or_else(|| get("year2")),
+        month: get("month")?,
+        day: get("day")?,
+        hour: get("hour")?,
+        minute: get("minute")?,
+        second: get("second")?,
+        fraction: get("fraction"),
+        zone: get("zone"),
+    })
+}
@@ -0,0 +1,126 @@
+//! Checks on captured values, to catch template drift.
+//!
+//! A value's regex decides what is captured; a validator decides whether the capture
+//! is one the consumer expects. When a new OS release starts printing
+//! `administratively down` where the template only ever saw `up` or `down`, the
+//! regex still matches but a validator reports it.
+//!
+//! Validators come from two places:
+//!
+//! * the `OneOf=` value option in the template, e.g.
+//!   `Value OneOf=up|down,Required STATUS (\S+)`;
+//! * [`TextFsmOptions::validator`](crate::TextFsmOptions::validator), for checks
+//!   supplied by library code.
+//!
+//! Failures are logged and collected in `TextFSM::validation_issues`, or abort the
+//! parse with [`TextFsmError::ValidationError`](crate::TextFsmError::ValidationError)
+//! when `strict_validation` is set.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+type CheckFn = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+/// A check applied to every value captured for one template value.
+#[derive(Clone)]
+pub enum Validator {
+    /// The capture must equal one of the listed strings.
+    OneOf(Vec<String>),
+    /// The whole capture must match the regex.
+    Matches(Regex),
+    /// A user-supplied check returning an explanation on failure.
+    Custom(Arc<CheckFn>),
+}
+
+impl Validator {
+    /// Builds a [`Validator::OneOf`] from any list of strings.
+    pub fn one_of<I, S>(allowed: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Validator::OneOf(allowed.into_iter().map(Into::into).collect())
+    }
+
+    /// Builds a [`Validator::Matches`], anchoring `pattern` to the whole capture.
+    pub fn matches(pattern: &str) -> crate::Result<Self> {
+        Regex::new(&format!("^(?:{})$", pattern))
+            .map(Validator::Matches)
+            .map_err(|e| crate::TextFsmError::ParseError(e.to_string()))
+    }
+
+    /// Builds a [`Validator::Custom`] from a closure.
+    pub fn custom<F>(check: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Validator::Custom(Arc::new(check))
+    }
+
+    /// Checks one captured string, returning the reason it was rejected.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            Validator::OneOf(allowed) => {
+                if allowed.iter().any(|a| a == value) {
+                    Ok(())
+                } else {
+                    Err(format!("expected one of {}", allowed.join("|")))
+                }
+            }
+            Validator::Matches(rx) => {
+                if rx.is_match(value) {
+                    Ok(())
+                } else {
+                    Err(format!("does not match {}", rx.as_str()))
+                }
+            }
+            Validator::Custom(check) => check(value),
+        }
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Validator::OneOf(allowed) => f.debug_tuple("OneOf").field(allowed).finish(),
+            Validator::Matches(rx) => f.debug_tuple("Matches").field(&rx.as_str()).finish(),
+            Validator::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl PartialEq for Validator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Validator::OneOf(a), Validator::OneOf(b)) => a == b,
+            (Validator::Matches(a), Validator::Matches(b)) => a.as_str() == b.as_str(),
+            (Validator::Custom(a), Validator::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// A captured value rejected by a validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// Name of the template value.
+    pub value: String,
+    /// The string that was captured.
+    pub captured: String,
+    /// The 1-based number of the input line it was captured from.
+    pub line_number: usize,
+    /// Why the validator rejected it.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value {} = '{}' at line {}: {}",
+            self.value, self.captured, self.line_number, self.message
+        )
+    }
+}
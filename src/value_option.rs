@@ -0,0 +1,145 @@
+//! Custom value options, beyond the built-in `Filldown`, `Key`, `Required`, `List`,
+//! `Fillup` and `OneOf=`.
+//!
+//! An option is a [`ValueOptionHandler`] registered under a name with
+//! [`register_value_option`]. Templates then use it like a built-in one, with an
+//! optional argument after `=`:
+//!
+//! ```text
+//! Value Metric=bytes,Required RX (\d+\s*\S+)
+//! ```
+//!
+//! Registration is process-wide and has to happen before a template using the
+//! option is compiled; an option nobody registered is still a template error.
+//! Handlers are shared by every parse, so any state they keep needs interior
+//! mutability.
+
+use crate::{DataRecord, Result, TextFsmError};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Options the template grammar already knows.
+const BUILTIN_OPTIONS: &[&str] = &["Filldown", "Key", "Required", "List", "Fillup", "OneOf"];
+
+/// Where an option is used: the template value and the argument given to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueOptionUse<'a> {
+    /// Name of the template value carrying the option.
+    pub value: &'a str,
+    /// The text after `=`, as in `Metric=bytes`.
+    pub argument: Option<&'a str>,
+}
+
+/// Hooks for a custom value option. Every hook defaults to doing nothing.
+pub trait ValueOptionHandler: Send + Sync {
+    /// Called with each capture of the value; returns the string to store.
+    fn on_assign(&self, option: &ValueOptionUse<'_>, captured: &str) -> String {
+        let _ = option;
+        captured.to_string()
+    }
+
+    /// Called on each record just before it is emitted.
+    fn on_record(&self, option: &ValueOptionUse<'_>, record: &mut DataRecord) {
+        let _ = (option, record);
+    }
+
+    /// Called when the value is cleared: after `Record`, `Clear` (unless the value
+    /// is also `Filldown`) and `Clearall`.
+    fn on_clear(&self, option: &ValueOptionUse<'_>) {
+        let _ = option;
+    }
+}
+
+/// A custom option as used by one template value.
+#[derive(Clone)]
+pub struct CustomValueOption {
+    /// The registered name.
+    pub name: String,
+    /// The text after `=`, if any.
+    pub argument: Option<String>,
+    handler: Arc<dyn ValueOptionHandler>,
+}
+
+impl CustomValueOption {
+    fn usage<'a>(&'a self, value: &'a str) -> ValueOptionUse<'a> {
+        ValueOptionUse {
+            value,
+            argument: self.argument.as_deref(),
+        }
+    }
+
+    pub(crate) fn assign(&self, value: &str, captured: &str) -> String {
+        self.handler.on_assign(&self.usage(value), captured)
+    }
+
+    pub(crate) fn record(&self, value: &str, record: &mut DataRecord) {
+        self.handler.on_record(&self.usage(value), record)
+    }
+
+    pub(crate) fn clear(&self, value: &str) {
+        self.handler.on_clear(&self.usage(value))
+    }
+}
+
+impl fmt::Debug for CustomValueOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomValueOption")
+            .field("name", &self.name)
+            .field("argument", &self.argument)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for CustomValueOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.argument == other.argument
+            && Arc::ptr_eq(&self.handler, &other.handler)
+    }
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn ValueOptionHandler>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Makes `name` usable as a value option in templates compiled from now on.
+///
+/// Registering a name again replaces its handler; built-in option names are refused.
+pub fn register_value_option<H>(name: &str, handler: H) -> Result<()>
+where
+    H: ValueOptionHandler + 'static,
+{
+    if BUILTIN_OPTIONS.contains(&name) || name.is_empty() || name.contains(['=', ',']) {
+        return Err(TextFsmError::ParseError(format!(
+            "Can not register value option {:?}",
+            name
+        )));
+    }
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), Arc::new(handler));
+    Ok(())
+}
+
+/// Resolves an option word such as `Metric=bytes` against the registered options.
+pub(crate) fn lookup(word: &str) -> Option<CustomValueOption> {
+    let (name, argument) = match word.split_once('=') {
+        Some((name, argument)) => (name, Some(argument.to_string())),
+        None => (word, None),
+    };
+    let handler = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)?
+        .clone();
+    Some(CustomValueOption {
+        name: name.to_string(),
+        argument,
+        handler,
+    })
+}
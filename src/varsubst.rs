@@ -7,10 +7,10 @@ use pest_derive::Parser;
 /// Handles formats like `$VAR`, `${VAR}`, and escaped `$$`.
 #[derive(Parser)]
 #[grammar = "varsubst.pest"]
-pub pub struct VariableParser;
+pub struct VariableParser;
 
 /// Represents a part of a parsed variable substitution string.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseChunk {
     /// A literal dollar sign (escaped `$$`).
     DollarDollar,
@@ -40,10 +40,10 @@ impl VariableParser {
                         out.push(ParseChunk::DollarDollar);
                     }
                     Rule::end_dollar => {
-                        log::warn!("unescaped dollar in the end of line '{}'", &input);
+                        tracing::warn!("unescaped dollar in the end of line '{}'", &input);
                         out.push(ParseChunk::DollarDollar);
                     }
-                    Rule::variable_name => {
+                    Rule::variable_name | Rule::braced_name => {
                         out.push(ParseChunk::Variable(inner_pair.as_str().to_string()));
                     }
                     Rule::literal => {
@@ -76,4 +76,3 @@ impl VariableParser {
         Ok(out)
     }
 }
-
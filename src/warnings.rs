@@ -0,0 +1,249 @@
+//! Non-fatal anomalies noticed while parsing.
+//!
+//! None of these stop a parse, but each usually means the template and the input
+//! disagree somewhere. Services can log them, count them, or fail on them as they see fit.
+
+use crate::{Records, ValidationIssue, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What went wrong; see [`ParseWarning`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WarningKind {
+    /// A record was emitted before its `Filldown` value had ever been captured.
+    FilldownWithoutValue { value: String },
+    /// A `List` capture was appended to a value holding a single string, so the value
+    /// was promoted to a list.
+    ListPromoted { value: String },
+    /// `Record` was requested but the record was discarded because these `Required`
+    /// values were unset.
+    RecordDropped { missing: Vec<String> },
+    /// A value was cut to fit `max_list_len` or `max_field_bytes`; reported once per
+    /// value and record.
+    FieldLimited { value: String },
+    /// A captured value was rejected by a validator.
+    Validation(ValidationIssue),
+}
+
+/// A non-fatal issue, tied to the input line being processed when it was noticed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParseWarning {
+    /// The 1-based input line number (the last line for anything raised at end of input).
+    pub line_number: usize,
+    #[serde(flatten)]
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line_number)?;
+        match &self.kind {
+            WarningKind::FilldownWithoutValue { value } => {
+                write!(f, "record emitted before Filldown value {} was set", value)
+            }
+            WarningKind::ListPromoted { value } => {
+                write!(f, "single value {} promoted to a list", value)
+            }
+            WarningKind::RecordDropped { missing } => write!(
+                f,
+                "record dropped, Required value(s) unset: {}",
+                missing.join(", ")
+            ),
+            WarningKind::FieldLimited { value } => write!(
+                f,
+                "value {} cut to the max_list_len / max_field_bytes limit",
+                value
+            ),
+            WarningKind::Validation(issue) => write!(f, "{}", issue),
+        }
+    }
+}
+
+/// The warnings raised by one parse, in input order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Warnings(Vec<ParseWarning>);
+
+impl Warnings {
+    pub(crate) fn push(&mut self, line_number: usize, kind: WarningKind) {
+        self.0.push(ParseWarning { line_number, kind });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ParseWarning> {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a ParseWarning;
+    type IntoIter = std::slice::Iter<'a, ParseWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Warnings {
+    type Item = ParseWarning;
+    type IntoIter = std::vec::IntoIter<ParseWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Why a record was discarded; see [`DroppedRecord`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DropReason {
+    /// `Record` was requested while these `Required` values were unset.
+    MissingRequired { missing: Vec<String> },
+    /// `Clear` or `Clearall` discarded values captured since the last record.
+    Cleared,
+    /// `TextFsmOptions::dedupe` found the record to be a duplicate.
+    Duplicate,
+}
+
+/// A record the engine discarded, with the values it held at the time.
+///
+/// Collected when `TextFsmOptions::track_dropped` is set.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DroppedRecord {
+    /// The 1-based input line being processed (the last line at end of input).
+    pub line_number: usize,
+    /// The state the engine was in.
+    pub state: String,
+    #[serde(flatten)]
+    pub reason: DropReason,
+    /// The values the record held; for `Cleared`, only the ones discarded.
+    pub fields: HashMap<String, Value>,
+}
+
+impl fmt::Display for DroppedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} ({}): record ", self.line_number, self.state)?;
+        match &self.reason {
+            DropReason::MissingRequired { missing } => write!(
+                f,
+                "dropped, Required value(s) unset: {}",
+                missing.join(", ")
+            )?,
+            DropReason::Cleared => write!(f, "cleared before it was recorded")?,
+            DropReason::Duplicate => write!(f, "dropped as a duplicate")?,
+        }
+        let mut names: Vec<&String> = self.fields.keys().collect();
+        names.sort();
+        for (i, name) in names.into_iter().enumerate() {
+            let sep = if i == 0 { " {" } else { "," };
+            write!(f, "{} {}: {}", sep, name, self.fields[name])?;
+        }
+        if !self.fields.is_empty() {
+            write!(f, " }}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The records of a parse together with the warnings it raised.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParseOutcome {
+    pub records: Records,
+    pub warnings: Warnings,
+    /// Records the engine discarded, when `TextFsmOptions::track_dropped` is set.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped: Vec<DroppedRecord>,
+}
+
+impl ParseOutcome {
+    /// Merges the engine's warnings and validation issues into line order.
+    pub(crate) fn new(
+        records: Records,
+        warnings: &Warnings,
+        issues: &[ValidationIssue],
+        dropped: &[DroppedRecord],
+    ) -> Self {
+        let mut all = warnings.clone();
+        for issue in issues {
+            all.push(issue.line_number, WarningKind::Validation(issue.clone()));
+        }
+        all.0.sort_by_key(|w| w.line_number);
+        ParseOutcome {
+            records,
+            warnings: all,
+            dropped: dropped.to_vec(),
+        }
+    }
+}
+
+/// How serious a [`Deprecation`] or a [`Diagnostic`](crate::Diagnostic) is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A template's `# Deprecated:` header, such as `# Deprecated: use show_vlan.textfsm`.
+///
+/// The message may start with a severity, as in `# Deprecated: error: removed in 2.0`;
+/// without one it is [`Severity::Warning`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Deprecation {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Deprecation {
+    /// Reads the `Deprecated` key of a template's metadata.
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        let value = metadata.get("Deprecated")?;
+        let (severity, message) = match value.split_once(':') {
+            Some((tag, message)) => match tag.trim().to_ascii_lowercase().as_str() {
+                "info" => (Severity::Info, message.trim()),
+                "warning" => (Severity::Warning, message.trim()),
+                "error" => (Severity::Error, message.trim()),
+                _ => (Severity::Warning, value.as_str()),
+            },
+            None => (Severity::Warning, value.as_str()),
+        };
+        Some(Deprecation {
+            severity,
+            message: message.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message.as_str() {
+            "" => write!(f, "deprecated"),
+            message => write!(f, "deprecated: {}", message),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Following a CLI table's index and templates on disk.
+//!
+//! [`CliTable::watch`] hands the table to a [`WatchedCliTable`], which watches the
+//! directory of every index file. A change to an index reloads the whole table; a
+//! change to any other file drops that file's cached template, so the next parse
+//! compiles the new version. Long-running services pick up template updates without
+//! a restart.
+
+use crate::{CliTable, Records, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use tracing::{debug, warn};
+
+/// A [`CliTable`] kept in sync with the files it was loaded from.
+///
+/// Watching stops when this is dropped.
+pub struct WatchedCliTable {
+    table: Arc<RwLock<CliTable>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl std::fmt::Debug for WatchedCliTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchedCliTable")
+            .field("table", &self.table)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WatchedCliTable {
+    /// The current table. Hold the guard briefly; reloads wait for it.
+    pub fn table(&self) -> RwLockReadGuard<'_, CliTable> {
+        self.table.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Parses `input` with the current table, as [`CliTable::parse`].
+    pub fn parse(&self, platform: &str, cmd: &str, input: &str) -> Result<Records> {
+        self.table().parse(platform, cmd, input)
+    }
+}
+
+impl CliTable {
+    /// Starts watching the index files and their template directories.
+    pub fn watch(self) -> Result<WatchedCliTable> {
+        let indexes: Vec<PathBuf> = self
+            .tables
+            .iter()
+            .map(|table| PathBuf::from(&table.fname))
+            .collect();
+        let table = Arc::new(RwLock::new(self));
+        let handler_table = Arc::clone(&table);
+        let handler_indexes = indexes.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) => handle_event(&handler_table, &handler_indexes, &event),
+                Err(e) => warn!("Template watch error: {}", e),
+            })?;
+        for index in &indexes {
+            let dir = match index.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            debug!("Watching {} for template changes", dir.display());
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        Ok(WatchedCliTable {
+            table,
+            _watcher: watcher,
+        })
+    }
+}
+
+fn handle_event(table: &RwLock<CliTable>, indexes: &[PathBuf], event: &Event) {
+    if event.kind.is_access() || event.kind.is_other() {
+        return;
+    }
+    let index_changed = event.paths.iter().any(|path| {
+        indexes
+            .iter()
+            .any(|index| crate::cli_table::same_file(index, path))
+    });
+    if index_changed {
+        let mut table = table.write().unwrap_or_else(|e| e.into_inner());
+        match table.reload() {
+            Ok(()) => debug!("Reloaded cli table index"),
+            // Editors often write in several steps; keep the old table until a
+            // later event brings a complete index.
+            Err(e) => warn!("Keeping previous cli table, reload failed: {}", e),
+        }
+    } else {
+        let table = table.read().unwrap_or_else(|e| e.into_inner());
+        for path in &event.paths {
+            debug!("Invalidating cached template {}", path.display());
+            table.invalidate_template(path);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use asyncfsm::{TextFSM, TextFsmError, TextFsmOptions, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const TEMPLATE: &str = r###"Value Port (\S+)
+Value Status (up|down)
+
+Start
+  ^${Port} is ${Status} -> Call(Label) Call(CountDown) Record
+  ^end -> Call(Missing)
+"###;
+
+#[test]
+fn test_call_actions() {
+    let down = Arc::new(AtomicUsize::new(0));
+    let counter = down.clone();
+    let options = TextFsmOptions::default()
+        .action("Label", |ctx| {
+            let status = ctx.captures.get("Status").unwrap().to_string();
+            let label = format!("{} ({}, line {})", status, ctx.state, ctx.line_number);
+            ctx.record
+                .fields
+                .insert("Label".into(), Value::Single(label));
+            Ok(())
+        })
+        .action("CountDown", move |ctx| {
+            if ctx.line.ends_with("down") {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(())
+        });
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm
+        .parse_records("Gi0/1 is up\nGi0/2 is down\n", None)
+        .unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(
+        result[0].get("Label").unwrap().to_string(),
+        "up (Start, line 1)"
+    );
+    assert_eq!(
+        result[1].get("Label").unwrap().to_string(),
+        "down (Start, line 2)"
+    );
+    assert_eq!(down.load(Ordering::SeqCst), 1);
+
+    fsm.reset();
+    match fsm.parse_records("end\n", None) {
+        Err(TextFsmError::ActionError {
+            action,
+            line_number,
+            ..
+        }) => assert_eq!((action.as_str(), line_number), ("Missing", 1)),
+        other => panic!("expected an action error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_failing_action_aborts() {
+    let template = "Value X (\\S+)\n\nStart\n  ^${X} -> Call(Check)\n";
+    let options = TextFsmOptions::default().action("Check", |ctx| match ctx.record.get("X") {
+        Some(Value::Single(x)) if x == "bad" => Err("bad value".to_string()),
+        _ => Ok(()),
+    });
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let err = fsm.parse_records("good\nbad\n", None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Action 'Check' failed at line 2: bad value (template line 4)"
+    );
+}
@@ -0,0 +1,70 @@
+use asyncfsm::{AddressFormats, DataRecordConversion, Ipv6Format, MacFormat, TextFSM, Value};
+
+#[test]
+fn test_mac_formats() {
+    for (format, expected) in [
+        (MacFormat::Colon, "aa:bb:cc:dd:ee:ff"),
+        (MacFormat::Hyphen, "aa-bb-cc-dd-ee-ff"),
+        (MacFormat::Dotted, "aabb.ccdd.eeff"),
+        (MacFormat::Bare, "aabbccddeeff"),
+    ] {
+        for input in ["AABB.CCDD.EEFF", "aa:bb:cc:dd:ee:ff", "AA-BB-CC-DD-EE-FF"] {
+            assert_eq!(format.format(input).as_deref(), Some(expected), "{}", input);
+        }
+    }
+    assert_eq!(MacFormat::Colon.format("aabb.ccdd"), None);
+    assert_eq!(MacFormat::Colon.format("incomplete"), None);
+}
+
+#[test]
+fn test_ipv6_formats() {
+    let full = "2001:0db8:0000:0000:0000:0000:0000:0001";
+    assert_eq!(
+        Ipv6Format::Compressed.format(full).as_deref(),
+        Some("2001:db8::1")
+    );
+    assert_eq!(
+        Ipv6Format::Expanded.format("2001:DB8::1/64").as_deref(),
+        Some("2001:0db8:0000:0000:0000:0000:0000:0001/64")
+    );
+    assert_eq!(Ipv6Format::Compressed.format("10.0.0.1"), None);
+    assert_eq!(Ipv6Format::Compressed.format("2001:db8::/200"), None);
+}
+
+#[test]
+fn test_normalize_addresses_conversion() {
+    let template = r###"Value MAC (\S+)
+Value List ADDRESSES (\S+)
+Value OTHER (\S+)
+
+Start
+  ^mac ${MAC} ${OTHER}
+  ^ ${ADDRESSES}
+  ^end -> Record
+
+EOF
+"###;
+    let formats = AddressFormats::new()
+        .mac("mac", MacFormat::Colon)
+        .ipv6("ADDRESSES", Ipv6Format::Compressed);
+    let records = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records(
+            "mac 0011.2233.4455 0011.2233.4455\n 2001:DB8:0:0::1/64\n fe80::1%eth0\nend\n",
+            Some(DataRecordConversion::NormalizeAddresses(formats)),
+        )
+        .unwrap();
+    assert_eq!(
+        records[0].fields["MAC"],
+        Value::Single("00:11:22:33:44:55".into())
+    );
+    assert_eq!(
+        records[0].fields["OTHER"],
+        Value::Single("0011.2233.4455".into())
+    );
+    // A scoped address is not a plain IPv6 address and stays as it is.
+    assert_eq!(
+        records[0].fields["ADDRESSES"],
+        Value::List(vec!["2001:db8::1/64".into(), "fe80::1%eth0".into()])
+    );
+}
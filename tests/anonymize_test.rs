@@ -0,0 +1,82 @@
+use asyncfsm::{Anonymizer, TextFSM};
+
+const TEMPLATE: &str = r#"Value HOSTNAME (\S+)
+Value List IP (\d+\.\d+\.\d+\.\d+(?:/\d+)?)
+Value MAC ([0-9a-f.]+)
+Value SERIAL (\w+)
+
+Start
+  ^hostname ${HOSTNAME}
+  ^ip ${IP}
+  ^mac ${MAC}
+  ^serial ${SERIAL} -> Record
+"#;
+
+const INPUT: &str = "hostname edge-7
+ip 10.1.1.1
+ip 10.1.1.10/24
+mac 00aa.bb12.cd34
+serial FOC1234X
+edge-7#ping 10.1.1.1 source 10.1.1.10
+";
+
+fn anonymize(fields: &[&str]) -> (Anonymizer, String) {
+    let records = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .parse_records(INPUT, None)
+        .unwrap();
+    let mut anonymizer = Anonymizer::new(fields.iter().copied());
+    anonymizer.learn(&records);
+    let text = anonymizer.rewrite(INPUT);
+    (anonymizer, text)
+}
+
+#[test]
+fn test_values_are_replaced_consistently() {
+    let (anonymizer, text) = anonymize(&["hostname", "IP", "MAC", "SERIAL"]);
+    assert_eq!(anonymizer.len(), 6);
+    let host = anonymizer.pseudonym("edge-7").unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[1], "ip 192.0.2.1");
+    assert_eq!(lines[2], "ip 192.0.2.2/24");
+    assert_eq!(
+        lines[5],
+        format!("{}#ping 192.0.2.1 source 192.0.2.2", host)
+    );
+    for secret in ["edge-7", "10.1.1", "00aa.bb12.cd34", "FOC1234X"] {
+        assert!(!text.contains(secret), "{} left in\n{}", secret, text);
+    }
+}
+
+#[test]
+fn test_pseudonyms_keep_the_shape_of_the_value() {
+    let (anonymizer, text) = anonymize(&["HOSTNAME", "MAC", "SERIAL"]);
+    let shape = |s: &str| -> String {
+        s.chars()
+            .map(|c| match c {
+                '0'..='9' => '9',
+                'a'..='z' => 'a',
+                'A'..='Z' => 'A',
+                c => c,
+            })
+            .collect()
+    };
+    assert_eq!(shape(anonymizer.pseudonym("FOC1234X").unwrap()), "AAA9999A");
+    assert_eq!(shape(anonymizer.pseudonym("edge-7").unwrap()), "aaaa-9");
+    let mac = anonymizer.pseudonym("00aa.bb12.cd34").unwrap();
+    assert!(mac.chars().all(|c| c == '.' || c.is_ascii_hexdigit()));
+    assert_eq!(mac.len(), 14);
+    // The template still parses the rewritten text the same way.
+    let records = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .parse_records(&text, None)
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        records[0].get("SERIAL").unwrap().to_string(),
+        anonymizer.pseudonym("FOC1234X").unwrap()
+    );
+    // Unselected fields are left alone, and the result is the same on every run.
+    assert!(text.contains("ip 10.1.1.1\n"));
+    assert_eq!(anonymize(&["HOSTNAME", "MAC", "SERIAL"]).1, text);
+}
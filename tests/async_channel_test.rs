@@ -0,0 +1,94 @@
+#![cfg(feature = "async")]
+
+use asyncfsm::TextFSM;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+const TEMPLATE: &str = "Value N (\\d+)\n\nStart\n  ^${N} -> Record\n";
+
+#[tokio::test]
+async fn test_parse_to_channel_sends_every_record() {
+    let (tx, mut rx) = mpsc::channel(4);
+    let input = "1\n2\n3\n";
+    let parse = tokio::spawn(async move {
+        let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+        fsm.parse_to_channel(input.as_bytes(), tx, None).await
+    });
+    let mut values = vec![];
+    while let Some(record) = rx.recv().await {
+        values.push(record.get("N").unwrap().to_string());
+    }
+    assert_eq!(values, ["1", "2", "3"]);
+    assert_eq!(parse.await.unwrap().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_a_full_channel_pauses_reading() {
+    let (mut writer, reader) = tokio::io::duplex(64);
+    let written = Arc::new(AtomicBool::new(false));
+    let done = Arc::clone(&written);
+    tokio::spawn(async move {
+        for n in 0..1000 {
+            writer
+                .write_all(format!("{}\n", n).as_bytes())
+                .await
+                .unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+    });
+    let (tx, mut rx) = mpsc::channel(1);
+    let parse = tokio::spawn(async move {
+        let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+        fsm.parse_to_channel(BufReader::new(reader), tx, None).await
+    });
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    // Nobody receives, so the parser waits on the channel and stops draining the pipe.
+    assert!(!written.load(Ordering::SeqCst));
+    assert_eq!(rx.len(), 1);
+
+    let mut received = 0;
+    while rx.recv().await.is_some() {
+        received += 1;
+    }
+    assert_eq!(received, 1000);
+    assert!(written.load(Ordering::SeqCst));
+    assert_eq!(parse.await.unwrap().unwrap(), 1000);
+}
+
+#[tokio::test]
+async fn test_dropping_the_receiver_stops_the_parse() {
+    let (tx, rx) = mpsc::channel(1);
+    drop(rx);
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let sent = fsm
+        .parse_to_channel("1\n2\n".as_bytes(), tx, None)
+        .await
+        .unwrap();
+    assert_eq!(sent, 0);
+}
+
+#[tokio::test]
+async fn test_max_records_holds_within_a_line() {
+    use asyncfsm::TextFsmOptions;
+
+    // Each line emits two records.
+    let template = "Value N (\\d+)\n\nStart\n  ^${N} -> Continue.Record\n  ^\\d+ ${N} -> Record\n";
+    let (tx, mut rx) = mpsc::channel(4);
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().max_records(3));
+    let sent = fsm
+        .parse_to_channel("1 2\n3 4\n".as_bytes(), tx, None)
+        .await
+        .unwrap();
+    assert_eq!(sent, 3);
+    let mut values = vec![];
+    while let Some(record) = rx.recv().await {
+        values.push(record.get("N").unwrap().to_string());
+    }
+    assert_eq!(values, ["1", "2", "3"]);
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "async")]
+
+use asyncfsm::{CliTable, TextFsmError};
+
+const INDEX: &str = "tests/basic_template/cli/index";
+
+#[tokio::test]
+async fn test_parse_async_matches_sync() {
+    let table = CliTable::from_file_async(INDEX).await.unwrap();
+    let input = std::fs::read_to_string("tests/basic_template/cli/cisco_version_example").unwrap();
+
+    let records = table
+        .parse_async("Cisco", "show version", input.as_bytes())
+        .await
+        .unwrap();
+    let expected = CliTable::from_file(INDEX)
+        .unwrap()
+        .parse("Cisco", "show version", &input)
+        .unwrap();
+    assert_eq!(records, expected);
+    assert!(!records.is_empty());
+}
+
+#[tokio::test]
+async fn test_preload_async_fills_cache() {
+    let table = CliTable::from_file_async(INDEX).await.unwrap();
+    let compiled = table.preload_async().await.unwrap();
+    assert!(compiled > 0);
+    assert_eq!(table.template_cache.read().unwrap().len(), compiled);
+
+    // Clones share the cache, so nothing is compiled twice.
+    let clone = table.clone();
+    assert_eq!(clone.preload_async().await.unwrap(), compiled);
+    assert_eq!(table.template_cache.read().unwrap().len(), compiled);
+}
+
+#[tokio::test]
+async fn test_parse_async_unknown_command() {
+    let table = CliTable::from_file_async(INDEX).await.unwrap();
+    let err = table
+        .parse_async("Cisco", "show nothing", &b""[..])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TextFsmError::TemplateNotFound(_)));
+}
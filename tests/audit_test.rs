@@ -0,0 +1,52 @@
+#![cfg(feature = "clitable")]
+
+use asyncfsm::{CliTable, PlatformCoverage};
+
+#[test]
+fn test_audit_index() {
+    let dir = std::env::temp_dir().join("asyncfsm_audit_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = [
+        (
+            "index",
+            "Template, Hostname, Platform, Command\n\
+             ok.textfsm, .*, cisco_ios, sh[[ow]] ver[[sion]]\n\
+             ok.textfsm:broken.textfsm, .*, cisco_ios, sh[[ow]] ip int[[erface]] br[[ief]]\n\
+             missing.textfsm, .*, arista_eos, sh[[ow]] vlan\n",
+        ),
+        ("ok.textfsm", "Value A (\\S+)\n\nStart\n  ^${A} -> Record\n"),
+        (
+            "broken.textfsm",
+            "Value A (\\S+)\n\nStart\n  ^${B} -> Record\n",
+        ),
+    ];
+    for (file, content) in files {
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+
+    let audit = CliTable::from_file(dir.join("index")).unwrap().audit();
+    assert_eq!(audit.engine_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!((audit.templates, audit.compiled), (3, 1));
+    assert!(!audit.is_compatible());
+    assert_eq!(
+        audit.platforms,
+        vec![
+            PlatformCoverage {
+                platform: "arista_eos".into(),
+                commands: 1
+            },
+            PlatformCoverage {
+                platform: "cisco_ios".into(),
+                commands: 2
+            },
+        ]
+    );
+    let failed: Vec<&str> = audit
+        .failures
+        .iter()
+        .map(|f| f.template.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(failed, vec!["broken.textfsm", "missing.textfsm"]);
+    assert!(audit.to_string().contains("3 templates, 1 compile, 2 fail"));
+}
@@ -18,7 +18,7 @@ Interface GigabitEthernet0/2 is down
 "#;
 
     let mut fsm = TextFSM::from_string(template).unwrap();
-    let result = fsm.parse_string(data, None).unwrap();
+    let result = fsm.parse_records(data, None).unwrap();
 
     assert_eq!(result.len(), 2);
 
@@ -65,7 +65,7 @@ interface Vlan1
 "#;
 
     let mut fsm = TextFSM::from_string(template).unwrap();
-    let result = fsm.parse_string(data, None).unwrap();
+    let result = fsm.parse_records(data, None).unwrap();
 
     assert_eq!(result.len(), 4);
     assert_eq!(
@@ -134,6 +134,62 @@ fn test_clitable_parsing() {
     assert_eq!(row.templates, vec!["clitable_templateD"]);
 }
 
+#[test]
+#[cfg(feature = "clitable")]
+fn test_clitable_template_fallback() {
+    use asyncfsm::CliTable;
+    let cli_table = CliTable::from_file("tests/basic_template/template/parseindex_index").unwrap();
+
+    // The first template of the row matches three columns.
+    let found = cli_table
+        .parse_match("VendorA", "sh ver", "a b c\n")
+        .unwrap();
+    assert!(found.template.ends_with("clitable_templateA"));
+    assert_eq!(found.records.len(), 1);
+
+    // With two columns only the second template yields records.
+    let found = cli_table
+        .parse_match("VendorA", "sh ver", "a b\nx y\n")
+        .unwrap();
+    assert!(found.template.ends_with("clitable_templateB"));
+    assert_eq!(found.records.len(), 2);
+    assert_eq!(found.records[0].get("Col4").unwrap().to_string(), "b");
+    assert_eq!(found.unmatched_lines, 0);
+
+    // When nothing matches, the first template's empty result is kept.
+    let found = cli_table
+        .parse_match("VendorA", "sh ver", "nothing here\n")
+        .unwrap();
+    assert!(found.template.ends_with("clitable_templateA"));
+    assert!(found.records.is_empty());
+    assert_eq!(found.unmatched_ratio(), 1.0);
+}
+
+#[test]
+#[cfg(feature = "clitable")]
+fn test_clitable_parse_best() {
+    use asyncfsm::CliTable;
+    let cli_table = CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let input =
+        std::fs::read_to_string("tests/basic_template/cli/cisco_bgp_summary_example").unwrap();
+
+    let best = cli_table.parse_best(Some("Cisco"), &input).unwrap();
+    assert_eq!(best.platform, "Cisco");
+    assert_eq!(best.command, "show ip bgp summary");
+    assert!(best.result.template.ends_with("cisco_bgp_summary_template"));
+    assert!(best.score > 0.0 && best.score <= 1.0);
+    assert_eq!(best.score, 1.0 - best.result.unmatched_ratio());
+
+    // Without a hint every platform competes, and the Cisco template still fits best.
+    let any = cli_table.parse_best(None, &input).unwrap();
+    assert_eq!(any.result.template, best.result.template);
+
+    assert!(cli_table
+        .parse_best(Some("Cisco"), "nothing to see\n")
+        .is_none());
+    assert!(cli_table.parse_best(Some("NoSuchVendor"), &input).is_none());
+}
+
 #[test]
 #[cfg(feature = "clitable")]
 fn test_clitable_parse_fail() {
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "clitable", feature = "yaml"))]
+use asyncfsm::{CliTable, Inventory, ResultEnvelope};
+use std::path::Path;
+
+#[test]
+fn test_inventory_run() {
+    let table = CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let inventory = Inventory::from_file("tests/batch/hosts.yaml").unwrap();
+    let results = inventory.run(&table, Path::new("tests/batch")).unwrap();
+
+    assert_eq!(results.len(), 2);
+    let r1 = &results["r1"];
+    assert_eq!(r1.platform, "Cisco");
+    assert_eq!(r1.commands.len(), 2);
+    assert_eq!(
+        r1.commands["show version"][0]
+            .get("Version")
+            .unwrap()
+            .to_string(),
+        "12.2(31)SGA1"
+    );
+    assert!(!r1.commands["show ip bgp summary"].is_empty());
+    assert!(!results["j1"].commands["show version"].is_empty());
+}
+
+#[test]
+fn test_inventory_missing_capture() {
+    let table = CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let inventory = Inventory::from_yaml_str(
+        "hosts:\n  r9:\n    platform: Cisco\n    captures:\n      show version: nope.txt\n",
+    )
+    .unwrap();
+    assert!(inventory.run(&table, Path::new("tests/batch")).is_err());
+}
+
+#[test]
+fn test_inventory_keep_going() {
+    let table = CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let inventory = Inventory::from_yaml_str(
+        "hosts:\n  r1:\n    platform: Cisco\n    captures:\n      show version: r1/show_version.txt\n      show clock: r1/show_version.txt\n      show ip bgp summary: nope.txt\n",
+    )
+    .unwrap();
+    let results = inventory.run_keep_going(&table, Path::new("tests/batch"));
+    let r1 = &results["r1"];
+    assert_eq!(r1.commands.len(), 1);
+    assert!(!r1.commands["show version"].is_empty());
+    assert_eq!(r1.errors.len(), 2);
+    assert!(r1.errors["show clock"].contains("Template not found"));
+    assert!(r1.errors["show ip bgp summary"].contains("I/O error"));
+}
+
+#[test]
+fn test_inventory_envelopes() {
+    let table = CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let inventory = Inventory::from_yaml_str(
+        "hosts:\n  r1:\n    platform: Cisco\n    captures:\n      show version: r1/show_version.txt\n      show clock: r1/show_version.txt\n",
+    )
+    .unwrap();
+    let envelopes = inventory.run_envelopes(&table, Path::new("tests/batch"));
+    let summary: Vec<(&str, &str, bool)> = envelopes
+        .iter()
+        .map(|e| (e.host.as_str(), e.command.as_str(), e.failed))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("r1", "show clock", true), ("r1", "show version", false)]
+    );
+    let ResultEnvelope { records, error, .. } = &envelopes[1];
+    assert!(!records.is_empty() && error.is_none());
+    assert!(envelopes[0].records.is_empty());
+    assert!(envelopes[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("Template not found"));
+}
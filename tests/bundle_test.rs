@@ -0,0 +1,47 @@
+#![cfg(feature = "bundle")]
+use asyncfsm::{Bundle, BundleOptions, BundleOutput, DataRecordConversion, TextFsmOptions};
+
+const TEMPLATE: &str = "Value Name (\\S+)\n\nStart\n  ^Name: ${Name} -> Record\n  ^skip -> Other\n\nOther\n  ^Name: ${Name} -> Record\n";
+
+#[test]
+fn test_bundle_round_trip() {
+    let options = BundleOptions::from(&TextFsmOptions::default().start_state("Other"))
+        .with_conversion(Some(&DataRecordConversion::LowercaseKeys));
+    let bundle = Bundle::new(
+        "templates/people.textfsm",
+        TEMPLATE,
+        "/captures/people.txt",
+        b"Name: a\r\nName: b\n".to_vec(),
+        options,
+    )
+    .expected(serde_json::json!([{"name": "a"}]));
+    assert_eq!(bundle.template_name, "people.textfsm");
+    let BundleOutput::Records(records) = &bundle.actual else {
+        panic!("{:?}", bundle.actual);
+    };
+    assert_eq!(records[0]["name"], "a");
+
+    let mut archive = vec![];
+    bundle.write_to(&mut archive).unwrap();
+    let read = Bundle::read_from(archive.as_slice()).unwrap();
+    assert_eq!(read, bundle);
+    assert_eq!(read.options.start_state.as_deref(), Some("Other"));
+    assert_eq!(read.run(), bundle.actual);
+}
+
+#[test]
+fn test_bundle_records_template_errors() {
+    let bundle = Bundle::new("bad", "Value X (\n", "in", vec![], BundleOptions::default());
+    assert!(matches!(&bundle.actual, BundleOutput::Error(e) if e.contains("line 1")));
+    let mut archive = vec![];
+    bundle.write_to(&mut archive).unwrap();
+    assert_eq!(
+        Bundle::read_from(archive.as_slice()).unwrap().run(),
+        bundle.actual
+    );
+}
+
+#[test]
+fn test_bundle_rejects_other_files() {
+    assert!(Bundle::read_from(&b"not a bundle"[..]).is_err());
+}
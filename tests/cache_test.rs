@@ -0,0 +1,55 @@
+#![cfg(feature = "cache")]
+use asyncfsm::{ResultCache, TextFSM, TextFsmOptions};
+use std::path::PathBuf;
+
+const TEMPLATE: &str =
+    "Value Key NAME (\\S+)\nValue AGE (\\d+)\n\nStart\n  ^${NAME} is ${AGE} -> Record\n";
+
+fn scratch(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("asyncfsm-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.txt");
+    std::fs::write(&input, "ann is 30\nbob is 41\n").unwrap();
+    (dir.join("cache"), input)
+}
+
+#[test]
+fn test_cache_hit() {
+    let (dir, input) = scratch("cache-hit");
+    let cache = ResultCache::new(&dir);
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let first = cache.parse_file(&mut fsm, &input).unwrap();
+    assert!(!first.cached);
+    let second = cache.parse_file(&mut fsm, &input).unwrap();
+    assert!(second.cached);
+    assert_eq!(second.records, first.records);
+    assert_eq!(second.records.schema(), ["NAME", "AGE"]);
+    assert!(second.records[0].record_key.is_some());
+    assert_eq!(second.records[0].record_key, first.records[0].record_key);
+    assert_eq!(second.lines, 2);
+
+    // Another template, or other input bytes, is another entry.
+    let mut other = TextFSM::from_string(&TEMPLATE.replace("\\d+", "\\d{2}")).unwrap();
+    assert!(!cache.parse_file(&mut other, &input).unwrap().cached);
+    std::fs::write(&input, "ann is 31\n").unwrap();
+    let changed = cache.parse_file(&mut fsm, &input).unwrap();
+    assert!(!changed.cached);
+    assert_eq!(changed.records[0].get("AGE").unwrap().to_string(), "31");
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_cache_bypassed_for_actions() {
+    let (dir, input) = scratch("cache-actions");
+    let cache = ResultCache::new(&dir);
+    let options = TextFsmOptions::default().action("Noop", |_| Ok(()));
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    assert!(ResultCache::key(&fsm.parser, &fsm.options, b"").is_none());
+    cache.parse_file(&mut fsm, &input).unwrap();
+    assert!(!cache.parse_file(&mut fsm, &input).unwrap().cached);
+    assert!(!dir.exists());
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,126 @@
+use asyncfsm::{Coercion, Coercions, DataRecordConversion, DecimalMark, TextFSM, Value};
+
+fn check(coercion: Coercion, cases: &[(&str, Option<&str>)]) {
+    for (text, expected) in cases {
+        assert_eq!(
+            coercion.apply(text).as_deref(),
+            *expected,
+            "{:?} of {:?}",
+            coercion,
+            text
+        );
+    }
+}
+
+#[test]
+fn test_durations() {
+    check(
+        Coercion::Duration,
+        &[
+            ("1w2d", Some("777600")),
+            ("2d03h", Some("183600")),
+            ("5h30m10s", Some("19810")),
+            ("01:02:03", Some("3723")),
+            ("3d04:05:06", Some("273906")),
+            ("1.5h", Some("5400")),
+            (
+                "1 year, 2 weeks, 3 days, 4 hours and 5 minutes",
+                Some("33019500"),
+            ),
+            ("45 secs", Some("45")),
+            ("never", None),
+            ("5mo", None),
+            ("", None),
+        ],
+    );
+}
+
+#[test]
+fn test_rates_and_sizes() {
+    check(
+        Coercion::Bandwidth,
+        &[
+            ("4.5 Gbps", Some("4500000000")),
+            ("100 Mbit/sec", Some("100000000")),
+            ("1000 Kb/s", Some("1000000")),
+            ("10M", Some("10000000")),
+            ("1544 bits/sec", Some("1544")),
+            ("0.5 bps", Some("0.5")),
+            ("fast", None),
+            ("10 MB", None),
+        ],
+    );
+    check(
+        Coercion::Bytes,
+        &[
+            ("1024 KB", Some("1048576")),
+            ("1.5 MiB", Some("1572864")),
+            ("4G", Some("4294967296")),
+            ("512 bytes", Some("512")),
+            ("1 Kbps", None),
+        ],
+    );
+}
+
+#[test]
+fn test_locale_numbers() {
+    check(
+        Coercion::Number(DecimalMark::Auto),
+        &[
+            ("1.234,56", Some("1234.56")),
+            ("1,234,567.5", Some("1234567.5")),
+            ("1 234 567", Some("1234567")),
+            ("1\u{202f}234,5", Some("1234.5")),
+            ("1'234'567", Some("1234567")),
+            ("1.234.567", Some("1234567")),
+            ("-0,5", Some("-0.5")),
+            ("0.125", Some("0.125")),
+            ("42", Some("42")),
+            // One mark before three digits may be either.
+            ("1,234", None),
+            ("12,34,567", None),
+            ("1.234 567,8", None),
+            ("1,", None),
+        ],
+    );
+    check(
+        Coercion::Number(DecimalMark::Comma),
+        &[
+            ("1.234", Some("1234")),
+            ("1,234", Some("1.234")),
+            ("1,234.5", None),
+        ],
+    );
+    check(
+        Coercion::Number(DecimalMark::Point),
+        &[("1,234", Some("1234")), ("1.234,5", None)],
+    );
+}
+
+#[test]
+fn test_coerce_conversion() {
+    let template = r###"Value UPTIME (.+)
+Value List SIZES (\S+ \S+)
+
+Start
+  ^uptime ${UPTIME}
+  ^size ${SIZES}
+
+"###;
+    let coercions = Coercions::new()
+        .field("uptime", Coercion::Duration)
+        .field("SIZES", Coercion::Bytes);
+    let records = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records(
+            "uptime 2 weeks, 1 day\nsize 2 KB\nsize ? KB\n",
+            Some(DataRecordConversion::Coerce(coercions)),
+        )
+        .unwrap();
+    assert_eq!(records[0].fields["UPTIME"], Value::Single("1296000".into()));
+    // A value the coercion can not read is kept.
+    assert_eq!(
+        records[0].fields["SIZES"],
+        Value::List(vec!["2048".into(), "? KB".into()])
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "compress")]
+use asyncfsm::compress::Compression;
+use asyncfsm::{record_sink, TextFSM};
+use std::io::Read;
+
+const TEMPLATE: &str = "Value NAME (\\S+)\n\nStart\n  ^${NAME} -> Record\n";
+
+#[test]
+fn test_file_sinks_compress_by_extension() {
+    let dir = std::env::temp_dir().join(format!("asyncfsm_compress_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, compression) in [
+        ("out.ndjson.gz", Compression::Gzip),
+        ("out.ndjson.zst", Compression::Zstd),
+    ] {
+        let path = dir.join(file);
+        assert_eq!(Compression::from_path(&path), Some(compression));
+        let fsm = TextFSM::from_string(TEMPLATE).unwrap();
+        let mut sink = record_sink(&format!("ndjson:{}", path.display())).unwrap();
+        fsm.parse_reader("a\nb\n".as_bytes())
+            .write_to(sink.as_mut(), None)
+            .unwrap();
+
+        // Closing the sink ends the compressed stream; it need not be dropped first.
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!bytes.starts_with(b"{"), "{} is not compressed", file);
+        let mut text = String::new();
+        compression
+            .reader(bytes.as_slice())
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "{\"NAME\":\"a\"}\n{\"NAME\":\"b\"}\n");
+        drop(sink);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_closing_reports_a_failed_end_of_stream() {
+    let dir = std::env::temp_dir().join(format!("asyncfsm_compress_full_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Every write to /dev/full fails, but the output stays buffered until it is closed.
+    let path = dir.join("out.ndjson.gz");
+    std::os::unix::fs::symlink("/dev/full", &path).unwrap();
+    let mut sink = record_sink(&format!("ndjson:{}", path.display())).unwrap();
+    let fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let err = fsm
+        .parse_reader("a\nb\n".as_bytes())
+        .write_to(sink.as_mut(), None)
+        .unwrap_err();
+    assert!(err.to_string().contains("space"), "{}", err);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_compression_names() {
+    use std::io::Write;
+
+    assert_eq!("gzip".parse::<Compression>().unwrap(), Compression::Gzip);
+    assert_eq!("zst".parse::<Compression>().unwrap(), Compression::Zstd);
+    let err = "bzip2".parse::<Compression>().unwrap_err();
+    assert!(err.to_string().contains("gzip, zstd"), "{}", err);
+    assert_eq!(Compression::from_path("out.json"), None);
+
+    let mut writer = Compression::Zstd.writer(vec![]).unwrap();
+    writer.write_all(b"records").unwrap();
+    let compressed = writer.finish().unwrap();
+    let mut text = String::new();
+    Compression::Zstd
+        .reader(compressed.as_slice())
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "records");
+}
@@ -0,0 +1,67 @@
+use asyncfsm::{Severity, TextFSMParser, TextFsmError};
+
+#[test]
+fn test_all_capture_problems_are_reported_at_once() {
+    let template = "Value Host (\\S+)\nValue A (\\d+)\n\nStart\n  ^${HOST} up\n  ^${A} (?P<A>\\d+) -> Record\n  ^${Missing} ${Host}\n";
+    let Err(TextFsmError::TemplateErrors { file, diagnostics }) =
+        TextFSMParser::from_string(template)
+    else {
+        panic!("expected template errors");
+    };
+    assert_eq!(file, None);
+    let errors: Vec<String> = diagnostics.errors().map(|d| d.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "line 5, column 4: error: ${HOST} is not a declared Value (did you mean `${Host}`?)",
+            "line 6, column 3: error: A is captured more than once in this rule",
+            "line 7, column 4: error: ${Missing} is not a declared Value",
+        ]
+    );
+    let host = diagnostics.errors().next().unwrap();
+    assert_eq!(&template[host.span.start..host.span.end], "${HOST}");
+}
+
+#[test]
+fn test_compilation_goes_on_past_the_first_problem() {
+    let template = "Macro _IPV4_ (\\d+)\nValue Keyy A (\\S+)\nValue B (\\S+)\n\nstart\n  ^${A} ( -> Next\n  ^${A} -> Detial\n\nDetail\n  ^x\n";
+    let diagnostics = TextFSMParser::diagnose(template);
+    let found: Vec<(Severity, usize, &str, Option<&str>)> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.severity,
+                d.span.line,
+                d.message.as_str(),
+                d.suggestion.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Severity::Error, 1, "Macro _IPV4_ already defined", None),
+            (Severity::Error, 2, "Unknown option \"Keyy\"", Some("Key")),
+            (Severity::Warning, 3, "Value B is never captured", None),
+            (Severity::Error, 5, "Start state not found", Some("Start")),
+            (
+                Severity::Error,
+                6,
+                "invalid regex: Opening parenthesis without closing parenthesis",
+                None
+            ),
+            (
+                Severity::Error,
+                7,
+                "State Detial is not defined",
+                Some("Detail")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_macros_and_escaped_dollars_are_not_captures() {
+    let template = "Macro ip (\\d+\\.\\d+\\.\\d+\\.\\d+)\nValue Addr (${ip})\n\nStart\n  ^${Addr} costs $$5 from ${ip}\n";
+    assert!(TextFSMParser::diagnose(template).is_empty());
+}
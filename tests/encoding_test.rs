@@ -0,0 +1,62 @@
+use asyncfsm::{TextFSM, TextFsmOptions};
+
+const TEMPLATE: &str = r###"Value Name (\S+)
+Value Age (\d+)
+
+Start
+  ^My name is ${Name} and I am ${Age} years old -> Record
+"###;
+
+fn write_input(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("asyncfsm_encoding_{}", name));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn names(options: TextFsmOptions, path: &std::path::Path) -> Vec<String> {
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    fsm.parse_file_records(path, None)
+        .unwrap()
+        .iter()
+        .map(|r| r.get("Name").unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_parse_file_decodes_non_utf8_lossily() {
+    let path = write_input(
+        "latin1.txt",
+        b"My name is J\xe9r\xf4me and I am 42 years old\nMy name is Jane and I am 34 years old\n",
+    );
+    let names = names(TextFsmOptions::default(), &path);
+    assert_eq!(names.len(), 2);
+    #[cfg(feature = "encoding_rs")]
+    assert_eq!(names[0], "Jérôme");
+    #[cfg(not(feature = "encoding_rs"))]
+    assert_eq!(names[0], "J\u{FFFD}r\u{FFFD}me");
+    assert_eq!(names[1], "Jane");
+
+    let path = write_input(
+        "bom.txt",
+        b"\xEF\xBB\xBFMy name is Ann and I am 3 years old\n",
+    );
+    assert_eq!(self::names(TextFsmOptions::default(), &path), ["Ann"]);
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn test_explicit_encoding() {
+    let utf16: Vec<u8> = "My name is Zoë and I am 7 years old\n"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    let path = write_input("utf16.txt", &utf16);
+    let encoding = asyncfsm::encoding::for_label("utf-16le").unwrap();
+    assert_eq!(
+        names(TextFsmOptions::default().encoding(encoding), &path),
+        ["Zoë"]
+    );
+    assert!(asyncfsm::encoding::for_label("no-such-charset").is_err());
+}
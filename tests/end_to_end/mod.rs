@@ -4,7 +4,7 @@ use asyncfsm::TextFSM;
 fn test_end_to_end() {
     let mut textfsm = TextFSM::from_file("tests/end_to_end/sample.template").unwrap();
     let result = textfsm
-        .parse_file("tests/end_to_end/sample.data", None)
+        .parse_file_records("tests/end_to_end/sample.data", None)
         .unwrap();
 
     assert_eq!(result.len(), 2);
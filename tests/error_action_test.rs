@@ -0,0 +1,61 @@
+use asyncfsm::{TextFSM, TextFsmError};
+
+const TEMPLATE: &str = r###"Value Interface (\S+)
+
+Start
+  ^interface ${Interface} -> Record
+  ^\s*$$
+  ^. -> Error "Could not parse line:"
+"###;
+
+#[test]
+fn test_error_action_details() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let err = fsm
+        .parse_records("interface Gi0/1\n\ngarbage here\n", None)
+        .unwrap_err();
+    match err {
+        TextFsmError::TemplateErrorAction {
+            message,
+            state,
+            line_number,
+            line,
+            span,
+        } => {
+            assert_eq!(message.as_deref(), Some("Could not parse line:"));
+            assert_eq!(state, "Start");
+            assert_eq!(line_number, 3);
+            assert_eq!(line, "garbage here");
+            assert_eq!(span.line, 6);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_error_action_streaming() {
+    let fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let results: Vec<_> = fsm
+        .parse_reader(std::io::Cursor::new("interface Gi0/1\ngarbage\n"))
+        .collect();
+    assert!(results[0].is_ok());
+    let err = results[1].as_ref().unwrap_err().to_string();
+    assert!(err.contains("line 2"));
+    assert!(err.contains("Could not parse line:"));
+    assert!(err.contains("'garbage'"));
+}
+
+#[test]
+fn test_error_action_without_message() {
+    let template = "Start\n  ^. -> Error\n";
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let err = fsm.parse_records("x\n", None).unwrap_err();
+    assert!(matches!(
+        err,
+        TextFsmError::TemplateErrorAction {
+            message: None,
+            line_number: 1,
+            ..
+        }
+    ));
+}
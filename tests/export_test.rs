@@ -1,3 +1,6 @@
+// Exercises the `Vec<DataRecord>` API that `parse_string` kept for existing callers.
+#![allow(deprecated)]
+
 use asyncfsm::{OutputFormat, TextFSM, TextFsmExport};
 
 fn get_results() -> Vec<asyncfsm::DataRecord> {
@@ -0,0 +1,84 @@
+use asyncfsm::{DataRecord, DeviceFacts, DeviceModel, InterfaceFacts};
+
+fn record(fields: &[(&str, &str)]) -> DataRecord {
+    let mut rec = DataRecord::new();
+    for (k, v) in fields {
+        rec.insert(k.to_string(), v.to_string());
+    }
+    rec
+}
+
+#[test]
+fn test_facts_from_model() {
+    let model = DeviceModel::from_commands(vec![
+        (
+            "show version".to_string(),
+            vec![record(&[
+                ("HOSTNAME", "r1"),
+                ("VERSION", "15.2(4)M"),
+                ("HARDWARE", "ISR4331"),
+                ("SERIAL", "FDO1234"),
+            ])],
+        ),
+        (
+            "show interfaces".to_string(),
+            vec![
+                record(&[
+                    ("INTERFACE", "GigabitEthernet0/1"),
+                    ("LINK_STATUS", "up"),
+                    ("PROTOCOL_STATUS", "up (connected)"),
+                    ("MTU", "1500"),
+                    ("IP_ADDRESS", "10.0.0.1"),
+                    ("PREFIX_LENGTH", "24"),
+                ]),
+                record(&[
+                    ("INTERFACE", "GigabitEthernet0/2"),
+                    ("LINK_STATUS", "administratively down"),
+                    ("PROTOCOL_STATUS", "down"),
+                ]),
+            ],
+        ),
+        (
+            "show ip interface brief".to_string(),
+            vec![
+                record(&[("INTF", "Gi0/1"), ("IPADDR", "10.0.0.1")]),
+                record(&[("INTF", "Gi0/2"), ("IPADDR", "unassigned")]),
+            ],
+        ),
+    ]);
+    let facts = DeviceFacts::from_model(&model);
+
+    assert_eq!(facts.hostname.as_deref(), Some("r1"));
+    assert_eq!(facts.model.as_deref(), Some("ISR4331"));
+    assert_eq!(facts.serial.as_deref(), Some("FDO1234"));
+    assert_eq!(facts.version.as_deref(), Some("15.2(4)M"));
+    assert_eq!(facts.interfaces.len(), 2);
+    assert_eq!(
+        facts.interfaces["GigabitEthernet0/1"],
+        InterfaceFacts {
+            enabled: Some(true),
+            oper_status: Some("up".into()),
+            mtu: Some(1500),
+            ipv4: vec!["10.0.0.1/24".into()],
+            ..Default::default()
+        }
+    );
+    let gi02 = &facts.interfaces["GigabitEthernet0/2"];
+    assert_eq!(gi02.enabled, Some(false));
+    assert_eq!(gi02.oper_status.as_deref(), Some("down"));
+    assert!(gi02.ipv4.is_empty());
+}
+
+#[cfg(feature = "clitable")]
+#[test]
+fn test_facts_from_dir() {
+    let table = asyncfsm::CliTable::from_file("tests/basic_template/cli/index").unwrap();
+    let facts = DeviceFacts::from_dir(&table, Some("Cisco"), "tests/batch/r1").unwrap();
+    assert_eq!(facts.model.as_deref(), Some("WS-C4948-10GE"));
+    assert_eq!(facts.version.as_deref(), Some("12.2(31)SGA1"));
+    assert_eq!(
+        facts.uptime.as_deref(),
+        Some("3 days, 13 hours, 53 minutes")
+    );
+    assert!(facts.interfaces.is_empty());
+}
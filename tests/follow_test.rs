@@ -0,0 +1,50 @@
+#![cfg(feature = "follow")]
+
+use asyncfsm::follow::FollowFile;
+use asyncfsm::{CancellationToken, TextFSM, TextFsmOptions};
+use std::io::{BufReader, Write};
+use std::time::Duration;
+
+#[test]
+fn test_follow_file_streams_appended_lines() {
+    let dir = std::env::temp_dir().join(format!("asyncfsm-follow-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+    std::fs::write(&path, "log: one\n").unwrap();
+
+    let token = CancellationToken::new();
+    let reader = FollowFile::open(&path)
+        .unwrap()
+        .poll_interval(Duration::from_millis(10))
+        .cancel_token(token.clone());
+    let fsm = TextFSM::from_string("Value MSG (.+)\n\nStart\n  ^log: ${MSG}\n")
+        .unwrap()
+        .with_options(TextFsmOptions::default().line_mode(true));
+    let mut records = fsm.parse_reader(BufReader::new(reader));
+    assert_eq!(
+        records.next().unwrap().unwrap().fields["MSG"].to_string(),
+        "one"
+    );
+
+    let writer = {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            let mut log = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+            writeln!(log, "log: two").unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            // A truncated file is read again from the start.
+            std::fs::write(&path, "log: three\n").unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        })
+    };
+    let rest: Vec<String> = records
+        .map(|r| r.unwrap().fields["MSG"].to_string())
+        .collect();
+    writer.join().unwrap();
+    assert_eq!(rest, ["two", "three"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
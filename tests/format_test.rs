@@ -0,0 +1,52 @@
+use asyncfsm::format::format_template;
+use asyncfsm::Template;
+
+const MESSY: &str = r#"# Vendor: cisco
+Value Required,Filldown   HOST (\S+)
+# the address
+Value  IP   (\S+)
+Start
+ ^${HOST} ${IP} -> Next.Record
+    # details follow
+ ^Detail -> Detail
+# the detail lines
+Detail
+   ^x -> Continue
+
+
+   ^$$ -> Start
+"#;
+
+const FORMATTED: &str = r#"# Vendor: cisco
+Value Filldown,Required HOST (\S+)
+# the address
+Value                   IP   (\S+)
+
+Start
+  ^${HOST} ${IP} -> Record
+  # details follow
+  ^Detail -> Detail
+
+# the detail lines
+Detail
+  ^x -> Continue
+
+  ^$$ -> Start
+"#;
+
+#[test]
+fn test_format_keeps_comments_and_is_idempotent() {
+    assert_eq!(format_template(MESSY).unwrap(), FORMATTED);
+    assert_eq!(format_template(FORMATTED).unwrap(), FORMATTED);
+}
+
+#[test]
+fn test_to_textfsm_string_round_trips() {
+    let template = Template::new(MESSY).unwrap();
+    let text = template.to_textfsm_string();
+    assert_eq!(
+        text,
+        "# Vendor: cisco\nValue Filldown,Required HOST (\\S+)\nValue                   IP   (\\S+)\n\nStart\n  ^${HOST} ${IP} -> Record\n  ^Detail -> Detail\n\nDetail\n  ^x -> Continue\n  ^$$ -> Start\n"
+    );
+    assert_eq!(Template::new(&text).unwrap().to_textfsm_string(), text);
+}
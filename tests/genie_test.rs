@@ -0,0 +1,83 @@
+#![cfg(feature = "genie")]
+
+use asyncfsm::genie::to_genie;
+use asyncfsm::{DataRecordConversion, TextFSM};
+use serde_json::json;
+
+#[test]
+fn test_show_interfaces_to_genie() {
+    let template = r###"Value Required INTERFACE (\S+)
+Value LINK_STATUS (.+?)
+Value PROTOCOL_STATUS (\S+)
+Value HARDWARE_TYPE (.+?)
+Value IP_ADDRESS (\S+)
+Value PREFIX_LENGTH (\d+)
+Value MTU (\d+)
+Value BANDWIDTH (\d+\s+\w+)
+Value DUPLEX (\S+)
+Value SPEED (\S+)
+Value INPUT_PACKETS (\d+)
+Value CRC (\d+)
+
+Start
+  ^\S+\s+is\s+ -> Continue.Record
+  ^${INTERFACE}\s+is\s+${LINK_STATUS},\s+line\s+protocol\s+is\s+${PROTOCOL_STATUS}
+  ^\s+Hardware\s+is\s+${HARDWARE_TYPE}$$
+  ^\s+Internet\s+address\s+is\s+${IP_ADDRESS}/${PREFIX_LENGTH}
+  ^\s+MTU\s+${MTU}.*BW\s+${BANDWIDTH}
+  ^\s+${DUPLEX},\s+${SPEED},
+  ^\s+${INPUT_PACKETS}\s+packets\s+input
+  ^\s+\d+\s+input\s+errors,\s+${CRC}\s+CRC
+"###;
+    let input = "GigabitEthernet1 is up, line protocol is up
+  Hardware is CSR vNIC
+  Internet address is 10.0.0.1/24
+  MTU 1500 bytes, BW 1000000 Kbit/sec, DLY 10 usec,
+  Full-duplex, 1000Mb/s, link type is auto
+  12345 packets input, 1000 bytes
+  0 input errors, 2 CRC, 0 frame
+GigabitEthernet2 is administratively down, line protocol is down
+  Hardware is CSR vNIC
+";
+    let records = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records(input, Some(DataRecordConversion::LowercaseKeys))
+        .unwrap();
+    let genie = to_genie("sh int", &records).unwrap();
+    assert_eq!(
+        genie,
+        json!({
+            "GigabitEthernet1": {
+                "oper_status": "up",
+                "line_protocol": "up",
+                "enabled": true,
+                "type": "CSR vNIC",
+                "ipv4": {"10.0.0.1/24": {"ip": "10.0.0.1", "prefix_length": "24"}},
+                "mtu": 1500,
+                "bandwidth": 1000000,
+                "duplex_mode": "full",
+                "port_speed": "1000mbps",
+                "counters": {"in_pkts": 12345, "in_crc_errors": 2},
+            },
+            "GigabitEthernet2": {
+                "oper_status": "down",
+                "line_protocol": "down",
+                "enabled": false,
+                "type": "CSR vNIC",
+            },
+        })
+    );
+}
+
+#[test]
+fn test_unsupported_command() {
+    let records = TextFSM::from_string("Value A (\\S+)\n\nStart\n  ^${A} -> Record\n")
+        .unwrap()
+        .parse_records("x\n", None)
+        .unwrap();
+    assert!(to_genie("show ip route", &records).is_err());
+    assert_eq!(
+        to_genie("show version", &records).unwrap(),
+        json!({ "version": {} })
+    );
+}
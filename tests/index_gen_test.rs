@@ -0,0 +1,67 @@
+#![cfg(feature = "clitable")]
+
+use asyncfsm::{generate_index, CliTable};
+use std::path::PathBuf;
+
+const TEMPLATE: &str = "Value A (\\S+)\n\nStart\n  ^${A} -> Record\n";
+
+fn template_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_generate_from_file_names() {
+    let dir = template_dir(
+        "asyncfsm_index_gen_names",
+        &[
+            ("cisco_ios_show_ip_route.textfsm", TEMPLATE),
+            ("cisco_ios_show_ip_interface_brief.textfsm", TEMPLATE),
+            ("cisco_ios_show_inventory.textfsm", TEMPLATE),
+            ("cisco_ios_show_interfaces.textfsm", TEMPLATE),
+            ("notes.txt", "not a template"),
+            ("unknown.textfsm", TEMPLATE),
+        ],
+    );
+    let index = generate_index(&dir).unwrap();
+    let rows: Vec<(&str, &str)> = index
+        .rows
+        .iter()
+        .map(|row| (row.platform.as_str(), row.command.as_str()))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("cisco_ios", "sh[[ow]] int[[erfaces]]"),
+            ("cisco_ios", "sh[[ow]] inv[[entory]]"),
+            ("cisco_ios", "sh[[ow]] ip in[[terface]] br[[ief]]"),
+            ("cisco_ios", "sh[[ow]] ip ro[[ute]]"),
+        ]
+    );
+    assert_eq!(index.skipped, vec![dir.join("unknown.textfsm")]);
+}
+
+#[test]
+fn test_generated_index_loads() {
+    let dir = template_dir(
+        "asyncfsm_index_gen_load",
+        &[
+            ("cisco_ios_show_version.textfsm", TEMPLATE),
+            (
+                "ifconfig.textfsm",
+                "# Platform: linux\n# Command: if[[config]] -a\nValue A (\\S+)\n\nStart\n  ^${A} -> Record\n",
+            ),
+        ],
+    );
+    let index = generate_index(&dir).unwrap();
+    std::fs::write(dir.join("index"), index.to_string()).unwrap();
+
+    let table = CliTable::from_file(dir.join("index")).unwrap();
+    assert_eq!(table.parse("cisco_ios", "sh ver", "x\n").unwrap().len(), 1);
+    assert_eq!(table.parse("linux", "if -a", "x\n").unwrap().len(), 1);
+}
@@ -0,0 +1,99 @@
+use asyncfsm::inspect::Transition;
+use asyncfsm::{TemplateSummary, TextFSMParser};
+
+const TEMPLATE: &str = r#"Value Filldown HOST (\S+)
+Value INTERFACE (\S+)
+Value List ADDRESS (\S+)
+
+Start
+  ^Host ${HOST}
+  ^Interface ${INTERFACE} -> Interface
+  ^. -> Error "unexpected line"
+
+Interface
+  ^  Address ${ADDRESS}
+  ^Interface -> Continue.Record Start
+  ^$$ -> Record Start
+
+Unused
+  ^x -> Interface
+"#;
+
+fn summary() -> TemplateSummary {
+    TemplateSummary::new(&TextFSMParser::from_string(TEMPLATE).unwrap())
+}
+
+#[test]
+fn test_summary_groups_transitions_by_target() {
+    let summary = summary();
+    assert_eq!(summary.values[0].options.as_deref(), Some("Filldown"));
+    assert_eq!(summary.values[1].options, None);
+    let interface = &summary.states[1];
+    assert_eq!(interface.rules, 3);
+    assert_eq!(
+        interface.transitions,
+        vec![Transition {
+            to: "Start".into(),
+            rules: vec![1, 2],
+        }]
+    );
+    assert!(interface.reachable);
+    assert!(!summary.states[2].reachable);
+}
+
+#[test]
+fn test_summary_display() {
+    assert_eq!(
+        summary().to_string(),
+        r"values:
+  HOST (\S+) [Filldown]
+  INTERFACE (\S+)
+  ADDRESS (\S+) [List]
+states:
+  Start (3 rules)
+    -> Interface (rule 2)
+    -> Error (rule 3)
+  Interface (3 rules)
+    -> Start (rules 2, 3)
+  Unused (1 rule) unreachable
+    -> Interface (rule 1)
+"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_ast_serializes_rules_and_actions() {
+    let ast = asyncfsm::TemplateAst::new(&TextFSMParser::from_string(TEMPLATE).unwrap());
+    let json = serde_json::to_value(&ast).unwrap();
+    assert_eq!(
+        json["values"][0]["options"],
+        serde_json::json!(["Filldown"])
+    );
+    assert_eq!(
+        json["states"][0]["rules"][2],
+        serde_json::json!({
+            "pattern": "^.",
+            "regex": "^.",
+            "values": [],
+            "line_action": "next",
+            "next_state": "Error",
+            "error_message": "unexpected line",
+        })
+    );
+    assert_eq!(
+        json["states"][1]["rules"][1],
+        serde_json::json!({
+            "pattern": "^Interface",
+            "regex": "^Interface",
+            "values": [],
+            "line_action": "continue",
+            "record_action": "record",
+            "next_state": "Start",
+        })
+    );
+    assert_eq!(
+        json["states"][0]["rules"][0]["values"],
+        serde_json::json!(["HOST"])
+    );
+}
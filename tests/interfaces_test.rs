@@ -0,0 +1,74 @@
+use asyncfsm::{DataRecordConversion, InterfaceNames, InterfaceStyle, TextFSM, Value};
+
+#[test]
+fn test_normalize_names() {
+    let long = InterfaceNames::default();
+    for (name, expected) in [
+        ("Gi0/1", Some("GigabitEthernet0/1")),
+        ("gig 0/1.100", Some("GigabitEthernet0/1.100")),
+        ("GigabitEthernet0/1", Some("GigabitEthernet0/1")),
+        ("Po10", Some("Port-channel10")),
+        ("Te1/0/1", Some("TenGigabitEthernet1/0/1")),
+        ("Twe1/0/1", Some("TwentyFiveGigE1/0/1")),
+        ("G0/1", None),
+        ("xe-0/0/0", None),
+        ("Gi", None),
+    ] {
+        assert_eq!(long.normalize(name).as_deref(), expected, "{}", name);
+    }
+    let short = InterfaceNames::for_platform("cisco_nxos").style(InterfaceStyle::Short);
+    assert_eq!(short.normalize("Ethernet1/1").as_deref(), Some("Eth1/1"));
+    assert_eq!(short.normalize("port-channel5").as_deref(), Some("Po5"));
+    let custom = InterfaceNames::for_platform("arista_eos").alias("Recirc-Channel", "Rc");
+    assert_eq!(custom.normalize("Rc1").as_deref(), Some("Recirc-Channel1"));
+}
+
+#[test]
+fn test_normalize_interfaces_conversion() {
+    let template = r###"Value LOCAL_INTERFACE (\S+)
+Value NEIGHBOR (\S+)
+Value List MEMBER_PORTS (\S+)
+
+Start
+  ^${LOCAL_INTERFACE} to ${NEIGHBOR}
+  ^  member ${MEMBER_PORTS}
+  ^end -> Record
+
+EOF
+"###;
+    let input = "Po1 to Gi9\n  member Gi0/1\n  member Te1/1\nend\n";
+    let parse = |conversion| {
+        TextFSM::from_string(template)
+            .unwrap()
+            .parse_records(input, Some(conversion))
+            .unwrap()
+    };
+    let records = parse(DataRecordConversion::NormalizeInterfaces(
+        InterfaceNames::default(),
+    ));
+    assert_eq!(
+        records[0].fields["LOCAL_INTERFACE"],
+        Value::Single("Port-channel1".into())
+    );
+    // NEIGHBOR is not an interface field unless named.
+    assert_eq!(records[0].fields["NEIGHBOR"], Value::Single("Gi9".into()));
+    assert_eq!(
+        records[0].fields["MEMBER_PORTS"],
+        Value::List(vec![
+            "GigabitEthernet0/1".into(),
+            "TenGigabitEthernet1/1".into()
+        ])
+    );
+
+    let records = parse(DataRecordConversion::NormalizeInterfaces(
+        InterfaceNames::default().fields(["neighbor"]),
+    ));
+    assert_eq!(
+        records[0].fields["LOCAL_INTERFACE"],
+        Value::Single("Po1".into())
+    );
+    assert_eq!(
+        records[0].fields["NEIGHBOR"],
+        Value::Single("GigabitEthernet9".into())
+    );
+}
@@ -0,0 +1,71 @@
+use asyncfsm::{Template, TokenKind};
+
+fn kinds(template: &str) -> Vec<(TokenKind, &str)> {
+    Template::lex(template)
+        .iter()
+        .map(|t| (t.kind, &template[t.span.start..t.span.end]))
+        .collect()
+}
+
+#[test]
+fn test_lex_declarations_and_rules() {
+    let template = "# Vendor: cisco\nValue Required,OneOf=up|down STATUS (\\S+)\nMacro _PORT_ (Gi\\d+)\n\nStart\n  ^${STATUS} on $_PORT_$$ -> Next.Record Done\n  ^bad -> Error \"bad line\"\n  ^x -> Call(Count) Continue\n";
+    use TokenKind::*;
+    assert_eq!(
+        kinds(template),
+        [
+            (Comment, "# Vendor: cisco"),
+            (Keyword, "Value"),
+            (ValueOption, "Required"),
+            (Punctuation, ","),
+            (ValueOption, "OneOf=up|down"),
+            (Name, "STATUS"),
+            (Regex, "(\\S+)"),
+            (Keyword, "Macro"),
+            (Name, "_PORT_"),
+            (Regex, "(Gi\\d+)"),
+            (State, "Start"),
+            (Regex, "^"),
+            (Reference, "${STATUS}"),
+            (Regex, " on "),
+            (Reference, "$_PORT_"),
+            (Regex, "$$"),
+            (Arrow, "->"),
+            (Action, "Next"),
+            (Punctuation, "."),
+            (Action, "Record"),
+            (State, "Done"),
+            (Regex, "^bad"),
+            (Arrow, "->"),
+            (Action, "Error"),
+            (Message, "\"bad line\""),
+            (Regex, "^x"),
+            (Arrow, "->"),
+            (Action, "Call(Count)"),
+            (Action, "Continue"),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_templates_that_do_not_compile() {
+    let template = "Value Filldown HOST\nValue \u{e9}t\u{e9} (x)\n\nStart extra\n  ^${HOST\n";
+    let tokens = Template::lex(template);
+    use TokenKind::*;
+    assert_eq!(
+        kinds(template),
+        [
+            (Keyword, "Value"),
+            (ValueOption, "Filldown"),
+            (Name, "HOST"),
+            (Keyword, "Value"),
+            (Name, "\u{e9}t\u{e9}"),
+            (Regex, "(x)"),
+            (State, "Start"),
+            (Invalid, "extra"),
+            (Regex, "^${HOST"),
+        ]
+    );
+    // Columns count characters, not bytes.
+    assert_eq!((tokens[5].span.line, tokens[5].span.column), (2, 11));
+}
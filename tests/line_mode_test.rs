@@ -0,0 +1,62 @@
+use asyncfsm::{TextFSM, TextFsmOptions};
+
+const SYSLOG: &str = r###"Value Filldown HOST (\S+)
+Value TIMESTAMP (\w{3}\s+\d+ \d\d:\d\d:\d\d)
+Value FACILITY (\w+)
+Value SEVERITY (\d)
+Value MNEMONIC (\w+)
+Value MESSAGE (.*)
+
+Start
+  ^--- host ${HOST}
+  ^${TIMESTAMP}: %${FACILITY}-${SEVERITY}-${MNEMONIC}: ${MESSAGE}
+  ^.*CRASH -> Error
+"###;
+
+#[test]
+fn test_line_mode_emits_a_record_per_line() {
+    let input = "--- host r1
+Mar  1 00:01:02: %LINK-3-UPDOWN: Interface Gi0/1, changed state to down
+this line matches nothing
+Mar  1 00:01:05: %SYS-5-CONFIG_I: Configured from console
+";
+    let mut fsm = TextFSM::from_string(SYSLOG)
+        .unwrap()
+        .with_options(TextFsmOptions::default().line_mode(true));
+    let records = fsm.parse_records(input, None).unwrap();
+
+    // The host line fills nothing but the Filldown value, so it is not a record.
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].get("HOST").unwrap().to_string(), "r1");
+    assert_eq!(records[0].get("MNEMONIC").unwrap().to_string(), "UPDOWN");
+    assert_eq!(records[1].get("HOST").unwrap().to_string(), "r1");
+    assert_eq!(records[1].get("SEVERITY").unwrap().to_string(), "5");
+    assert_eq!(fsm.unmatched_lines, 1);
+
+    // Without the mode no rule records, so only the last line comes out, at the end.
+    let mut fsm = TextFSM::from_string(SYSLOG).unwrap();
+    assert_eq!(fsm.parse_records(input, None).unwrap().len(), 1);
+
+    let mut fsm = TextFSM::from_string(SYSLOG)
+        .unwrap()
+        .with_options(TextFsmOptions::default().line_mode(true));
+    assert!(fsm.parse_records("SYSTEM CRASH\n", None).is_err());
+}
+
+#[test]
+fn test_line_mode_needs_a_single_state() {
+    let template = "Value A (\\S+)\n\nStart\n  ^x -> Other\n\nOther\n  ^${A} -> Record\n";
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().line_mode(true));
+    let err = fsm.parse_records("x\n", None).unwrap_err();
+    assert!(err.to_string().contains("only a Start state"), "{}", err);
+
+    let mut iter = TextFSM::from_string(SYSLOG)
+        .unwrap()
+        .with_options(TextFsmOptions::default().line_mode(true))
+        .parse_reader("Mar  1 00:01:02: %LINK-3-UPDOWN: down\n".as_bytes());
+    let record = iter.next().unwrap().unwrap();
+    assert_eq!(record.get("FACILITY").unwrap().to_string(), "LINK");
+    assert!(iter.next().is_none());
+}
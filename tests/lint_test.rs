@@ -0,0 +1,43 @@
+use asyncfsm::lint::{fix, lint, LintKind};
+
+const TEMPLATE: &str = r#"Value Required INTERFACE (\S+)
+Value STATUS (\S+)
+
+Start
+  # status lines
+  ^${INTERFACE}\s+${STATUS} -> Record
+  ^Interface ${INTERFACE} is ${STATUS}
+  ^up|down -> Next.Record
+  ^.*error -> Error
+  ^(?i)${INTERFACE}\s*$$
+"#;
+
+#[test]
+fn test_lint_reports_loose_anchors_with_their_lines() {
+    let issues: Vec<(usize, LintKind, bool)> = lint(TEMPLATE)
+        .unwrap()
+        .iter()
+        .map(|issue| (issue.line, issue.kind, issue.fix.is_some()))
+        .collect();
+    assert_eq!(
+        issues,
+        vec![
+            (6, LintKind::OpenEnd, true),
+            (8, LintKind::UnanchoredAlternative, true),
+            (9, LintKind::LeadingWildcard, false),
+        ]
+    );
+}
+
+#[test]
+fn test_fix_rewrites_only_the_fixable_rules() {
+    let fixed = fix(TEMPLATE, &lint(TEMPLATE).unwrap());
+    assert_eq!(
+        fixed,
+        TEMPLATE
+            .replace(r"${STATUS} -> Record", r"${STATUS}\s*$$ -> Record")
+            .replace("^up|down", "^(?:up|down)")
+    );
+    let remaining: Vec<LintKind> = lint(&fixed).unwrap().iter().map(|i| i.kind).collect();
+    assert_eq!(remaining, vec![LintKind::LeadingWildcard]);
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "lsp")]
+use serde_json::{json, Value};
+
+#[cfg(feature = "lsp")]
+const TEMPLATE: &str = "Value Required NAME (\\S+)\nValue Filldwn PORT (\\d+)\n\nStart\n  ^Name: ${NAME} -> Record Detail\n\nDetail\n  ^${NAME}\n";
+
+/// Frames `messages` as an editor sends them, serves them and returns the replies.
+#[cfg(feature = "lsp")]
+fn exchange(messages: &[Value]) -> Vec<Value> {
+    let mut input = vec![];
+    for message in messages {
+        let body = message.to_string();
+        input.extend(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes());
+    }
+    let mut output = vec![];
+    asyncfsm::lsp::serve(input.as_slice(), &mut output).unwrap();
+    let mut output = String::from_utf8(output).unwrap();
+    let mut replies = vec![];
+    while let Some((header, rest)) = output.split_once("\r\n\r\n") {
+        let length: usize = header["Content-Length: ".len()..].parse().unwrap();
+        replies.push(serde_json::from_str(&rest[..length]).unwrap());
+        output = rest[length..].to_string();
+    }
+    replies
+}
+
+#[cfg(feature = "lsp")]
+fn open(text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": "file:///t.textfsm", "languageId": "textfsm", "version": 1, "text": text } },
+    })
+}
+
+#[cfg(feature = "lsp")]
+fn request(id: u64, method: &str, line: u64, character: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": "file:///t.textfsm" },
+            "position": { "line": line, "character": character },
+        },
+    })
+}
+
+#[test]
+#[cfg(feature = "lsp")]
+fn test_open_templates_get_diagnostics() {
+    let replies = exchange(&[open(TEMPLATE)]);
+    assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+    let diagnostics = &replies[0]["params"]["diagnostics"];
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 1, "character": 6 })
+    );
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("`Filldown`"));
+
+    // Once it compiles, the lint has its say.
+    let fixed = TEMPLATE.replace("Filldwn", "Filldown");
+    let replies = exchange(&[open(&fixed)]);
+    let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+    assert!(diagnostics.iter().all(|d| d["severity"] != 1));
+    let lint = diagnostics
+        .iter()
+        .find(|d| d["source"] == "asyncfsm-lint")
+        .unwrap();
+    assert_eq!(lint["range"]["start"], json!({ "line": 7, "character": 2 }));
+}
+
+#[test]
+#[cfg(feature = "lsp")]
+fn test_hover_definition_and_symbols() {
+    let template = TEMPLATE.replace("Filldwn", "Filldown");
+    let replies = exchange(&[
+        open(&template),
+        request(1, "textDocument/hover", 4, 12),
+        request(2, "textDocument/definition", 4, 12),
+        request(3, "textDocument/definition", 4, 30),
+        request(4, "textDocument/documentSymbol", 0, 0),
+        request(5, "textDocument/hover", 4, 4),
+        request(6, "textDocument/semanticTokens/full", 0, 0),
+    ]);
+    let hover = &replies[1]["result"];
+    assert_eq!(
+        hover["contents"]["value"],
+        "```textfsm\nValue Required NAME (\\S+)\n```"
+    );
+    assert_eq!(
+        hover["range"]["start"],
+        json!({ "line": 4, "character": 9 })
+    );
+    assert_eq!(
+        replies[2]["result"]["range"],
+        json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 25 } })
+    );
+    assert_eq!(
+        replies[3]["result"]["range"]["start"],
+        json!({ "line": 6, "character": 0 })
+    );
+
+    let symbols = replies[4]["result"].as_array().unwrap();
+    let names: Vec<&str> = symbols
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Start", "Detail"]);
+    assert_eq!(
+        symbols[0]["range"]["end"],
+        json!({ "line": 4, "character": 33 })
+    );
+
+    // Plain text in a rule refers to nothing.
+    assert_eq!(replies[5]["result"], Value::Null);
+
+    // `Value` is a keyword, and `Required` a modifier right after it.
+    let data = &replies[6]["result"]["data"];
+    assert_eq!(
+        data.as_array().unwrap()[..10],
+        [0, 0, 5, 1, 0, 0, 6, 8, 2, 0]
+    );
+}
@@ -0,0 +1,76 @@
+use asyncfsm::{register_macro, TextFSM, TextFsmError, Value};
+
+fn parse(template: &str, input: &str) -> Vec<Vec<(String, Value)>> {
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    fsm.parse_records(input, None)
+        .unwrap()
+        .iter()
+        .map(|r| r.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .collect()
+}
+
+#[test]
+fn test_template_and_builtin_macros() {
+    let template = r###"Macro _PORT_ ((?:Gi|Te)\d+/\d+)
+Macro SUBIF (${_PORT_}\.\d+)
+Value PORT (${SUBIF}|${_PORT_})
+Value ADDRESS (${_IPV4_PREFIX_})
+Value MAC (${_MAC_})
+
+Start
+  ^interface ${PORT}
+  ^ ip address ${ADDRESS}
+  ^ mac ${MAC} -> Record
+  ^ peer ${_IPV6_} on ${_INTERFACE_} -> Next
+
+EOF
+"###;
+    let input = "interface Gi0/1.100\n ip address 10.0.0.1/24\n peer fe80::1 on Vlan10\n mac 0011.2233.4455\ninterface Fa0/1\n ip address 10.0.0.300/24\n mac 00:11:22:33:44:55\n";
+    let records = parse(template, input);
+    assert_eq!(records.len(), 2);
+    let field = |r: usize, name: &str| {
+        records[r]
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.to_string())
+    };
+    assert_eq!(field(0, "PORT").as_deref(), Some("Gi0/1.100"));
+    assert_eq!(field(0, "ADDRESS").as_deref(), Some("10.0.0.1/24"));
+    assert_eq!(field(0, "MAC").as_deref(), Some("0011.2233.4455"));
+    // `Fa0/1` is no `_PORT_` and `10.0.0.300` no IPv4 address.
+    assert_eq!(field(1, "PORT").as_deref(), Some(""));
+    assert_eq!(field(1, "ADDRESS").as_deref(), Some(""));
+    assert_eq!(field(1, "MAC").as_deref(), Some("00:11:22:33:44:55"));
+}
+
+#[test]
+fn test_registered_macro() {
+    register_macro("_VLAN_ID_", r"(?:409[0-4]|40[0-8]\d|[1-3]?\d{1,3})").unwrap();
+    let template = "Value VLAN (${_VLAN_ID_})\n\nStart\n  ^vlan ${VLAN}$$ -> Record\n";
+    let records = parse(template, "vlan 10\nvlan 5000\nvlan 4094\n");
+    assert_eq!(records.len(), 2);
+    assert!(register_macro("_IPV4_", r"\d+").is_err());
+    assert!(register_macro("not-a-name", r"\d+").is_err());
+}
+
+#[test]
+fn test_macro_errors() {
+    let error = |template: &str| match TextFSM::from_string(template) {
+        Err(TextFsmError::TemplateErrors { diagnostics, .. }) => {
+            diagnostics.errors().next().unwrap().message.clone()
+        }
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    };
+    assert_eq!(
+        error("Value A (${_NOPE_})\n\nStart\n  ^${A} -> Record\n"),
+        "unknown macro ${_NOPE_}"
+    );
+    assert_eq!(
+        error("Macro _IPV4_ (\\d+)\nValue A (\\S+)\n\nStart\n  ^${A} -> Record\n"),
+        "Macro _IPV4_ already defined"
+    );
+    assert_eq!(
+        error("Macro A (\\d+)\nValue A (\\S+)\n\nStart\n  ^${A} -> Record\n"),
+        "Macro A has the name of a value"
+    );
+}
@@ -0,0 +1,87 @@
+#![cfg(all(feature = "json", feature = "yaml"))]
+
+use asyncfsm::mapping::{mapping, register_mapping, Mapping};
+use asyncfsm::TextFSM;
+use serde_json::json;
+
+const TEMPLATE: &str = r###"Value INTERFACE (\S+)
+Value LINK_STATUS (.+?)
+Value PROTOCOL_STATUS (\S+)
+Value MTU (\S+)
+Value List VLANS (\d+)
+
+Start
+  ^${INTERFACE} is ${LINK_STATUS}, line protocol is ${PROTOCOL_STATUS}
+  ^  vlan ${VLANS}
+  ^  MTU ${MTU} -> Record
+"###;
+
+const INPUT: &str = "Gi1 is up, line protocol is up
+  vlan 10
+  vlan 20
+  MTU 1500 bytes
+Gi2 is administratively down, line protocol is down
+  MTU unknown
+";
+
+#[test]
+fn test_mapping_file_rules() {
+    let custom = Mapping::from_yaml_str(
+        r#"
+path: example:ports/port
+fields:
+  id: interface
+  settings/mtu: { field: MTU, type: integer }
+  settings/shutdown:
+    field: LINK_STATUS
+    type: boolean
+    values: { administratively down: "true", up: "false" }
+  settings/vlans: { field: VLANS, type: integer }
+  kind: { value: ethernet }
+"#,
+    )
+    .unwrap();
+    let records = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .parse_records(INPUT, None)
+        .unwrap();
+    assert_eq!(
+        custom.apply(&records),
+        json!({ "example:ports": { "port": [
+            {
+                "id": "Gi1",
+                "kind": "ethernet",
+                "settings": { "mtu": 1500, "shutdown": false, "vlans": [10, 20] },
+            },
+            {
+                "id": "Gi2",
+                "kind": "ethernet",
+                "settings": { "shutdown": true, "vlans": [] },
+            },
+        ] } })
+    );
+    assert!(Mapping::from_yaml_str("path: a\nfields:\n  x: { fields: A }\n").is_err());
+}
+
+#[test]
+fn test_builtin_and_registered_mappings() {
+    let records = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .parse_records(INPUT, None)
+        .unwrap();
+    let document = mapping("openconfig-interfaces").unwrap().apply(&records);
+    let interfaces = &document["openconfig-interfaces:interfaces"]["interface"];
+    assert_eq!(interfaces[0]["state"]["oper-status"], "UP");
+    assert_eq!(interfaces[1]["config"]["enabled"], false);
+    assert_eq!(interfaces[1]["state"]["admin-status"], "DOWN");
+
+    assert!(mapping("example-ports").is_err());
+    register_mapping(
+        "example-ports",
+        Mapping::from_yaml_str("path: ports\nfields:\n  name: INTERFACE\n").unwrap(),
+    );
+    assert_eq!(
+        mapping("example-ports").unwrap().apply(&records),
+        json!({ "ports": [{ "name": "Gi1" }, { "name": "Gi2" }] })
+    );
+}
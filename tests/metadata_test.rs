@@ -0,0 +1,90 @@
+use asyncfsm::{Deprecation, Severity, TextFSM, TextFSMParser};
+
+#[test]
+fn test_template_metadata() {
+    let template = r###"# Author: network team
+# Command: show version
+# OS: ios
+#
+# Free-form description that is not metadata.
+Value Version (\S+)
+
+# Comment: not part of the header
+Start
+  ^Version ${Version}
+"###;
+    let fsm = TextFSM::from_string(template).unwrap();
+    let metadata = fsm.metadata();
+    assert_eq!(metadata.len(), 3);
+    assert_eq!(metadata["Author"], "network team");
+    assert_eq!(metadata["Command"], "show version");
+    assert_eq!(metadata["OS"], "ios");
+}
+
+#[test]
+fn test_template_without_metadata() {
+    let parser = TextFSMParser::from_string("Start\n  ^x -> Next\n").unwrap();
+    assert!(parser.metadata().is_empty());
+}
+
+#[test]
+fn test_deprecation_header() {
+    let parser = TextFSMParser::from_string(
+        "# Deprecated: use cisco_ios_show_vlan.textfsm\nStart\n  ^x -> Next\n",
+    )
+    .unwrap();
+    let deprecation = parser.deprecation().unwrap();
+    assert_eq!(deprecation.severity, Severity::Warning);
+    assert_eq!(deprecation.message, "use cisco_ios_show_vlan.textfsm");
+    assert_eq!(
+        deprecation.to_string(),
+        "deprecated: use cisco_ios_show_vlan.textfsm"
+    );
+
+    let parser =
+        TextFSMParser::from_string("# Deprecated: Error: removed in 2.0\nStart\n  ^x -> Next\n")
+            .unwrap();
+    assert_eq!(
+        parser.deprecation(),
+        Some(Deprecation {
+            severity: Severity::Error,
+            message: "removed in 2.0".into()
+        })
+    );
+    assert_eq!(
+        TextFSMParser::from_string("Start\n  ^x -> Next\n")
+            .unwrap()
+            .deprecation(),
+        None
+    );
+}
+
+#[cfg(feature = "clitable")]
+#[test]
+fn test_cli_table_reports_deprecation() {
+    let dir = std::env::temp_dir().join("asyncfsm_deprecation_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("index"),
+        "Template, Hostname, Platform, Command\nold.textfsm, .*, cisco_ios, sh[[ow]] ver[[sion]]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("old.textfsm"),
+        "# Deprecated: info: use new.textfsm\nValue VERSION (\\S+)\n\nStart\n  ^Version ${VERSION} -> Record\n",
+    )
+    .unwrap();
+    let table = asyncfsm::CliTable::from_file(dir.join("index")).unwrap();
+    let found = table
+        .parse_match("cisco_ios", "show version", "Version 15.2\n")
+        .unwrap();
+    assert_eq!(found.records.len(), 1);
+    assert_eq!(
+        found.deprecation,
+        Some(Deprecation {
+            severity: Severity::Info,
+            message: "use new.textfsm".into()
+        })
+    );
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "metrics")]
+use asyncfsm::metrics::{LINES_PARSED, RECORDS_EMITTED, TEMPLATES_COMPILED};
+use asyncfsm::TextFSM;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+#[test]
+fn test_parse_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let mut fsm =
+            TextFSM::from_string("Value Name (\\S+)\n\nStart\n  ^${Name} -> Record\n").unwrap();
+        fsm.parse_records("a\nb\nc\n", None).unwrap();
+    });
+
+    let counters: Vec<(String, u64)> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter_map(|(key, _, _, value)| match value {
+            DebugValue::Counter(n) => Some((key.key().name().to_string(), n)),
+            _ => None,
+        })
+        .collect();
+    assert!(counters.contains(&(TEMPLATES_COMPILED.to_string(), 1)));
+    assert!(counters.contains(&(LINES_PARSED.to_string(), 3)));
+    assert!(counters.contains(&(RECORDS_EMITTED.to_string(), 3)));
+}
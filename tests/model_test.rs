@@ -0,0 +1,58 @@
+use asyncfsm::{DataRecord, DeviceModel, Value};
+
+fn record(fields: &[(&str, &str)]) -> DataRecord {
+    let mut rec = DataRecord::new();
+    for (k, v) in fields {
+        rec.insert(k.to_string(), v.to_string());
+    }
+    rec
+}
+
+#[test]
+fn test_device_model_assembly() {
+    let outputs = vec![
+        (
+            "show version".to_string(),
+            vec![record(&[("VERSION", "15.2"), ("HOSTNAME", "r1")])],
+        ),
+        (
+            "show interfaces".to_string(),
+            vec![
+                record(&[("INTERFACE", "Gi0/1"), ("LINK_STATUS", "up")]),
+                record(&[("INTERFACE", "Gi0/2"), ("LINK_STATUS", "down")]),
+            ],
+        ),
+        (
+            "show ip interface brief".to_string(),
+            vec![record(&[("INTF", "Gi0/1"), ("IPADDR", "10.0.0.1")])],
+        ),
+        (
+            "show cdp neighbors".to_string(),
+            vec![record(&[("NEIGHBOR", "sw1"), ("LOCAL_INTERFACE", "Gi0/1")])],
+        ),
+        ("show clock".to_string(), vec![record(&[("TIME", "12:00")])]),
+    ];
+    let model = DeviceModel::from_commands(outputs);
+
+    assert_eq!(model.facts["HOSTNAME"], Value::Single("r1".to_string()));
+    assert_eq!(model.interfaces.len(), 2);
+    let gi01 = &model.interfaces["Gi0/1"];
+    assert_eq!(gi01["LINK_STATUS"].to_string(), "up");
+    assert_eq!(gi01["IPADDR"].to_string(), "10.0.0.1");
+    assert_eq!(model.neighbors.len(), 1);
+    assert_eq!(
+        model.neighbors[0]["COMMAND"].to_string(),
+        "show cdp neighbors"
+    );
+    assert_eq!(model.commands["show clock"].len(), 1);
+}
+
+#[test]
+fn test_bgp_peers_are_neighbors() {
+    let model = DeviceModel::from_commands(vec![(
+        "show ip bgp summary".to_string(),
+        vec![record(&[("BGP_NEIGH", "192.0.2.1"), ("STATE_PFXRCD", "10")])],
+    )]);
+    assert_eq!(model.neighbors.len(), 1);
+    assert!(model.commands.is_empty());
+}
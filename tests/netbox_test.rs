@@ -0,0 +1,113 @@
+#![cfg(all(feature = "json", feature = "yaml"))]
+
+use asyncfsm::mapping::Mapping;
+use asyncfsm::netbox::{Netbox, NetboxObject};
+use asyncfsm::TextFSM;
+use serde_json::json;
+
+const INTERFACES: &str = r###"Value INTERFACE (\S+)
+Value LINK_STATUS (.+?)
+Value MAC_ADDRESS (\S+)
+Value IP_ADDRESS (\S+)
+Value PREFIX_LENGTH (\d+)
+Value MTU (\d+)
+Value BANDWIDTH (\d+)
+
+Start
+  ^${INTERFACE} is ${LINK_STATUS}, line protocol
+  ^  address is ${MAC_ADDRESS}
+  ^  Internet address is ${IP_ADDRESS}/${PREFIX_LENGTH}
+  ^  MTU ${MTU} bytes, BW ${BANDWIDTH} Kbit -> Record
+"###;
+
+const INPUT: &str = "Gi1 is up, line protocol is up
+  address is 0011.2233.4455
+  Internet address is 10.0.0.1/24
+  MTU 1500 bytes, BW 1000000 Kbit
+Gi2 is administratively down, line protocol is down
+  address is 0011.2233.4456
+  MTU 1500 bytes, BW 1000000 Kbit
+";
+
+#[test]
+fn test_netbox_interfaces_and_addresses() {
+    let records = TextFSM::from_string(INTERFACES)
+        .unwrap()
+        .parse_records(INPUT, None)
+        .unwrap();
+    assert_eq!(
+        NetboxObject::detect(&records),
+        Some(NetboxObject::Interface)
+    );
+    assert_eq!(
+        Netbox::new().device("r1").payloads(&records).unwrap(),
+        json!([
+            {
+                "device": { "name": "r1" },
+                "name": "Gi1",
+                "type": "other",
+                "enabled": true,
+                "mtu": 1500,
+                "speed": 1000000,
+                "mac_address": "0011.2233.4455",
+            },
+            {
+                "device": { "name": "r1" },
+                "name": "Gi2",
+                "type": "other",
+                "enabled": false,
+                "mtu": 1500,
+                "speed": 1000000,
+                "mac_address": "0011.2233.4456",
+            },
+        ])
+    );
+
+    // Interfaces without an address are left out.
+    let addresses = Netbox::new()
+        .mapping(asyncfsm::mapping::mapping("netbox-ip-addresses").unwrap())
+        .payloads(&records)
+        .unwrap();
+    assert_eq!(
+        addresses,
+        json!([{ "address": "10.0.0.1/24", "status": "active", "description": "Gi1" }])
+    );
+}
+
+#[test]
+fn test_netbox_inventory_and_custom_mapping() {
+    let template = "Value NAME (.+?)\nValue PID (\\S+)\nValue SN (\\S+)\n\nStart\n  ^NAME: \"${NAME}\"\n  ^PID: ${PID} , SN: ${SN} -> Record\n";
+    let records = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records("NAME: \"Chassis\"\nPID: C9300-48P , SN: FOC1234\n", None)
+        .unwrap();
+    assert_eq!(
+        NetboxObject::detect(&records),
+        Some(NetboxObject::InventoryItem)
+    );
+    assert_eq!(
+        NetboxObject::InventoryItem.endpoint(),
+        "dcim/inventory-items"
+    );
+    let payloads = Netbox::new().payloads(&records).unwrap();
+    assert_eq!(
+        payloads,
+        json!([{ "name": "Chassis", "part_id": "C9300-48P", "serial": "FOC1234", "discovered": true }])
+    );
+
+    let custom = Mapping::from_yaml_str("fields:\n  asset_tag: SN\n").unwrap();
+    assert_eq!(
+        Netbox::new()
+            .mapping(custom)
+            .device("r1")
+            .payloads(&records)
+            .unwrap(),
+        json!([{ "asset_tag": "FOC1234", "device": { "name": "r1" } }])
+    );
+
+    let unknown = TextFSM::from_string("Value ROUTE (\\S+)\n\nStart\n  ^${ROUTE} -> Record\n")
+        .unwrap()
+        .parse_records("10.0.0.0/8\n", None)
+        .unwrap();
+    assert!(Netbox::new().payloads(&unknown).is_err());
+}
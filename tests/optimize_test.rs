@@ -0,0 +1,71 @@
+use asyncfsm::optimize::{optimize, Finding};
+use asyncfsm::{TextFSM, TextFSMParser};
+
+const TEMPLATE: &str = r#"Value INTERFACE (\S+)
+Value MTU (\d+)
+
+Start
+  ^Interface ${INTERFACE}
+  ^  MTU ${MTU} -> Record
+  ^  MTU ${MTU} -> Record
+  ^! -> Next
+  ^end
+  ^Building -> Continue
+  ^Current
+  ^.* -> Next
+  ^unused
+"#;
+
+#[test]
+fn test_shadowed_rules_are_removed_and_plain_ones_merged() {
+    let optimized = optimize(&TextFSMParser::from_string(TEMPLATE).unwrap()).unwrap();
+    let findings: Vec<_> = optimized
+        .findings
+        .iter()
+        .map(|f| match f {
+            Finding::Shadowed { state, rule, by } => {
+                format!("{}:{} shadowed by {}", state, rule, by)
+            }
+            Finding::Merged { state, rules } => format!("{}:{:?} merged", state, rules),
+            Finding::AlwaysMatches { state, rule } => format!("{}:{} always", state, rule),
+            Finding::NeverMatches { state, rule } => format!("{}:{} never", state, rule),
+        })
+        .collect();
+    assert_eq!(
+        findings,
+        vec![
+            "Start:2 shadowed by 1",
+            "Start:[3, 4] merged",
+            "Start:[6, 7] merged",
+            "Start:7 always",
+            "Start:8 shadowed by 7",
+        ]
+    );
+    assert!(!optimized.is_unchanged());
+    assert_eq!(
+        optimized.template,
+        r#"Value INTERFACE (\S+)
+Value MTU       (\d+)
+
+Start
+  ^Interface ${INTERFACE}
+  ^  MTU ${MTU} -> Record
+  ^(?:!|end)
+  ^Building -> Continue
+  ^(?:Current|.*)
+"#
+    );
+}
+
+#[test]
+fn test_optimized_template_parses_the_same_records() {
+    let input = "Building\nCurrent\nInterface Gi1\n  MTU 1500\n!\nInterface Gi2\n  MTU 9000\nend\n";
+    let optimized = optimize(&TextFSMParser::from_string(TEMPLATE).unwrap()).unwrap();
+    let parse = |template: &str| {
+        TextFSM::from_string(template)
+            .unwrap()
+            .parse_records(input, None)
+            .unwrap()
+    };
+    assert_eq!(parse(&optimized.template), parse(TEMPLATE));
+}
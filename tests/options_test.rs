@@ -0,0 +1,540 @@
+use asyncfsm::{
+    CancellationToken, Dedupe, FilldownReset, TextFSM, TextFsmOptions, WhitespaceNormalization,
+};
+
+const SECTIONS_TEMPLATE: &str = r###"Value Hostname (\S+)
+Value Interface (\S+)
+
+Start
+  ^hostname ${Hostname}
+  ^interfaces: -> InterfaceSection
+
+InterfaceSection
+  ^  ${Interface} -> Record
+"###;
+
+#[test]
+fn test_default_start_state() {
+    let mut fsm = TextFSM::from_string(SECTIONS_TEMPLATE).unwrap();
+    let result = fsm.parse_records("  Gi0/1\n  Gi0/2\n", None).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_start_state_override() {
+    let options = TextFsmOptions::default().start_state("InterfaceSection");
+    let mut fsm = TextFSM::from_string(SECTIONS_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records("  Gi0/1\n  Gi0/2\n", None).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].get("Interface").unwrap().to_string(), "Gi0/1");
+    assert_eq!(result[1].get("Interface").unwrap().to_string(), "Gi0/2");
+
+    // reset() returns to the configured start state, not Start
+    fsm.reset();
+    assert_eq!(fsm.curr_state, "InterfaceSection");
+}
+
+#[test]
+fn test_unknown_start_state() {
+    let options = TextFsmOptions::default().start_state("Missing");
+    let mut fsm = TextFSM::from_string(SECTIONS_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    assert!(fsm.parse_records("  Gi0/1\n", None).is_err());
+}
+
+const PEOPLE_TEMPLATE: &str = r###"Value Name (\S+)
+
+Start
+  ^Name: ${Name} -> Record
+"###;
+
+#[test]
+fn test_max_records() {
+    let options = TextFsmOptions::default().max_records(2);
+    let mut fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm
+        .parse_records("Name: a\nName: b\nName: c\nName: d\n", None)
+        .unwrap();
+    assert_eq!(result.len(), 2);
+    assert!(fsm.truncated);
+}
+
+#[test]
+fn test_max_records_not_reached() {
+    let options = TextFsmOptions::default().max_records(10);
+    let mut fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records("Name: a\nName: b\n", None).unwrap();
+    assert_eq!(result.len(), 2);
+    assert!(!fsm.truncated);
+}
+
+#[test]
+fn test_max_records_streaming() {
+    let options = TextFsmOptions::default().max_records(1);
+    let fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let mut iter = fsm.parse_reader(std::io::Cursor::new("Name: a\nName: b\n"));
+    assert!(iter.next().is_some());
+    assert!(iter.next().is_none());
+    assert!(iter.is_truncated());
+}
+
+#[test]
+fn test_skip_records_then_limit() {
+    let options = TextFsmOptions::default()
+        .skip_records(1)
+        .max_records(2)
+        .with_ids(true);
+    let mut fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm
+        .parse_records("Name: a\nName: b\nName: c\nName: d\n", None)
+        .unwrap();
+    let kept: Vec<(String, Option<usize>)> = result
+        .iter()
+        .map(|r| (r.get("Name").unwrap().to_string(), r.index))
+        .collect();
+    assert_eq!(
+        kept,
+        [("b".to_string(), Some(1)), ("c".to_string(), Some(2))]
+    );
+    assert!(fsm.truncated);
+}
+
+#[test]
+fn test_sample_is_repeatable() {
+    let input: String = (0..1000).map(|i| format!("Name: n{}\n", i)).collect();
+    let sample = |rate: f64| {
+        let mut fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+            .unwrap()
+            .with_options(TextFsmOptions::default().sample(rate));
+        fsm.parse_records(&input, None).unwrap()
+    };
+    let tenth = sample(0.1);
+    assert!((50..150).contains(&tenth.len()), "{}", tenth.len());
+    assert_eq!(tenth, sample(0.1));
+    assert_eq!(sample(1.0).len(), 1000);
+    assert!(sample(0.0).is_empty());
+}
+
+#[test]
+fn test_stop_after() {
+    let options = TextFsmOptions::default().stop_after(std::time::Duration::ZERO);
+    let mut fsm = TextFSM::from_string(PEOPLE_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records("Name: a\nName: b\n", None).unwrap();
+    assert!(result.is_empty());
+    assert!(fsm.truncated);
+}
+
+#[test]
+fn test_cancel_token() {
+    let template = "Value Name (\\S+)\n\nStart\n  ^Name: ${Name} -> Call(Stop) Record\n";
+    let token = CancellationToken::new();
+    let handle = token.clone();
+    let options = TextFsmOptions::default()
+        .cancel_token(token)
+        .action("Stop", move |ctx| {
+            if ctx.line_number == 2 {
+                handle.cancel();
+            }
+            Ok(())
+        });
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let result = fsm
+        .parse_records("Name: a\nName: b\nName: c\n", None)
+        .unwrap();
+    assert_eq!(result.len(), 2);
+    assert!(fsm.cancelled);
+    assert!(fsm.truncated);
+}
+
+#[test]
+fn test_continue_loop_guard() {
+    let template = r###"Value Name (\S+)
+
+Start
+  ^Name -> Continue Other
+
+Other
+  ^Name -> Continue Start
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let err = fsm
+        .parse_records("Name: a\n", None)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Rule evaluation limit"));
+    assert!(err.contains("Start[rule 0] -> Other[rule 0]"));
+}
+
+#[test]
+fn test_continue_chain_within_limit() {
+    let template = r###"Value Name (\S+)
+
+Start
+  ^Name: ${Name} -> Continue Other
+
+Other
+  ^Name -> Record Start
+"###;
+    let options = TextFsmOptions::default().max_rule_evaluations(2);
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records("Name: a\nName: b\n", None).unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+const EOF_TEMPLATE: &str = r###"Value Name (\S+)
+
+Start
+  ^Name: ${Name}
+"###;
+
+#[test]
+fn test_implicit_eof_flush() {
+    let mut fsm = TextFSM::from_string(EOF_TEMPLATE).unwrap();
+    let result = fsm.parse_records("Name: a\n", None).unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[test]
+fn test_explicit_eof_suppresses_flush() {
+    let template = format!("{}\nEOF\n", EOF_TEMPLATE);
+    let mut fsm = TextFSM::from_string(&template).unwrap();
+    assert!(fsm.parser.explicit_eof);
+    let result = fsm.parse_records("Name: a\n", None).unwrap();
+    assert!(result.is_empty());
+
+    let fsm = TextFSM::from_string(&template).unwrap();
+    assert_eq!(
+        fsm.parse_reader(std::io::Cursor::new("Name: a\n")).count(),
+        0
+    );
+}
+
+#[test]
+fn test_end_state_suppresses_flush() {
+    let template = r###"Value Name (\S+)
+
+Start
+  ^Name: ${Name}
+  ^done -> End
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let result = fsm.parse_records("Name: a\ndone\nName: b\n", None).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_flush_on_eof_override() {
+    let template = format!("{}\nEOF\n", EOF_TEMPLATE);
+    let options = TextFsmOptions::default().flush_on_eof(true);
+    let mut fsm = TextFSM::from_string(&template)
+        .unwrap()
+        .with_options(options);
+    assert_eq!(fsm.parse_records("Name: a\n", None).unwrap().len(), 1);
+
+    let options = TextFsmOptions::default().flush_on_eof(false);
+    let mut fsm = TextFSM::from_string(EOF_TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    assert!(fsm.parse_records("Name: a\n", None).unwrap().is_empty());
+}
+
+#[test]
+fn test_unmatched_lines() {
+    let mut fsm = TextFSM::from_string(SECTIONS_TEMPLATE).unwrap();
+    fsm.parse_records("hostname r1\nuptime 3 days\ninterfaces:\n  Gi0/1\n", None)
+        .unwrap();
+    assert_eq!(fsm.line_number, 4);
+    assert_eq!(fsm.unmatched_lines, 1);
+    assert_eq!(fsm.unmatched_ratio(), 0.25);
+
+    fsm.reset();
+    assert_eq!(fsm.unmatched_ratio(), 0.0);
+
+    // Streamed input, as the CLI reads stdin, counts the same lines.
+    let input = "hostname r1\nuptime 3 days\ninterfaces:\n  Gi0/1\n";
+    let mut iter = fsm.parse_reader(input.as_bytes());
+    for record in iter.by_ref() {
+        record.unwrap();
+    }
+    assert_eq!(iter.fsm().unmatched_lines, 1);
+    assert_eq!(iter.unmatched_ratio(), 0.25);
+}
+
+#[test]
+fn test_with_raw() {
+    let template = r###"Value Required Name (\S+)
+Value Mtu (\d+)
+
+Start
+  ^interface ${Name}
+  ^  mtu ${Mtu}
+  ^! -> Record
+"###;
+    let input = "banner\ninterface Gi0/1\n  mtu 1500\n  shutdown\n!\ninterface Gi0/2\n";
+    let options = TextFsmOptions::default().with_raw(true);
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records(input, None).unwrap();
+    assert_eq!(result.len(), 2);
+
+    let raw = result[0].raw.as_ref().unwrap();
+    assert_eq!((raw.first_line, raw.last_line), (2, 5));
+    assert_eq!(raw.text, "interface Gi0/1\n  mtu 1500\n  shutdown\n!\n");
+    // The last record is flushed at end of input.
+    let raw = result[1].raw.as_ref().unwrap();
+    assert_eq!((raw.first_line, raw.last_line), (6, 6));
+    assert_eq!(raw.text, "interface Gi0/2\n");
+
+    // Off by default.
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let result = fsm.parse_records(input, None).unwrap();
+    assert!(result[0].raw.is_none());
+}
+
+#[test]
+fn test_normalize_whitespace() {
+    let template = r###"Value Port (\S+)
+Value Status (\S+)
+
+Start
+  ^${Port}   ${Status}$$ -> Record
+"###;
+    // A tab-aligned line, then one with a stray carriage return left by `lines()`.
+    let input = "Gi0/1\tup  \r\nGi0/2   down\r\r\n";
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    assert!(fsm.parse_records(input, None).unwrap().is_empty());
+
+    let options = TextFsmOptions::default().normalize_whitespace(WhitespaceNormalization::all());
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let result = fsm.parse_records(input, None).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].get("Status").unwrap().to_string(), "up");
+
+    let tabs = WhitespaceNormalization {
+        expand_tabs: Some(4),
+        ..Default::default()
+    };
+    assert_eq!(tabs.apply("ab\tc\td"), "ab  c   d");
+    assert_eq!(tabs.apply("a \r"), "a \r");
+}
+
+#[test]
+fn test_field_limits() {
+    let template = "Value List LINES (.+)\nValue MSG (\\S+)\n\nStart\n  ^msg ${MSG}\n  ^end -> Record\n  ^${LINES}\n";
+    let input = "one\ntwo\nthree\nfour\nmsg abcdéfgh\nend\nfive\nend\n";
+
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().max_list_len(2));
+    let records = fsm.parse_records(input, None).unwrap();
+    assert_eq!(
+        records[0].get("LINES").unwrap().to_string(),
+        "[\"one\", \"two\"]"
+    );
+    assert_eq!(records[1].get("LINES").unwrap().to_string(), "[\"five\"]");
+    assert_eq!(fsm.warnings.len(), 1);
+    assert_eq!(
+        fsm.warnings.iter().next().unwrap().to_string(),
+        "line 3: value LINES cut to the max_list_len / max_field_bytes limit"
+    );
+
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().max_field_bytes(5));
+    let records = fsm.parse_records(input, None).unwrap();
+    assert_eq!(records[0].get("LINES").unwrap().to_string(), "[\"one\"]");
+    assert_eq!(records[0].get("MSG").unwrap().to_string(), "abcd");
+    assert_eq!(fsm.warnings.len(), 2);
+}
+
+#[test]
+fn test_dedupe() {
+    let template =
+        "Value Key PORT (\\S+)\nValue STATUS (\\S+)\n\nStart\n  ^${PORT} ${STATUS} -> Record\n";
+    let input = "a up\na up\nb up\na up\na down\n";
+    let parse = |dedupe| {
+        let mut fsm = TextFSM::from_string(template)
+            .unwrap()
+            .with_options(TextFsmOptions::default().dedupe(dedupe));
+        let records = fsm.parse_records(input, None).unwrap();
+        let rows: Vec<String> = records
+            .iter()
+            .map(|r| format!("{} {}", r.get("PORT").unwrap(), r.get("STATUS").unwrap()))
+            .collect();
+        (rows, fsm.duplicate_records)
+    };
+    assert_eq!(parse(Dedupe::Off).0.len(), 5);
+    assert_eq!(
+        parse(Dedupe::Consecutive),
+        (
+            vec!["a up".into(), "b up".into(), "a up".into(), "a down".into()],
+            1
+        )
+    );
+    assert_eq!(parse(Dedupe::Key), (vec!["a up".into(), "b up".into()], 3));
+}
+
+#[test]
+fn test_with_ids() {
+    let template =
+        "Value PORT (\\S+)\nValue STATUS (\\S+)\n\nStart\n  ^${PORT} ${STATUS} -> Record\n";
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().with_ids(true));
+    let first = fsm.parse_chunk("a up\nb up\n", None).unwrap();
+    let state = fsm.checkpoint();
+    let mut resumed = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().with_ids(true));
+    resumed.resume(state).unwrap();
+    let second = resumed.parse_chunk("a up\n", None).unwrap();
+
+    let indexes: Vec<Option<usize>> = first.iter().chain(second.iter()).map(|r| r.index).collect();
+    assert_eq!(indexes, vec![Some(0), Some(1), Some(2)]);
+    assert_eq!(first[0].id, second[0].id);
+    assert_ne!(first[0].id, first[1].id);
+    assert_eq!(first[0].id.as_deref(), Some(first[0].content_id().as_str()));
+    assert_eq!(first[0].content_id().len(), 16);
+
+    let plain = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records("a up\n", None)
+        .unwrap();
+    assert_eq!((plain[0].index, plain[0].id.clone()), (None, None));
+}
+
+#[test]
+fn test_filldown_reset() {
+    let template = r"Value Filldown HOST (\S+)
+Value IFACE (\S+)
+
+Start
+  ^host ${HOST} -> Interfaces
+  ^section -> Interfaces
+  ^reset -> Clearall
+
+Interfaces
+  ^  ${IFACE} -> Record
+  ^end -> Start
+
+EOF
+";
+    let input = "host r1\n  e0\nend\nsection\n  e1\nend\nreset\nsection\n  e2\n";
+    let hosts = |reset| {
+        let mut fsm = TextFSM::from_string(template)
+            .unwrap()
+            .with_options(TextFsmOptions::default().filldown_reset(reset));
+        fsm.parse_records(input, None)
+            .unwrap()
+            .iter()
+            .map(|r| r.get("HOST").unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(hosts(FilldownReset::Clearall), ["r1", "r1", ""]);
+    assert_eq!(hosts(FilldownReset::StateChange), ["r1", "", ""]);
+    assert_eq!(hosts(FilldownReset::Never), ["r1", "r1", "r1"]);
+
+    // Streaming moves between states the same way.
+    let fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().filldown_reset(FilldownReset::StateChange));
+    let streamed: Vec<String> = fsm
+        .parse_reader(input.as_bytes())
+        .map(|r| r.unwrap().get("HOST").unwrap().to_string())
+        .collect();
+    assert_eq!(streamed, ["r1", "", ""]);
+}
+
+#[test]
+fn test_carry_over() {
+    let template = r"Value Filldown VRF (\S+)
+Value NEIGHBOR (\S+)
+Value AS (\d+)
+
+Start
+  ^vrf ${VRF}
+  ^  neighbor ${NEIGHBOR}
+  ^    remote-as ${AS} -> Record
+
+EOF
+";
+    let parts = [
+        "vrf red\n  neighbor 10.0.0.1\n",
+        "    remote-as 65001\n  neighbor 10.0.0.2\n",
+        "    remote-as 65002\n",
+    ];
+    let neighbors = |records: &asyncfsm::Records| -> Vec<(String, String, String)> {
+        records
+            .iter()
+            .map(|r| {
+                let get = |n: &str| r.get(n).map(|v| v.to_string()).unwrap_or_default();
+                (get("VRF"), get("NEIGHBOR"), get("AS"))
+            })
+            .collect()
+    };
+    let expected = [
+        (
+            "red".to_string(),
+            "10.0.0.1".to_string(),
+            "65001".to_string(),
+        ),
+        (
+            "red".to_string(),
+            "10.0.0.2".to_string(),
+            "65002".to_string(),
+        ),
+    ];
+
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().carry_over(true));
+    let mut records = vec![];
+    for part in parts {
+        records.extend(neighbors(&fsm.parse_records(part, None).unwrap()));
+    }
+    records.extend(neighbors(&fsm.finish(None).unwrap()));
+    assert_eq!(records, expected);
+
+    // Without carry-over each input stands alone, and the split records are lost.
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let second = fsm.parse_records(parts[1], None).unwrap();
+    assert_eq!(
+        neighbors(&second),
+        [(String::new(), String::new(), "65001".to_string())]
+    );
+
+    let dir = std::env::temp_dir().join(format!("asyncfsm-carry-over-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files: Vec<_> = parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let path = dir.join(format!("log.{}", i));
+            std::fs::write(&path, part).unwrap();
+            path
+        })
+        .collect();
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    assert_eq!(neighbors(&fsm.parse_files(&files, None).unwrap()), expected);
+    std::fs::remove_dir_all(dir).unwrap();
+}
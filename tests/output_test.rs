@@ -0,0 +1,67 @@
+use asyncfsm::{
+    output_writer, output_writer_names, register_output_writer, write_records, DataRecord,
+    OutputWriter, TextFSM,
+};
+use std::io::{self, Write};
+
+const TEMPLATE: &str = r###"Value Name (\S+)
+Value Age (\d+)
+
+Start
+  ^${Name} ${Age} -> Record
+"###;
+
+/// Writes `Name=Age;` pairs between brackets.
+struct Pairs;
+
+impl OutputWriter for Pairs {
+    fn header(&mut self, out: &mut dyn Write, fields: &[String]) -> io::Result<()> {
+        write!(out, "[{}]", fields.join("/"))
+    }
+
+    fn record(
+        &mut self,
+        out: &mut dyn Write,
+        fields: &[String],
+        record: &DataRecord,
+    ) -> io::Result<()> {
+        let values: Vec<String> = fields
+            .iter()
+            .map(|f| record.get(f).unwrap().to_string())
+            .collect();
+        write!(out, "{};", values.join("="))
+    }
+
+    fn footer(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "[end]")
+    }
+}
+
+fn render(name: &str) -> String {
+    let records = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .parse_records("ann 3\nbob 40\n", None)
+        .unwrap();
+    let mut out = vec![];
+    let mut writer = output_writer(name).unwrap();
+    write_records(writer.as_mut(), &mut out, &records).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_registered_writer() {
+    register_output_writer("pairs", || Pairs);
+    assert!(output_writer_names().contains(&"pairs".to_string()));
+    assert_eq!(render("pairs"), "[Name/Age]ann=3;bob=40;[end]\n");
+}
+
+#[test]
+fn test_builtin_writers() {
+    assert_eq!(render("xml").lines().next(), Some("<results>"));
+    #[cfg(feature = "json")]
+    assert_eq!(
+        render("jsonl"),
+        "{\"Name\":\"ann\",\"Age\":\"3\"}\n{\"Name\":\"bob\",\"Age\":\"40\"}\n"
+    );
+    assert!(output_writer("no-such-writer").is_err());
+}
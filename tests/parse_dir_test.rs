@@ -0,0 +1,62 @@
+use asyncfsm::batch::parse_dir;
+use asyncfsm::{DirOptions, DirProgress, Template};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+fn capture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("asyncfsm-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("r1")).unwrap();
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join("r1/a.txt"), "Name: a\nName: b\n").unwrap();
+    std::fs::write(dir.join("r1/b.txt"), "Name: c\nbad\n").unwrap();
+    std::fs::write(dir.join("c.txt"), "Name: d\n").unwrap();
+    std::fs::write(dir.join("notes.md"), "Name: e\n").unwrap();
+    std::fs::write(dir.join(".git/HEAD"), "Name: f\n").unwrap();
+    dir
+}
+
+#[test]
+fn test_parse_dir() {
+    let dir = capture_dir("parse-dir");
+    let template =
+        Template::new("Value Name (\\S+)\n\nStart\n  ^Name: ${Name} -> Record\n  ^bad -> Error\n")
+            .unwrap();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let log = Arc::clone(&seen);
+    let options = DirOptions::new()
+        .jobs(2)
+        .extension("txt")
+        .on_progress(move |p| log.lock().unwrap().push(*p));
+    let result = parse_dir(&dir, &template, &options).unwrap();
+
+    let paths: Vec<PathBuf> = result.files.iter().map(|f| f.path.clone()).collect();
+    assert_eq!(paths, [PathBuf::from("c.txt"), PathBuf::from("r1/a.txt")]);
+    assert_eq!(result.records(), 3);
+    assert_eq!(result.files[1].lines, 2);
+    assert_eq!(
+        result.errors.keys().collect::<Vec<_>>(),
+        [&PathBuf::from("r1/b.txt")]
+    );
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 4);
+    assert_eq!(seen[0].files_done, 0);
+    assert_eq!(
+        seen[3],
+        DirProgress {
+            files_done: 3,
+            files_total: 3,
+            records: 3,
+            errors: 1,
+        }
+    );
+    assert!(seen.windows(2).all(|w| w[0].files_done < w[1].files_done));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parse_dir_missing() {
+    let template = Template::new("Value Name (\\S+)\n\nStart\n  ^${Name} -> Record\n").unwrap();
+    assert!(parse_dir("/nonexistent/captures", &template, &DirOptions::new()).is_err());
+}
@@ -174,3 +174,20 @@ GetDescription
     let pairs = TextFSMParser::parse(Rule::state_definitions, input).unwrap();
     assert_eq!(pairs.count(), 1);
 }
+
+#[test]
+fn test_unexpected_pairs_are_errors() {
+    let input = "Value PORT (\\d+)\n";
+    let pair = TextFSMParser::parse(Rule::value_definition, input)
+        .unwrap()
+        .next()
+        .unwrap();
+    assert!(matches!(
+        TextFSMParser::parse_state_rule(&pair),
+        Err(TextFsmError::ParseError(_))
+    ));
+    assert!(matches!(
+        TextFSMParser::parse_state_rule_transition(&pair),
+        Err(TextFsmError::ParseError(_))
+    ));
+}
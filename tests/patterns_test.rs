@@ -0,0 +1,81 @@
+use asyncfsm::{patterns, TextFSM};
+use regex::Regex;
+
+fn whole(pattern: &str) -> Regex {
+    Regex::new(&format!("^{}$", pattern)).unwrap()
+}
+
+#[test]
+fn test_patterns_match_whole_values() {
+    let cases: &[(&str, &[&str], &[&str])] = &[
+        (
+            patterns::IPV4,
+            &["10.0.0.1", "255.255.255.255"],
+            &["10.0.0.256", "10.0.0"],
+        ),
+        (
+            patterns::IPV4_PREFIX,
+            &["10.0.0.0/8", "0.0.0.0/0"],
+            &["10.0.0.0/33"],
+        ),
+        (
+            patterns::IPV6,
+            &["2001:db8::1", "::", "fe80::1", "::ffff:10.0.0.1"],
+            &["2001", "g::1"],
+        ),
+        (
+            patterns::IPV6_PREFIX,
+            &["2001:db8::/32"],
+            &["2001:db8::/129"],
+        ),
+        (
+            patterns::MAC,
+            &["00:11:22:33:44:55", "00-11-22-33-44-55", "0011.2233.4455"],
+            &["0011.2233", "00:11:22:33:44:5g"],
+        ),
+        (
+            patterns::VLAN,
+            &["1", "99", "4094"],
+            &["0", "4095", "10000"],
+        ),
+        (
+            patterns::VLAN_RANGE,
+            &["10", "1,10-20,4094"],
+            &["1,", "10-4095"],
+        ),
+        (
+            patterns::INTERFACE,
+            &[
+                "GigabitEthernet0/1",
+                "Gi0/1.100",
+                "Port-channel10",
+                "xe-0/0/0",
+            ],
+            &["Gi", "0/1"],
+        ),
+    ];
+    for (pattern, good, bad) in cases {
+        let re = whole(pattern);
+        for value in *good {
+            assert!(re.is_match(value), "{} should match {}", pattern, value);
+        }
+        for value in *bad {
+            assert!(
+                !re.is_match(value),
+                "{} should not match {}",
+                pattern,
+                value
+            );
+        }
+    }
+}
+
+#[test]
+fn test_every_pattern_is_a_builtin_macro() {
+    for (name, pattern) in patterns::PATTERNS {
+        assert_eq!(patterns::by_macro_name(name), Some(*pattern));
+        assert_eq!(Regex::new(pattern).unwrap().captures_len(), 1, "{}", name);
+        let template = format!("Value V (${{{}}})\n\nStart\n  ^${{V}} -> Record\n", name);
+        assert!(TextFSM::from_string(&template).is_ok(), "{}", name);
+    }
+}
@@ -0,0 +1,144 @@
+use asyncfsm::{DataRecordConversion, NestedValue, Nesting, Pipeline, Template, TextFSM, Value};
+
+const SECTIONS: &str = r#"Value VRF (\S+)
+Value List BODY (.+)
+
+Start
+  ^vrf -> Continue.Record
+  ^vrf ${VRF}
+  ^  ${BODY}
+"#;
+
+const NEIGHBORS: &str = r#"Value NEIGHBOR (\S+)
+Value List PREFIX (\S+)
+
+Start
+  ^neighbor -> Continue.Record
+  ^neighbor ${NEIGHBOR}
+  ^  prefix ${PREFIX}
+"#;
+
+const INPUT: &str = "vrf red
+  neighbor 10.0.0.1
+    prefix 192.0.2.0/24
+    prefix 198.51.100.0/24
+  neighbor 10.0.0.2
+vrf blue
+  neighbor 10.1.0.1
+";
+
+fn records(value: Option<&NestedValue>) -> &[asyncfsm::NestedRecord] {
+    match value {
+        Some(NestedValue::Records(records)) => records,
+        other => panic!("expected nested records, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sections_nest_across_levels() {
+    let pipeline = Pipeline::new(Template::new(SECTIONS).unwrap())
+        .section("BODY", Template::new(NEIGHBORS).unwrap());
+    let vrfs = pipeline.parse_string(INPUT).unwrap();
+    assert_eq!(vrfs.len(), 2);
+    assert_eq!(
+        vrfs[0].get("VRF"),
+        Some(&NestedValue::Value(Value::Single("red".into())))
+    );
+    let red = records(vrfs[0].get("BODY"));
+    assert_eq!(red.len(), 2);
+    assert_eq!(
+        red[0].get("PREFIX"),
+        Some(&NestedValue::Value(Value::List(vec![
+            "192.0.2.0/24".into(),
+            "198.51.100.0/24".into()
+        ])))
+    );
+    assert_eq!(records(vrfs[1].get("BODY")).len(), 1);
+}
+
+#[test]
+fn test_a_pipeline_can_be_a_section() {
+    let prefixes =
+        Template::new("Value NET (\\S+)\n\nStart\n  ^prefix ${NET} -> Record\n").unwrap();
+    let neighbors = Template::new(
+        "Value NEIGHBOR (\\S+)\nValue List BODY (.+)\n\nStart\n  ^neighbor -> Continue.Record\n  ^neighbor ${NEIGHBOR}\n  ^  ${BODY}\n",
+    )
+    .unwrap();
+    let pipeline = Pipeline::new(Template::new(SECTIONS).unwrap())
+        .section("BODY", Pipeline::new(neighbors).section("BODY", prefixes));
+    let vrfs = pipeline.parse_string(INPUT).unwrap();
+    let nets = records(records(vrfs[0].get("BODY"))[0].get("BODY"));
+    assert_eq!(
+        nets.iter()
+            .map(|n| n.get("NET").cloned())
+            .collect::<Vec<_>>(),
+        vec![
+            Some(NestedValue::Value(Value::Single("192.0.2.0/24".into()))),
+            Some(NestedValue::Value(Value::Single("198.51.100.0/24".into()))),
+        ]
+    );
+}
+
+#[test]
+fn test_nested_records_serialize_in_template_order() {
+    let pipeline = Pipeline::new(Template::new(SECTIONS).unwrap())
+        .section("BODY", Template::new(NEIGHBORS).unwrap());
+    let json = serde_json::to_string(&pipeline.parse_string(INPUT).unwrap()[1]).unwrap();
+    assert_eq!(
+        json,
+        r#"{"VRF":"blue","BODY":[{"NEIGHBOR":"10.1.0.1","PREFIX":[]}]}"#
+    );
+}
+
+const FLAT: &str = r#"# Nest: VRF -> NEIGHBORS; NEIGHBOR, AS -> PREFIXES
+Value Filldown VRF (\S+)
+Value Filldown NEIGHBOR (\S+)
+Value Filldown AS (\d+)
+Value PREFIX (\S+)
+
+Start
+  ^vrf ${VRF}
+  ^  neighbor ${NEIGHBOR} remote-as ${AS}
+  ^    prefix ${PREFIX} -> Record
+
+EOF
+"#;
+
+#[test]
+fn test_nesting_groups_filldown_parents() {
+    let fsm = TextFSM::from_string(FLAT).unwrap();
+    let nesting = Nesting::from_template(&fsm.parser).unwrap().unwrap();
+    assert_eq!(
+        nesting,
+        Nesting::new()
+            .level(["VRF"], "NEIGHBORS")
+            .level(["NEIGHBOR", "AS"], "PREFIXES")
+    );
+    let input = "vrf red\n  neighbor 10.0.0.1 remote-as 65001\n    prefix 192.0.2.0/24\n    prefix 198.51.100.0/24\n  neighbor 10.0.0.2 remote-as 65002\n    prefix 203.0.113.0/24\nvrf blue\n  neighbor 10.1.0.1 remote-as 65001\n    prefix 192.0.2.0/24\n";
+    let records = TextFSM::from_string(FLAT)
+        .unwrap()
+        .parse_records(input, Some(DataRecordConversion::LowercaseKeys))
+        .unwrap();
+    let json = serde_json::to_value(nesting.apply(&records)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"vrf": "red", "NEIGHBORS": [
+                {"neighbor": "10.0.0.1", "as": "65001", "PREFIXES": [
+                    {"prefix": "192.0.2.0/24"}, {"prefix": "198.51.100.0/24"}
+                ]},
+                {"neighbor": "10.0.0.2", "as": "65002", "PREFIXES": [{"prefix": "203.0.113.0/24"}]}
+            ]},
+            {"vrf": "blue", "NEIGHBORS": [
+                {"neighbor": "10.1.0.1", "as": "65001", "PREFIXES": [{"prefix": "192.0.2.0/24"}]}
+            ]}
+        ])
+    );
+}
+
+#[test]
+fn test_nesting_spec_errors() {
+    for spec in ["", "VRF", "VRF ->", ", -> X"] {
+        assert!(Nesting::parse(spec).is_err(), "{:?} was accepted", spec);
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "json")]
+use asyncfsm::publish::{FieldTemplate, Publisher, RecordPublisher};
+use asyncfsm::{DataRecord, Result, TextFSM};
+use std::io::Cursor;
+
+#[derive(Default)]
+struct MemoryPublisher {
+    messages: Vec<(String, Option<String>, String)>,
+    flushed: bool,
+}
+
+impl Publisher for MemoryPublisher {
+    fn publish(&mut self, topic: &str, key: Option<&str>, payload: &[u8]) -> Result<()> {
+        self.messages.push((
+            topic.to_string(),
+            key.map(str::to_string),
+            String::from_utf8(payload.to_vec()).unwrap(),
+        ));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.flushed = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_field_template() {
+    let mut rec = DataRecord::new();
+    rec.insert("HOST".to_string(), "r1".to_string());
+    let template = FieldTemplate::new("devices.${HOST}.$MISSING-$$").unwrap();
+    assert_eq!(template.render(&rec), "devices.r1.-$");
+}
+
+#[test]
+fn test_publish_records() {
+    let template = "Value Name (\\S+)\nValue Age (\\d+)\n\nStart\n  ^${Name} ${Age} -> Record\n";
+    let fsm = TextFSM::from_string(template).unwrap();
+    let records = fsm.parse_reader(Cursor::new("alice 30\nbob 25\n"));
+
+    let mut sink = RecordPublisher::new(MemoryPublisher::default(), "people.${Age}")
+        .unwrap()
+        .with_key("${Name}")
+        .unwrap();
+    assert_eq!(sink.send_all(records).unwrap(), 2);
+
+    let publisher = sink.into_inner();
+    assert!(publisher.flushed);
+    assert_eq!(publisher.messages[0].0, "people.30");
+    assert_eq!(publisher.messages[0].1.as_deref(), Some("alice"));
+    assert!(publisher.messages[1].2.contains("\"Name\":\"bob\""));
+}
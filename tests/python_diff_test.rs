@@ -0,0 +1,254 @@
+//! Differential tests against the reference Python `textfsm`.
+//!
+//! Each case is a template and an input, either taken from the example corpus with
+//! lines dropped at random or generated from a small template grammar. Both
+//! implementations parse it and the structured results must agree. A divergence is
+//! shrunk to a minimal template and input before it is reported.
+//!
+//! Run with `cargo test --features python_diff --test python_diff_test`. The
+//! interpreter is `$ASYNCFSM_PYTHON` (default `python3`) and needs `textfsm`
+//! installed; without it the tests report that they were skipped and pass.
+//! `$PYTHON_DIFF_CASES` sets the number of cases per test (default 64).
+#![cfg(feature = "python_diff")]
+
+use asyncfsm::{TextFSM, Value};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestError, TestRunner};
+use serde_json::{json, Value as Json};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+const RUNNER: &str = r#"
+import io, json, sys
+import textfsm
+case = json.load(sys.stdin)
+try:
+    fsm = textfsm.TextFSM(io.StringIO(case["template"]))
+    rows = fsm.ParseText(case["input"])
+    print(json.dumps({"header": fsm.header, "rows": rows}))
+except Exception as e:
+    print(json.dumps({"error": type(e).__name__}))
+"#;
+
+#[derive(Debug, Clone)]
+struct Case {
+    template: String,
+    input: String,
+}
+
+fn python() -> String {
+    std::env::var("ASYNCFSM_PYTHON").unwrap_or_else(|_| "python3".to_string())
+}
+
+fn python_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new(python())
+            .args(["-c", "import textfsm"])
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    })
+}
+
+/// Empty strings, empty lists and absent fields all mean "not captured".
+fn normalize(value: Json) -> Json {
+    match value {
+        Json::String(s) if s.is_empty() => Json::Null,
+        Json::Array(items) if items.is_empty() => Json::Null,
+        other => other,
+    }
+}
+
+/// Parses with Python: `{"header", "rows"}` on success, `{"error"}` on failure.
+fn run_python(case: &Case) -> Json {
+    let mut child = Command::new(python())
+        .args(["-c", RUNNER])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("python starts");
+    let request = json!({"template": case.template, "input": case.input});
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.to_string().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let mut result: Json = serde_json::from_slice(&output.stdout).expect("runner prints JSON");
+    if let Some(rows) = result.get_mut("rows") {
+        for row in rows.as_array_mut().unwrap() {
+            for cell in row.as_array_mut().unwrap() {
+                *cell = normalize(cell.take());
+            }
+        }
+    }
+    result
+}
+
+/// Parses with this crate, shaped like [`run_python`]'s output.
+fn run_rust(case: &Case) -> Json {
+    let mut fsm = match TextFSM::from_string(&case.template) {
+        Ok(fsm) => fsm,
+        Err(e) => return json!({"error": e.kind()}),
+    };
+    let records = match fsm.parse_records(&case.input, None) {
+        Ok(records) => records,
+        Err(e) => return json!({"error": e.kind()}),
+    };
+    let header = records.schema().to_vec();
+    let rows: Vec<Vec<Json>> = records
+        .iter()
+        .map(|rec| {
+            header
+                .iter()
+                .map(|name| match rec.get(name) {
+                    Some(Value::Single(s)) => normalize(json!(s)),
+                    Some(Value::List(items)) => normalize(json!(items)),
+                    None => Json::Null,
+                })
+                .collect()
+        })
+        .collect();
+    json!({"header": header, "rows": rows})
+}
+
+/// Error kinds differ between the implementations, so only "both failed" is compared.
+fn check(case: &Case) -> Result<(), TestCaseError> {
+    let rust = run_rust(case);
+    let python = run_python(case);
+    if rust.get("error").is_some() && python.get("error").is_some() {
+        return Ok(());
+    }
+    if rust != python {
+        return Err(TestCaseError::fail(format!(
+            "rust:   {}\npython: {}",
+            rust, python
+        )));
+    }
+    Ok(())
+}
+
+fn run(strategy: impl Strategy<Value = Case>) {
+    if !python_available() {
+        eprintln!("skipped: {} cannot import textfsm", python());
+        return;
+    }
+    let cases = std::env::var("PYTHON_DIFF_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(64);
+    let mut runner = TestRunner::new(Config {
+        cases,
+        failure_persistence: None,
+        ..Config::default()
+    });
+    match runner.run(&strategy, |case| check(&case)) {
+        Ok(()) => {}
+        Err(TestError::Fail(reason, case)) => panic!(
+            "divergence from Python textfsm\n{}\n--- template ---\n{}--- input ---\n{}",
+            reason, case.template, case.input
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// The template/example pairs shipped with the tests.
+fn corpus() -> Vec<(String, Vec<String>)> {
+    let mut pairs = vec![(
+        "tests/end_to_end/sample.template".to_string(),
+        "tests/end_to_end/sample.data".to_string(),
+    )];
+    for entry in std::fs::read_dir("tests/basic_template/cli").unwrap() {
+        let path = entry.unwrap().path().to_string_lossy().into_owned();
+        if let Some(stem) = path.strip_suffix("_template") {
+            let example = format!("{}_example", stem);
+            if std::path::Path::new(&example).exists() {
+                pairs.push((path, example));
+            }
+        }
+    }
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(template, example)| {
+            let lines = std::fs::read_to_string(example)
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect();
+            (std::fs::read_to_string(template).unwrap(), lines)
+        })
+        .collect()
+}
+
+/// A corpus template with a random subset of its example's lines.
+fn corpus_case() -> impl Strategy<Value = Case> {
+    let corpus = corpus();
+    (0..corpus.len()).prop_flat_map(move |i| {
+        let (template, lines) = corpus[i].clone();
+        let len = lines.len();
+        proptest::sample::subsequence(lines, 0..=len).prop_map(move |kept| Case {
+            template: template.clone(),
+            input: kept.iter().map(|l| format!("{}\n", l)).collect(),
+        })
+    })
+}
+
+const OPTIONS: &[&str] = &["", "Filldown ", "Required ", "List ", "Key ", "Fillup "];
+const PATTERNS: &[&str] = &[r"(\d+)", r"(\S+)", r"(\w+)", r"(a|b|c)"];
+const ACTIONS: &[&str] = &[
+    "",
+    " -> Record",
+    " -> Continue",
+    " -> Continue.Record",
+    " -> Next.Record",
+    " -> Clear",
+    " -> Clearall",
+    " -> Next.Clearall",
+];
+const TOKENS: &[&str] = &["1", "22", "a", "b", "c", "x-1", "foo"];
+
+/// A template with a few values and single-state rules `^pN ${VALUE}`, plus input
+/// lines built from the same prefixes.
+fn generated_case() -> impl Strategy<Value = Case> {
+    let values = prop::collection::vec((0..OPTIONS.len(), 0..PATTERNS.len()), 1..4);
+    values.prop_flat_map(|values| {
+        let count = values.len();
+        let rules = prop::collection::vec((0..4usize, 0..count, 0..ACTIONS.len()), 1..6);
+        let lines = prop::collection::vec((0..5usize, 0..TOKENS.len()), 0..12);
+        (Just(values), rules, lines).prop_map(|(values, rules, lines)| {
+            let mut template = String::new();
+            for (i, (option, pattern)) in values.iter().enumerate() {
+                template.push_str(&format!(
+                    "Value {}V{} {}\n",
+                    OPTIONS[*option], i, PATTERNS[*pattern]
+                ));
+            }
+            template.push_str("\nStart\n");
+            for (prefix, value, action) in rules {
+                template.push_str(&format!(
+                    "  ^p{} ${{V{}}}{}\n",
+                    prefix, value, ACTIONS[action]
+                ));
+            }
+            let input = lines
+                .iter()
+                .map(|(prefix, token)| format!("p{} {}\n", prefix, TOKENS[*token]))
+                .collect();
+            Case { template, input }
+        })
+    })
+}
+
+#[test]
+fn corpus_matches_python() {
+    run(corpus_case());
+}
+
+#[test]
+fn generated_templates_match_python() {
+    run(generated_case());
+}
@@ -28,7 +28,7 @@ fn test_individual_templates() {
                     .unwrap_or_else(|_| panic!("Failed to parse template {}", template_path));
 
                 let result = textfsm
-                    .parse_file(&example_path, None)
+                    .parse_file_records(&example_path, None)
                     .unwrap_or_else(|_| panic!("Failed to parse data file {}", example_path));
 
                 assert!(
@@ -101,3 +101,38 @@ fn test_cli_table_index() {
         );
     }
 }
+
+#[test]
+#[cfg(feature = "clitable")]
+fn test_cli_table_detect() {
+    let data_dir = get_data_dir();
+    let cli_table = CliTable::from_file(format!("{}/index", data_dir)).unwrap();
+
+    let example = fs::read_to_string(format!("{}/juniper_version_example", data_dir)).unwrap();
+    assert_eq!(
+        cli_table.guess_platform(&example).as_deref(),
+        Some("Juniper")
+    );
+
+    let capture = format!("router.abc> show version\n{}", example);
+    let detected = cli_table.detect(&capture).expect("should detect");
+    assert_eq!(detected.platform, "Juniper");
+    assert_eq!(detected.command, "show version");
+    assert_eq!(detected.row.templates, vec!["juniper_version_template"]);
+}
+
+#[test]
+#[cfg(feature = "clitable")]
+fn test_cli_table_custom_fingerprint() {
+    let data_dir = get_data_dir();
+    let mut cli_table = CliTable::from_file(format!("{}/index", data_dir)).unwrap();
+    cli_table
+        .add_fingerprint("Force10", "Value Os (FTOS)\n\nStart\n  ^.*${Os}\n")
+        .unwrap();
+
+    let capture = "sw1#sh ver\nDell Networking OS (FTOS) Version 9.1\n";
+    let detected = cli_table.detect(capture).expect("should detect");
+    assert_eq!(detected.platform, "Force10");
+    assert_eq!(detected.row.templates, vec!["f10_version_template"]);
+    assert!(cli_table.detect("no prompt here\n").is_none());
+}
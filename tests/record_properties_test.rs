@@ -0,0 +1,153 @@
+//! Properties of record construction: value promotion, Filldown, keys and conversion.
+//!
+//! These pin down the intended semantics of `record.rs` and the parts of the engine that
+//! build records, so a refactor that changes them fails here rather than in production.
+
+use asyncfsm::{DataRecord, DataRecordConversion, Records, TextFSM, Value};
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+fn word() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9]{0,6}"
+}
+
+fn parse(template: &str, input: &str, conversion: Option<DataRecordConversion>) -> Records {
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    fsm.parse_records(input, conversion).unwrap()
+}
+
+fn text(rec: &DataRecord, name: &str) -> String {
+    rec.get(name).map(|v| v.to_string()).unwrap_or_default()
+}
+
+proptest! {
+    #[test]
+    fn appending_a_single_replaces_a_single(old in word(), new in word()) {
+        let mut rec = DataRecord::new();
+        rec.append_value("V".into(), Value::Single(old));
+        rec.append_value("V".into(), Value::Single(new.clone()));
+        prop_assert_eq!(rec.get("V"), Some(&Value::Single(new)));
+    }
+
+    #[test]
+    fn appending_a_list_promotes_a_single(old in word(), items in prop::collection::vec(word(), 0..5)) {
+        let mut rec = DataRecord::new();
+        rec.append_value("V".into(), Value::Single(old.clone()));
+        rec.append_value("V".into(), Value::List(items.clone()));
+        let mut expected = vec![old];
+        expected.extend(items);
+        prop_assert_eq!(rec.get("V"), Some(&Value::List(expected)));
+    }
+
+    #[test]
+    fn list_appends_keep_their_order(chunks in prop::collection::vec(prop::collection::vec(word(), 0..4), 1..6)) {
+        let mut rec = DataRecord::new();
+        for chunk in &chunks {
+            rec.append_value("V".into(), Value::List(chunk.clone()));
+        }
+        prop_assert_eq!(rec.get("V"), Some(&Value::List(chunks.concat())));
+    }
+
+    #[test]
+    fn repeated_inserts_collect_in_order(items in prop::collection::vec(word(), 1..6)) {
+        let mut rec = DataRecord::new();
+        for item in &items {
+            rec.insert("V".into(), item.clone());
+        }
+        let expected = if items.len() == 1 {
+            Value::Single(items[0].clone())
+        } else {
+            Value::List(items)
+        };
+        prop_assert_eq!(rec.get("V"), Some(&expected));
+    }
+
+    #[test]
+    fn list_captures_keep_input_order(groups in prop::collection::vec(prop::collection::vec(1u16..4095, 1..5), 1..5)) {
+        let template = "Value List VLAN (\\d+)\n\nStart\n  ^vlan ${VLAN}\n  ^end -> Record\n";
+        let mut input = String::new();
+        for group in &groups {
+            for vlan in group {
+                input.push_str(&format!("vlan {}\n", vlan));
+            }
+            input.push_str("end\n");
+        }
+        let records = parse(template, &input, None);
+        prop_assert_eq!(records.len(), groups.len());
+        for (rec, group) in records.iter().zip(&groups) {
+            let expected: Vec<String> = group.iter().map(u16::to_string).collect();
+            prop_assert_eq!(rec.get("VLAN"), Some(&Value::List(expected)));
+        }
+    }
+
+    #[test]
+    fn filldown_carries_the_last_value_seen(
+        blocks in prop::collection::vec((prop::option::of(word()), prop::collection::vec(word(), 1..4)), 1..6)
+    ) {
+        let template = "Value Filldown HOST (\\S+)\nValue IFACE (\\S+)\n\nStart\n  ^host ${HOST}\n  ^iface ${IFACE} -> Record\n\nEOF\n";
+        let mut input = String::new();
+        let mut expected = Vec::new();
+        let mut host = String::new();
+        for (new_host, ifaces) in &blocks {
+            if let Some(h) = new_host {
+                input.push_str(&format!("host {}\n", h));
+                host = h.clone();
+            }
+            for iface in ifaces {
+                input.push_str(&format!("iface {}\n", iface));
+                expected.push((host.clone(), iface.clone()));
+            }
+        }
+        let records = parse(template, &input, None);
+        let got: Vec<(String, String)> = records
+            .iter()
+            .map(|r| (text(r, "HOST"), text(r, "IFACE")))
+            .collect();
+        prop_assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn keys_depend_only_on_key_values(rows in prop::collection::vec((word(), word(), word()), 1..8)) {
+        let template = "Value Key A (\\w+)\nValue Key B (\\w+)\nValue C (\\w+)\n\nStart\n  ^${A} ${B} ${C} -> Record\n";
+        let input: String = rows.iter().map(|(a, b, c)| format!("{} {} {}\n", a, b, c)).collect();
+        let records = parse(template, &input, None);
+        prop_assert_eq!(records.len(), rows.len());
+        let mut seen: HashMap<&String, (&String, &String)> = HashMap::new();
+        for (rec, (a, b, _)) in records.iter().zip(&rows) {
+            let key = rec.record_key.as_ref().expect("records with Key values have a key");
+            match seen.get(key) {
+                Some(&prev) => prop_assert_eq!(prev, (a, b)),
+                None => {
+                    prop_assert!(!seen.values().any(|&prev| prev == (a, b)));
+                    seen.insert(key, (a, b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lowercase_conversion_is_idempotent(
+        rows in prop::collection::vec(prop::collection::hash_map("[A-Za-z][A-Za-z_]{0,5}", word(), 0..4), 0..5)
+    ) {
+        let records: Vec<DataRecord> = rows
+            .into_iter()
+            .map(|fields| DataRecord {
+                fields: fields.into_iter().map(|(k, v)| (k, Value::Single(v))).collect(),
+                ..Default::default()
+            })
+            .collect();
+        let schema: Vec<String> = records.iter().flat_map(|r| r.keys().cloned()).collect();
+        let once = Records::new(records, schema).convert(Some(DataRecordConversion::LowercaseKeys));
+        let twice = once.clone().convert(Some(DataRecordConversion::LowercaseKeys));
+        prop_assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn converting_while_parsing_matches_converting_afterwards(rows in prop::collection::vec((word(), word()), 0..6)) {
+        let template = "Value Name (\\w+)\nValue ADDR (\\w+)\n\nStart\n  ^${Name} ${ADDR} -> Record\n";
+        let input: String = rows.iter().map(|(n, a)| format!("{} {}\n", n, a)).collect();
+        let during = parse(template, &input, Some(DataRecordConversion::LowercaseKeys));
+        let after = parse(template, &input, None).convert(Some(DataRecordConversion::LowercaseKeys));
+        prop_assert_eq!(during, after);
+    }
+}
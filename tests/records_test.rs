@@ -0,0 +1,114 @@
+use asyncfsm::{DataRecord, DataRecordConversion, Plucked, Records, TextFSM};
+
+const TEMPLATE: &str = r###"Value ZONE (\S+)
+Value NAME (\S+)
+Value ADDRESS (\S+)
+
+Start
+  ^${ZONE} ${NAME} ${ADDRESS} -> Record
+"###;
+
+fn parse(conversion: Option<DataRecordConversion>) -> Records {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    fsm.parse_records("lan r1 192.0.2.1\nwan r2 198.51.100.1\n", conversion)
+        .unwrap()
+}
+
+#[test]
+fn test_records_behave_like_a_vec() {
+    let records = parse(None);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].get("NAME").unwrap().to_string(), "r2");
+    let names: Vec<String> = records
+        .iter()
+        .map(|r| r.get("NAME").unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["r1", "r2"]);
+
+    let owned: Vec<DataRecord> = records.clone().into_iter().collect();
+    assert_eq!(records, owned);
+    assert_eq!(records.into_vec(), owned);
+}
+
+#[test]
+fn test_schema_follows_template_order() {
+    assert_eq!(parse(None).schema(), ["ZONE", "NAME", "ADDRESS"]);
+    assert_eq!(
+        parse(Some(DataRecordConversion::LowercaseKeys)).schema(),
+        ["zone", "name", "address"]
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_serialization_is_ordered() {
+    let json = serde_json::to_string(&parse(None)).unwrap();
+    assert!(
+        json.starts_with(r#"[{"ZONE":"lan","NAME":"r1","ADDRESS":"192.0.2.1","record_key":null}"#)
+    );
+
+    let back: Records = serde_json::from_str(r#"[{"A":"1"}]"#).unwrap();
+    assert_eq!(back[0].get("A").unwrap().to_string(), "1");
+}
+
+#[test]
+#[cfg(feature = "csv_export")]
+fn test_csv_headers_follow_schema() {
+    use asyncfsm::{OutputFormat, TextFsmExport};
+
+    let csv = parse(None).export(OutputFormat::Csv).unwrap();
+    assert_eq!(csv.lines().next().unwrap(), "ZONE,NAME,ADDRESS");
+}
+
+#[test]
+fn test_pluck_paths() {
+    let records = parse(None);
+    let strings = |path: &str| -> Vec<String> {
+        records
+            .pluck(path)
+            .unwrap()
+            .iter()
+            .map(|p| p.as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(strings("records[*].NAME"), ["r1", "r2"]);
+    assert_eq!(strings("NAME"), ["r1", "r2"]);
+    assert_eq!(strings("[1].ADDRESS"), ["198.51.100.1"]);
+    assert_eq!(strings("records[?ZONE==wan].NAME"), ["r2"]);
+    assert_eq!(strings("records[?ZONE!=wan].NAME"), ["r1"]);
+    assert!(strings("records[*].MISSING").is_empty());
+
+    assert_eq!(
+        records.pluck("records[0]").unwrap(),
+        [Plucked::Record(&records[0])]
+    );
+    assert!(records.pluck("records[x]").is_err());
+    assert!(records.pluck("NAME.SUB").is_err());
+}
+
+#[test]
+fn test_sort_by_line_is_stable() {
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(asyncfsm::TextFsmOptions::default().with_raw(true));
+    let parsed = fsm
+        .parse_records("lan r1 192.0.2.1\nwan r2 198.51.100.1\n", None)
+        .unwrap();
+    let schema = parsed.schema().to_vec();
+    let mut tied = parsed[1].clone();
+    tied.fields
+        .insert("NAME".into(), asyncfsm::Value::Single("r3".into()));
+    let mut records = Records::new(vec![parsed[1].clone(), tied, parsed[0].clone()], schema);
+    records.sort_by_line();
+    let names: Vec<String> = records
+        .iter()
+        .map(|r| {
+            format!(
+                "{}@{}",
+                r.get("NAME").unwrap(),
+                r.raw.as_ref().unwrap().first_line
+            )
+        })
+        .collect();
+    assert_eq!(names, ["r1@1", "r2@2", "r3@2"]);
+}
@@ -0,0 +1,54 @@
+use asyncfsm::{RegexFlags, TextFSM, TextFSMParser};
+
+const TEMPLATE: &str = r###"Value Interface (\S+)
+Value Status (up|down)
+
+Start
+  ^Interface ${Interface} is ${Status} -> Record
+"###;
+
+const INPUT: &str = "Interface Gi0/1 is UP\ninterface Gi0/2 is down\n";
+
+fn statuses(parser: TextFSMParser) -> Vec<String> {
+    let mut fsm = TextFSM::from_parser(parser);
+    fsm.parse_records(INPUT, None)
+        .unwrap()
+        .iter()
+        .map(|r| r.get("Status").unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_regex_flags_header() {
+    assert!(statuses(TextFSMParser::from_string(TEMPLATE).unwrap()).is_empty());
+
+    let template = format!("# RegexFlags: i\n{}", TEMPLATE);
+    let parser = TextFSMParser::from_string(&template).unwrap();
+    assert!(parser.regex_flags.case_insensitive);
+    assert_eq!(statuses(parser), ["UP", "down"]);
+
+    assert!(TextFSMParser::from_string(&format!("# RegexFlags: q\n{}", TEMPLATE)).is_err());
+}
+
+#[test]
+fn test_with_regex_flags() {
+    let flags = RegexFlags::parse("i").unwrap();
+    assert_eq!(flags.inline_group(), "(?i)");
+    let parser = TextFSMParser::from_string(TEMPLATE)
+        .unwrap()
+        .with_regex_flags(flags)
+        .unwrap();
+    assert_eq!(statuses(parser.clone()), ["UP", "down"]);
+
+    // Replacing the flags drops the earlier ones.
+    let parser = parser.with_regex_flags(RegexFlags::default()).unwrap();
+    assert!(statuses(parser).is_empty());
+}
+
+#[test]
+fn test_inline_flags_per_rule() {
+    let template = TEMPLATE.replace("^Interface", "^(?i)interface");
+    let statuses = statuses(TextFSMParser::from_string(&template).unwrap());
+    // The group covers the whole rule, including the value regexes.
+    assert_eq!(statuses, ["UP", "down"]);
+}
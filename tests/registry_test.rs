@@ -0,0 +1,132 @@
+#![cfg(feature = "registry")]
+
+use asyncfsm::registry::checksum;
+use asyncfsm::{GitSource, TemplateRegistry, TextFsmError};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Creates a repository with an index at `templates/index`, tagged `v1`.
+fn template_repo(name: &str) -> (PathBuf, PathBuf) {
+    let root = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&root);
+    let repo = root.join("repo");
+    std::fs::create_dir_all(repo.join("templates")).unwrap();
+    std::fs::write(
+        repo.join("templates/index"),
+        "Template, Platform, Command\nname_template, x, sh[[ow]] name\n",
+    )
+    .unwrap();
+    std::fs::write(
+        repo.join("templates/name_template"),
+        "Value NAME (\\S+)\n\nStart\n  ^name ${NAME} -> Record\n",
+    )
+    .unwrap();
+    git(&repo, &["init", "--quiet"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "templates"]);
+    git(&repo, &["tag", "v1"]);
+    (repo, root.join("cache"))
+}
+
+#[test]
+fn test_fetch_and_parse() {
+    let (repo, cache) = template_repo("asyncfsm_registry_fetch");
+    let url = repo.to_str().unwrap();
+    let source = GitSource::new(url, "v1").cache_dir(&cache);
+
+    let table = source.load().unwrap();
+    let records = table.parse("x", "show name", "name r1\n").unwrap();
+    assert_eq!(records[0].get("NAME").unwrap().to_string(), "r1");
+    assert!(source.checkout_dir().starts_with(&cache));
+
+    // A second load reuses the checkout even if the origin is gone.
+    std::fs::remove_dir_all(&repo).unwrap();
+    assert!(source.load().is_ok());
+}
+
+#[test]
+fn test_checksum_verification() {
+    let (repo, cache) = template_repo("asyncfsm_registry_checksum");
+    let expected = checksum(&repo.join("templates")).unwrap();
+    let url = repo.to_str().unwrap();
+
+    let index = GitSource::new(url, "v1")
+        .cache_dir(&cache)
+        .checksum(&expected)
+        .fetch()
+        .unwrap();
+    assert!(index.ends_with("templates/index"));
+
+    let err = GitSource::new(url, "v1")
+        .cache_dir(&cache)
+        .checksum(&"0".repeat(64))
+        .fetch()
+        .unwrap_err();
+    assert!(matches!(err, TextFsmError::RegistryError(msg) if msg.contains("checksum mismatch")));
+}
+
+#[test]
+fn test_unknown_ref() {
+    let (repo, cache) = template_repo("asyncfsm_registry_badref");
+    let err = GitSource::new(repo.to_str().unwrap(), "v9")
+        .cache_dir(&cache)
+        .fetch()
+        .unwrap_err();
+    assert!(matches!(err, TextFsmError::RegistryError(_)));
+}
+
+const EMBEDDED_V2: &str = "# Version: 2\nValue HOST (\\S+)\n\nStart\n  ^name ${HOST} -> Record\n";
+
+#[test]
+fn test_registry_precedence_and_versions() {
+    let (repo, _) = template_repo("asyncfsm_registry_lookup");
+    let mut registry = TemplateRegistry::new();
+    registry
+        .add_embedded("x", "sh[[ow]] name", "embedded_name", EMBEDDED_V2)
+        .unwrap();
+    registry.add_index(repo.join("templates/index")).unwrap();
+    assert_eq!(registry.entries().len(), 2);
+
+    // The source added first wins.
+    let entry = registry.lookup("x", "sh name", None).unwrap();
+    assert_eq!(entry.name, "embedded_name");
+    assert_eq!(entry.version.as_deref(), Some("2"));
+    assert_eq!(entry.source, "embedded");
+    assert!(entry.path().is_none());
+    let records = registry.parse("x", "show name", None, "name r1\n").unwrap();
+    assert_eq!(records[0].get("HOST").unwrap().to_string(), "r1");
+
+    // A version without a match finds nothing; the index template has no version.
+    assert!(registry.lookup("x", "show name", Some("1")).is_none());
+    assert!(registry.lookup("y", "show name", None).is_none());
+    let by_name: Vec<_> = registry.by_name("name_template").collect();
+    assert_eq!(by_name.len(), 1);
+    assert!(by_name[0]
+        .path()
+        .unwrap()
+        .ends_with("templates/name_template"));
+}
+
+#[test]
+fn test_registry_git_version_defaults_to_ref() {
+    let (repo, cache) = template_repo("asyncfsm_registry_git");
+    let mut registry = TemplateRegistry::new();
+    registry
+        .add_git(&GitSource::new(repo.to_str().unwrap(), "v1").cache_dir(&cache))
+        .unwrap();
+    let entry = registry.lookup("x", "show name", Some("v1")).unwrap();
+    assert_eq!(entry.name, "name_template");
+    assert!(entry.source.ends_with("#v1"));
+    assert!(entry.load().is_ok());
+}
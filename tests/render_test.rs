@@ -0,0 +1,64 @@
+use asyncfsm::{DataRecord, LineTemplate, TextFSM, Value};
+
+const STANZA: &str =
+    "interface ${INTERFACE}\n description ${DESCRIPTION}\n ip address ${IP} ${MASK}\n!";
+
+fn record(fields: &[(&str, &str)]) -> DataRecord {
+    let mut rec = DataRecord::new();
+    for (k, v) in fields {
+        rec.insert(k.to_string(), v.to_string());
+    }
+    rec
+}
+
+#[test]
+fn test_render_skips_empty_lines() {
+    let template = LineTemplate::new(STANZA).unwrap();
+    let records = vec![
+        record(&[
+            ("INTERFACE", "Gi0/1"),
+            ("DESCRIPTION", "uplink"),
+            ("IP", "192.0.2.1"),
+            ("MASK", "255.255.255.0"),
+        ]),
+        record(&[("INTERFACE", "Gi0/2"), ("DESCRIPTION", "")]),
+    ];
+    assert_eq!(
+        template.render(&records),
+        "interface Gi0/1\n description uplink\n ip address 192.0.2.1 255.255.255.0\n!\n\
+         interface Gi0/2\n!\n"
+    );
+}
+
+#[test]
+fn test_render_repeats_list_lines() {
+    let template = LineTemplate::new("vlan ${VLAN}\n name ${NAME}\n tagged $PORTS").unwrap();
+    let mut rec = record(&[("VLAN", "10"), ("NAME", "users")]);
+    rec.fields.insert(
+        "PORTS".to_string(),
+        Value::List(vec!["Gi0/1".to_string(), "Gi0/2".to_string()]),
+    );
+    assert_eq!(
+        template.render_record(&rec),
+        "vlan 10\n name users\n tagged Gi0/1\n tagged Gi0/2\n"
+    );
+}
+
+#[test]
+fn test_parse_render_round_trip() {
+    let mut fsm = TextFSM::from_string(
+        "Value INTERFACE (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^interface ${INTERFACE} mtu ${MTU} -> Record\n",
+    )
+    .unwrap();
+    let input = "interface Gi0/1 mtu 1500\ninterface Gi0/2 mtu 9000\n";
+    let mut records = fsm.parse_records(input, None).unwrap();
+    records[1]
+        .fields
+        .insert("MTU".to_string(), Value::Single("9216".to_string()));
+
+    let template = LineTemplate::new("interface ${INTERFACE} mtu ${MTU}").unwrap();
+    assert_eq!(
+        template.render(&records),
+        "interface Gi0/1 mtu 1500\ninterface Gi0/2 mtu 9216\n"
+    );
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "report")]
+
+use asyncfsm::{DataRecord, Report};
+
+fn record(fields: &[(&str, &str)]) -> DataRecord {
+    let mut rec = DataRecord::new();
+    for (k, v) in fields {
+        rec.insert(k.to_string(), v.to_string());
+    }
+    rec
+}
+
+fn interfaces() -> Vec<DataRecord> {
+    vec![
+        record(&[("INTERFACE", "Gi0/10"), ("VRF", "blue"), ("MTU", "9000")]),
+        record(&[("INTERFACE", "Gi0/2"), ("VRF", "red"), ("MTU", "1500")]),
+        record(&[("INTERFACE", "Gi0/1"), ("VRF", "blue"), ("MTU", "1500")]),
+    ]
+}
+
+#[test]
+fn test_render_records() {
+    let report =
+        Report::from_string("{{#each records}}{{INTERFACE}} <{{VRF}}>\n{{/each}}").unwrap();
+    assert_eq!(
+        report.render(&interfaces()).unwrap(),
+        "Gi0/10 <blue>\nGi0/2 <red>\nGi0/1 <blue>\n"
+    );
+}
+
+#[test]
+fn test_group_by_helper() {
+    let report = Report::from_string(
+        "{{#each (group_by records \"VRF\")}}{{@key}}:{{#each this}} {{INTERFACE}}{{/each}}\n{{/each}}",
+    )
+    .unwrap();
+    assert_eq!(
+        report.render(&interfaces()).unwrap(),
+        "blue: Gi0/10 Gi0/1\nred: Gi0/2\n"
+    );
+}
+
+#[test]
+fn test_sort_by_helper_is_numeric() {
+    let report =
+        Report::from_string("{{#each (sort_by records \"MTU\")}}{{MTU}} {{/each}}").unwrap();
+    assert_eq!(report.render(&interfaces()).unwrap(), "1500 1500 9000 ");
+}
+
+#[test]
+fn test_invalid_template() {
+    assert!(Report::from_string("{{#each records}}").is_err());
+}
@@ -0,0 +1,45 @@
+use asyncfsm::{TemplateResolver, TextFsmError};
+use std::path::PathBuf;
+
+const CLI_DIR: &str = "tests/basic_template/cli";
+
+#[test]
+fn test_resolve_by_name_in_dir() {
+    let resolver = TemplateResolver::new().with_dir(CLI_DIR);
+    let path = resolver.resolve("cisco_version_template").unwrap();
+    assert_eq!(path, PathBuf::from(CLI_DIR).join("cisco_version_template"));
+    assert!(resolver.load("cisco_version_template").is_ok());
+}
+
+#[test]
+fn test_resolve_adds_extension() {
+    let dir = std::env::temp_dir().join("asyncfsm_resolver_ext");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("simple.textfsm"),
+        "Value A (\\S+)\n\nStart\n  ^${A} -> Record\n",
+    )
+    .unwrap();
+
+    let resolver = TemplateResolver::new().with_dir(CLI_DIR).with_dir(&dir);
+    assert_eq!(
+        resolver.resolve("simple").unwrap(),
+        dir.join("simple.textfsm")
+    );
+}
+
+#[test]
+fn test_existing_path_wins() {
+    let path = format!("{}/juniper_version_template", CLI_DIR);
+    let resolver = TemplateResolver::new().with_dir("/nonexistent");
+    assert_eq!(resolver.resolve(&path).unwrap(), PathBuf::from(&path));
+}
+
+#[test]
+fn test_unknown_template() {
+    let resolver = TemplateResolver::new().with_dir(CLI_DIR);
+    assert!(matches!(
+        resolver.resolve("no_such_template"),
+        Err(TextFsmError::TemplateNotFound(name)) if name == "no_such_template"
+    ));
+}
@@ -0,0 +1,77 @@
+use asyncfsm::{FieldChange, FieldType, SchemaDiff, TextFSMParser};
+
+fn schema_diff(old: &str, new: &str) -> SchemaDiff {
+    SchemaDiff::between(
+        &TextFSMParser::from_string(old).unwrap(),
+        &TextFSMParser::from_string(new).unwrap(),
+    )
+}
+
+#[test]
+fn test_same_fields_are_not_breaking() {
+    let old = "Value NAME (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^${NAME} ${MTU}\n";
+    let new =
+        "Value Filldown NAME (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^${NAME}\n  ^  mtu ${MTU}\n";
+    let diff = schema_diff(old, new);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "records have the same fields\n");
+
+    let diff = schema_diff(
+        old,
+        &new.replace("Value MTU", "Value SPEED (\\S+)\nValue MTU"),
+    );
+    assert_eq!(
+        diff.fields,
+        vec![FieldChange::Added {
+            name: "SPEED".into(),
+            field_type: FieldType::Single
+        }]
+    );
+    assert!(!diff.is_breaking());
+}
+
+#[test]
+fn test_breaking_field_changes() {
+    let old = r###"Value PORT (\S+)
+Value VLAN (\d+)
+Value DESCRIPTION (.*)
+
+Start
+  ^${PORT} ${VLAN} ${DESCRIPTION}
+"###;
+    let new = r###"Value INTERFACE (\S+)
+Value List VLAN (\d+)
+Value MTU (\d+)
+
+Start
+  ^${INTERFACE} ${VLAN} ${MTU}
+"###;
+    let diff = schema_diff(old, new);
+    assert!(diff.is_breaking());
+    assert_eq!(
+        diff.fields,
+        vec![
+            FieldChange::Renamed {
+                old: "PORT".into(),
+                new: "INTERFACE".into()
+            },
+            FieldChange::TypeChanged {
+                name: "VLAN".into(),
+                old: FieldType::Single,
+                new: FieldType::List
+            },
+            FieldChange::Removed {
+                name: "DESCRIPTION".into(),
+                field_type: FieldType::Single
+            },
+            FieldChange::Added {
+                name: "MTU".into(),
+                field_type: FieldType::Single
+            },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "~ PORT renamed to INTERFACE\n~ VLAN: single -> list\n- DESCRIPTION (single)\n+ MTU (single)\n"
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "script")]
+
+use asyncfsm::{RecordScript, TextFSM};
+
+const TEMPLATE: &str = r###"Value Interface (\S+)
+Value Rate (\d+)
+Value Bandwidth (\d+)
+Value List Vlans (\d+)
+
+Start
+  ^${Interface} ${Rate}/${Bandwidth}
+  ^  vlan ${Vlans}
+  ^! -> Record
+"###;
+
+const INPUT: &str =
+    "GigabitEthernet0/1 250/1000\n  vlan 10\n  vlan 20\n!\nGigabitEthernet0/2 0/1000\n!\n";
+
+#[test]
+fn test_record_script() {
+    let script = RecordScript::new(
+        r#"
+        if record.Rate == "0" {
+            record = ();
+        } else {
+            record.Interface.replace("GigabitEthernet", "Gi");
+            record.Percent = parse_int(record.Rate) * 100 / parse_int(record.Bandwidth);
+            record.Vlans.push("99");
+            record.remove("Bandwidth");
+        }
+        "#,
+    )
+    .unwrap();
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let records = script
+        .apply(fsm.parse_records(INPUT, None).unwrap())
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    let get = |name: &str| records[0].get(name).map(|v| v.to_string());
+    assert_eq!(get("Interface").unwrap(), "Gi0/1");
+    assert_eq!(get("Percent").unwrap(), "25");
+    match records[0].get("Vlans") {
+        Some(asyncfsm::Value::List(vlans)) => assert_eq!(vlans.len(), 3),
+        other => panic!("expected a list, got {:?}", other),
+    }
+    assert!(get("Bandwidth").is_none());
+    assert_eq!(records.schema()[0], "Interface");
+}
+
+#[test]
+fn test_script_errors() {
+    assert!(RecordScript::new("record.x = ").is_err());
+    let script = RecordScript::new("record = 5;").unwrap();
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    assert!(script
+        .apply(fsm.parse_records(INPUT, None).unwrap())
+        .is_err());
+}
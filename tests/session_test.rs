@@ -0,0 +1,144 @@
+use asyncfsm::{SessionState, TextFSM};
+
+const TEMPLATE: &str = r###"Value Filldown Chassis (\S+)
+Value Name (\S+)
+Value Age (\d+)
+
+Start
+  ^Chassis: ${Chassis}
+  ^Name: ${Name}
+  ^Age: ${Age} -> Record
+"###;
+
+#[test]
+fn test_parse_chunks_with_split_line() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let first = fsm
+        .parse_chunk("Chassis: c1\nName: Alice\nAge: 3", None)
+        .unwrap();
+    assert!(first.is_empty());
+    assert_eq!(fsm.partial_line, "Age: 3");
+
+    let second = fsm.parse_chunk("0\nName: Bob\n", None).unwrap();
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].get("Age").unwrap().to_string(), "30");
+
+    let rest = fsm.finish(None).unwrap();
+    assert_eq!(rest.len(), 1);
+    assert_eq!(rest[0].get("Name").unwrap().to_string(), "Bob");
+    assert_eq!(rest[0].get("Chassis").unwrap().to_string(), "c1");
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_checkpoint_and_resume() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    fsm.parse_chunk("Chassis: c1\nName: Alice\n", None).unwrap();
+    let saved = serde_json::to_string(&fsm.checkpoint()).unwrap();
+
+    let mut resumed = TextFSM::from_string(TEMPLATE).unwrap();
+    let state: SessionState = serde_json::from_str(&saved).unwrap();
+    resumed.resume(state).unwrap();
+    let records = resumed
+        .parse_chunk("Age: 30\nName: Bob\nAge: 25\n", None)
+        .unwrap();
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].get("Name").unwrap().to_string(), "Alice");
+    assert_eq!(records[1].get("Chassis").unwrap().to_string(), "c1");
+}
+
+#[test]
+fn test_resume_unknown_state() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let state = SessionState {
+        curr_state: "Nowhere".to_string(),
+        ..Default::default()
+    };
+    assert!(fsm.resume(state).is_err());
+}
+
+#[test]
+fn test_resume_keeps_dedupe_state() {
+    use asyncfsm::{Dedupe, TextFsmOptions};
+
+    let template =
+        "Value Key NAME (\\S+)\nValue AGE (\\d+)\n\nStart\n  ^${NAME} ${AGE} -> Record\n";
+    let parse = |dedupe, first: &str, second: &str| {
+        let options = || TextFsmOptions::default().dedupe(dedupe);
+        let mut fsm = TextFSM::from_string(template)
+            .unwrap()
+            .with_options(options());
+        let mut names = fsm.parse_chunk(first, None).unwrap().len();
+        let mut resumed = TextFSM::from_string(template)
+            .unwrap()
+            .with_options(options());
+        resumed.resume(fsm.checkpoint()).unwrap();
+        names += resumed.parse_chunk(second, None).unwrap().len();
+        names
+    };
+    assert_eq!(parse(Dedupe::Consecutive, "a 1\n", "a 1\nb 2\n"), 2);
+    assert_eq!(parse(Dedupe::Key, "a 1\nb 2\n", "a 3\nc 4\n"), 3);
+}
+
+#[test]
+fn test_resume_keeps_raw_block() {
+    use asyncfsm::TextFsmOptions;
+
+    let template =
+        "Value NAME (\\S+)\nValue AGE (\\d+)\n\nStart\n  ^name ${NAME}\n  ^age ${AGE} -> Record\n";
+    let options = || TextFsmOptions::default().with_raw(true);
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options());
+    assert!(fsm.parse_chunk("name a\n", None).unwrap().is_empty());
+    let mut resumed = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options());
+    resumed.resume(fsm.checkpoint()).unwrap();
+    let records = resumed.parse_chunk("age 1\n", None).unwrap();
+    let raw = records[0].raw.as_ref().unwrap();
+    assert_eq!((raw.first_line, raw.last_line), (1, 2));
+    assert_eq!(raw.text, "name a\nage 1\n");
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_resume_keeps_stats_and_warnings() {
+    use asyncfsm::{Dedupe, TextFsmOptions};
+
+    let template = "Value Required NAME (\\S+)\nValue OneOf=up|down STATUS (\\S+)\n\n\
+                    Start\n  ^name ${NAME}\n  ^status ${STATUS} -> Record\n";
+    let input = "status down\nname a\nstatus up\nnoise\nname a\nstatus up\n\
+                 status down\nname b\nstatus gone\nx\n";
+    let options = || TextFsmOptions::default().dedupe(Dedupe::Consecutive);
+    let fresh = || {
+        TextFSM::from_string(template)
+            .unwrap()
+            .with_options(options())
+    };
+
+    let mut whole = fresh();
+    let mut expected = whole.parse_chunk(input, None).unwrap().len();
+    expected += whole.finish(None).unwrap().len();
+
+    let (first, second) = input.split_at(input.rfind("status down").unwrap());
+    let mut fsm = fresh();
+    let mut records = fsm.parse_chunk(first, None).unwrap().len();
+    let saved = serde_json::to_string(&fsm.checkpoint()).unwrap();
+    let mut resumed = fresh();
+    resumed.resume(serde_json::from_str(&saved).unwrap()).unwrap();
+    records += resumed.parse_chunk(second, None).unwrap().len();
+    records += resumed.finish(None).unwrap().len();
+
+    assert_eq!(records, expected);
+    assert_eq!(resumed.unmatched_lines, whole.unmatched_lines);
+    assert_eq!(resumed.unmatched_lines, 2);
+    assert_eq!(resumed.duplicate_records, whole.duplicate_records);
+    assert_eq!(resumed.duplicate_records, 1);
+    assert_eq!(resumed.truncated, whole.truncated);
+    assert_eq!(resumed.warnings, whole.warnings);
+    assert_eq!(resumed.warnings.len(), 2);
+    assert_eq!(resumed.validation_issues, whole.validation_issues);
+    assert_eq!(resumed.validation_issues.len(), 1);
+}
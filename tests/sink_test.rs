@@ -0,0 +1,216 @@
+#[cfg(feature = "json")]
+use asyncfsm::sink::JsonSink;
+#[cfg(feature = "yaml")]
+use asyncfsm::sink::YamlSink;
+use asyncfsm::sink::{RecordSink, WriterSink};
+use asyncfsm::{record_sink, TextFSM};
+
+const TEMPLATE: &str = "Value NAME (\\S+)\nValue List PORTS (\\d+)\n\nStart\n  ^${NAME}: -> Continue\n  ^.* ${PORTS}\n  ^end -> Record\n";
+
+fn parse(input: &str) -> asyncfsm::Records {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    fsm.parse_records(input, None).unwrap()
+}
+
+#[test]
+fn test_builtin_sinks_write_whole_results() {
+    let records = parse("a: 1\na: 2\nend\n");
+
+    #[cfg(feature = "json")]
+    {
+        let mut json = JsonSink::new(vec![]);
+        json.write_all(&records).unwrap();
+        assert_eq!(
+            String::from_utf8(json.into_inner()).unwrap(),
+            "[\n  {\"NAME\":\"a\",\"PORTS\":[\"1\",\"2\"],\"record_key\":null}\n]\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    {
+        let mut yaml = YamlSink::new(vec![]);
+        yaml.write_all(&records).unwrap();
+        assert_eq!(
+            String::from_utf8(yaml.into_inner()).unwrap(),
+            "- NAME: a\n  PORTS:\n  - '1'\n  - '2'\n  record_key: null\n"
+        );
+    }
+
+    // Writers registered by name write through the same trait.
+    let writer = asyncfsm::output_writer("text").unwrap();
+    let mut text = WriterSink::new(writer, vec![]);
+    text.write_all(&records).unwrap();
+    assert!(String::from_utf8(text.into_inner())
+        .unwrap()
+        .contains("NAME"));
+
+    let err = record_sink("parquet").err().unwrap();
+    assert!(err.to_string().contains("text"), "{}", err);
+    #[cfg(feature = "json")]
+    assert!(err.to_string().contains("ndjson"), "{}", err);
+}
+
+#[test]
+#[cfg(feature = "csv_export")]
+fn test_streamed_records_reach_a_file_sink() {
+    let dir = std::env::temp_dir().join(format!("asyncfsm_sink_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.csv");
+    let mut sink = record_sink(&format!("csv:{}", path.display())).unwrap();
+    let fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let written = fsm
+        .parse_reader("a: 1\nend\nb: 2\nend\n".as_bytes())
+        .write_to(sink.as_mut(), None)
+        .unwrap();
+    assert_eq!(written, 2);
+    drop(sink);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "NAME,PORTS\na,\"[\"\"1\"\"]\"\nb,\"[\"\"2\"\"]\"\n"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn test_sqlite_sink_inserts_rows() {
+    use asyncfsm::sink::SqliteSink;
+
+    let records = parse("a: 1\na: 2\nend\nb: 3\nend\n");
+    let mut sink = SqliteSink::new(rusqlite::Connection::open_in_memory().unwrap(), "ports");
+    sink.write_all(&records).unwrap();
+    let connection = sink.into_inner();
+    let mut rows = connection
+        .prepare("SELECT NAME, PORTS FROM ports ORDER BY NAME")
+        .unwrap();
+    let rows: Vec<(String, String)> = rows
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("a".to_string(), "[\"1\",\"2\"]".to_string()),
+            ("b".to_string(), "[\"3\"]".to_string())
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_partitioned_sink_writes_a_sink_per_value() {
+    use asyncfsm::sink::{NdjsonSink, PartitionedSink};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Buffer = Rc<RefCell<Vec<u8>>>;
+
+    /// Output that stays readable after the partition drops its sink.
+    struct Shared(Buffer);
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let outputs: RefCell<Vec<(String, Buffer)>> = RefCell::new(vec![]);
+    let records = parse("a: 1\nend\nb: 2\nend\na: 3\nend\n");
+    let mut sink = PartitionedSink::new("NAME", |value: &str| {
+        let out = Rc::new(RefCell::new(vec![]));
+        outputs
+            .borrow_mut()
+            .push((value.to_string(), Rc::clone(&out)));
+        Ok(Box::new(NdjsonSink::new(Shared(out))) as Box<dyn RecordSink>)
+    });
+    sink.write_all(&records).unwrap();
+    sink.write_partition("c", std::slice::from_ref(&records[0]))
+        .unwrap();
+    assert_eq!(sink.partitions().collect::<Vec<_>>(), ["a", "b", "c"]);
+    drop(sink);
+
+    let outputs: Vec<(String, String)> = outputs
+        .into_inner()
+        .into_iter()
+        .map(|(value, out)| (value, String::from_utf8(out.take()).unwrap()))
+        .collect();
+    assert_eq!(
+        outputs,
+        [
+            (
+                "a".to_string(),
+                "{\"NAME\":\"a\",\"PORTS\":[\"1\"]}\n{\"NAME\":\"a\",\"PORTS\":[\"3\"]}\n"
+                    .to_string()
+            ),
+            (
+                "b".to_string(),
+                "{\"NAME\":\"b\",\"PORTS\":[\"2\"]}\n".to_string()
+            ),
+            (
+                "c".to_string(),
+                "{\"NAME\":\"a\",\"PORTS\":[\"1\"]}\n".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_partition_file_names_do_not_collide() {
+    use asyncfsm::sink::PartitionFileNames;
+
+    let mut names = PartitionFileNames::new();
+    assert_eq!(names.name("/eth0/1"), "eth0_1");
+    assert_eq!(names.name(""), "_none");
+    assert_eq!(names.name(".hidden"), "_.hidden");
+    let colliding: Vec<String> = ["a/b", "a_b", "a:b"]
+        .iter()
+        .map(|value| names.name(value))
+        .collect();
+    assert_eq!(colliding[0], "a_b");
+    assert!(colliding[1].starts_with("a_b-") && colliding[2].starts_with("a_b-"));
+    assert_ne!(colliding[1], colliding[2]);
+    // A value keeps its name.
+    assert_eq!(names.name("a:b"), colliding[2]);
+    assert_eq!(names.name("a/b"), "a_b");
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_json_sink_spans_several_inputs() {
+    let (first, second) = (parse("a: 1\nend\n"), parse("b: 2\nend\n"));
+    let mut json = JsonSink::new(vec![]);
+    for _ in 0..2 {
+        json.open(first.schema()).unwrap();
+        json.write(&first).unwrap();
+        json.write(&second).unwrap();
+        json.close().unwrap();
+    }
+    // Each stream holds one array; opening again starts the next one afresh.
+    let text = String::from_utf8(json.into_inner()).unwrap();
+    let arrays: Vec<serde_json::Value> = serde_json::Deserializer::from_str(&text)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(arrays.len(), 2);
+    assert_eq!(arrays[0], arrays[1]);
+    assert_eq!(arrays[0].as_array().unwrap().len(), 2);
+    assert_eq!(arrays[0][1]["NAME"], "b");
+}
+
+#[test]
+#[cfg(feature = "csv_export")]
+fn test_csv_sink_spans_several_inputs() {
+    let (first, second) = (parse("a: 1\nend\n"), parse("b: 2\nend\n"));
+    let mut csv = asyncfsm::sink::CsvSink::new(vec![]);
+    csv.open(first.schema()).unwrap();
+    csv.write(&first).unwrap();
+    csv.write(&second).unwrap();
+    csv.close().unwrap();
+    assert_eq!(
+        String::from_utf8(csv.into_inner().unwrap()).unwrap(),
+        "NAME,PORTS\na,\"[\"\"1\"\"]\"\nb,\"[\"\"2\"\"]\"\n"
+    );
+}
@@ -0,0 +1,31 @@
+use asyncfsm::{TextFSM, TextFSMParser, TextFsmError};
+
+const TEMPLATE: &str = "Value Required NAME (\\S+)\n\nStart\n  ^Name: ${NAME} -> Record Detail\n\nDetail\n  ^bad -> Error \"bad line\"\n  ^x\n";
+
+#[test]
+fn test_compiled_template_points_back_to_its_source() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let text = |span: asyncfsm::Span| &TEMPLATE[span.start..span.end];
+
+    let name = &parser.values["NAME"];
+    assert_eq!(text(name.span()), "Value Required NAME (\\S+)");
+    let detail = &parser.states["Detail"];
+    assert_eq!((text(detail.span()), detail.span().line), ("Detail", 6));
+
+    let rule = &parser.states["Start"].rules()[0];
+    assert_eq!(text(rule.span()), "^Name: ${NAME} -> Record Detail");
+    assert_eq!((rule.span().line, rule.span().column), (4, 3));
+    assert_eq!(text(rule.action_span().unwrap()), "Record Detail");
+    assert_eq!(detail.rules()[1].action_span(), None);
+}
+
+#[test]
+fn test_error_actions_name_the_template_line() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let err = fsm.parse_records("Name: a\nbad\n", None).unwrap_err();
+    let TextFsmError::TemplateErrorAction { span, .. } = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(&TEMPLATE[span.start..span.end], "Error \"bad line\"");
+    assert!(err.to_string().ends_with("template line 7)"), "{}", err);
+}
@@ -0,0 +1,136 @@
+use asyncfsm::tdiff::{RuleChange, StateChange, ValueChange};
+use asyncfsm::{TemplateDiff, TextFSMParser};
+
+const OLD: &str = r#"Value INTERFACE (\S+)
+Value STATUS (\S+)
+Value MTU (\d+)
+
+Start
+  ^Interface ${INTERFACE} is ${STATUS}
+  ^  MTU ${MTU} -> Record
+  ^. -> Error
+
+Legacy
+  ^old
+"#;
+
+const NEW: &str = r#"Value INTERFACE (\S+)
+Value Required STATUS (up|down)
+Value DESCRIPTION (.*)
+
+Start
+  ^Interface ${INTERFACE} is now ${STATUS}
+  ^  Description ${DESCRIPTION}
+  ^  MTU \d+ -> Record
+  ^. -> Error
+
+Detail
+  ^x -> Start
+"#;
+
+fn diff(old: &str, new: &str) -> TemplateDiff {
+    TemplateDiff::between(
+        &TextFSMParser::from_string(old).unwrap(),
+        &TextFSMParser::from_string(new).unwrap(),
+    )
+}
+
+#[test]
+fn test_identical_templates_have_no_differences() {
+    let d = diff(OLD, OLD);
+    assert!(d.is_empty());
+    assert_eq!(d.to_string(), "templates are equivalent\n");
+}
+
+#[test]
+fn test_value_changes() {
+    let d = diff(OLD, NEW);
+    assert_eq!(
+        d.values,
+        vec![
+            ValueChange::Changed {
+                name: "STATUS".into(),
+                options: Some(["".into(), "Required".into()]),
+                regex: Some([r"(\S+)".into(), "(up|down)".into()]),
+            },
+            ValueChange::Removed {
+                name: "MTU".into(),
+                definition: r"Value MTU (\d+)".into(),
+            },
+            ValueChange::Added {
+                name: "DESCRIPTION".into(),
+                definition: "Value DESCRIPTION (.*)".into(),
+            },
+        ]
+    );
+    let reordered = diff(
+        "Value A (a)\nValue B (b)\n\nStart\n  ^${A}${B}\n",
+        "Value B (b)\nValue A (a)\n\nStart\n  ^${A}${B}\n",
+    );
+    assert_eq!(
+        reordered.values,
+        vec![ValueChange::Reordered {
+            old: vec!["A".into(), "B".into()],
+            new: vec!["B".into(), "A".into()],
+        }]
+    );
+}
+
+#[test]
+fn test_rule_and_state_changes() {
+    let d = diff(OLD, NEW);
+    assert_eq!(
+        d.states,
+        vec![
+            StateChange::Changed {
+                name: "Start".into(),
+                rules: vec![
+                    RuleChange::Changed {
+                        old_index: 0,
+                        new_index: 0,
+                        old: "^Interface ${INTERFACE} is ${STATUS}".into(),
+                        new: "^Interface ${INTERFACE} is now ${STATUS}".into(),
+                    },
+                    RuleChange::Changed {
+                        old_index: 1,
+                        new_index: 1,
+                        old: "^  MTU ${MTU} -> Record".into(),
+                        new: "^  Description ${DESCRIPTION}".into(),
+                    },
+                    RuleChange::Added {
+                        index: 2,
+                        rule: r"^  MTU \d+ -> Record".into(),
+                    },
+                ],
+            },
+            StateChange::Removed {
+                name: "Legacy".into(),
+                rules: vec!["^old".into()],
+            },
+            StateChange::Added {
+                name: "Detail".into(),
+                rules: vec!["^x -> Start".into()],
+            },
+        ]
+    );
+    let report = d.to_string();
+    assert!(report.contains("  ~ STATUS: options (none) -> Required\n"));
+    assert!(report.contains("      ~ rule 1:\n"));
+    assert!(report.contains("          at column 28: \"\" -> \"now \"\n"));
+    assert!(report.contains("  - Legacy (1 rule)\n"));
+    assert!(report.contains("  + Detail (1 rule)\n      ^x -> Start\n"));
+}
+
+#[test]
+fn test_transition_changes_are_rule_changes() {
+    let d = diff(
+        "Value A (a)\n\nStart\n  ^${A} -> Record\n",
+        "Value A (a)\n\nStart\n  ^${A} -> Continue.Record\n",
+    );
+    let json = serde_json::to_value(&d).unwrap();
+    assert_eq!(json["states"][0]["change"], "changed");
+    assert_eq!(
+        json["states"][0]["rules"][0]["new"],
+        "^${A} -> Continue.Record"
+    );
+}
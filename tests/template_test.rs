@@ -0,0 +1,72 @@
+use asyncfsm::{DataRecordConversion, Template, TextFSM, TextFsmOptions};
+use std::sync::Arc;
+
+const TEMPLATE: &str = r#"# Platform: test
+Value Filldown HOST (\S+)
+Value PORT (\S+)
+
+Start
+  ^host ${HOST}
+  ^port ${PORT} -> Record
+
+EOF
+"#;
+
+#[test]
+fn test_sessions_do_not_share_parse_state() {
+    let template = Template::new(TEMPLATE).unwrap();
+    let mut first = template.session();
+    assert!(first.parse_chunk("host r1\n", None).unwrap().is_empty());
+
+    let records = template.parse_records("port b\n", None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert!(records[0]
+        .get("HOST")
+        .is_none_or(|h| h.to_string().is_empty()));
+
+    let records = first.parse_chunk("port a\n", None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].get("HOST").unwrap().to_string(), "r1");
+    assert_eq!(template.value_names(), ["HOST", "PORT"]);
+    assert_eq!(template.metadata().get("Platform").unwrap(), "test");
+}
+
+#[test]
+fn test_sessions_start_with_the_template_options() {
+    let template = Template::new(TEMPLATE)
+        .unwrap()
+        .with_options(TextFsmOptions::default().max_records(1));
+    let records = template
+        .parse_records(
+            "port a\nport b\n",
+            Some(DataRecordConversion::LowercaseKeys),
+        )
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records.schema(), ["host", "port"]);
+    assert!(Template::from(&template.session()).options().max_records == Some(1));
+}
+
+#[test]
+fn test_one_template_parses_on_many_threads() {
+    let template = Arc::new(Template::new(TEMPLATE).unwrap());
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let template = Arc::clone(&template);
+            std::thread::spawn(move || {
+                let input = format!("host r{}\nport p{}\nport q{}\n", i, i, i);
+                template.parse_records(&input, None).unwrap()
+            })
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        let records = handle.join().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records
+            .iter()
+            .all(|r| r.get("HOST").unwrap().to_string() == format!("r{}", i)));
+    }
+
+    let fsm = TextFSM::from_parser(Arc::new(template.parser().clone()));
+    assert_eq!(fsm.parser.value_names, template.value_names());
+}
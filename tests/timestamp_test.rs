@@ -0,0 +1,86 @@
+#![cfg(feature = "timestamps")]
+
+use asyncfsm::{
+    Coercion, Coercions, DataRecordConversion, TextFSM, TimestampOutput, Timestamps, Value,
+};
+
+#[test]
+fn test_layouts() {
+    let timestamps = Timestamps::new().year(2024);
+    for (text, expected) in [
+        ("2006-01-02T15:04:05Z", Some("2006-01-02T15:04:05+00:00")),
+        (
+            "2006-01-02 15:04:05.5 +02:00",
+            Some("2006-01-02T15:04:05.5+02:00"),
+        ),
+        (
+            "Mon Jan  2 15:04:05 2006",
+            Some("2006-01-02T15:04:05+00:00"),
+        ),
+        (
+            "Mon Jan 2 15:04:05 GMT 2006",
+            Some("2006-01-02T15:04:05+00:00"),
+        ),
+        ("Jan 2 2006 15:04:05", Some("2006-01-02T15:04:05+00:00")),
+        (
+            "*Mar  1 00:01:02.123",
+            Some("2024-03-01T00:01:02.123+00:00"),
+        ),
+        (
+            "15:04:05.123 UTC Mon Jan 2 2006",
+            Some("2006-01-02T15:04:05.123+00:00"),
+        ),
+        ("01/02/2006 15:04:05", Some("2006-01-02T15:04:05+00:00")),
+        ("Feb 30 2006 15:04:05", None),
+        ("yesterday", None),
+    ] {
+        assert_eq!(timestamps.parse(text).as_deref(), expected, "{}", text);
+    }
+}
+
+#[test]
+fn test_zones_and_outputs() {
+    let berlin = Timestamps::new().zone("Europe/Berlin").unwrap();
+    assert_eq!(
+        berlin.parse("2024-07-01 12:00:00").as_deref(),
+        Some("2024-07-01T12:00:00+02:00")
+    );
+    // A zone in the text wins over the assumed one; `PST` is ambiguous and does not.
+    assert_eq!(
+        berlin.parse("12:00:00 UTC Mon Jul 1 2024").as_deref(),
+        Some("2024-07-01T12:00:00+00:00")
+    );
+    assert_eq!(
+        berlin.parse("12:00:00 PST Mon Jul 1 2024").as_deref(),
+        Some("2024-07-01T12:00:00+02:00")
+    );
+    let millis = Timestamps::new().output(TimestampOutput::EpochMillis);
+    assert_eq!(
+        millis.parse("1970-01-01T00:00:01.5-01:00").as_deref(),
+        Some("3601500")
+    );
+    assert!(Timestamps::new().zone("Nowhere/Special").is_err());
+}
+
+#[test]
+fn test_timestamp_coercion() {
+    let template = r###"Value TIME (.+)
+
+Start
+  ^clock ${TIME} -> Record
+
+"###;
+    let coercions = Coercions::new().field(
+        "time",
+        Coercion::Timestamp(Timestamps::new().output(TimestampOutput::EpochSeconds)),
+    );
+    let records = TextFSM::from_string(template)
+        .unwrap()
+        .parse_records(
+            "clock *10:00:00.000 UTC Thu Jan 1 1970\nclock unknown\n",
+            Some(DataRecordConversion::Coerce(coercions)),
+        )
+        .unwrap();
+    assert_eq!(records[0].fields["TIME"], Value::Single("36000".into()));
+    assert_eq!(records[1].fields["TIME"], Value::Single("unknown".into()));
+}
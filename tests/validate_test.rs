@@ -0,0 +1,65 @@
+use asyncfsm::{TextFSM, TextFsmError, TextFsmOptions, Validator};
+
+const TEMPLATE: &str = r###"Value INTERFACE (\S+)
+Value OneOf=up|down STATUS (\S+)
+Value MTU (\d+)
+
+Start
+  ^${INTERFACE} is ${STATUS}, mtu ${MTU} -> Record
+"###;
+
+const INPUT: &str = "Gi0/1 is up, mtu 1500\nGi0/2 is testing, mtu 9000\nGi0/3 is down, mtu 100\n";
+
+#[test]
+fn test_one_of_option_collects_issues() {
+    let mut fsm = TextFSM::from_string(TEMPLATE).unwrap();
+    let result = fsm.parse_records(INPUT, None).unwrap();
+    assert_eq!(result.len(), 3);
+    assert_eq!(fsm.validation_issues.len(), 1);
+    let issue = &fsm.validation_issues[0];
+    assert_eq!(issue.value, "STATUS");
+    assert_eq!(issue.captured, "testing");
+    assert_eq!(issue.line_number, 2);
+    assert_eq!(issue.message, "expected one of up|down");
+}
+
+#[test]
+fn test_strict_validation_fails_parse() {
+    let options = TextFsmOptions::default().strict_validation(true);
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    match fsm.parse_records(INPUT, None) {
+        Err(TextFsmError::ValidationError(issue)) => assert_eq!(issue.captured, "testing"),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_library_validators() {
+    let options = TextFsmOptions::default()
+        .validator("MTU", Validator::matches(r"1500|9\d{3}").unwrap())
+        .validator(
+            "INTERFACE",
+            Validator::custom(|v| {
+                if v.starts_with("Gi") {
+                    Ok(())
+                } else {
+                    Err("not a GigabitEthernet port".to_string())
+                }
+            }),
+        );
+    let mut fsm = TextFSM::from_string(TEMPLATE)
+        .unwrap()
+        .with_options(options);
+    fsm.parse_records(INPUT, None).unwrap();
+    let rejected: Vec<(&str, &str)> = fsm
+        .validation_issues
+        .iter()
+        .map(|i| (i.value.as_str(), i.captured.as_str()))
+        .collect();
+    assert_eq!(rejected, vec![("STATUS", "testing"), ("MTU", "100")]);
+
+    fsm.reset();
+    assert!(fsm.validation_issues.is_empty());
+}
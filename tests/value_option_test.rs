@@ -0,0 +1,75 @@
+use asyncfsm::{
+    register_value_option, DataRecord, TextFSM, Value, ValueOptionHandler, ValueOptionUse,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Scales captures such as `2k` by the unit named in the argument.
+struct Metric;
+
+impl ValueOptionHandler for Metric {
+    fn on_assign(&self, option: &ValueOptionUse<'_>, captured: &str) -> String {
+        let scale = match option.argument {
+            Some("bytes") => 1024.0,
+            _ => 1000.0,
+        };
+        match captured.strip_suffix('k') {
+            Some(n) => (n.parse::<f64>().unwrap() * scale).to_string(),
+            None => captured.to_string(),
+        }
+    }
+}
+
+/// Numbers the records and counts how often the value was cleared.
+#[derive(Clone, Default)]
+struct Sequence {
+    records: Arc<AtomicUsize>,
+    clears: Arc<AtomicUsize>,
+}
+
+impl ValueOptionHandler for Sequence {
+    fn on_record(&self, option: &ValueOptionUse<'_>, record: &mut DataRecord) {
+        let n = self.records.fetch_add(1, Ordering::SeqCst) + 1;
+        record
+            .fields
+            .insert(option.value.to_string(), Value::Single(n.to_string()));
+    }
+
+    fn on_clear(&self, _option: &ValueOptionUse<'_>) {
+        self.clears.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_custom_value_options() {
+    register_value_option("Metric", Metric).unwrap();
+    let sequence = Sequence::default();
+    register_value_option("Sequence", sequence.clone()).unwrap();
+
+    let template = r###"Value Required Port (\S+)
+Value Metric=bytes Rx (\S+)
+Value Metric Tx (\S+)
+Value Sequence Seq (\d*)
+
+Start
+  ^${Port} ${Rx} ${Tx} -> Record
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let result = fsm
+        .parse_records("Gi0/1 2k 2k\nGi0/2 10 20\n", None)
+        .unwrap();
+    let get = |i: usize, name: &str| result[i].get(name).unwrap().to_string();
+    assert_eq!(get(0, "Rx"), "2048");
+    assert_eq!(get(0, "Tx"), "2000");
+    assert_eq!(get(1, "Rx"), "10");
+    assert_eq!(get(0, "Seq"), "1");
+    assert_eq!(get(1, "Seq"), "2");
+    assert_eq!(sequence.clears.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_unregistered_and_builtin_options() {
+    let template = "Value Unheard X (\\S+)\n\nStart\n  ^${X} -> Record\n";
+    assert!(TextFSM::from_string(template).is_err());
+    assert!(register_value_option("Filldown", Metric).is_err());
+}
@@ -0,0 +1,166 @@
+use asyncfsm::{Dedupe, DropReason, TextFSM, TextFsmOptions, Value, WarningKind};
+
+#[test]
+fn test_record_dropped_for_missing_required() {
+    let template = r###"Value Required NAME (\S+)
+Value PORT (\d+)
+
+Start
+  ^name ${NAME}
+  ^port ${PORT} -> Record
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let outcome = fsm.parse_outcome("name a\nport 1\nport 2\n", None).unwrap();
+    assert_eq!(outcome.records.len(), 1);
+    // Dropped once by the rule on line 3 and again by the flush at end of input.
+    let warnings: Vec<_> = outcome.warnings.iter().collect();
+    assert_eq!(warnings.len(), 2);
+    for warning in warnings {
+        assert_eq!(warning.line_number, 3);
+        assert_eq!(
+            warning.kind,
+            WarningKind::RecordDropped {
+                missing: vec!["NAME".to_string()]
+            }
+        );
+    }
+}
+
+#[test]
+fn test_filldown_without_value() {
+    let template = r###"Value Filldown CHASSIS (\S+)
+Value SLOT (\d+)
+
+Start
+  ^chassis ${CHASSIS}
+  ^slot ${SLOT} -> Record
+
+EOF
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let outcome = fsm
+        .parse_outcome("slot 1\nchassis c1\nslot 2\n", None)
+        .unwrap();
+    assert_eq!(outcome.records.len(), 2);
+    assert_eq!(outcome.warnings.len(), 1);
+    let warning = outcome.warnings.iter().next().unwrap();
+    assert_eq!(warning.line_number, 1);
+    assert_eq!(
+        warning.kind,
+        WarningKind::FilldownWithoutValue {
+            value: "CHASSIS".to_string()
+        }
+    );
+    assert_eq!(
+        warning.to_string(),
+        "line 1: record emitted before Filldown value CHASSIS was set"
+    );
+}
+
+#[test]
+fn test_validation_issues_are_merged_in_line_order() {
+    let template = r###"Value Required NAME (\S+)
+Value OneOf=up|down STATUS (\S+)
+
+Start
+  ^name ${NAME}
+  ^status ${STATUS} -> Record
+"###;
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    let outcome = fsm
+        .parse_outcome("status up\nname a\nstatus testing\n", None)
+        .unwrap();
+    let lines: Vec<usize> = outcome.warnings.iter().map(|w| w.line_number).collect();
+    assert_eq!(lines, vec![1, 3]);
+    assert!(matches!(
+        outcome.warnings.iter().nth(1).unwrap().kind,
+        WarningKind::Validation(_)
+    ));
+
+    fsm.reset();
+    assert!(fsm.warnings.is_empty());
+}
+
+#[test]
+fn test_dropped_records_are_tracked() {
+    let template = r###"Value Required NAME (\S+)
+Value PORT (\d+)
+
+Start
+  ^name ${NAME}
+  ^port ${PORT} -> Record
+  ^reset -> Clear
+
+EOF
+"###;
+    let options = TextFsmOptions::default()
+        .track_dropped(true)
+        .dedupe(Dedupe::Consecutive);
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(options);
+    let input = "port 1\nname a\nport 2\nname a\nport 2\nname b\nreset\n";
+    let outcome = fsm.parse_outcome(input, None).unwrap();
+    assert_eq!(outcome.records.len(), 1);
+    let dropped: Vec<_> = outcome
+        .dropped
+        .iter()
+        .map(|d| (d.line_number, d.state.as_str(), d.reason.clone()))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            (
+                1,
+                "Start",
+                DropReason::MissingRequired {
+                    missing: vec!["NAME".to_string()]
+                }
+            ),
+            (5, "Start", DropReason::Duplicate),
+            (7, "Start", DropReason::Cleared),
+        ]
+    );
+    assert_eq!(outcome.dropped[0].fields["PORT"], Value::Single("1".into()));
+    assert_eq!(outcome.dropped[2].fields["NAME"], Value::Single("b".into()));
+    assert_eq!(
+        outcome.dropped[0].to_string(),
+        "line 1 (Start): record dropped, Required value(s) unset: NAME { PORT: 1 }"
+    );
+    let json = serde_json::to_value(&outcome.dropped[1]).unwrap();
+    assert_eq!(json["reason"], "duplicate");
+    assert_eq!(json["fields"]["NAME"], "a");
+
+    // Without the option nothing is kept.
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    assert!(fsm.parse_outcome(input, None).unwrap().dropped.is_empty());
+}
+
+#[test]
+fn test_track_matches() {
+    let template =
+        "Value INTF (\\S+)\nValue STATUS (up|down)\n\nStart\n  ^${INTF} is ${STATUS} -> Record\n";
+    let mut fsm = TextFSM::from_string(template)
+        .unwrap()
+        .with_options(TextFsmOptions::default().track_matches(true));
+    fsm.parse_records("Gi1 is up\nnoise\nGi2 is down\n", None)
+        .unwrap();
+    let lines: Vec<usize> = fsm.matches.iter().map(|m| m.line_number).collect();
+    assert_eq!(lines, [1, 3]);
+    let found = &fsm.matches[1];
+    assert_eq!(
+        (found.state.as_str(), found.rule, found.template_line),
+        ("Start", 1, 5)
+    );
+    let captured: Vec<(&str, &str)> = found
+        .captures
+        .iter()
+        .map(|c| (c.name.as_str(), found.captured(c)))
+        .collect();
+    assert_eq!(captured, [("INTF", "Gi2"), ("STATUS", "down")]);
+
+    // Nothing is kept unless asked for.
+    let mut fsm = TextFSM::from_string(template).unwrap();
+    fsm.parse_records("Gi1 is up\n", None).unwrap();
+    assert!(fsm.matches.is_empty());
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "notify")]
+
+use asyncfsm::CliTable;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const TEMPLATE_A: &str = "Value NAME (\\S+)\n\nStart\n  ^name ${NAME} -> Record\n";
+const TEMPLATE_B: &str = "Value HOST (\\S+)\n\nStart\n  ^name ${HOST} -> Record\n";
+
+fn setup(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("index"),
+        "Template, Platform, Command\nname_template, x, sh[[ow]] name\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("name_template"), TEMPLATE_A).unwrap();
+    dir
+}
+
+fn first_key(records: &asyncfsm::Records) -> String {
+    records[0].fields.keys().next().unwrap().clone()
+}
+
+/// Polls `check` until it returns true or a few seconds pass.
+fn eventually(check: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if check() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_invalidate_and_reload() {
+    let dir = setup("asyncfsm_watch_reload");
+    let mut table = CliTable::from_file(dir.join("index")).unwrap();
+    let records = table.parse("x", "show name", "name r1\n").unwrap();
+    assert_eq!(first_key(&records), "NAME");
+
+    // The compiled template is cached until invalidated.
+    std::fs::write(dir.join("name_template"), TEMPLATE_B).unwrap();
+    let records = table.parse("x", "show name", "name r1\n").unwrap();
+    assert_eq!(first_key(&records), "NAME");
+    table.invalidate_template(&std::fs::canonicalize(dir.join("name_template")).unwrap());
+    let records = table.parse("x", "show name", "name r1\n").unwrap();
+    assert_eq!(first_key(&records), "HOST");
+
+    std::fs::write(
+        dir.join("index"),
+        "Template, Platform, Command\nname_template, x, sh[[ow]] host\n",
+    )
+    .unwrap();
+    table.reload().unwrap();
+    assert!(table.parse("x", "show name", "name r1\n").is_err());
+    assert!(table.parse("x", "show host", "name r1\n").is_ok());
+}
+
+#[test]
+fn test_watch_picks_up_changes() {
+    let dir = setup("asyncfsm_watch_events");
+    let watched = CliTable::from_file(dir.join("index"))
+        .unwrap()
+        .watch()
+        .unwrap();
+    let parse = |cmd: &str| watched.parse("x", cmd, "name r1\n");
+    assert_eq!(first_key(&parse("show name").unwrap()), "NAME");
+
+    std::fs::write(dir.join("name_template"), TEMPLATE_B).unwrap();
+    assert!(eventually(|| parse("show name")
+        .map(|r| first_key(&r) == "HOST")
+        .unwrap_or(false)));
+
+    std::fs::write(
+        Path::new(&dir).join("index"),
+        "Template, Platform, Command\nname_template, x, sh[[ow]] host\n",
+    )
+    .unwrap();
+    assert!(eventually(|| parse("show host").is_ok()));
+    assert!(parse("show name").is_err());
+}